@@ -20,7 +20,7 @@ pub(crate) use barycentric::{
 };
 pub(crate) use batch_data::BatchDataConfig;
 pub(crate) use blob_data::BlobDataConfig;
-pub(crate) use decoder::{witgen, DecoderConfig, DecoderConfigArgs};
+pub(crate) use decoder::{witgen, AssignedDecoderConfigExports, DecoderConfig, DecoderConfigArgs};
 pub(crate) use rlc::RlcConfig;
 
 pub use circuit::BatchCircuit;
@@ -399,8 +399,8 @@ mod tests {
         for z in 0..10 {
             let z = Scalar::from(u64::try_from(13241234 + z).unwrap());
             assert_eq!(
-                reth_point_evaluation(z, &batch.get_coefficients().map(|c| Scalar::from_raw(c.0))),
-                interpolate(z, &batch.get_coefficients().map(|c| Scalar::from_raw(c.0)))
+                reth_point_evaluation(z, &batch.get_coefficients(0).map(|c| Scalar::from_raw(c.0))),
+                interpolate(z, &batch.get_coefficients(0).map(|c| Scalar::from_raw(c.0)))
             );
         }
     }
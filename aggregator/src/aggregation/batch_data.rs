@@ -1,5 +1,4 @@
 use ethers_core::utils::keccak256;
-use halo2_ecc::bigint::CRTInteger;
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter, Region, Value},
     halo2curves::bn256::Fr,
@@ -13,8 +12,8 @@ use zkevm_circuits::{
 };
 
 use crate::{
-    aggregation::rlc::POWS_OF_256,
-    blob::{BatchData, BLOB_WIDTH, N_BYTES_U256},
+    aggregation::{rlc::POWS_OF_256, AssignedBarycentricEvaluationConfig},
+    blob::{BatchData, BLOB_WIDTH, MAX_BLOBS_PER_BATCH, N_BYTES_U256},
     RlcConfig,
 };
 
@@ -59,7 +58,8 @@ pub struct BatchDataConfig<const N_SNARKS: usize> {
 pub struct AssignedBatchDataExport {
     pub num_valid_chunks: AssignedCell<Fr, Fr>,
     pub batch_data_len: AssignedCell<Fr, Fr>,
-    pub versioned_hash: Vec<AssignedCell<Fr, Fr>>,
+    /// The versioned hash of each of the MAX_BLOBS_PER_BATCH blobs.
+    pub versioned_hashes: Vec<Vec<AssignedCell<Fr, Fr>>>,
     pub chunk_data_digests: Vec<Vec<AssignedCell<Fr, Fr>>>,
     pub bytes_rlc: AssignedCell<Fr, Fr>,
 }
@@ -381,7 +381,7 @@ impl<const N_SNARKS: usize> BatchDataConfig<N_SNARKS> {
         // `core.rs`. Since these are already constrained, we can just use them as is.
         chunks_are_padding: &[AssignedCell<Fr, Fr>],
         batch_data: &BatchData<N_SNARKS>,
-        barycentric_assignments: &[CRTInteger<Fr>],
+        barycentric_assignments: &[AssignedBarycentricEvaluationConfig],
     ) -> Result<AssignedBatchDataExport, Error> {
         self.load_range_tables(layouter)?;
 
@@ -398,6 +398,7 @@ impl<const N_SNARKS: usize> BatchDataConfig<N_SNARKS> {
                     challenge_value,
                     rlc_config,
                     chunks_are_padding,
+                    batch_data,
                     barycentric_assignments,
                     &assigned_rows,
                 )
@@ -529,7 +530,8 @@ impl<const N_SNARKS: usize> BatchDataConfig<N_SNARKS> {
         // The chunks_are_padding assigned cells are exports from the conditional constraints in
         // `core.rs`. Since these are already constrained, we can just use them as is.
         chunks_are_padding: &[AssignedCell<Fr, Fr>],
-        barycentric_assignments: &[CRTInteger<Fr>],
+        batch_data: &BatchData<N_SNARKS>,
+        barycentric_assignments: &[AssignedBarycentricEvaluationConfig],
         assigned_rows: &[AssignedBatchDataConfig],
     ) -> Result<AssignedBatchDataExport, Error> {
         let n_rows_metadata = BatchData::<N_SNARKS>::n_rows_metadata();
@@ -889,6 +891,9 @@ impl<const N_SNARKS: usize> BatchDataConfig<N_SNARKS> {
         ////////////////////////////////////////////////////////////////////////////////
 
         let mut challenge_digest_preimage_keccak_rlc = zero.clone();
+        // the keccak input RLC of metadata_digest || chunk data digests, which all the blobs'
+        // challenge digest preimages start with.
+        let mut digests_keccak_rlc = zero.clone();
         let rows = assigned_rows
             .iter()
             .skip(n_rows_metadata + n_rows_data + n_rows_digest_rlc)
@@ -925,6 +930,9 @@ impl<const N_SNARKS: usize> BatchDataConfig<N_SNARKS> {
                     &digest_keccak_rlc,
                     &mut rlc_config_offset,
                 )?;
+                if i == N_SNARKS {
+                    digests_keccak_rlc = challenge_digest_preimage_keccak_rlc.clone();
+                }
             }
         }
         region.constrain_equal(
@@ -946,23 +954,92 @@ impl<const N_SNARKS: usize> BatchDataConfig<N_SNARKS> {
         for chunk in chunk_data_digests_bytes.chunks_exact(N_BYTES_U256) {
             chunk_data_digests.push(chunk.to_vec());
         }
-        let challenge_digest = assigned_rows
+        let mut challenge_digests = vec![assigned_rows
+            .iter()
+            .rev()
+            .take(N_BYTES_U256)
+            .map(|row| row.byte.clone())
+            .collect::<Vec<AssignedCell<Fr, Fr>>>()];
+        let mut versioned_hashes = vec![assigned_rows
             .iter()
             .rev()
+            .skip(N_BYTES_U256)
             .take(N_BYTES_U256)
             .map(|row| row.byte.clone())
-            .collect::<Vec<AssignedCell<Fr, Fr>>>();
+            .rev()
+            .collect::<Vec<_>>()];
+
+        ////////////////////////////////////////////////////////////////////////////////
+        //////////////////////// EXTRA BLOBS CHALLENGE DIGESTS /////////////////////////
+        ////////////////////////////////////////////////////////////////////////////////
+
+        // The "digest" sections only cover the first blob. The challenge digest of each other
+        // blob is keccak(metadata_digest || chunk data digests || versioned_hash), i.e. its
+        // preimage differs from the first blob's only in the versioned hash.
+        let challenge_digest_preimage_len = {
+            let thirty_two =
+                rlc_config.load_private(region, &Fr::from(32), &mut rlc_config_offset)?;
+            let thirty_two_cell = rlc_config.thirty_two_cell(thirty_two.cell().region_index);
+            region.constrain_equal(thirty_two.cell(), thirty_two_cell)?;
+            let n_digests = rlc_config.add(region, n_snarks, two, &mut rlc_config_offset)?;
+            rlc_config.mul(region, &n_digests, &thirty_two, &mut rlc_config_offset)?
+        };
+        for blob_idx in 1..MAX_BLOBS_PER_BATCH {
+            let versioned_hash = batch_data.get_versioned_hash(blob_idx);
+            let challenge_digest = keccak256(batch_data.get_challenge_digest_preimage(blob_idx));
+
+            let mut versioned_hash_bytes = Vec::with_capacity(N_BYTES_U256);
+            for &byte in versioned_hash.as_bytes() {
+                versioned_hash_bytes.push(rlc_config.load_private(
+                    region,
+                    &Fr::from(byte as u64),
+                    &mut rlc_config_offset,
+                )?);
+            }
+            let mut challenge_digest_bytes = Vec::with_capacity(N_BYTES_U256);
+            for &byte in challenge_digest.iter() {
+                challenge_digest_bytes.push(rlc_config.load_private(
+                    region,
+                    &Fr::from(byte as u64),
+                    &mut rlc_config_offset,
+                )?);
+            }
+
+            let versioned_hash_keccak_rlc = rlc_config.rlc(
+                region,
+                &versioned_hash_bytes,
+                &r_keccak,
+                &mut rlc_config_offset,
+            )?;
+            let challenge_digest_preimage_rlc = rlc_config.mul_add(
+                region,
+                &digests_keccak_rlc,
+                &r32,
+                &versioned_hash_keccak_rlc,
+                &mut rlc_config_offset,
+            )?;
+            let challenge_digest_rlc = rlc_config.rlc(
+                region,
+                &challenge_digest_bytes,
+                &r_evm,
+                &mut rlc_config_offset,
+            )?;
+            rlc_config.lookup_keccak_rlcs(
+                region,
+                &challenge_digest_preimage_rlc,
+                &challenge_digest_rlc,
+                &challenge_digest_preimage_len,
+                &mut rlc_config_offset,
+            )?;
+
+            challenge_digests.push(challenge_digest_bytes.into_iter().rev().collect());
+            versioned_hashes.push(versioned_hash_bytes);
+        }
+
         let export = AssignedBatchDataExport {
             num_valid_chunks,
             batch_data_len,
-            versioned_hash: assigned_rows
-                .iter()
-                .rev()
-                .skip(N_BYTES_U256)
-                .take(N_BYTES_U256)
-                .map(|row| row.byte.clone())
-                .rev()
-                .collect(),
+            versioned_hashes,
             chunk_data_digests,
             // bytes rlc is from the last row of the "chunk data" section.
             bytes_rlc: assigned_rows
@@ -976,40 +1053,46 @@ impl<const N_SNARKS: usize> BatchDataConfig<N_SNARKS> {
         //////////////////////////// CHALLENGE DIGEST CHECK ////////////////////////////
         ////////////////////////////////////////////////////////////////////////////////
 
-        assert_eq!(barycentric_assignments.len(), BLOB_WIDTH + 1);
-        let challenge_digest_crt = barycentric_assignments
-            .get(BLOB_WIDTH)
-            .expect("challenge digest CRT");
-        let challenge_digest_limb1 = rlc_config.inner_product(
-            region,
-            &challenge_digest[0..11],
-            &pows_of_256,
-            &mut rlc_config_offset,
-        )?;
-        let challenge_digest_limb2 = rlc_config.inner_product(
-            region,
-            &challenge_digest[11..22],
-            &pows_of_256,
-            &mut rlc_config_offset,
-        )?;
-        let challenge_digest_limb3 = rlc_config.inner_product(
-            region,
-            &challenge_digest[22..32],
-            &pows_of_256[0..10],
-            &mut rlc_config_offset,
-        )?;
-        region.constrain_equal(
-            challenge_digest_limb1.cell(),
-            challenge_digest_crt.truncation.limbs[0].cell(),
-        )?;
-        region.constrain_equal(
-            challenge_digest_limb2.cell(),
-            challenge_digest_crt.truncation.limbs[1].cell(),
-        )?;
-        region.constrain_equal(
-            challenge_digest_limb3.cell(),
-            challenge_digest_crt.truncation.limbs[2].cell(),
-        )?;
+        assert_eq!(barycentric_assignments.len(), MAX_BLOBS_PER_BATCH);
+        for (barycentric, challenge_digest) in
+            barycentric_assignments.iter().zip_eq(challenge_digests)
+        {
+            assert_eq!(barycentric.barycentric_assignments.len(), BLOB_WIDTH + 1);
+            let challenge_digest_crt = barycentric
+                .barycentric_assignments
+                .get(BLOB_WIDTH)
+                .expect("challenge digest CRT");
+            let challenge_digest_limb1 = rlc_config.inner_product(
+                region,
+                &challenge_digest[0..11],
+                &pows_of_256,
+                &mut rlc_config_offset,
+            )?;
+            let challenge_digest_limb2 = rlc_config.inner_product(
+                region,
+                &challenge_digest[11..22],
+                &pows_of_256,
+                &mut rlc_config_offset,
+            )?;
+            let challenge_digest_limb3 = rlc_config.inner_product(
+                region,
+                &challenge_digest[22..32],
+                &pows_of_256[0..10],
+                &mut rlc_config_offset,
+            )?;
+            region.constrain_equal(
+                challenge_digest_limb1.cell(),
+                challenge_digest_crt.truncation.limbs[0].cell(),
+            )?;
+            region.constrain_equal(
+                challenge_digest_limb2.cell(),
+                challenge_digest_crt.truncation.limbs[1].cell(),
+            )?;
+            region.constrain_equal(
+                challenge_digest_limb3.cell(),
+                challenge_digest_crt.truncation.limbs[2].cell(),
+            )?;
+        }
 
         Ok(export)
     }
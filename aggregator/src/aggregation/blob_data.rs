@@ -1,7 +1,4 @@
-use std::io::Write;
-
 use gadgets::util::Expr;
-use halo2_ecc::bigint::CRTInteger;
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter, Region, Value},
     halo2curves::bn256::Fr,
@@ -12,8 +9,13 @@ use itertools::Itertools;
 use zkevm_circuits::{table::U8Table, util::Challenges};
 
 use crate::{
-    aggregation::{decoder::witgen::init_zstd_encoder, rlc::POWS_OF_256},
-    blob::{BatchData, BLOB_WIDTH, N_BLOB_BYTES, N_DATA_BYTES_PER_COEFFICIENT},
+    aggregation::{
+        batch_data::AssignedBatchDataExport, decoder::AssignedDecoderConfigExports,
+        rlc::POWS_OF_256, AssignedBarycentricEvaluationConfig,
+    },
+    blob::{
+        BatchData, BLOB_WIDTH, MAX_BLOBS_PER_BATCH, N_BLOB_BYTES, N_DATA_BYTES_PER_COEFFICIENT,
+    },
     RlcConfig,
 };
 
@@ -25,19 +27,33 @@ use crate::{
 /// Since the check for the most-significant byte being zero is already done in the
 /// BarycentricConfig, in the BlobDataConfig we only represent the 31 meaningful bytes. Hence the
 /// BlobDataConfig has 4096 * 31 rows. Each row is a byte value and the purpose of the
-/// BlobDataConfig is to compute a random-linear combination of these bytes. These bytes are in
-/// fact the zstd encoded form of the raw batch data represented in BatchDataConfig.
+/// BlobDataConfig is to compute a random-linear combination of these bytes. From batch version
+/// [`BATCH_VERSION_CODEC_TAG`](crate::BATCH_VERSION_CODEC_TAG), the first byte is the codec tag
+/// (see [`CodecTag`](crate::CodecTag)), and the remaining bytes are the raw batch data represented
+/// in BatchDataConfig, encoded with that codec. Blobs of earlier versions hold the zstd encoded
+/// batch data from the first byte. The random-linear combination and the length only cover the
+/// encoded bytes, i.e. they exclude the codec tag.
+///
+/// A batch spans up to [`MAX_BLOBS_PER_BATCH`] blobs, so the BlobDataConfig has
+/// MAX_BLOBS_PER_BATCH * 4096 * 31 rows, the i-th blob holding the i-th N_BLOB_BYTES bytes of the
+/// blob envelope. The blobs past the batch data are padding, i.e. all their bytes are zero.
 #[derive(Clone, Debug)]
 pub struct BlobDataConfig<const N_SNARKS: usize> {
     /// Selector to mark the first row in the layout, enabled at offset=0.
     q_first: Selector,
-    /// Whether the row is enabled or not. We need exactly N_BLOB_BYTES rows, enabled from offset=1
-    /// to offset=N_BLOB_BYTES.
+    /// Selector to mark the first byte of the blob, i.e. the codec tag if any, enabled at
+    /// offset=1.
+    q_codec: Selector,
+    /// Whether the first byte of the blob is the codec tag, only assigned at offset=1.
+    has_codec_tag: Column<Advice>,
+    /// Whether the row is enabled or not. We need exactly MAX_BLOBS_PER_BATCH * N_BLOB_BYTES - 1
+    /// rows for the encoded bytes, enabled from offset=2 to offset=MAX_BLOBS_PER_BATCH *
+    /// N_BLOB_BYTES.
     q_enabled: Selector,
     /// The byte value at this row.
     byte: Column<Advice>,
-    /// Whether or not this is a padded row. This can be the case if not all bytes in the blob
-    /// (4096 * 31) could be filled. Padded bytes must be 0 and bytes_rlc must continue while in
+    /// Whether or not this is a padded row. This can be the case if not all bytes in the blobs
+    /// (MAX_BLOBS_PER_BATCH * 4096 * 31) could be filled. Padded bytes must be 0 and bytes_rlc must continue while in
     /// the padded region.
     is_padding: Column<Advice>,
     /// running RLC of bytes seen so far. It remains unchanged once padded territory starts.
@@ -47,8 +63,15 @@ pub struct BlobDataConfig<const N_SNARKS: usize> {
 }
 
 pub struct AssignedBlobDataExport {
+    /// Whether the blob envelope starts with the codec tag.
+    pub has_codec_tag: AssignedCell<Fr, Fr>,
+    /// The first byte of the blob, i.e. the codec tag if any, which is then boolean: 1 for zstd
+    /// and 0 for uncompressed.
+    pub codec_tag: AssignedCell<Fr, Fr>,
     pub bytes_rlc: AssignedCell<Fr, Fr>,
     pub bytes_len: AssignedCell<Fr, Fr>,
+    /// Whether each blob but the first holds batch data, i.e. its first byte is not padding.
+    pub extra_blobs_are_valid: Vec<AssignedCell<Fr, Fr>>,
 }
 
 impl<const N_SNARKS: usize> BlobDataConfig<N_SNARKS> {
@@ -60,13 +83,17 @@ impl<const N_SNARKS: usize> BlobDataConfig<N_SNARKS> {
         let config = Self {
             q_enabled: meta.selector(),
             q_first: meta.complex_selector(),
+            q_codec: meta.selector(),
+            has_codec_tag: meta.advice_column(),
             byte: meta.advice_column(),
             is_padding: meta.advice_column(),
             bytes_rlc: meta.advice_column_in(SecondPhase),
             bytes_len: meta.advice_column(),
         };
 
+        meta.enable_equality(config.has_codec_tag);
         meta.enable_equality(config.byte);
+        meta.enable_equality(config.is_padding);
         meta.enable_equality(config.bytes_rlc);
         meta.enable_equality(config.bytes_len);
 
@@ -91,6 +118,28 @@ impl<const N_SNARKS: usize> BlobDataConfig<N_SNARKS> {
             ]
         });
 
+        meta.create_gate("BlobDataConfig: codec tag row", |meta| {
+            let is_codec = meta.query_selector(config.q_codec);
+
+            let has_codec_tag = meta.query_advice(config.has_codec_tag, Rotation::cur());
+            let byte = meta.query_advice(config.byte, Rotation::cur());
+            let bytes_rlc = meta.query_advice(config.bytes_rlc, Rotation::cur());
+            let bytes_len = meta.query_advice(config.bytes_len, Rotation::cur());
+            let is_padding = meta.query_advice(config.is_padding, Rotation::cur());
+
+            vec![
+                // has_codec_tag is boolean
+                is_codec.expr() * has_codec_tag.expr() * (1.expr() - has_codec_tag.expr()),
+                // the codec tag is boolean, i.e. either uncompressed or zstd.
+                is_codec.expr() * has_codec_tag.expr() * byte.expr() * (1.expr() - byte.expr()),
+                // with the codec tag, the encoded bytes start on the next row. Otherwise this is
+                // the first encoded byte.
+                is_codec.expr() * (bytes_rlc - (1.expr() - has_codec_tag.expr()) * byte.expr()),
+                is_codec.expr() * (bytes_len - (1.expr() - has_codec_tag.expr())),
+                is_codec.expr() * is_padding,
+            ]
+        });
+
         meta.create_gate("BlobDataConfig: main gate", |meta| {
             let is_enabled = meta.query_selector(config.q_enabled);
 
@@ -144,14 +193,15 @@ impl<const N_SNARKS: usize> BlobDataConfig<N_SNARKS> {
         challenge_value: Challenges<Value<Fr>>,
         rlc_config: &RlcConfig,
         batch_data: &BatchData<N_SNARKS>,
-        barycentric_assignments: &[CRTInteger<Fr>],
+        barycentric_assignments: &[AssignedBarycentricEvaluationConfig],
     ) -> Result<AssignedBlobDataExport, Error> {
-        let (assigned_bytes, bytes_rlc, bytes_len) = layouter.assign_region(
-            || "BlobData bytes",
-            |mut region| self.assign_rows(&mut region, batch_data, &challenge_value),
-        )?;
+        let (has_codec_tag, assigned_bytes, blobs_are_padding, bytes_rlc, bytes_len) = layouter
+            .assign_region(
+                || "BlobData bytes",
+                |mut region| self.assign_rows(&mut region, batch_data, &challenge_value),
+            )?;
 
-        let cooked_bytes_len = layouter.assign_region(
+        let (cooked_bytes_len, extra_blobs_are_valid) = layouter.assign_region(
             || "BlobData internal checks",
             |mut region| {
                 self.assign_internal_checks(
@@ -159,17 +209,153 @@ impl<const N_SNARKS: usize> BlobDataConfig<N_SNARKS> {
                     rlc_config,
                     barycentric_assignments,
                     &assigned_bytes,
+                    &blobs_are_padding,
                     &bytes_len,
                 )
             },
         )?;
 
         Ok(AssignedBlobDataExport {
+            has_codec_tag,
+            codec_tag: assigned_bytes[0].clone(),
             bytes_rlc,
             bytes_len: cooked_bytes_len,
+            extra_blobs_are_valid,
         })
     }
 
+    /// Link the blob bytes to the batch data bytes as per the blob's codec, i.e. through the zstd
+    /// decoder for a zstd payload and directly for an uncompressed payload, where the decoder is
+    /// left unlinked.
+    ///
+    /// The blob envelope starts with the codec tag iff the batch version is
+    /// [`BATCH_VERSION_CODEC_TAG`](crate::BATCH_VERSION_CODEC_TAG), the other versions supported by
+    /// the batch circuit having zstd payloads without a tag.
+    pub fn assign_codec_checks(
+        &self,
+        layouter: &mut impl Layouter<Fr>,
+        rlc_config: &RlcConfig,
+        batch_version: &AssignedCell<Fr, Fr>,
+        blob_data_exports: &AssignedBlobDataExport,
+        batch_data_exports: &AssignedBatchDataExport,
+        decoder_exports: &AssignedDecoderConfigExports,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "BlobData codec checks",
+            |mut region| -> Result<(), Error> {
+                rlc_config.init(&mut region)?;
+                let mut rlc_config_offset = 0;
+
+                let one = {
+                    let one =
+                        rlc_config.load_private(&mut region, &Fr::one(), &mut rlc_config_offset)?;
+                    let one_cell = rlc_config.one_cell(one.cell().region_index);
+                    region.constrain_equal(one.cell(), one_cell)?;
+                    one
+                };
+                let four = {
+                    let four = rlc_config.load_private(
+                        &mut region,
+                        &Fr::from(4),
+                        &mut rlc_config_offset,
+                    )?;
+                    let four_cell = rlc_config.four_cell(four.cell().region_index);
+                    region.constrain_equal(four.cell(), four_cell)?;
+                    four
+                };
+
+                // has_codec_tag == (batch_version == BATCH_VERSION_CODEC_TAG), i.e. 1 + 4. This is
+                // codec::has_codec_tag restricted to the versions the batch circuit accepts, see
+                // BatchCircuit::new, which avoids a range check on the version.
+                const _: () = assert!(crate::BATCH_VERSION_CODEC_TAG == 5);
+                let tagged_version =
+                    rlc_config.add(&mut region, &one, &four, &mut rlc_config_offset)?;
+                let version_diff = rlc_config.sub(
+                    &mut region,
+                    batch_version,
+                    &tagged_version,
+                    &mut rlc_config_offset,
+                )?;
+                let is_tagged_version =
+                    rlc_config.is_zero(&mut region, &version_diff, &mut rlc_config_offset)?;
+                region.constrain_equal(
+                    is_tagged_version.cell(),
+                    blob_data_exports.has_codec_tag.cell(),
+                )?;
+
+                // blobs without the codec tag hold a zstd payload.
+                let is_zstd = rlc_config.select(
+                    &mut region,
+                    &blob_data_exports.codec_tag,
+                    &one,
+                    &blob_data_exports.has_codec_tag,
+                    &mut rlc_config_offset,
+                )?;
+                let is_uncompressed =
+                    rlc_config.not(&mut region, &is_zstd, &mut rlc_config_offset)?;
+
+                // if zstd: equate rlc (from blob data) with decoder's encoded_rlc
+                rlc_config.conditional_enforce_equal(
+                    &mut region,
+                    &blob_data_exports.bytes_rlc,
+                    &decoder_exports.encoded_rlc,
+                    &is_zstd,
+                    &mut rlc_config_offset,
+                )?;
+                // if zstd: equate len(blob_bytes) with decoder's encoded_len
+                rlc_config.conditional_enforce_equal(
+                    &mut region,
+                    &blob_data_exports.bytes_len,
+                    &decoder_exports.encoded_len,
+                    &is_zstd,
+                    &mut rlc_config_offset,
+                )?;
+                // if zstd: equate rlc (from batch data) with decoder's decoded_rlc
+                rlc_config.conditional_enforce_equal(
+                    &mut region,
+                    &batch_data_exports.bytes_rlc,
+                    &decoder_exports.decoded_rlc,
+                    &is_zstd,
+                    &mut rlc_config_offset,
+                )?;
+                // if zstd: equate len(batch_data) with decoder's decoded_len
+                rlc_config.conditional_enforce_equal(
+                    &mut region,
+                    &batch_data_exports.batch_data_len,
+                    &decoder_exports.decoded_len,
+                    &is_zstd,
+                    &mut rlc_config_offset,
+                )?;
+
+                // if uncompressed: equate rlc (from blob data) with rlc (from batch data)
+                rlc_config.conditional_enforce_equal(
+                    &mut region,
+                    &blob_data_exports.bytes_rlc,
+                    &batch_data_exports.bytes_rlc,
+                    &is_uncompressed,
+                    &mut rlc_config_offset,
+                )?;
+                // if uncompressed: equate len(blob_bytes) with len(batch_data). Like the
+                // decoder's encoded_len, the exported len(blob_bytes) is off by one.
+                let cooked_batch_data_len = rlc_config.add(
+                    &mut region,
+                    &one,
+                    &batch_data_exports.batch_data_len,
+                    &mut rlc_config_offset,
+                )?;
+                rlc_config.conditional_enforce_equal(
+                    &mut region,
+                    &blob_data_exports.bytes_len,
+                    &cooked_batch_data_len,
+                    &is_uncompressed,
+                    &mut rlc_config_offset,
+                )?;
+
+                Ok(())
+            },
+        )
+    }
+
     #[allow(clippy::type_complexity)]
     pub fn assign_rows(
         &self,
@@ -178,27 +364,21 @@ impl<const N_SNARKS: usize> BlobDataConfig<N_SNARKS> {
         challenges: &Challenges<Value<Fr>>,
     ) -> Result<
         (
+            AssignedCell<Fr, Fr>,
+            Vec<AssignedCell<Fr, Fr>>,
             Vec<AssignedCell<Fr, Fr>>,
             AssignedCell<Fr, Fr>,
             AssignedCell<Fr, Fr>,
         ),
         Error,
     > {
-        let batch_bytes = batch_data.get_batch_data_bytes();
-        let blob_bytes = {
-            let mut encoder = init_zstd_encoder(None);
-            encoder
-                .set_pledged_src_size(Some(batch_bytes.len() as u64))
-                .map_err(|_| Error::Synthesis)?;
-            encoder
-                .write_all(&batch_bytes)
-                .map_err(|_| Error::Synthesis)?;
-            encoder.finish().map_err(|_| Error::Synthesis)?
-        };
-        assert!(blob_bytes.len() <= N_BLOB_BYTES, "too many blob bytes");
+        let n_rows_bytes = MAX_BLOBS_PER_BATCH * N_BLOB_BYTES;
+        let envelope_bytes = batch_data.get_envelope_bytes();
+        assert!(envelope_bytes.len() <= n_rows_bytes, "too many blob bytes");
 
         self.q_first.enable(region, 0)?;
-        for i in 1..=N_BLOB_BYTES {
+        self.q_codec.enable(region, 1)?;
+        for i in 2..=n_rows_bytes {
             self.q_enabled.enable(region, i)?;
         }
 
@@ -211,11 +391,42 @@ impl<const N_SNARKS: usize> BlobDataConfig<N_SNARKS> {
             )?;
         }
 
-        let mut assigned_bytes = Vec::with_capacity(N_BLOB_BYTES);
-        let mut bytes_rlc = Value::known(Fr::zero());
-        let mut last_bytes_rlc = None;
-        let mut last_bytes_len = None;
-        for (i, &byte) in blob_bytes.iter().enumerate() {
+        // the first byte is either the codec tag or the first encoded byte.
+        let has_codec_tag = batch_data.has_codec_tag();
+        let first_byte = Value::known(Fr::from(envelope_bytes[0] as u64));
+        let mut bytes_rlc = if has_codec_tag {
+            Value::known(Fr::zero())
+        } else {
+            first_byte
+        };
+        let n_tag_bytes = usize::from(has_codec_tag);
+
+        let assigned_has_codec_tag = region.assign_advice(
+            || "has_codec_tag",
+            self.has_codec_tag,
+            1,
+            || Value::known(Fr::from(has_codec_tag as u64)),
+        )?;
+        let mut assigned_bytes = Vec::with_capacity(n_rows_bytes);
+        // the is_padding cells at the first byte of each blob.
+        let mut blobs_are_padding = Vec::with_capacity(MAX_BLOBS_PER_BATCH);
+        assigned_bytes.push(region.assign_advice(|| "byte", self.byte, 1, || first_byte)?);
+        blobs_are_padding.push(region.assign_advice(
+            || "is_padding",
+            self.is_padding,
+            1,
+            || Value::known(Fr::zero()),
+        )?);
+        let mut last_bytes_rlc =
+            region.assign_advice(|| "bytes_rlc", self.bytes_rlc, 1, || bytes_rlc)?;
+        let mut last_bytes_len = region.assign_advice(
+            || "bytes_len",
+            self.bytes_len,
+            1,
+            || Value::known(Fr::from((1 - n_tag_bytes) as u64)),
+        )?;
+
+        for (i, &byte) in envelope_bytes.iter().enumerate().skip(1) {
             let byte_value = Value::known(Fr::from(byte as u64));
             bytes_rlc = bytes_rlc * challenges.keccak_input() + byte_value;
 
@@ -225,37 +436,41 @@ impl<const N_SNARKS: usize> BlobDataConfig<N_SNARKS> {
                 i + 1,
                 || byte_value,
             )?);
-            region.assign_advice(
+            let is_padding = region.assign_advice(
                 || "is_padding",
                 self.is_padding,
                 i + 1,
                 || Value::known(Fr::zero()),
             )?;
+            if i % N_BLOB_BYTES == 0 {
+                blobs_are_padding.push(is_padding);
+            }
             last_bytes_rlc =
-                Some(region.assign_advice(|| "bytes_rlc", self.bytes_rlc, i + 1, || bytes_rlc)?);
-            last_bytes_len = Some(region.assign_advice(
+                region.assign_advice(|| "bytes_rlc", self.bytes_rlc, i + 1, || bytes_rlc)?;
+            last_bytes_len = region.assign_advice(
                 || "bytes_len",
                 self.bytes_len,
                 i + 1,
-                || Value::known(Fr::from(i as u64 + 1)),
-            )?);
+                || Value::known(Fr::from((i + 1 - n_tag_bytes) as u64)),
+            )?;
         }
 
-        let mut last_bytes_rlc = last_bytes_rlc.expect("at least 1 byte guaranteed");
-        let mut last_bytes_len = last_bytes_len.expect("at least 1 byte guaranteed");
-        for i in blob_bytes.len()..N_BLOB_BYTES {
+        for i in envelope_bytes.len()..n_rows_bytes {
             assigned_bytes.push(region.assign_advice(
                 || "byte",
                 self.byte,
                 i + 1,
                 || Value::known(Fr::zero()),
             )?);
-            region.assign_advice(
+            let is_padding = region.assign_advice(
                 || "is_padding",
                 self.is_padding,
                 i + 1,
                 || Value::known(Fr::one()),
             )?;
+            if i % N_BLOB_BYTES == 0 {
+                blobs_are_padding.push(is_padding);
+            }
             last_bytes_rlc = region.assign_advice(
                 || "bytes_rlc",
                 self.bytes_rlc,
@@ -269,18 +484,26 @@ impl<const N_SNARKS: usize> BlobDataConfig<N_SNARKS> {
                 || last_bytes_len.value().cloned(),
             )?;
         }
-
-        Ok((assigned_bytes, last_bytes_rlc, last_bytes_len))
+        assert_eq!(blobs_are_padding.len(), MAX_BLOBS_PER_BATCH);
+
+        Ok((
+            assigned_has_codec_tag,
+            assigned_bytes,
+            blobs_are_padding,
+            last_bytes_rlc,
+            last_bytes_len,
+        ))
     }
 
     pub fn assign_internal_checks(
         &self,
         region: &mut Region<Fr>,
         rlc_config: &RlcConfig,
-        barycentric_assignments: &[CRTInteger<Fr>],
+        barycentric_assignments: &[AssignedBarycentricEvaluationConfig],
         assigned_bytes: &[AssignedCell<Fr, Fr>],
+        blobs_are_padding: &[AssignedCell<Fr, Fr>],
         bytes_len: &AssignedCell<Fr, Fr>,
-    ) -> Result<AssignedCell<Fr, Fr>, Error> {
+    ) -> Result<(AssignedCell<Fr, Fr>, Vec<AssignedCell<Fr, Fr>>), Error> {
         rlc_config.init(region)?;
         let mut rlc_config_offset = 0;
 
@@ -311,40 +534,66 @@ impl<const N_SNARKS: usize> BlobDataConfig<N_SNARKS> {
         //////////////////////////////////// LINKING ///////////////////////////////////
         ////////////////////////////////////////////////////////////////////////////////
 
-        assert_eq!(barycentric_assignments.len(), BLOB_WIDTH + 1);
-        let blob_crts = barycentric_assignments
+        assert_eq!(barycentric_assignments.len(), MAX_BLOBS_PER_BATCH);
+        for (barycentric, blob_bytes) in barycentric_assignments
             .iter()
-            .take(BLOB_WIDTH)
-            .collect::<Vec<_>>();
-        let mut blob_fields: Vec<Vec<AssignedCell<Fr, Fr>>> = Vec::with_capacity(BLOB_WIDTH);
-        for chunk in assigned_bytes.chunks_exact(N_DATA_BYTES_PER_COEFFICIENT) {
-            // blob bytes are supposed to be deserialised in big-endianness. However, we
-            // have the export from BarycentricConfig in little-endian bytes.
-            blob_fields.push(chunk.iter().rev().cloned().collect());
+            .zip_eq(assigned_bytes.chunks_exact(N_BLOB_BYTES))
+        {
+            assert_eq!(barycentric.barycentric_assignments.len(), BLOB_WIDTH + 1);
+            let blob_crts = barycentric
+                .barycentric_assignments
+                .iter()
+                .take(BLOB_WIDTH)
+                .collect::<Vec<_>>();
+            let mut blob_fields: Vec<Vec<AssignedCell<Fr, Fr>>> = Vec::with_capacity(BLOB_WIDTH);
+            for chunk in blob_bytes.chunks_exact(N_DATA_BYTES_PER_COEFFICIENT) {
+                // blob bytes are supposed to be deserialised in big-endianness. However, we
+                // have the export from BarycentricConfig in little-endian bytes.
+                blob_fields.push(chunk.iter().rev().cloned().collect());
+            }
+
+            for (blob_crt, blob_field) in blob_crts.iter().zip_eq(blob_fields.iter()) {
+                let limb1 = rlc_config.inner_product(
+                    region,
+                    &blob_field[0..11],
+                    &pows_of_256,
+                    &mut rlc_config_offset,
+                )?;
+                let limb2 = rlc_config.inner_product(
+                    region,
+                    &blob_field[11..22],
+                    &pows_of_256,
+                    &mut rlc_config_offset,
+                )?;
+                let limb3 = rlc_config.inner_product(
+                    region,
+                    &blob_field[22..31],
+                    &pows_of_256[0..9],
+                    &mut rlc_config_offset,
+                )?;
+                region.constrain_equal(limb1.cell(), blob_crt.truncation.limbs[0].cell())?;
+                region.constrain_equal(limb2.cell(), blob_crt.truncation.limbs[1].cell())?;
+                region.constrain_equal(limb3.cell(), blob_crt.truncation.limbs[2].cell())?;
+            }
         }
 
-        for (blob_crt, blob_field) in blob_crts.iter().zip_eq(blob_fields.iter()) {
-            let limb1 = rlc_config.inner_product(
-                region,
-                &blob_field[0..11],
-                &pows_of_256,
-                &mut rlc_config_offset,
-            )?;
-            let limb2 = rlc_config.inner_product(
+        // As is_padding only transitions from 0 to 1 once, a blob holds batch data iff its first
+        // byte is not padding, and then so do all the blobs before it.
+        let mut extra_blobs_are_valid = Vec::with_capacity(MAX_BLOBS_PER_BATCH - 1);
+        for blob_is_padding in blobs_are_padding.iter().skip(1) {
+            // copy is_padding into this region.
+            let is_padding = {
+                let mut value = Fr::zero();
+                blob_is_padding.value().map(|v| value = *v);
+                let is_padding = rlc_config.load_private(region, &value, &mut rlc_config_offset)?;
+                region.constrain_equal(is_padding.cell(), blob_is_padding.cell())?;
+                is_padding
+            };
+            extra_blobs_are_valid.push(rlc_config.not(
                 region,
-                &blob_field[11..22],
-                &pows_of_256,
+                &is_padding,
                 &mut rlc_config_offset,
-            )?;
-            let limb3 = rlc_config.inner_product(
-                region,
-                &blob_field[22..31],
-                &pows_of_256[0..9],
-                &mut rlc_config_offset,
-            )?;
-            region.constrain_equal(limb1.cell(), blob_crt.truncation.limbs[0].cell())?;
-            region.constrain_equal(limb2.cell(), blob_crt.truncation.limbs[1].cell())?;
-            region.constrain_equal(limb3.cell(), blob_crt.truncation.limbs[2].cell())?;
+            )?);
         }
 
         // The zstd decoder (DecoderConfig) exports an encoded length that is 1 more than the
@@ -352,6 +601,6 @@ impl<const N_SNARKS: usize> BlobDataConfig<N_SNARKS> {
         // by adding +1 to it before exporting.
         let cooked_bytes_len = rlc_config.add(region, bytes_len, &one, &mut rlc_config_offset)?;
 
-        Ok(cooked_bytes_len)
+        Ok((cooked_bytes_len, extra_blobs_are_valid))
     }
 }
@@ -1,7 +1,9 @@
 use crate::{
-    blob::BatchData, witgen::MultiBlockProcessResult, LOG_DEGREE, PI_CHAIN_ID,
+    blob::{BatchData, MAX_BLOBS_PER_BATCH},
+    witgen::MultiBlockProcessResult,
+    CodecTag, LOG_DEGREE, MAX_CHUNK_VKS, PI_CHAIN_ID, PI_CHUNK_VK_DIGESTS, PI_CHUNK_VK_INDICES,
     PI_CURRENT_BATCH_HASH, PI_CURRENT_STATE_ROOT, PI_CURRENT_WITHDRAW_ROOT, PI_PARENT_BATCH_HASH,
-    PI_PARENT_STATE_ROOT,
+    PI_PARENT_STATE_ROOT, PI_POST_L1_MSG_HASH, PI_PREV_L1_MSG_HASH,
 };
 use ark_std::{end_timer, start_timer};
 use halo2_base::{Context, ContextParams};
@@ -11,7 +13,10 @@ use halo2_ecc::{ecc::EccChip, fields::fp::FpConfig};
 
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
-    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    halo2curves::{
+        bn256::{Bn256, Fr, G1Affine},
+        group::ff::PrimeField,
+    },
     plonk::{Circuit, ConstraintSystem, Error, Selector},
     poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
 };
@@ -21,26 +26,28 @@ use rand::Rng;
 use std::rc::Rc;
 use std::{env, fs::File};
 
+#[cfg(not(feature = "disable_proof_aggregation"))]
+use snark_verifier::loader::halo2::halo2_ecc::halo2_base;
 #[cfg(not(feature = "disable_proof_aggregation"))]
 use snark_verifier::loader::halo2::{halo2_ecc::halo2_base::AssignedValue, Halo2Loader};
 use snark_verifier::pcs::kzg::KzgSuccinctVerifyingKey;
 #[cfg(not(feature = "disable_proof_aggregation"))]
-use snark_verifier::{
-    loader::halo2::halo2_ecc::halo2_base,
-    pcs::kzg::{Bdfg21, Kzg},
-};
-#[cfg(not(feature = "disable_proof_aggregation"))]
-use snark_verifier_sdk::{aggregate, flatten_accumulator};
+use snark_verifier_sdk::flatten_accumulator;
 use snark_verifier_sdk::{CircuitExt, Snark, SnarkWitness};
 use zkevm_circuits::util::Challenges;
 
+#[cfg(not(feature = "disable_proof_aggregation"))]
+use crate::core::aggregate_with_chunk_vks;
 use crate::{
     aggregation::witgen::process,
     batch::BatchHash,
     constants::{ACC_LEN, DIGEST_LEN},
-    core::{assign_batch_hashes, extract_proof_and_instances_with_pairing_check},
+    core::{
+        assign_batch_hashes, extract_proof_and_instances_with_pairing_check, pack_chunk_vk_indices,
+    },
+    recursion::preprocessed_digest,
     util::parse_hash_digest_cells,
-    AssignedBarycentricEvaluationConfig, ConfigParams,
+    ConfigParams,
 };
 
 use super::BatchCircuitConfig;
@@ -60,9 +67,15 @@ pub struct BatchCircuit<const N_SNARKS: usize> {
     // - current_batch_hash (2 elements)
     // - chain id (1 element)
     // - current_withdraw_root (2 elements)
+    // - chunk vk indices (1 element)
+    // - chunk vk digests (MAX_CHUNK_VKS elements)
     pub flattened_instances: Vec<Fr>,
     // accumulation scheme proof, private input
     pub as_proof: Value<Vec<u8>>,
+    // digests of the whitelisted chunk vks, padded with zeros to MAX_CHUNK_VKS
+    pub chunk_vk_digests: [Fr; MAX_CHUNK_VKS],
+    // index in chunk_vk_digests of the vk of each snark
+    pub chunk_vk_indices: Vec<usize>,
     // batch hash circuit for which the snarks are generated
     // the chunks in this batch are also padded already
     pub batch_hash: BatchHash<N_SNARKS>,
@@ -74,9 +87,50 @@ impl<const N_SNARKS: usize> BatchCircuit<N_SNARKS> {
         snarks_with_padding: &[Snark],
         rng: impl Rng + Send,
         batch_hash: BatchHash<N_SNARKS>,
+        chunk_vk_digests: &[Fr],
     ) -> Result<Self, snark_verifier::Error> {
         let timer = start_timer!(|| "generate aggregation circuit");
 
+        // The snarks are verified against the whitelisted chunk vks, the index of each snark's vk
+        // being public. The vks can only differ in their preprocessed part, i.e. be of circuits
+        // of the same shape.
+        if chunk_vk_digests.is_empty() || chunk_vk_digests.len() > MAX_CHUNK_VKS {
+            return Err(snark_verifier::Error::AssertionFailure(format!(
+                "expected 1 to {MAX_CHUNK_VKS} chunk vks, got {}",
+                chunk_vk_digests.len()
+            )));
+        }
+        // the indices are packed into a single field element
+        assert!(N_SNARKS * MAX_CHUNK_VKS.ilog2() as usize < Fr::NUM_BITS as usize);
+        let chunk_vk_indices = snarks_with_padding
+            .iter()
+            .enumerate()
+            .map(|(i, snark)| {
+                let digest = preprocessed_digest(&snark.protocol);
+                chunk_vk_digests
+                    .iter()
+                    .position(|d| *d == digest)
+                    .ok_or_else(|| {
+                        snark_verifier::Error::AssertionFailure(format!(
+                            "{i}-th snark is not verified by a whitelisted chunk vk"
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let protocol_shape = |snark: &Snark| {
+            let mut protocol = snark.protocol.clone();
+            protocol.preprocessed.clear();
+            protocol.transcript_initial_state = None;
+            serde_json::to_string(&protocol).expect("protocol is serializable")
+        };
+        if !snarks_with_padding.iter().map(protocol_shape).all_equal() {
+            return Err(snark_verifier::Error::AssertionFailure(
+                "snarks are of circuits of different shapes".to_string(),
+            ));
+        }
+        let mut padded_chunk_vk_digests = [Fr::zero(); MAX_CHUNK_VKS];
+        padded_chunk_vk_digests[..chunk_vk_digests.len()].copy_from_slice(chunk_vk_digests);
+
         // sanity check: snarks's public input matches chunk_hashes
         for (chunk, snark) in batch_hash
             .chunks_with_padding
@@ -100,6 +154,26 @@ impl<const N_SNARKS: usize> BatchCircuit<N_SNARKS> {
             }
         }
 
+        // The batch hash preimage, and hence the public inputs, are laid out as per the v3 batch
+        // header.
+        if !batch_hash
+            .batch_header
+            .header_codec()
+            .is_ok_and(|codec| codec.is_supported_by_batch_circuit())
+        {
+            return Err(snark_verifier::Error::AssertionFailure(format!(
+                "batch header version {} is not supported by the batch circuit",
+                batch_hash.batch_header.version
+            )));
+        }
+        // The batch hash preimage, the BatchDataConfig and the BlobDataConfig are laid out for the
+        // data of a batch in up to MAX_BLOBS_PER_BATCH blobs.
+        if batch_hash.num_blobs() > MAX_BLOBS_PER_BATCH {
+            return Err(snark_verifier::Error::AssertionFailure(format!(
+                "batch data spans more than {MAX_BLOBS_PER_BATCH} blobs"
+            )));
+        }
+
         // extract the accumulators and proofs
         let svk = params.get_g()[0].into();
 
@@ -116,11 +190,18 @@ impl<const N_SNARKS: usize> BatchCircuit<N_SNARKS> {
         // - current_batch_hash (2 elements)
         // - chain id (1 element)
         // - current_withdraw_root (2 elements)
+        // - chunk vk indices (1 element)
+        // - chunk vk digests (MAX_CHUNK_VKS elements)
+        // - prev_l1_msg_hash (2 elements)
+        // - post_l1_msg_hash (2 elements)
         let flattened_instances: Vec<Fr> = [
             acc_instances.as_slice(),
             batch_hash.instances_exclude_acc::<Fr>()[0]
                 .clone()
                 .as_slice(),
+            &[pack_chunk_vk_indices(&chunk_vk_indices)],
+            padded_chunk_vk_digests.as_slice(),
+            batch_hash.l1_msg_hash_instances::<Fr>().as_slice(),
         ]
         .concat();
 
@@ -131,6 +212,8 @@ impl<const N_SNARKS: usize> BatchCircuit<N_SNARKS> {
             flattened_instances,
             as_proof: Value::known(as_proof),
             batch_hash,
+            chunk_vk_digests: padded_chunk_vk_digests,
+            chunk_vk_indices,
         })
     }
 
@@ -195,7 +278,7 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
                 |region| {
                     if first_pass {
                         first_pass = false;
-                        return Ok(AssignedBarycentricEvaluationConfig::default());
+                        return Ok(vec![]);
                     }
 
                     let mut ctx = Context::new(
@@ -207,14 +290,20 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
                         },
                     );
 
-                    let barycentric = config.barycentric.assign(
-                        &mut ctx,
-                        &self.batch_hash.point_evaluation_assignments.coefficients,
-                        self.batch_hash
-                            .point_evaluation_assignments
-                            .challenge_digest,
-                        self.batch_hash.point_evaluation_assignments.evaluation,
-                    );
+                    // one evaluation for each of the MAX_BLOBS_PER_BATCH blobs.
+                    let barycentric = self
+                        .batch_hash
+                        .point_evaluation_assignments()
+                        .iter()
+                        .map(|point_evaluation_assignments| {
+                            config.barycentric.assign(
+                                &mut ctx,
+                                &point_evaluation_assignments.coefficients,
+                                point_evaluation_assignments.challenge_digest,
+                                point_evaluation_assignments.evaluation,
+                            )
+                        })
+                        .collect::<Vec<_>>();
 
                     config.barycentric.scalar.range.finalize(&mut ctx);
                     ctx.print_stats(&["barycentric evaluation"]);
@@ -224,6 +313,12 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
             )?
         };
 
+        // packed indices of the chunk vks that the snarks are verified against
+        #[cfg(not(feature = "disable_proof_aggregation"))]
+        let mut chunk_vk_indices = None;
+        // whitelisted chunk vk digests, exposed as public inputs
+        #[cfg(not(feature = "disable_proof_aggregation"))]
+        let mut chunk_vk_digests = vec![];
         #[cfg(not(feature = "disable_proof_aggregation"))]
         let (accumulator_instances, snark_inputs, barycentric) = {
             use halo2_proofs::halo2curves::bn256::Fq;
@@ -234,11 +329,7 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
                 |region| {
                     if first_pass {
                         first_pass = false;
-                        return Ok((
-                            vec![],
-                            vec![],
-                            AssignedBarycentricEvaluationConfig::default(),
-                        ));
+                        return Ok((vec![], vec![], vec![]));
                     }
 
                     // stores accumulators for all snarks, including the padded ones
@@ -264,14 +355,26 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
                     // - instances which are the public inputs of each chunk (prefixed with 12
                     //   instances from previous accumulators)
                     // - new accumulator
+                    // - packed indices of the chunk vks
+                    // - digests of the whitelisted chunk vks
                     //
                     log::debug!("aggregation: chunk aggregation");
-                    let (assigned_aggregation_instances, acc) = aggregate::<Kzg<Bn256, Bdfg21>>(
+                    let (
+                        assigned_aggregation_instances,
+                        acc,
+                        assigned_chunk_vk_indices,
+                        assigned_chunk_vk_digests,
+                    ) = aggregate_with_chunk_vks(
                         &self.svk,
                         &loader,
+                        config.flex_gate(),
                         &self.snarks_with_padding,
+                        &self.chunk_vk_digests,
+                        &self.chunk_vk_indices,
                         self.as_proof(),
                     );
+                    chunk_vk_indices = Some(assigned_chunk_vk_indices);
+                    chunk_vk_digests = assigned_chunk_vk_digests;
                     for (i, e) in assigned_aggregation_instances[0].iter().enumerate() {
                         log::trace!("{}-th instance: {:?}", i, e.value)
                     }
@@ -295,14 +398,20 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
 
                     let mut ctx = Rc::into_inner(loader).unwrap().into_ctx();
                     log::debug!("batching: assigning barycentric");
-                    let barycentric = config.barycentric.assign(
-                        &mut ctx,
-                        &self.batch_hash.point_evaluation_assignments.coefficients,
-                        self.batch_hash
-                            .point_evaluation_assignments
-                            .challenge_digest,
-                        self.batch_hash.point_evaluation_assignments.evaluation,
-                    );
+                    // one evaluation for each of the MAX_BLOBS_PER_BATCH blobs.
+                    let barycentric = self
+                        .batch_hash
+                        .point_evaluation_assignments()
+                        .iter()
+                        .map(|point_evaluation_assignments| {
+                            config.barycentric.assign(
+                                &mut ctx,
+                                &point_evaluation_assignments.coefficients,
+                                point_evaluation_assignments.challenge_digest,
+                                point_evaluation_assignments.evaluation,
+                            )
+                        })
+                        .collect::<Vec<_>>();
 
                     ctx.print_stats(&["barycentric"]);
 
@@ -337,11 +446,11 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
             // - batch_data_hash_preimage
             // - preimage for blob metadata
             // - preimage of chunk data digest (only for valid chunks)
-            // - preimage of challenge digest
+            // - preimage of challenge digest for each of MAX_BLOBS_PER_BATCH blobs
             let preimages = self.batch_hash.extract_hash_preimages();
             assert_eq!(
                 preimages.len(),
-                4 + N_SNARKS + self.batch_hash.number_of_valid_chunks,
+                3 + N_SNARKS + self.batch_hash.number_of_valid_chunks + MAX_BLOBS_PER_BATCH,
                 "error extracting preimages"
             );
             end_timer!(timer);
@@ -360,7 +469,9 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
                 challenges,
                 &chunks_are_valid,
                 self.batch_hash.number_of_valid_chunks,
+                self.batch_hash.num_blobs(),
                 &preimages,
+                &self.batch_hash.padded_batch_hash_preimage(),
             )
             .map_err(|e| {
                 log::error!("assign_batch_hashes err {:#?}", e);
@@ -428,6 +539,20 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
             for (i, v) in accumulator_instances.iter().enumerate() {
                 layouter.constrain_instance(v.cell(), config.instance, i)?;
             }
+            let chunk_vk_indices = chunk_vk_indices.expect("assigned in the second pass");
+            layouter.constrain_instance(
+                chunk_vk_indices.cell(),
+                config.instance,
+                PI_CHUNK_VK_INDICES,
+            )?;
+            assert!(chunk_vk_digests.len() == MAX_CHUNK_VKS);
+            for (i, digest) in chunk_vk_digests.iter().enumerate() {
+                layouter.constrain_instance(
+                    digest.cell(),
+                    config.instance,
+                    PI_CHUNK_VK_DIGESTS + i,
+                )?;
+            }
         }
 
         // ========================================================================
@@ -446,7 +571,15 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
             PI_CURRENT_WITHDRAW_ROOT,
             PI_CURRENT_WITHDRAW_ROOT + 1,
             PI_CHAIN_ID,
+            PI_PREV_L1_MSG_HASH,
+            PI_PREV_L1_MSG_HASH + 1,
+            PI_POST_L1_MSG_HASH,
+            PI_POST_L1_MSG_HASH + 1,
         ];
+        assert_eq!(
+            hash_derived_public_input_cells.len(),
+            instance_offsets.len()
+        );
         for (c, inst_offset) in hash_derived_public_input_cells
             .into_iter()
             .zip(instance_offsets.into_iter())
@@ -456,10 +589,6 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
 
         // blob data config
         {
-            let barycentric_assignments = &barycentric.barycentric_assignments;
-            let challenge_le = &barycentric.z_le;
-            let evaluation_le = &barycentric.y_le;
-
             let batch_data = BatchData::from(&self.batch_hash);
 
             let blob_data_exports = config.blob_data_config.assign(
@@ -467,7 +596,7 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
                 challenges,
                 &config.rlc_config,
                 &batch_data,
-                barycentric_assignments,
+                &barycentric,
             )?;
 
             let batch_data_exports = config.batch_data_config.assign(
@@ -476,11 +605,18 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
                 &config.rlc_config,
                 &assigned_batch_hash.chunks_are_padding,
                 &batch_data,
-                barycentric_assignments,
+                &barycentric,
             )?;
 
-            let batch_bytes = batch_data.get_batch_data_bytes();
-            let encoded_batch_bytes = batch_data.get_encoded_batch_data_bytes();
+            // The decoder is only linked to the blob and the batch data for a zstd payload. For
+            // any other codec, it decodes the zstd encoding of a default batch, independent of the
+            // batch being proven.
+            let decoder_batch_data = match batch_data.codec {
+                CodecTag::Zstd => batch_data.clone(),
+                CodecTag::Uncompressed => BatchData::<N_SNARKS>::default(),
+            };
+            let batch_bytes = decoder_batch_data.get_batch_data_bytes();
+            let encoded_batch_bytes = decoder_batch_data.get_encoded_batch_data_bytes();
 
             let MultiBlockProcessResult {
                 witness_rows,
@@ -543,55 +679,68 @@ impl<const N_SNARKS: usize> Circuit<Fr> for BatchCircuit<N_SNARKS> {
                         }
                     }
 
-                    for (c, ec) in evaluation_le
-                        .iter()
-                        .zip_eq(assigned_batch_hash.blob.y.iter().rev())
-                    {
-                        log::trace!("blob y: {:?} {:?}", c.value(), ec.value());
-                        region.constrain_equal(c.cell(), ec.cell())?;
+                    // The commitments to all blobs are linked, including the empty blobs past
+                    // the batch data, whose commitments pad the batch hash preimage but are not
+                    // part of the batch hash.
+                    for (blob_idx, barycentric) in barycentric.iter().enumerate() {
+                        for (c, ec) in barycentric
+                            .y_le
+                            .iter()
+                            .zip_eq(assigned_batch_hash.blob.y[blob_idx].iter().rev())
+                        {
+                            log::trace!("blob[{blob_idx}] y: {:?} {:?}", c.value(), ec.value());
+                            region.constrain_equal(c.cell(), ec.cell())?;
+                        }
+
+                        for (c, ec) in barycentric
+                            .z_le
+                            .iter()
+                            .zip_eq(assigned_batch_hash.blob.z[blob_idx].iter().rev())
+                        {
+                            log::trace!("blob[{blob_idx}] z: {:?} {:?}", c.value(), ec.value());
+                            region.constrain_equal(c.cell(), ec.cell())?;
+                        }
                     }
 
-                    for (c, ec) in challenge_le
+                    for (blob_idx, (versioned_hash, expected_versioned_hash)) in batch_data_exports
+                        .versioned_hashes
                         .iter()
-                        .zip_eq(assigned_batch_hash.blob.z.iter().rev())
+                        .zip_eq(assigned_batch_hash.blob.versioned_hash.iter())
+                        .enumerate()
                     {
-                        log::trace!("blob z: {:?} {:?}", c.value(), ec.value());
-                        region.constrain_equal(c.cell(), ec.cell())?;
+                        for (c, ec) in versioned_hash.iter().zip_eq(expected_versioned_hash.iter())
+                        {
+                            log::trace!(
+                                "blob[{blob_idx}] version hash: {:?} {:?}",
+                                c.value(),
+                                ec.value()
+                            );
+                            region.constrain_equal(c.cell(), ec.cell())?;
+                        }
                     }
 
-                    for (c, ec) in batch_data_exports
-                        .versioned_hash
+                    // the number of blobs committed in the batch hash is the number of blobs
+                    // holding batch data.
+                    for (c, ec) in blob_data_exports
+                        .extra_blobs_are_valid
                         .iter()
-                        .zip_eq(assigned_batch_hash.blob.versioned_hash.iter())
+                        .zip_eq(assigned_batch_hash.blob.extra_blobs_are_valid.iter())
                     {
-                        log::trace!("blob version hash: {:?} {:?}", c.value(), ec.value());
                         region.constrain_equal(c.cell(), ec.cell())?;
                     }
 
-                    // equate rlc (from blob data) with decoder's encoded_rlc
-                    region.constrain_equal(
-                        blob_data_exports.bytes_rlc.cell(),
-                        decoder_exports.encoded_rlc.cell(),
-                    )?;
-                    // equate len(blob_bytes) with decoder's encoded_len
-                    region.constrain_equal(
-                        blob_data_exports.bytes_len.cell(),
-                        decoder_exports.encoded_len.cell(),
-                    )?;
-                    // equate rlc (from batch data) with decoder's decoded_rlc
-                    region.constrain_equal(
-                        batch_data_exports.bytes_rlc.cell(),
-                        decoder_exports.decoded_rlc.cell(),
-                    )?;
-                    // equate len(batch_data) with decoder's decoded_len
-                    region.constrain_equal(
-                        batch_data_exports.batch_data_len.cell(),
-                        decoder_exports.decoded_len.cell(),
-                    )?;
-
                     Ok(())
                 },
             )?;
+
+            config.blob_data_config.assign_codec_checks(
+                &mut layouter,
+                &config.rlc_config,
+                &assigned_batch_hash.batch_version,
+                &blob_data_exports,
+                &batch_data_exports,
+                &decoder_exports,
+            )?;
         }
 
         end_timer!(witness_time);
@@ -609,7 +758,11 @@ impl<const N_SNARKS: usize> CircuitExt<Fr> for BatchCircuit<N_SNARKS> {
         // - current_batch_hash (2 elements)
         // - chain id (1 element)
         // - current_withdraw_root (2 elements)
-        vec![ACC_LEN + 11]
+        // - chunk vk indices (1 element)
+        // - chunk vk digests (MAX_CHUNK_VKS elements)
+        // - prev_l1_msg_hash (2 elements)
+        // - post_l1_msg_hash (2 elements)
+        vec![ACC_LEN + 16 + MAX_CHUNK_VKS]
     }
 
     fn instances(&self) -> Vec<Vec<Fr>> {
@@ -11,7 +11,7 @@ use snark_verifier::{
     util::arithmetic::modulus,
 };
 use zkevm_circuits::{
-    keccak_circuit::{KeccakCircuitConfig, KeccakCircuitConfigArgs},
+    keccak_circuit::{KeccakCircuitConfig, KeccakCircuitConfigArgs, DEFAULT_KECCAK_ROWS},
     table::{BitwiseOpTable, KeccakTable, Pow2Table, PowOfRandTable, RangeTable, U8Table},
     util::{Challenges, SubCircuitConfig},
 };
@@ -73,6 +73,8 @@ impl<const N_SNARKS: usize> BatchCircuitConfig<N_SNARKS> {
             let keccak_circuit_config_args = KeccakCircuitConfigArgs {
                 keccak_table: keccak_table.clone(),
                 challenges: challenges_exprs,
+                // with k = 21 this fits 2**21 / (12 * 25) * 136 = 0.95M bytes
+                num_rows_per_round: DEFAULT_KECCAK_ROWS,
             };
 
             (
@@ -130,6 +130,15 @@ impl RlcConfig {
         }
     }
 
+    #[inline]
+    pub(crate) fn thirty_two_cell(&self, region_index: RegionIndex) -> Cell {
+        Cell {
+            region_index,
+            row_offset: FIXED_OFFSET_32,
+            column: self.fixed.into(),
+        }
+    }
+
     #[inline]
     pub(crate) fn fixed_up_to_max_agg_snarks_cell(
         &self,
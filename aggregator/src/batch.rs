@@ -5,15 +5,31 @@ use eth_types::{ToBigEndian, H256};
 use ethers_core::utils::keccak256;
 use gadgets::{util::split_h256, Field};
 use serde::{Deserialize, Serialize};
+use std::{io::Result, iter::once};
 
 use crate::{
-    blob::{BatchData, PointEvaluationAssignments},
+    blob::{BatchData, PointEvaluationAssignments, MAX_BLOBS_PER_BATCH},
     chunk::ChunkInfo,
+    codec::CodecTag,
 };
 
+/// Encodings of the batch header by version.
+mod header_codec;
+
+pub use header_codec::BatchHeaderCodec;
+
+/// The commitment to a blob of a batch, other than the first one.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobCommitment {
+    /// The versioned hash of the blob
+    pub versioned_hash: H256,
+    /// The blob data proof: z (32), y (32)
+    pub blob_data_proof: [H256; 2],
+}
+
 /// Batch header provides additional fields from the context (within recursion)
 /// for constructing the preimage of the batch hash.
-#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct BatchHeader<const N_SNARKS: usize> {
     /// the batch version
     pub version: u8,
@@ -29,10 +45,62 @@ pub struct BatchHeader<const N_SNARKS: usize> {
     pub last_block_timestamp: u64,
     /// The data hash of the batch
     pub data_hash: H256,
-    /// The versioned hash of the blob with this batch's data
+    /// The versioned hash of the (first) blob with this batch's data
     pub blob_versioned_hash: H256,
-    /// The blob data proof: z (32), y (32)
+    /// The blob data proof of the (first) blob: z (32), y (32)
     pub blob_data_proof: [H256; 2],
+    /// The commitments to the remaining blobs, if the batch's data spans multiple blobs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_blobs: Vec<BlobCommitment>,
+    /// The bitmap of L1 messages skipped in the batch, only present in headers before version 3
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_l1_message_bitmap: Vec<u8>,
+    /// The codec of the batch data in the blob. It is not part of the batch hash preimage, but
+    /// committed to by the blob versioned hash. Batches before version 5 only support zstd.
+    #[serde(default)]
+    pub codec: CodecTag,
+    /// The rolling hash of the L1 messages popped before the batch. It is not part of the batch
+    /// hash preimage, but a public input of the batch circuit.
+    #[serde(default)]
+    pub prev_l1_msg_hash: H256,
+    /// The rolling hash of the L1 messages popped up to the end of the batch. It is not part of
+    /// the batch hash preimage, but a public input of the batch circuit.
+    #[serde(default)]
+    pub post_l1_msg_hash: H256,
+}
+
+impl BlobCommitment {
+    /// Compute the commitment to the blob at blob_idx of the batch data.
+    pub(crate) fn new<const N_SNARKS: usize>(
+        batch_data: &BatchData<N_SNARKS>,
+        blob_idx: usize,
+    ) -> Self {
+        Self::from_point_evaluation(
+            batch_data.get_versioned_hash(blob_idx),
+            &PointEvaluationAssignments::new(batch_data, blob_idx),
+        )
+    }
+
+    fn from_point_evaluation(
+        versioned_hash: H256,
+        point_evaluation_assignments: &PointEvaluationAssignments,
+    ) -> Self {
+        Self {
+            versioned_hash,
+            blob_data_proof: [
+                H256::from_slice(&point_evaluation_assignments.challenge.to_be_bytes()),
+                H256::from_slice(&point_evaluation_assignments.evaluation.to_be_bytes()),
+            ],
+        }
+    }
+
+    /// The commitment as it is encoded in the batch header, i.e. versioned_hash || z || y.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        once(self.versioned_hash)
+            .chain(self.blob_data_proof)
+            .flat_map(|h| h.to_fixed_bytes())
+            .collect()
+    }
 }
 
 impl<const N_SNARKS: usize> BatchHeader<N_SNARKS> {
@@ -45,6 +113,7 @@ impl<const N_SNARKS: usize> BatchHeader<N_SNARKS> {
         parent_batch_hash: H256,
         last_block_timestamp: u64,
         chunks: &[ChunkInfo],
+        codec: CodecTag,
     ) -> Self {
         assert_ne!(chunks.len(), 0);
         assert!(chunks.len() <= N_SNARKS);
@@ -75,8 +144,12 @@ impl<const N_SNARKS: usize> BatchHeader<N_SNARKS> {
             .collect::<Vec<_>>();
         let batch_data_hash = keccak256(batch_data_hash_preimage);
 
-        let batch_data = BatchData::<N_SNARKS>::new(number_of_valid_chunks, &chunks_with_padding);
+        let batch_data = BatchData::<N_SNARKS>::new(number_of_valid_chunks, &chunks_with_padding)
+            .with_codec(version, codec);
         let point_evaluation_assignments = PointEvaluationAssignments::from(&batch_data);
+        let extra_blobs = (1..batch_data.num_blobs())
+            .map(|blob_idx| BlobCommitment::new(&batch_data, blob_idx))
+            .collect();
 
         Self {
             version,
@@ -86,43 +159,40 @@ impl<const N_SNARKS: usize> BatchHeader<N_SNARKS> {
             parent_batch_hash,
             last_block_timestamp,
             data_hash: batch_data_hash.into(),
-            blob_versioned_hash: batch_data.get_versioned_hash(),
+            blob_versioned_hash: batch_data.get_versioned_hash(0),
             blob_data_proof: [
                 H256::from_slice(&point_evaluation_assignments.challenge.to_be_bytes()),
                 H256::from_slice(&point_evaluation_assignments.evaluation.to_be_bytes()),
             ],
+            extra_blobs,
+            codec,
+            prev_l1_msg_hash: chunks_with_padding[0].prev_l1_msg_hash,
+            post_l1_msg_hash: chunks_with_padding[N_SNARKS - 1].post_l1_msg_hash,
         }
     }
 
-    /// Returns the batch hash as per BatchHeaderV3.
-    pub fn batch_hash(&self) -> H256 {
-        // the current batch hash is build as
-        // keccak256(
-        //     version ||
-        //     batch_index ||
-        //     l1_message_popped ||
-        //     total_l1_message_popped ||
-        //     batch_data_hash ||
-        //     versioned_hash ||
-        //     parent_batch_hash ||
-        //     last_block_timestamp ||
-        //     z ||
-        //     y
-        // )
-        let batch_hash_preimage = [
-            vec![self.version].as_slice(),
-            self.batch_index.to_be_bytes().as_ref(),
-            self.l1_message_popped.to_be_bytes().as_ref(),
-            self.total_l1_message_popped.to_be_bytes().as_ref(),
-            self.data_hash.as_bytes(),
-            self.blob_versioned_hash.as_bytes(),
-            self.parent_batch_hash.as_bytes(),
-            self.last_block_timestamp.to_be_bytes().as_ref(),
-            self.blob_data_proof[0].to_fixed_bytes().as_ref(),
-            self.blob_data_proof[1].to_fixed_bytes().as_ref(),
-        ]
-        .concat();
-        keccak256(batch_hash_preimage).into()
+    /// The codec of this header, as per its version.
+    pub fn header_codec(&self) -> Result<BatchHeaderCodec> {
+        BatchHeaderCodec::for_version(self.version)
+    }
+
+    /// Encode the header as per its version, i.e. the preimage of the batch hash.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        Ok(self.header_codec()?.encode(self))
+    }
+
+    /// Decode a header as per its version byte.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let version = bytes.first().copied().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "empty batch header")
+        })?;
+        BatchHeaderCodec::for_version(version)?.decode(bytes)
+    }
+
+    /// Returns the batch hash, i.e. the keccak256 digest of the header encoded as per its version
+    /// (see [`BatchHeaderCodec`]). Fails for unsupported versions.
+    pub fn batch_hash(&self) -> Result<H256> {
+        Ok(keccak256(self.encode()?).into())
     }
 }
 
@@ -157,10 +227,11 @@ pub struct BatchHash<const N_SNARKS: usize> {
     pub(crate) current_batch_hash: H256,
     /// The number of chunks that contain meaningful data, i.e. not padded chunks.
     pub(crate) number_of_valid_chunks: usize,
-    /// 4844 point evaluation check related assignments.
-    pub(crate) point_evaluation_assignments: PointEvaluationAssignments,
-    /// The 4844 versioned hash for the blob.
-    pub(crate) versioned_hash: H256,
+    /// 4844 point evaluation check related assignments, for each of the MAX_BLOBS_PER_BATCH
+    /// blobs. The blobs past the batch data are empty.
+    pub(crate) point_evaluation_assignments: Vec<PointEvaluationAssignments>,
+    /// The 4844 versioned hash for each of the MAX_BLOBS_PER_BATCH blobs.
+    pub(crate) versioned_hashes: Vec<H256>,
     /// The context batch header
     pub(crate) batch_header: BatchHeader<N_SNARKS>,
 }
@@ -199,6 +270,12 @@ impl<const N_SNARKS: usize> BatchHash<N_SNARKS> {
             N_SNARKS,
             "input chunk slice does not match N_SNARKS"
         );
+        // the batch hash preimage is encoded as per the header's version.
+        assert!(
+            batch_header.header_codec().is_ok(),
+            "unsupported batch header version {}",
+            batch_header.version
+        );
 
         let number_of_valid_chunks = match chunks_with_padding
             .iter()
@@ -255,6 +332,10 @@ impl<const N_SNARKS: usize> BatchHash<N_SNARKS> {
                     chunks_with_padding[i].post_state_root,
                     chunks_with_padding[i + 1].prev_state_root,
                 );
+                assert_eq!(
+                    chunks_with_padding[i].post_l1_msg_hash,
+                    chunks_with_padding[i + 1].prev_l1_msg_hash,
+                );
             }
         }
 
@@ -274,36 +355,66 @@ impl<const N_SNARKS: usize> BatchHash<N_SNARKS> {
             "Expect provided BatchHeader's data_hash field to be correct"
         );
 
-        let batch_data = BatchData::<N_SNARKS>::new(number_of_valid_chunks, chunks_with_padding);
-        let point_evaluation_assignments = PointEvaluationAssignments::from(&batch_data);
+        let batch_data = BatchData::<N_SNARKS>::new(number_of_valid_chunks, chunks_with_padding)
+            .with_codec(batch_header.version, batch_header.codec);
+        let point_evaluation_assignments = (0..MAX_BLOBS_PER_BATCH)
+            .map(|blob_idx| PointEvaluationAssignments::new(&batch_data, blob_idx))
+            .collect::<Vec<_>>();
+        let versioned_hashes = (0..MAX_BLOBS_PER_BATCH)
+            .map(|blob_idx| batch_data.get_versioned_hash(blob_idx))
+            .collect::<Vec<_>>();
 
         assert_eq!(
             batch_header.blob_data_proof[0],
-            H256::from_slice(&point_evaluation_assignments.challenge.to_be_bytes()),
+            H256::from_slice(&point_evaluation_assignments[0].challenge.to_be_bytes()),
             "Expect provided BatchHeader's blob_data_proof field 0 to be correct"
         );
         assert_eq!(
             batch_header.blob_data_proof[1],
-            H256::from_slice(&point_evaluation_assignments.evaluation.to_be_bytes()),
+            H256::from_slice(&point_evaluation_assignments[0].evaluation.to_be_bytes()),
             "Expect provided BatchHeader's blob_data_proof field 1 to be correct"
         );
 
-        let versioned_hash = batch_data.get_versioned_hash();
-
         assert_eq!(
-            batch_header.blob_versioned_hash, versioned_hash,
+            batch_header.blob_versioned_hash, versioned_hashes[0],
             "Expect provided BatchHeader's blob_versioned_hash field to be correct"
         );
 
-        let current_batch_hash = batch_header.batch_hash();
+        let extra_blobs = (1..batch_data.num_blobs())
+            .map(|blob_idx| {
+                BlobCommitment::from_point_evaluation(
+                    versioned_hashes[blob_idx],
+                    &point_evaluation_assignments[blob_idx],
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            batch_header.extra_blobs, extra_blobs,
+            "Expect provided BatchHeader's extra_blobs field to be correct"
+        );
+
+        assert_eq!(
+            batch_header.prev_l1_msg_hash, chunks_with_padding[0].prev_l1_msg_hash,
+            "Expect provided BatchHeader's prev_l1_msg_hash field to be correct"
+        );
+        assert_eq!(
+            batch_header.post_l1_msg_hash,
+            chunks_with_padding[N_SNARKS - 1].post_l1_msg_hash,
+            "Expect provided BatchHeader's post_l1_msg_hash field to be correct"
+        );
+
+        let current_batch_hash = batch_header
+            .batch_hash()
+            .expect("batch header version checked above");
 
         log::info!(
-            "batch hash {:?}, datahash {}, z {}, y {}, versioned hash {:x}",
+            "batch hash {:?}, datahash {}, z {}, y {}, versioned hash {:x}, blobs {}",
             current_batch_hash,
             hex::encode(batch_data_hash),
-            hex::encode(point_evaluation_assignments.challenge.to_be_bytes()),
-            hex::encode(point_evaluation_assignments.evaluation.to_be_bytes()),
-            versioned_hash,
+            hex::encode(point_evaluation_assignments[0].challenge.to_be_bytes()),
+            hex::encode(point_evaluation_assignments[0].evaluation.to_be_bytes()),
+            versioned_hashes[0],
+            batch_data.num_blobs(),
         );
 
         Self {
@@ -316,14 +427,39 @@ impl<const N_SNARKS: usize> BatchHash<N_SNARKS> {
             current_batch_hash,
             number_of_valid_chunks,
             point_evaluation_assignments,
-            versioned_hash,
+            versioned_hashes,
             batch_header,
         }
     }
 
-    /// Return the blob polynomial and its evaluation at challenge
-    pub fn point_evaluation_assignments(&self) -> PointEvaluationAssignments {
-        self.point_evaluation_assignments.clone()
+    /// Return the blob polynomial and its evaluation at challenge, for each of the
+    /// MAX_BLOBS_PER_BATCH blobs.
+    pub fn point_evaluation_assignments(&self) -> &[PointEvaluationAssignments] {
+        &self.point_evaluation_assignments
+    }
+
+    /// The number of blobs that the batch data spans.
+    pub(crate) fn num_blobs(&self) -> usize {
+        1 + self.batch_header.extra_blobs.len()
+    }
+
+    /// The batch hash preimage as laid out in the batch circuit, i.e. followed by the commitments
+    /// to the empty blobs past the batch data, up to MAX_BLOBS_PER_BATCH blobs.
+    pub(crate) fn padded_batch_hash_preimage(&self) -> Vec<u8> {
+        let mut preimage = self
+            .batch_header
+            .encode()
+            .expect("batch header version checked at construction");
+        for blob_idx in self.num_blobs()..MAX_BLOBS_PER_BATCH {
+            preimage.extend(
+                BlobCommitment::from_point_evaluation(
+                    self.versioned_hashes[blob_idx],
+                    &self.point_evaluation_assignments[blob_idx],
+                )
+                .to_bytes(),
+            );
+        }
+        preimage
     }
 
     /// Extract all the hash inputs that will ever be used.
@@ -335,7 +471,7 @@ impl<const N_SNARKS: usize> BatchHash<N_SNARKS> {
     /// - batch_data_hash_preimage
     /// - preimage for blob metadata
     /// - chunk\[i\].flattened_l2_signed_data for i in \[0, N_SNARKS)
-    /// - preimage for challenge digest
+    /// - preimage for challenge digest of each of the MAX_BLOBS_PER_BATCH blobs
     pub(crate) fn extract_hash_preimages(&self) -> Vec<Vec<u8>> {
         let mut res = vec![];
 
@@ -350,33 +486,13 @@ impl<const N_SNARKS: usize> BatchHash<N_SNARKS> {
         //     parent_batch_hash ||
         //     last_block_timestamp ||
         //     z ||
-        //     y
+        //     y ||
+        //     extra_blobs[0].versioned_hash || extra_blobs[0].z || extra_blobs[0].y || ...
         // )
-        let batch_hash_preimage = [
-            [self.batch_header.version].as_ref(),
-            self.batch_header.batch_index.to_be_bytes().as_ref(),
-            self.batch_header.l1_message_popped.to_be_bytes().as_ref(),
-            self.batch_header
-                .total_l1_message_popped
-                .to_be_bytes()
-                .as_ref(),
-            self.data_hash.as_bytes(),
-            self.versioned_hash.as_bytes(),
-            self.batch_header.parent_batch_hash.as_bytes(),
-            self.batch_header
-                .last_block_timestamp
-                .to_be_bytes()
-                .as_ref(),
-            self.point_evaluation_assignments
-                .challenge
-                .to_be_bytes()
-                .as_ref(),
-            self.point_evaluation_assignments
-                .evaluation
-                .to_be_bytes()
-                .as_ref(),
-        ]
-        .concat();
+        let batch_hash_preimage = self
+            .batch_header
+            .encode()
+            .expect("batch header version checked at construction");
         res.push(batch_hash_preimage);
 
         // compute piHash for each chunk for i in [0..N_SNARKS)
@@ -426,6 +542,9 @@ impl<const N_SNARKS: usize> BatchHash<N_SNARKS> {
     /// current_batch_hash
     /// chain_id
     /// current_withdraw_hash
+    ///
+    /// The L1 message hashes follow the chunk vk whitelist in the public inputs, see
+    /// [`Self::l1_msg_hash_instances`].
     pub(crate) fn instances_exclude_acc<F: Field>(&self) -> Vec<Vec<F>> {
         let mut res: Vec<F> = [
             self.parent_state_root,
@@ -446,8 +565,24 @@ impl<const N_SNARKS: usize> BatchHash<N_SNARKS> {
         vec![res]
     }
 
+    /// Compute the public inputs for the rolling hash of the L1 messages:
+    /// prev_l1_msg_hash
+    /// post_l1_msg_hash
+    pub(crate) fn l1_msg_hash_instances<F: Field>(&self) -> Vec<F> {
+        [
+            self.batch_header.prev_l1_msg_hash,
+            self.batch_header.post_l1_msg_hash,
+        ]
+        .into_iter()
+        .flat_map(|h| {
+            let (hi, lo) = split_h256(h);
+            [hi, lo]
+        })
+        .collect()
+    }
+
     /// ...
     pub fn batch_header(&self) -> BatchHeader<N_SNARKS> {
-        self.batch_header
+        self.batch_header.clone()
     }
 }
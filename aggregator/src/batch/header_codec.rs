@@ -0,0 +1,269 @@
+use eth_types::H256;
+use std::io::{Error, ErrorKind, Result};
+
+use super::{BatchHeader, BlobCommitment};
+
+/// The encodings of a batch header, i.e. the preimage of the batch hash, selected by the header's
+/// version byte.
+///
+/// Rolling out a new header layout amounts to adding a variant and its version range to
+/// [`BatchHeaderCodec::for_version`]. This only covers the native encoding: the BatchCircuit
+/// hard-codes the [`BatchHeaderCodec::V3`] layout of the batch hash preimage, so proving batches
+/// of another layout also requires laying out its preimage in the circuit, see
+/// [`BatchHeaderCodec::is_supported_by_batch_circuit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchHeaderCodec {
+    /// Headers of version 0:
+    ///
+    /// version || batch_index || l1_message_popped || total_l1_message_popped || data_hash ||
+    /// parent_batch_hash || skipped_l1_message_bitmap
+    V0,
+    /// Headers of versions 1 and 2, which commit to the blob versioned hash:
+    ///
+    /// version || batch_index || l1_message_popped || total_l1_message_popped || data_hash ||
+    /// blob_versioned_hash || parent_batch_hash || skipped_l1_message_bitmap
+    V1,
+    /// Headers of versions 3 to 5, which drop the skipped L1 message bitmap and commit to the
+    /// last block timestamp and the blob data proof:
+    ///
+    /// version || batch_index || l1_message_popped || total_l1_message_popped || data_hash ||
+    /// blob_versioned_hash || parent_batch_hash || last_block_timestamp || z || y ||
+    /// extra_blobs[0].versioned_hash || extra_blobs[0].z || extra_blobs[0].y || ...
+    ///
+    /// where the extra blobs are only present if the batch's data spans multiple blobs.
+    V3,
+}
+
+/// version (1) || batch_index (8) || l1_message_popped (8) || total_l1_message_popped (8)
+const N_BYTES_HEADER_PREFIX: usize = 25;
+
+/// The bitmap of skipped L1 messages is a sequence of 256-bit words.
+const N_BYTES_BITMAP_WORD: usize = 32;
+
+/// The size of an extra blob's versioned hash and blob data proof.
+const N_BYTES_BLOB_COMMITMENT: usize = 96;
+
+impl BatchHeaderCodec {
+    /// The codec of headers with the given version.
+    pub fn for_version(version: u8) -> Result<Self> {
+        match version {
+            0 => Ok(Self::V0),
+            1..=2 => Ok(Self::V1),
+            3..=5 => Ok(Self::V3),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported batch header version: {version}"),
+            )),
+        }
+    }
+
+    /// Whether the BatchCircuit lays out the batch hash preimage, and hence its public inputs,
+    /// for headers of this codec. Only [`BatchHeaderCodec::V3`] is, the other codecs being used to
+    /// encode and decode the headers of past batches natively.
+    pub fn is_supported_by_batch_circuit(&self) -> bool {
+        matches!(self, Self::V3)
+    }
+
+    /// The size of an encoded header, excluding its dynamically sized tail, i.e. the skipped L1
+    /// message bitmap or the extra blobs.
+    pub fn fixed_len(&self) -> usize {
+        match self {
+            Self::V0 => N_BYTES_HEADER_PREFIX + 32 + 32,
+            Self::V1 => N_BYTES_HEADER_PREFIX + 32 + 32 + 32,
+            Self::V3 => N_BYTES_HEADER_PREFIX + 32 + 32 + 32 + 8 + 32 + 32,
+        }
+    }
+
+    /// Encode the header.
+    pub fn encode<const N_SNARKS: usize>(&self, header: &BatchHeader<N_SNARKS>) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.fixed_len());
+        bytes.push(header.version);
+        bytes.extend_from_slice(&header.batch_index.to_be_bytes());
+        bytes.extend_from_slice(&header.l1_message_popped.to_be_bytes());
+        bytes.extend_from_slice(&header.total_l1_message_popped.to_be_bytes());
+        bytes.extend_from_slice(header.data_hash.as_bytes());
+        match self {
+            Self::V0 => {
+                bytes.extend_from_slice(header.parent_batch_hash.as_bytes());
+                bytes.extend_from_slice(&header.skipped_l1_message_bitmap);
+            }
+            Self::V1 => {
+                bytes.extend_from_slice(header.blob_versioned_hash.as_bytes());
+                bytes.extend_from_slice(header.parent_batch_hash.as_bytes());
+                bytes.extend_from_slice(&header.skipped_l1_message_bitmap);
+            }
+            Self::V3 => {
+                bytes.extend_from_slice(header.blob_versioned_hash.as_bytes());
+                bytes.extend_from_slice(header.parent_batch_hash.as_bytes());
+                bytes.extend_from_slice(&header.last_block_timestamp.to_be_bytes());
+                for h in header.blob_data_proof {
+                    bytes.extend_from_slice(h.as_bytes());
+                }
+                for blob in header.extra_blobs.iter() {
+                    bytes.extend(blob.to_bytes());
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Decode a header. The fields that the header's layout lacks are left at their defaults.
+    pub fn decode<const N_SNARKS: usize>(&self, bytes: &[u8]) -> Result<BatchHeader<N_SNARKS>> {
+        let invalid = |msg: &str| Error::new(ErrorKind::InvalidData, format!("{self:?}: {msg}"));
+        if bytes.len() < self.fixed_len() {
+            return Err(invalid("header too short"));
+        }
+        let version = bytes[0];
+        if Self::for_version(version)? != *self {
+            return Err(invalid("unexpected version"));
+        }
+
+        let mut reader = Reader(bytes);
+        reader.skip(1);
+        let mut header = BatchHeader {
+            version,
+            batch_index: reader.read_u64(),
+            l1_message_popped: reader.read_u64(),
+            total_l1_message_popped: reader.read_u64(),
+            data_hash: reader.read_h256(),
+            ..Default::default()
+        };
+        match self {
+            Self::V0 | Self::V1 => {
+                if *self == Self::V1 {
+                    header.blob_versioned_hash = reader.read_h256();
+                }
+                header.parent_batch_hash = reader.read_h256();
+                if reader.0.len() % N_BYTES_BITMAP_WORD != 0 {
+                    return Err(invalid(
+                        "skipped L1 message bitmap is not made of 32-byte words",
+                    ));
+                }
+                header.skipped_l1_message_bitmap = reader.0.to_vec();
+            }
+            Self::V3 => {
+                header.blob_versioned_hash = reader.read_h256();
+                header.parent_batch_hash = reader.read_h256();
+                header.last_block_timestamp = reader.read_u64();
+                header.blob_data_proof = [reader.read_h256(), reader.read_h256()];
+                if reader.0.len() % N_BYTES_BLOB_COMMITMENT != 0 {
+                    return Err(invalid("extra blobs are not made of 96-byte commitments"));
+                }
+                while !reader.0.is_empty() {
+                    header.extra_blobs.push(BlobCommitment {
+                        versioned_hash: reader.read_h256(),
+                        blob_data_proof: [reader.read_h256(), reader.read_h256()],
+                    });
+                }
+            }
+        }
+        Ok(header)
+    }
+}
+
+/// Big-endian reader over a slice that is known to be long enough.
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn skip(&mut self, n: usize) {
+        self.0 = &self.0[n..];
+    }
+
+    fn read_u64(&mut self) -> u64 {
+        let value = u64::from_be_bytes(self.0[..8].try_into().expect("8 bytes"));
+        self.skip(8);
+        value
+    }
+
+    fn read_h256(&mut self) -> H256 {
+        let value = H256::from_slice(&self.0[..32]);
+        self.skip(32);
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MAX_AGG_SNARKS;
+
+    fn header(version: u8) -> BatchHeader<MAX_AGG_SNARKS> {
+        BatchHeader {
+            version,
+            batch_index: 6789,
+            l1_message_popped: 101,
+            total_l1_message_popped: 10101,
+            parent_batch_hash: H256::repeat_byte(1),
+            last_block_timestamp: 192837,
+            data_hash: H256::repeat_byte(2),
+            blob_versioned_hash: H256::repeat_byte(3),
+            blob_data_proof: [H256::repeat_byte(4), H256::repeat_byte(5)],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn header_codec_roundtrip() -> Result<()> {
+        for version in 0..=5 {
+            let mut header = header(version);
+            let codec = BatchHeaderCodec::for_version(version)?;
+            match codec {
+                BatchHeaderCodec::V0 | BatchHeaderCodec::V1 => {
+                    header.last_block_timestamp = 0;
+                    header.blob_data_proof = Default::default();
+                    header.skipped_l1_message_bitmap = vec![0xff; 64];
+                    if codec == BatchHeaderCodec::V0 {
+                        header.blob_versioned_hash = H256::zero();
+                    }
+                }
+                BatchHeaderCodec::V3 => header.extra_blobs = vec![BlobCommitment::default(); 2],
+            }
+
+            let bytes = codec.encode(&header);
+            let tail_len = match codec {
+                BatchHeaderCodec::V3 => 2 * N_BYTES_BLOB_COMMITMENT,
+                _ => 64,
+            };
+            assert_eq!(bytes.len(), codec.fixed_len() + tail_len);
+
+            let decoded = BatchHeader::<MAX_AGG_SNARKS>::decode(&bytes)?;
+            assert_eq!(codec.encode(&decoded), bytes);
+            assert_eq!(decoded.batch_hash()?, header.batch_hash()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn header_codec_v3_layout() {
+        // the layout of v3 headers is the one laid out by the BatchCircuit.
+        let header = header(3);
+        let bytes = BatchHeaderCodec::V3.encode(&header);
+        assert_eq!(bytes.len(), 193);
+        assert_eq!(
+            &bytes[crate::constants::BATCH_DATA_HASH_OFFSET..][..32],
+            header.data_hash.as_bytes()
+        );
+        assert_eq!(
+            &bytes[crate::constants::BATCH_Y_OFFSET..][..32],
+            header.blob_data_proof[1].as_bytes()
+        );
+        assert!(BatchHeaderCodec::V3.is_supported_by_batch_circuit());
+        assert!(!BatchHeaderCodec::V1.is_supported_by_batch_circuit());
+    }
+
+    #[test]
+    fn header_codec_invalid() {
+        assert!(BatchHeaderCodec::for_version(6).is_err());
+        assert!(header(6).batch_hash().is_err());
+
+        let bytes = BatchHeaderCodec::V3.encode(&header(3));
+        assert!(BatchHeader::<MAX_AGG_SNARKS>::decode(&bytes[..150]).is_err());
+        assert!(
+            BatchHeader::<MAX_AGG_SNARKS>::decode(&[bytes.clone(), vec![0; 10]].concat()).is_err()
+        );
+        assert!(BatchHeaderCodec::V0
+            .decode::<MAX_AGG_SNARKS>(&bytes)
+            .is_err());
+        assert!(BatchHeader::<MAX_AGG_SNARKS>::decode(&[]).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+//! Print a JSON report of the batch data carried in the blobs of a batch, e.g.
+//!
+//! cargo run --release --bin blob_inspector -- --batch-version 4 blob_0.hex > report.json
+use aggregator::{BlobReport, CodecTag, MAX_AGG_SNARKS};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::{fs, path::PathBuf};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Files holding the 4844 blobs of the batch in order, either hex-encoded or as raw bytes
+    #[clap(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// The version of the batch, which selects the layout of the blob envelope
+    #[clap(long)]
+    batch_version: u8,
+
+    /// Read a single file holding the batch data bytes (metadata || chunk data) instead of blobs
+    #[clap(long)]
+    raw: bool,
+
+    /// With --raw, recompute the blobs with the uncompressed codec rather than zstd
+    #[clap(long, requires = "raw")]
+    uncompressed: bool,
+}
+
+/// Read a file as hex, with an optional 0x prefix, falling back to its raw bytes.
+fn read_bytes(path: &PathBuf) -> Result<Vec<u8>> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let text = String::from_utf8_lossy(&bytes);
+    let text = text.trim();
+    Ok(hex::decode(text.strip_prefix("0x").unwrap_or(text)).unwrap_or(bytes))
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let inputs = args
+        .inputs
+        .iter()
+        .map(read_bytes)
+        .collect::<Result<Vec<_>>>()?;
+
+    let report = if args.raw {
+        if inputs.len() != 1 {
+            bail!("--raw expects a single input file");
+        }
+        let codec = if args.uncompressed {
+            CodecTag::Uncompressed
+        } else {
+            CodecTag::Zstd
+        };
+        BlobReport::from_batch_data_bytes::<MAX_AGG_SNARKS>(&inputs[0], args.batch_version, codec)?
+    } else {
+        BlobReport::from_blobs::<MAX_AGG_SNARKS>(&inputs, args.batch_version)?
+    };
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
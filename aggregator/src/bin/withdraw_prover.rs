@@ -0,0 +1,56 @@
+//! Print a JSON inclusion proof of a withdrawal message appended in a chunk, against the withdraw
+//! root committed by the chunk, e.g.
+//!
+//! cargo run --release --bin withdraw_prover -- --message-hash 0x.. --prev-l1-msg-hash 0x.. \
+//!     block_1.json block_2.json
+use aggregator::{prove_withdrawal, ChunkInfo};
+use anyhow::{Context, Result};
+use clap::Parser;
+use eth_types::{l2_types::BlockTrace, H256};
+use serde::Deserialize;
+use std::{fs, path::PathBuf};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Files holding the block traces of the chunk in order
+    #[clap(required = true)]
+    traces: Vec<PathBuf>,
+
+    /// Hash of the withdrawal message to prove
+    #[clap(long)]
+    message_hash: H256,
+
+    /// Rolling hash of the L1 messages popped before the chunk
+    #[clap(long)]
+    prev_l1_msg_hash: H256,
+}
+
+/// Read a block trace, either bare or as the result of a JSON-RPC response.
+fn read_block_trace(path: &PathBuf) -> Result<BlockTrace> {
+    #[derive(Deserialize)]
+    struct RpcResponse {
+        result: BlockTrace,
+    }
+
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_slice::<BlockTrace>(&bytes)
+        .or_else(|_| serde_json::from_slice::<RpcResponse>(&bytes).map(|resp| resp.result))
+        .with_context(|| format!("failed to parse block trace {}", path.display()))
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let traces = args
+        .traces
+        .iter()
+        .map(read_block_trace)
+        .collect::<Result<Vec<_>>>()?;
+    let chunk = ChunkInfo::from_block_traces(&traces, args.prev_l1_msg_hash);
+    let proof = prove_withdrawal(&traces, &chunk, args.message_hash)?;
+
+    println!("{}", serde_json::to_string_pretty(&proof)?);
+    Ok(())
+}
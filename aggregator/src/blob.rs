@@ -1,6 +1,7 @@
 use crate::{
-    aggregation::{interpolate, witgen::init_zstd_encoder, BLS_MODULUS},
-    BatchHash, ChunkInfo,
+    aggregation::{interpolate, BLS_MODULUS},
+    codec::has_codec_tag,
+    BatchHash, BlobCodec, ChunkInfo, CodecTag, ZstdCodec,
 };
 
 use eth_types::{ToBigEndian, H256, U256};
@@ -13,10 +14,9 @@ use halo2_proofs::{
     halo2curves::{bls12_381::Scalar, bn256::Fr},
 };
 use itertools::Itertools;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use revm_primitives::VERSIONED_HASH_VERSION_KZG;
 use std::{
-    io::Write,
     iter::{once, repeat},
     sync::Arc,
 };
@@ -41,8 +41,13 @@ pub const N_ROWS_NUM_CHUNKS: usize = 2;
 /// we explicitly set the most-significant byte to 0, effectively utilising only 31 bytes.
 pub const N_BLOB_BYTES: usize = BLOB_WIDTH * N_DATA_BYTES_PER_COEFFICIENT;
 
-/// Allow up to 5x compression via zstd encoding of the batch data.
-pub const N_BATCH_BYTES: usize = N_BLOB_BYTES * 5;
+/// The maximum number of blobs that the data of a batch can span. The blob envelope is split
+/// across the blobs in order, each blob holding up to N_BLOB_BYTES bytes.
+pub const MAX_BLOBS_PER_BATCH: usize = 6;
+
+/// The batch data that MAX_BLOBS_PER_BATCH blobs can carry uncompressed, which also allows up to
+/// MAX_BLOBS_PER_BATCH x compression of a single blob via zstd encoding of the batch data.
+pub const N_BATCH_BYTES: usize = N_BLOB_BYTES * MAX_BLOBS_PER_BATCH;
 
 /// KZG trusted setup
 pub static KZG_TRUSTED_SETUP: Lazy<Arc<c_kzg::KzgSettings>> = Lazy::new(|| {
@@ -72,6 +77,14 @@ pub struct BatchData<const N_SNARKS: usize> {
     /// copied over for the padded chunks. The `chunk_data_digest` for padded chunks is the
     /// `chunk_data_digest` of the last valid chunk (from Aggregation Circuit's perspective).
     pub chunk_data: [Vec<u8>; N_SNARKS],
+    /// The codec that the batch data bytes are encoded with in the blob.
+    pub codec: CodecTag,
+    /// The version of the batch, which selects the layout of the blob envelope (see
+    /// [`CodecTag`]).
+    pub batch_version: u8,
+    /// The blob envelope, computed on first use as encoding the batch data is costly. It is reset
+    /// by [`BatchData::with_codec`], the other fields are not to be modified once it is set.
+    envelope: OnceCell<Vec<u8>>,
 }
 
 impl<const N_SNARKS: usize> From<&BatchHash<N_SNARKS>> for BatchData<N_SNARKS> {
@@ -80,6 +93,10 @@ impl<const N_SNARKS: usize> From<&BatchHash<N_SNARKS>> for BatchData<N_SNARKS> {
             batch_hash.number_of_valid_chunks,
             &batch_hash.chunks_with_padding,
         )
+        .with_codec(
+            batch_hash.batch_header.version,
+            batch_hash.batch_header.codec,
+        )
     }
 }
 
@@ -115,6 +132,9 @@ impl<const N_SNARKS: usize> From<&Vec<Vec<u8>>> for BatchData<N_SNARKS> {
             num_valid_chunks: num_valid_chunks.try_into().unwrap(),
             chunk_sizes,
             chunk_data,
+            codec: CodecTag::default(),
+            batch_version: 0,
+            envelope: OnceCell::new(),
         }
     }
 }
@@ -132,6 +152,16 @@ fn kzg_to_versioned_hash(commitment: &c_kzg::KzgCommitment) -> H256 {
     H256::from_slice(&res[..])
 }
 
+/// Get the versioned hash of a 4844 blob, i.e. the BLOB_WIDTH coefficients as 32-bytes big-endian
+/// unsigned integers.
+pub(crate) fn blob_to_versioned_hash(blob: &[u8]) -> H256 {
+    let blob =
+        c_kzg::Blob::from_bytes(blob).expect("blob-coefficients to 4844 blob should succeed");
+    let c = c_kzg::KzgCommitment::blob_to_kzg_commitment(&blob, &KZG_TRUSTED_SETUP)
+        .expect("blob to kzg commitment should succeed");
+    kzg_to_versioned_hash(&c)
+}
+
 impl<const N_SNARKS: usize> BatchData<N_SNARKS> {
     /// The number of rows in Blob Data config's layout to represent the "digest rlc" section.
     /// - metadata digest RLC (1 row)
@@ -215,30 +245,54 @@ impl<const N_SNARKS: usize> BatchData<N_SNARKS> {
             num_valid_chunks: num_valid_chunks as u16,
             chunk_sizes,
             chunk_data,
+            codec: CodecTag::default(),
+            batch_version: 0,
+            envelope: OnceCell::new(),
         }
     }
 
-    /// Get the versioned hash as per EIP-4844.
-    pub(crate) fn get_versioned_hash(&self) -> H256 {
-        let coefficients = self.get_coefficients();
-        let blob = c_kzg::Blob::from_bytes(
+    /// Encode the batch data bytes in the blob with the given codec, in the blob envelope of
+    /// batches of the given version.
+    pub fn with_codec(mut self, batch_version: u8, codec: CodecTag) -> Self {
+        codec
+            .check_batch_version(batch_version)
+            .unwrap_or_else(|e| panic!("{e}"));
+        self.codec = codec;
+        self.batch_version = batch_version;
+        self.envelope = OnceCell::new();
+        self
+    }
+
+    /// Whether the blob envelope starts with the codec tag.
+    pub fn has_codec_tag(&self) -> bool {
+        has_codec_tag(self.batch_version)
+    }
+
+    /// Get the versioned hash of the blob at blob_idx as per EIP-4844. The blobs past the batch
+    /// data, up to MAX_BLOBS_PER_BATCH, are empty.
+    pub(crate) fn get_versioned_hash(&self, blob_idx: usize) -> H256 {
+        let coefficients = self.get_coefficients(blob_idx);
+        blob_to_versioned_hash(
             &coefficients
                 .iter()
                 .cloned()
                 .flat_map(|coeff| coeff.to_be_bytes())
                 .collect::<Vec<_>>(),
         )
-        .expect("blob-coefficients to 4844 blob should succeed");
-        let c = c_kzg::KzgCommitment::blob_to_kzg_commitment(&blob, &KZG_TRUSTED_SETUP)
-            .expect("blob to kzg commitment should succeed");
-        kzg_to_versioned_hash(&c)
     }
 
-    /// Get the preimage of the challenge digest.
-    pub(crate) fn get_challenge_digest_preimage(&self) -> Vec<u8> {
+    /// Get the versioned hashes of all blobs of the batch.
+    pub fn get_versioned_hashes(&self) -> Vec<H256> {
+        (0..self.num_blobs())
+            .map(|blob_idx| self.get_versioned_hash(blob_idx))
+            .collect()
+    }
+
+    /// Get the preimage of the challenge digest for the blob at blob_idx.
+    pub(crate) fn get_challenge_digest_preimage(&self, blob_idx: usize) -> Vec<u8> {
         let metadata_digest = keccak256(self.to_metadata_bytes());
         let chunk_digests = self.chunk_data.iter().map(keccak256);
-        let blob_versioned_hash = self.get_versioned_hash();
+        let blob_versioned_hash = self.get_versioned_hash(blob_idx);
 
         // preimage =
         //     metadata_digest ||
@@ -247,7 +301,8 @@ impl<const N_SNARKS: usize> BatchData<N_SNARKS> {
         //     blob_versioned_hash
         //
         // where chunk_data_digest for a padded chunk is set equal to the "last valid chunk"'s
-        // chunk_data_digest.
+        // chunk_data_digest, and blob_versioned_hash is the versioned hash of the blob at
+        // blob_idx.
         metadata_digest
             .into_iter()
             .chain(chunk_digests.flatten())
@@ -255,9 +310,9 @@ impl<const N_SNARKS: usize> BatchData<N_SNARKS> {
             .collect::<Vec<_>>()
     }
 
-    /// Compute the challenge digest from blob bytes.
-    pub(crate) fn get_challenge_digest(&self) -> U256 {
-        let challenge_digest = keccak256(self.get_challenge_digest_preimage());
+    /// Compute the challenge digest for the blob at blob_idx.
+    pub(crate) fn get_challenge_digest(&self, blob_idx: usize) -> U256 {
+        let challenge_digest = keccak256(self.get_challenge_digest_preimage(blob_idx));
         U256::from_big_endian(&challenge_digest)
     }
 
@@ -276,37 +331,59 @@ impl<const N_SNARKS: usize> BatchData<N_SNARKS> {
             .collect()
     }
 
-    /// Get the zstd encoded batch data bytes.
+    /// Get the zstd encoded batch data bytes, as witnessed in the zstd decoder.
     pub fn get_encoded_batch_data_bytes(&self) -> Vec<u8> {
-        let batch_data_bytes = self.get_batch_data_bytes();
-        let mut encoder = init_zstd_encoder(None);
-        encoder
-            .set_pledged_src_size(Some(batch_data_bytes.len() as u64))
-            .expect("infallible");
-        encoder.write_all(&batch_data_bytes).expect("infallible");
-        let encoded_bytes = encoder.finish().expect("infallible");
-        log::info!(
-            "compress batch data from {} to {}, compression ratio {:.2}, blob usage {:.3}",
-            batch_data_bytes.len(),
-            encoded_bytes.len(),
-            batch_data_bytes.len() as f32 / encoded_bytes.len() as f32,
-            encoded_bytes.len() as f32 / N_BLOB_BYTES as f32
-        );
-        encoded_bytes
+        ZstdCodec.encode(&self.get_batch_data_bytes())
+    }
+
+    /// Get the blob envelope, i.e. the codec tag, if any, followed by the encoded batch data
+    /// bytes.
+    pub fn get_envelope_bytes(&self) -> Vec<u8> {
+        self.envelope().to_vec()
+    }
+
+    fn envelope(&self) -> &[u8] {
+        self.envelope.get_or_init(|| {
+            let envelope = self
+                .codec
+                .encode_envelope(self.batch_version, &self.get_batch_data_bytes());
+            assert!(
+                envelope.len() <= MAX_BLOBS_PER_BATCH * N_BLOB_BYTES,
+                "too many bytes in batch data"
+            );
+            envelope
+        })
+    }
+
+    /// Get the bytes of each blob, i.e. the blob envelope split into N_BLOB_BYTES sized pieces.
+    pub fn get_blobs_bytes(&self) -> Vec<Vec<u8>> {
+        self.envelope()
+            .chunks(N_BLOB_BYTES)
+            .map(<[u8]>::to_vec)
+            .collect()
+    }
+
+    /// Get the bytes of the blob at blob_idx, which are empty for the blobs past the batch data.
+    pub(crate) fn get_blob_bytes(&self, blob_idx: usize) -> &[u8] {
+        assert!(blob_idx < MAX_BLOBS_PER_BATCH, "blob index out of range");
+        self.envelope()
+            .chunks(N_BLOB_BYTES)
+            .nth(blob_idx)
+            .unwrap_or_default()
+    }
+
+    /// The number of blobs that the batch data spans.
+    pub fn num_blobs(&self) -> usize {
+        self.envelope().len().div_ceil(N_BLOB_BYTES)
     }
 
-    /// Get the BLOB_WIDTH number of scalar field elements, as 32-bytes unsigned integers.
-    pub(crate) fn get_coefficients(&self) -> [U256; BLOB_WIDTH] {
+    /// Get the BLOB_WIDTH number of scalar field elements of the blob at blob_idx, as 32-bytes
+    /// unsigned integers.
+    pub(crate) fn get_coefficients(&self, blob_idx: usize) -> [U256; BLOB_WIDTH] {
         let mut coefficients = [[0u8; N_BYTES_U256]; BLOB_WIDTH];
 
         // We only consider the data from `valid` chunks and ignore the padded chunks.
-        let blob_bytes = self.get_encoded_batch_data_bytes();
-        assert!(
-            blob_bytes.len() < N_BLOB_BYTES,
-            "too many bytes in batch data"
-        );
-
-        for (i, &byte) in blob_bytes.iter().enumerate() {
+        for (i, &byte) in self.get_blob_bytes(blob_idx).iter().enumerate() {
             coefficients[i / 31][1 + (i % 31)] = byte;
         }
 
@@ -317,7 +394,7 @@ impl<const N_SNARKS: usize> BatchData<N_SNARKS> {
     /// eventually required to be checked for the consistency of blob's metadata, its chunks' bytes
     /// and the final blob preimage.
     pub fn preimages(&self) -> Vec<Vec<u8>> {
-        let mut preimages = Vec::with_capacity(2 + N_SNARKS);
+        let mut preimages = Vec::with_capacity(1 + N_SNARKS + MAX_BLOBS_PER_BATCH);
 
         // metadata
         preimages.push(self.to_metadata_bytes());
@@ -327,8 +404,11 @@ impl<const N_SNARKS: usize> BatchData<N_SNARKS> {
             preimages.push(chunk.to_vec());
         }
 
-        // preimage for challenge digest
-        preimages.push(self.get_challenge_digest_preimage());
+        // preimage for challenge digest of each blob, including the empty blobs past the batch
+        // data as the batch circuit evaluates MAX_BLOBS_PER_BATCH blobs.
+        for blob_idx in 0..MAX_BLOBS_PER_BATCH {
+            preimages.push(self.get_challenge_digest_preimage(blob_idx));
+        }
 
         preimages
     }
@@ -504,8 +584,8 @@ impl<const N_SNARKS: usize> BatchData<N_SNARKS> {
             })
             .unzip();
 
-        // challenge digest
-        let challenge_digest_preimage = self.get_challenge_digest_preimage();
+        // challenge digest, where the "digest" section only covers the first blob.
+        let challenge_digest_preimage = self.get_challenge_digest_preimage(0);
         let challenge_digest_preimage_rlc =
             challenge_digest_preimage.iter().fold(zero, |acc, &byte| {
                 acc * challenge.keccak_input() + Value::known(Fr::from(byte as u64))
@@ -516,7 +596,7 @@ impl<const N_SNARKS: usize> BatchData<N_SNARKS> {
         });
 
         // blob versioned hash
-        let versioned_hash = self.get_versioned_hash();
+        let versioned_hash = self.get_versioned_hash(0);
         let versioned_hash_rlc = versioned_hash.as_bytes().iter().fold(zero, |acc, &byte| {
             acc * challenge.evm_word() + Value::known(Fr::from(byte as u64))
         });
@@ -621,16 +701,23 @@ impl Default for PointEvaluationAssignments {
 
 impl<const N_SNARKS: usize> From<&BatchData<N_SNARKS>> for PointEvaluationAssignments {
     fn from(batch_data: &BatchData<N_SNARKS>) -> Self {
+        Self::new(batch_data, 0)
+    }
+}
+
+impl PointEvaluationAssignments {
+    /// Construct the point evaluation assignments for the blob at blob_idx.
+    pub fn new<const N_SNARKS: usize>(batch_data: &BatchData<N_SNARKS>, blob_idx: usize) -> Self {
         // blob polynomial in evaluation form.
         //
         // also termed P(x)
-        let coefficients = batch_data.get_coefficients();
+        let coefficients = batch_data.get_coefficients(blob_idx);
         let coefficients_as_scalars = coefficients.map(|coeff| Scalar::from_raw(coeff.0));
 
         // challenge := challenge_digest % BLS_MODULUS
         //
         // also termed z
-        let challenge_digest = batch_data.get_challenge_digest();
+        let challenge_digest = batch_data.get_challenge_digest(blob_idx);
         let (_, challenge) = challenge_digest.div_mod(*BLS_MODULUS);
 
         // y = P(z)
@@ -680,7 +767,10 @@ impl BatchDataRow<Fr> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::MAX_AGG_SNARKS;
+    use crate::{BatchHeader, BATCH_VERSION_CODEC_TAG, LOG_DEGREE, MAX_AGG_SNARKS};
+    use zkevm_circuits::keccak_circuit::{
+        keccak_packed_multi::get_num_rows_per_update, KeccakCircuit, DEFAULT_KECCAK_ROWS,
+    };
 
     #[test]
     #[ignore = "only required for logging challenge digest"]
@@ -761,7 +851,7 @@ mod tests {
             // blob data
             let batch_data: BatchData<MAX_AGG_SNARKS> = tcase.into();
             let point_evaluation_assignments = PointEvaluationAssignments::from(&batch_data);
-            let versioned_hash = batch_data.get_versioned_hash();
+            let versioned_hash = batch_data.get_versioned_hash(0);
             println!(
                 "[[ {:60} ]]\nchallenge (z) = {:0>64x}, evaluation (y) = {:0>64x}, versioned hash = {:0>64x}, batch_hash = {:0>64x}\n\n",
                 annotation,
@@ -773,6 +863,74 @@ mod tests {
         }
     }
 
+    #[test]
+    fn multi_blob_batch_data() {
+        let batch_data =
+            BatchData::<MAX_AGG_SNARKS>::from(&vec![vec![7; N_BLOB_BYTES], vec![9; 1000]])
+                .with_codec(BATCH_VERSION_CODEC_TAG, CodecTag::Uncompressed);
+
+        let blobs_bytes = batch_data.get_blobs_bytes();
+        assert_eq!(batch_data.num_blobs(), 2);
+        assert_eq!(blobs_bytes[0].len(), N_BLOB_BYTES);
+        assert_eq!(blobs_bytes.concat(), batch_data.get_envelope_bytes());
+
+        let versioned_hashes = batch_data.get_versioned_hashes();
+        assert_eq!(versioned_hashes.len(), 2);
+        assert_ne!(versioned_hashes[0], versioned_hashes[1]);
+        assert_ne!(
+            batch_data.get_challenge_digest(0),
+            batch_data.get_challenge_digest(1)
+        );
+        assert_eq!(
+            batch_data.preimages().len(),
+            1 + batch_data.num_valid_chunks as usize + MAX_BLOBS_PER_BATCH
+        );
+        assert!(batch_data.get_blob_bytes(2).is_empty());
+
+        // the compressed batch data still fits in a single blob.
+        assert_eq!(
+            batch_data
+                .with_codec(BATCH_VERSION_CODEC_TAG, CodecTag::Zstd)
+                .num_blobs(),
+            1
+        );
+    }
+
+    #[test]
+    fn max_batch_data_fits_batch_circuit() {
+        // uncompressed, the largest batch data fills all the blobs but for the codec tag.
+        let n_rows_data = BatchData::<MAX_AGG_SNARKS>::n_rows_data();
+        let chunks = ChunkInfo::mock_chunk_infos(&[vec![0xab; n_rows_data - 1]]);
+        let batch_header = BatchHeader::<MAX_AGG_SNARKS>::construct_from_chunks(
+            BATCH_VERSION_CODEC_TAG,
+            0,
+            0,
+            0,
+            H256::zero(),
+            0,
+            &chunks,
+            CodecTag::Uncompressed,
+        );
+        let batch_hash =
+            BatchHash::<MAX_AGG_SNARKS>::construct_with_unpadded(&chunks, batch_header);
+        assert_eq!(batch_hash.num_blobs(), MAX_BLOBS_PER_BATCH);
+
+        // the BatchDataConfig and the BlobDataConfig fit in the batch circuit.
+        let n_usable_rows = (1 << LOG_DEGREE) - 64; // as reserved by the DecoderConfig
+        assert!(BatchData::<MAX_AGG_SNARKS>::n_rows() < n_usable_rows);
+        assert!(MAX_BLOBS_PER_BATCH * N_BLOB_BYTES + 1 < n_usable_rows);
+
+        // and so do the keccak rows for all the hashes in the batch.
+        let keccak_capacity =
+            KeccakCircuit::<Fr>::capacity_for_row(1 << LOG_DEGREE, DEFAULT_KECCAK_ROWS).unwrap();
+        assert!(
+            KeccakCircuit::<Fr>::num_rows_for_inputs(
+                &batch_hash.extract_hash_preimages(),
+                DEFAULT_KECCAK_ROWS
+            ) <= keccak_capacity * get_num_rows_per_update(DEFAULT_KECCAK_ROWS)
+        );
+    }
+
     #[test]
     fn default_batch_data() {
         let mut default_metadata = [0u8; BatchData::<MAX_AGG_SNARKS>::n_rows_metadata()];
@@ -781,9 +939,9 @@ mod tests {
         let default_chunk_digests = [keccak256([]); MAX_AGG_SNARKS];
 
         let default_batch = BatchData::<MAX_AGG_SNARKS>::default();
-        let versioned_hash = default_batch.get_versioned_hash();
+        let versioned_hash = default_batch.get_versioned_hash(0);
         assert_eq!(
-            default_batch.get_challenge_digest(),
+            default_batch.get_challenge_digest(0),
             U256::from(keccak256(
                 default_metadata_digest
                     .into_iter()
@@ -3,7 +3,11 @@
 use eth_types::{base64, l2_types::BlockTrace, ToBigEndian, H256};
 use ethers_core::utils::keccak256;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "receipt-circuit")]
+use std::collections::BTreeMap;
 use std::iter;
+#[cfg(feature = "receipt-circuit")]
+use zkevm_circuits::witness::receipts_bytes;
 use zkevm_circuits::witness::Block;
 
 #[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -14,6 +18,10 @@ use zkevm_circuits::witness::Block;
 /// - the withdraw root after this chunk
 /// - the data hash of this chunk
 /// - the tx data hash of this chunk
+/// - the L1 message queue indices before and after this chunk
+/// - the rolling hash of L1 messages before and after this chunk
+/// - the hash of the receipts roots and logs blooms of the blocks in this chunk, with the
+///   `receipt-circuit` feature
 /// - flattened L2 tx bytes
 /// - if the chunk is padded (en empty but valid chunk that is padded for aggregation)
 pub struct ChunkInfo {
@@ -27,6 +35,22 @@ pub struct ChunkInfo {
     pub withdraw_root: H256,
     /// the data hash of this chunk
     pub data_hash: H256,
+    /// queue index of the first L1 message this chunk may pop
+    #[serde(default)]
+    pub start_l1_queue_index: u64,
+    /// total number of L1 messages popped after this chunk
+    #[serde(default)]
+    pub end_l1_queue_index: u64,
+    /// rolling hash of the L1 messages popped before this chunk
+    #[serde(default)]
+    pub prev_l1_msg_hash: H256,
+    /// rolling hash of the L1 messages popped up to the end of this chunk
+    #[serde(default)]
+    pub post_l1_msg_hash: H256,
+    /// hash of the number, logs bloom and receipts root of each block in this chunk
+    #[cfg(feature = "receipt-circuit")]
+    #[serde(default)]
+    pub receipts_hash: H256,
     /// Flattened L2 tx bytes (RLP-signed) in this chunk.
     #[serde(with = "base64")]
     pub tx_bytes: Vec<u8>,
@@ -35,8 +59,9 @@ pub struct ChunkInfo {
 }
 
 impl ChunkInfo {
-    /// Construct by block traces
-    pub fn from_block_traces(traces: &[BlockTrace]) -> Self {
+    /// Construct by block traces, given the rolling hash of the L1 messages popped before the
+    /// chunk.
+    pub fn from_block_traces(traces: &[BlockTrace], prev_l1_msg_hash: H256) -> Self {
         let data_bytes = iter::empty()
             .chain(
                 // header part
@@ -78,12 +103,42 @@ impl ChunkInfo {
         let chain_id = traces.first().unwrap().chain_id;
         let prev_state_root = traces.first().unwrap().storage_trace.root_before;
 
+        let start_l1_queue_index = traces.first().unwrap().start_l1_queue_index;
+        let l1_msgs = traces
+            .iter()
+            .flat_map(|b| b.transactions.iter().filter(|tx| tx.is_l1_tx()));
+        let end_l1_queue_index = l1_msgs
+            .clone()
+            // tx.nonce alias for queue_index for l1 msg tx
+            .map(|tx| tx.nonce + 1)
+            .fold(start_l1_queue_index, u64::max);
+        let post_l1_msg_hash = l1_msg_rolling_hash(prev_l1_msg_hash, l1_msgs.map(|tx| tx.tx_hash));
+
+        #[cfg(feature = "receipt-circuit")]
+        let receipts_hash = {
+            let receipts = traces
+                .iter()
+                .map(|b| {
+                    let number = b.header.number.expect("block num").as_u64();
+                    let bloom = b.header.logs_bloom.unwrap_or_default();
+                    (number, (b.header.receipts_root, bloom))
+                })
+                .collect::<BTreeMap<_, _>>();
+            H256(keccak256(receipts_bytes(&receipts)))
+        };
+
         Self {
             chain_id,
             prev_state_root,
             post_state_root,
             withdraw_root,
             data_hash,
+            start_l1_queue_index,
+            end_l1_queue_index,
+            prev_l1_msg_hash,
+            post_l1_msg_hash,
+            #[cfg(feature = "receipt-circuit")]
+            receipts_hash,
             tx_bytes,
             is_padding: false,
         }
@@ -158,12 +213,27 @@ impl ChunkInfo {
             .map(|(_, b_ctx)| b_ctx.state_root)
             .unwrap_or(block.prev_state_root);
 
+        let post_l1_msg_hash = l1_msg_rolling_hash(
+            block.prev_l1_msg_hash,
+            block
+                .txs
+                .iter()
+                .filter(|tx| tx.tx_type.is_l1_msg())
+                .map(|tx| tx.hash),
+        );
+
         Self {
             chain_id: block.chain_id,
             prev_state_root: block.prev_state_root,
             post_state_root,
             withdraw_root: H256(block.withdraw_root.to_be_bytes()),
             data_hash,
+            start_l1_queue_index: block.start_l1_queue_index,
+            end_l1_queue_index: total_l1_popped,
+            prev_l1_msg_hash: block.prev_l1_msg_hash,
+            post_l1_msg_hash,
+            #[cfg(feature = "receipt-circuit")]
+            receipts_hash: H256(keccak256(block.receipts_bytes())),
             tx_bytes: tx_bytes.to_vec(),
             is_padding,
         }
@@ -190,6 +260,13 @@ impl ChunkInfo {
             .collect::<Vec<_>>()
             .try_into()
             .expect("should not fail");
+        let l1_msg_hashes = (0..=MAX_AGG_SNARKS)
+            .map(|i| {
+                let mut hash = [0u8; 32];
+                hash[0] = i as u8;
+                hash.into()
+            })
+            .collect::<Vec<H256>>();
 
         txs_data
             .iter()
@@ -205,12 +282,20 @@ impl ChunkInfo {
                     root[0] = 255 - (i as u8);
                     root.into()
                 };
+                // each chunk pops i L1 messages
+                let start_l1_queue_index = (i * (i.saturating_sub(1)) / 2) as u64;
                 ChunkInfo {
                     chain_id: 123456,
                     prev_state_root: state_roots[i],
                     post_state_root: state_roots[i + 1],
                     withdraw_root,
                     data_hash,
+                    start_l1_queue_index,
+                    end_l1_queue_index: start_l1_queue_index + i as u64,
+                    prev_l1_msg_hash: l1_msg_hashes[i],
+                    post_l1_msg_hash: l1_msg_hashes[i + 1],
+                    #[cfg(feature = "receipt-circuit")]
+                    receipts_hash: H256::repeat_byte(i as u8),
                     tx_bytes: tx_data.to_vec(),
                     is_padding: false,
                 }
@@ -236,6 +321,18 @@ impl ChunkInfo {
         r.fill_bytes(&mut withdraw_root);
         let mut data_hash = [0u8; 32];
         r.fill_bytes(&mut data_hash);
+        let mut prev_l1_msg_hash = [0u8; 32];
+        r.fill_bytes(&mut prev_l1_msg_hash);
+        let mut post_l1_msg_hash = [0u8; 32];
+        r.fill_bytes(&mut post_l1_msg_hash);
+        #[cfg(feature = "receipt-circuit")]
+        let receipts_hash = {
+            let mut receipts_hash = [0u8; 32];
+            r.fill_bytes(&mut receipts_hash);
+            receipts_hash
+        };
+        let start_l1_queue_index = r.gen_range(0..u64::MAX / 2);
+        let end_l1_queue_index = start_l1_queue_index + r.gen_range(0..100);
 
         const N_TXS: usize = 10;
         const N_SENDERS: usize = 2;
@@ -284,6 +381,12 @@ impl ChunkInfo {
             post_state_root: post_state_root.into(),
             withdraw_root: withdraw_root.into(),
             data_hash: data_hash.into(),
+            start_l1_queue_index,
+            end_l1_queue_index,
+            prev_l1_msg_hash: prev_l1_msg_hash.into(),
+            post_l1_msg_hash: post_l1_msg_hash.into(),
+            #[cfg(feature = "receipt-circuit")]
+            receipts_hash: receipts_hash.into(),
             tx_bytes: txs.iter().flat_map(|tx| tx.rlp_unsigned()).collect(),
             is_padding: false,
         }
@@ -302,6 +405,12 @@ impl ChunkInfo {
             post_state_root: previous_chunk.post_state_root,
             withdraw_root: previous_chunk.withdraw_root,
             data_hash: previous_chunk.data_hash,
+            start_l1_queue_index: previous_chunk.start_l1_queue_index,
+            end_l1_queue_index: previous_chunk.end_l1_queue_index,
+            prev_l1_msg_hash: previous_chunk.prev_l1_msg_hash,
+            post_l1_msg_hash: previous_chunk.post_l1_msg_hash,
+            #[cfg(feature = "receipt-circuit")]
+            receipts_hash: previous_chunk.receipts_hash,
             tx_bytes: previous_chunk.tx_bytes.clone(),
             is_padding: true,
         }
@@ -314,7 +423,12 @@ impl ChunkInfo {
     ///     post state root ||
     ///     withdraw root ||
     ///     chunk data hash ||
-    ///     chunk txdata hash
+    ///     chunk txdata hash ||
+    ///     start l1 queue index ||
+    ///     end l1 queue index ||
+    ///     prev l1 msg hash ||
+    ///     post l1 msg hash ||
+    ///     receipts hash (with the `receipt-circuit` feature)
     /// )
     pub fn public_input_hash(&self) -> H256 {
         let preimage = self.extract_hash_preimage();
@@ -329,9 +443,18 @@ impl ChunkInfo {
     ///     post state root ||
     ///     withdraw root ||
     ///     chunk data hash ||
-    ///     chunk txdata hash
+    ///     chunk txdata hash ||
+    ///     start l1 queue index ||
+    ///     end l1 queue index ||
+    ///     prev l1 msg hash ||
+    ///     post l1 msg hash ||
+    ///     receipts hash (with the `receipt-circuit` feature)
     /// ]
     pub fn extract_hash_preimage(&self) -> Vec<u8> {
+        #[cfg(feature = "receipt-circuit")]
+        let receipts_hash = self.receipts_hash.as_bytes();
+        #[cfg(not(feature = "receipt-circuit"))]
+        let receipts_hash: &[u8] = &[];
         [
             self.chain_id.to_be_bytes().as_ref(),
             self.prev_state_root.as_bytes(),
@@ -339,7 +462,25 @@ impl ChunkInfo {
             self.withdraw_root.as_bytes(),
             self.data_hash.as_bytes(),
             self.tx_bytes_hash().as_bytes(),
+            self.start_l1_queue_index.to_be_bytes().as_ref(),
+            self.end_l1_queue_index.to_be_bytes().as_ref(),
+            self.prev_l1_msg_hash.as_bytes(),
+            self.post_l1_msg_hash.as_bytes(),
+            receipts_hash,
         ]
         .concat()
     }
 }
+
+/// The rolling hash of L1 messages after a chunk, i.e.
+/// keccak(prev l1 msg hash || l1 msg hash_0 || ... || l1 msg hash_n)
+fn l1_msg_rolling_hash(
+    prev_l1_msg_hash: H256,
+    l1_msg_hashes: impl IntoIterator<Item = H256>,
+) -> H256 {
+    let preimage = iter::once(prev_l1_msg_hash)
+        .chain(l1_msg_hashes)
+        .flat_map(|hash| hash.to_fixed_bytes())
+        .collect::<Vec<u8>>();
+    H256(keccak256(preimage))
+}
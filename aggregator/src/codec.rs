@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use zstd_encoder::zstd::stream::read::Decoder;
+
+use crate::{aggregation::witgen::init_zstd_encoder, blob::N_BLOB_BYTES};
+
+/// The first batch version whose blob envelope starts with the codec tag.
+pub const BATCH_VERSION_CODEC_TAG: u8 = 5;
+
+/// Whether the blob envelope of batches of the given version starts with the codec tag.
+///
+/// The batch circuit checks `batch_version == BATCH_VERSION_CODEC_TAG` instead, which agrees with
+/// this on the versions it accepts, i.e. as long as [`BATCH_VERSION_CODEC_TAG`] is the highest
+/// version supported by the batch circuit.
+pub fn has_codec_tag(batch_version: u8) -> bool {
+    batch_version >= BATCH_VERSION_CODEC_TAG
+}
+
+/// The codec that the payload of a blob is encoded with.
+///
+/// From batch version [`BATCH_VERSION_CODEC_TAG`], the blob envelope is the tag byte followed by
+/// the encoded batch data bytes:
+///
+/// blob := tag || codec.encode(batch_data_bytes)
+///
+/// Batches of earlier versions keep the legacy layout, i.e. the blob envelope is the zstd encoded
+/// batch data bytes without a tag.
+///
+/// In the aggregation circuit, the tag byte is constrained to be boolean. A zstd payload is linked
+/// to the batch data through the zstd decoder (DecoderConfig), while an uncompressed payload is
+/// linked to the batch data directly. Adding a codec requires a new variant here and a decoder
+/// config in the circuit that the payload is linked through.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum CodecTag {
+    /// The payload is the batch data bytes as is.
+    Uncompressed = 0,
+    /// The payload is a zstd frame, as produced by [`init_zstd_encoder`].
+    #[default]
+    Zstd = 1,
+}
+
+impl TryFrom<u8> for CodecTag {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Uncompressed),
+            1 => Ok(Self::Zstd),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown blob codec tag: {value}"),
+            )),
+        }
+    }
+}
+
+impl CodecTag {
+    /// The codec that this tag stands for.
+    pub fn codec(&self) -> &'static dyn BlobCodec {
+        match self {
+            Self::Uncompressed => &UncompressedCodec,
+            Self::Zstd => &ZstdCodec,
+        }
+    }
+
+    /// Check that batches of the given version can be encoded with this codec, i.e. that the
+    /// batch version either tags the blob envelope or this is the zstd codec.
+    pub fn check_batch_version(&self, batch_version: u8) -> Result<()> {
+        if *self != Self::Zstd && !has_codec_tag(batch_version) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("batch version {batch_version} only supports the zstd codec, got {self:?}"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Encode the batch data bytes into the blob envelope of a batch of the given version, i.e.
+    /// the tag followed by the payload, or only the payload for versions before
+    /// [`BATCH_VERSION_CODEC_TAG`].
+    pub fn encode_envelope(&self, batch_version: u8, batch_data_bytes: &[u8]) -> Vec<u8> {
+        self.check_batch_version(batch_version)
+            .expect("codec supported by batch version");
+        let payload = self.codec().encode(batch_data_bytes);
+        let tag = has_codec_tag(batch_version).then_some(*self as u8);
+        log::info!(
+            "encode batch data with {:?} from {} to {}, blob usage {:.3}",
+            self,
+            batch_data_bytes.len(),
+            payload.len(),
+            (payload.len() + usize::from(tag.is_some())) as f32 / N_BLOB_BYTES as f32,
+        );
+        tag.into_iter().chain(payload).collect()
+    }
+
+    /// Split the blob envelope of a batch of the given version into the codec tag and the
+    /// payload. Envelopes without a tag are zstd payloads.
+    pub fn split_envelope(batch_version: u8, blob_bytes: &[u8]) -> Result<(Self, &[u8])> {
+        if !has_codec_tag(batch_version) {
+            return Ok((Self::Zstd, blob_bytes));
+        }
+        let (&tag, payload) = blob_bytes
+            .split_first()
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "empty blob envelope"))?;
+        Ok((Self::try_from(tag)?, payload))
+    }
+}
+
+/// Encoding and decoding of the batch data bytes carried in a blob.
+pub trait BlobCodec: Sync {
+    /// The tag that identifies this codec in the blob envelope.
+    fn tag(&self) -> CodecTag;
+
+    /// Encode the batch data bytes.
+    fn encode(&self, batch_data_bytes: &[u8]) -> Vec<u8>;
+
+    /// Decode the batch data bytes from a payload. The payload may be followed by the zero bytes
+    /// that pad the blob, which codecs without a self-delimiting encoding return as is.
+    fn decode(&self, payload: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// The batch data bytes are stored as is.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UncompressedCodec;
+
+impl BlobCodec for UncompressedCodec {
+    fn tag(&self) -> CodecTag {
+        CodecTag::Uncompressed
+    }
+
+    fn encode(&self, batch_data_bytes: &[u8]) -> Vec<u8> {
+        batch_data_bytes.to_vec()
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        Ok(payload.to_vec())
+    }
+}
+
+/// The batch data bytes are compressed to a single zstd frame without the magic number.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ZstdCodec;
+
+impl BlobCodec for ZstdCodec {
+    fn tag(&self) -> CodecTag {
+        CodecTag::Zstd
+    }
+
+    fn encode(&self, batch_data_bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = init_zstd_encoder(None);
+        encoder
+            .set_pledged_src_size(Some(batch_data_bytes.len() as u64))
+            .expect("infallible");
+        encoder.write_all(batch_data_bytes).expect("infallible");
+        encoder.finish().expect("infallible")
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        let mut decoder = Decoder::new(payload)?.single_frame();
+        decoder.include_magicbytes(false)?;
+        let mut batch_data_bytes = Vec::new();
+        decoder.read_to_end(&mut batch_data_bytes)?;
+        Ok(batch_data_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BatchHeaderCodec;
+
+    #[test]
+    fn codec_tag_predicate_matches_batch_circuit() {
+        for batch_version in 0..=u8::MAX {
+            let supported = BatchHeaderCodec::for_version(batch_version)
+                .is_ok_and(|codec| codec.is_supported_by_batch_circuit());
+            if supported {
+                assert_eq!(
+                    has_codec_tag(batch_version),
+                    batch_version == BATCH_VERSION_CODEC_TAG,
+                    "batch version {batch_version}",
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn envelope_roundtrip() -> Result<()> {
+        let batch_data_bytes = (0..2000u32)
+            .flat_map(|i| (i % 7).to_be_bytes())
+            .collect::<Vec<u8>>();
+
+        for tag in [CodecTag::Uncompressed, CodecTag::Zstd] {
+            assert_eq!(tag.codec().tag(), tag);
+
+            // the blob is right-padded with zeros.
+            let mut blob_bytes = tag.encode_envelope(BATCH_VERSION_CODEC_TAG, &batch_data_bytes);
+            assert_eq!(blob_bytes[0], tag as u8);
+            let payload_len = blob_bytes.len() - 1;
+            blob_bytes.resize(blob_bytes.len() + 100, 0);
+
+            let (decoded_tag, payload) =
+                CodecTag::split_envelope(BATCH_VERSION_CODEC_TAG, &blob_bytes)?;
+            assert_eq!(decoded_tag, tag);
+            let decoded = tag.codec().decode(payload)?;
+            match tag {
+                CodecTag::Uncompressed => assert_eq!(decoded[..payload_len], batch_data_bytes),
+                CodecTag::Zstd => assert_eq!(decoded, batch_data_bytes),
+            }
+        }
+
+        assert!(CodecTag::split_envelope(BATCH_VERSION_CODEC_TAG, &[]).is_err());
+        assert!(CodecTag::split_envelope(BATCH_VERSION_CODEC_TAG, &[2, 0, 0]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_envelope() -> Result<()> {
+        let batch_data_bytes = (0..2000u32)
+            .flat_map(|i| (i % 7).to_be_bytes())
+            .collect::<Vec<u8>>();
+
+        for batch_version in 3..BATCH_VERSION_CODEC_TAG {
+            // the envelope is the zstd frame as is.
+            let blob_bytes = CodecTag::Zstd.encode_envelope(batch_version, &batch_data_bytes);
+            assert_eq!(blob_bytes, ZstdCodec.encode(&batch_data_bytes));
+
+            let (decoded_tag, payload) = CodecTag::split_envelope(batch_version, &blob_bytes)?;
+            assert_eq!(decoded_tag, CodecTag::Zstd);
+            assert_eq!(ZstdCodec.decode(payload)?, batch_data_bytes);
+
+            assert!(CodecTag::Uncompressed
+                .check_batch_version(batch_version)
+                .is_err());
+        }
+
+        Ok(())
+    }
+}
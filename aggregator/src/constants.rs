@@ -1,6 +1,9 @@
 // A chain_id is u64 and uses 8 bytes
 pub(crate) const CHAIN_ID_LEN: usize = 8;
 
+// An L1 message queue index is u64 and uses 8 bytes
+pub(crate) const L1_QUEUE_INDEX_LEN: usize = 8;
+
 // ================================
 // hash parameters
 // ================================
@@ -22,6 +25,11 @@ pub(crate) const LOG_DEGREE: u32 = 21;
 // - withdraw_root      32 bytes
 // - chunk_data_hash    32 bytes
 // - chunk_tx_data_hash 32 bytes
+// - start_l1_queue_index 8 bytes
+// - end_l1_queue_index   8 bytes
+// - prev_l1_msg_hash     32 bytes
+// - post_l1_msg_hash     32 bytes
+// - receipts_hash        32 bytes, with the receipt-circuit feature
 
 pub(crate) const CHUNK_CHAIN_ID_INDEX: usize = 0;
 pub(crate) const PREV_STATE_ROOT_INDEX: usize = 8;
@@ -29,12 +37,16 @@ pub(crate) const POST_STATE_ROOT_INDEX: usize = 40;
 pub(crate) const WITHDRAW_ROOT_INDEX: usize = 72;
 pub(crate) const CHUNK_DATA_HASH_INDEX: usize = 104;
 pub(crate) const CHUNK_TX_DATA_HASH_INDEX: usize = 136;
+pub(crate) const START_L1_QUEUE_INDEX_INDEX: usize = 168;
+pub(crate) const END_L1_QUEUE_INDEX_INDEX: usize = 176;
+pub(crate) const PREV_L1_MSG_HASH_INDEX: usize = 184;
+pub(crate) const POST_L1_MSG_HASH_INDEX: usize = 216;
 
 // ================================
 // indices for batch hash table
 // ================================
 //
-// the preimages are arranged as
+// the preimages are arranged as per BatchHeaderCodec::V3
 // - version:                  1 byte
 // - batch_index:              8 bytes
 // - l1_message_popped         8 bytes
@@ -45,12 +57,24 @@ pub(crate) const CHUNK_TX_DATA_HASH_INDEX: usize = 136;
 // - last_block_timestamp      8 bytes
 // - z                         32 bytes
 // - y                         32 bytes
+// - extra_blobs[i]            96 bytes each, i.e.
+//   - versioned_hash          32 bytes
+//   - z                       32 bytes
+//   - y                       32 bytes
+//
+// where the batch circuit pads the extra blobs to MAX_BLOBS_PER_BATCH - 1, the padding being
+// excluded from the batch hash.
 
+pub(crate) const BATCH_VERSION_OFFSET: usize = 0;
 pub(crate) const BATCH_DATA_HASH_OFFSET: usize = 25;
 pub(crate) const BATCH_BLOB_VERSIONED_HASH_OFFSET: usize = 57;
 pub(crate) const BATCH_PARENT_BATCH_HASH: usize = 89;
 pub(crate) const BATCH_Z_OFFSET: usize = 129;
 pub(crate) const BATCH_Y_OFFSET: usize = 161;
+pub(crate) const BATCH_EXTRA_BLOBS_OFFSET: usize = 193;
+pub(crate) const BLOB_COMMITMENT_LEN: usize = 96;
+pub(crate) const BLOB_COMMITMENT_Z_OFFSET: usize = 32;
+pub(crate) const BLOB_COMMITMENT_Y_OFFSET: usize = 64;
 
 // ================================
 // indices for public inputs
@@ -62,12 +86,20 @@ pub(crate) const BATCH_Y_OFFSET: usize = 161;
 // - current batch hash ..
 // - chain id (1 Fr cell)
 // - current withdraw root ..
+// - chunk vk indices (1 Fr cell)
+// - chunk vk digests (MAX_CHUNK_VKS Fr cells)
+// - prev l1 msg hash (2 cells: hi, lo)
+// - post l1 msg hash ..
 pub(crate) const PI_PARENT_STATE_ROOT: usize = ACC_LEN;
 pub(crate) const PI_PARENT_BATCH_HASH: usize = ACC_LEN + 2;
 pub(crate) const PI_CURRENT_STATE_ROOT: usize = ACC_LEN + 4;
 pub(crate) const PI_CURRENT_BATCH_HASH: usize = ACC_LEN + 6;
 pub(crate) const PI_CHAIN_ID: usize = ACC_LEN + 8;
 pub(crate) const PI_CURRENT_WITHDRAW_ROOT: usize = ACC_LEN + 9;
+pub(crate) const PI_CHUNK_VK_INDICES: usize = ACC_LEN + 11;
+pub(crate) const PI_CHUNK_VK_DIGESTS: usize = ACC_LEN + 12;
+pub(crate) const PI_PREV_L1_MSG_HASH: usize = PI_CHUNK_VK_DIGESTS + MAX_CHUNK_VKS;
+pub(crate) const PI_POST_L1_MSG_HASH: usize = PI_PREV_L1_MSG_HASH + 2;
 
 // ================================
 // aggregator parameters
@@ -85,3 +117,9 @@ pub(crate) const BITS: usize = 88;
 /// If the input size is less than this, dummy snarks
 /// will be padded.
 pub const MAX_AGG_SNARKS: usize = 45;
+
+/// Max number of chunk verifying keys that the snarks aggregated in a batch may be verified
+/// against. The digests of the whitelisted verifying keys, padded with zeros, are public, and so is
+/// the index of the verifying key of each snark, packed into a single field element in base
+/// MAX_CHUNK_VKS.
+pub const MAX_CHUNK_VKS: usize = 4;
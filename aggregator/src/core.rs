@@ -1,4 +1,6 @@
 use std::iter::repeat;
+#[cfg(not(feature = "disable_proof_aggregation"))]
+use std::rc::Rc;
 
 use ark_std::{end_timer, start_timer};
 use ethers_core::utils::keccak256;
@@ -12,6 +14,14 @@ use halo2_proofs::{
 };
 use itertools::Itertools;
 use rand::Rng;
+#[cfg(not(feature = "disable_proof_aggregation"))]
+use snark_verifier::{
+    loader::halo2::halo2_ecc::halo2_base::{
+        gates::{flex_gate::FlexGateConfig, GateInstructions},
+        AssignedValue, QuantumCell,
+    },
+    pcs::{kzg::KzgSuccinctVerifyingKey, AccumulationScheme},
+};
 use snark_verifier::{
     loader::native::NativeLoader,
     pcs::{
@@ -22,6 +32,8 @@ use snark_verifier::{
     verifier::PlonkVerifier,
     Error,
 };
+#[cfg(not(feature = "disable_proof_aggregation"))]
+use snark_verifier_sdk::{types::Halo2Loader, SnarkWitness};
 use snark_verifier_sdk::{
     types::{PoseidonTranscript, Shplonk, POSEIDON_SPEC},
     Snark,
@@ -31,15 +43,20 @@ use zkevm_circuits::{
     util::Challenges,
 };
 
+#[cfg(not(feature = "disable_proof_aggregation"))]
+use crate::recursion::dynamic_verify;
 use crate::{
+    blob::MAX_BLOBS_PER_BATCH,
     constants::{
-        BATCH_BLOB_VERSIONED_HASH_OFFSET, BATCH_Y_OFFSET, BATCH_Z_OFFSET, CHAIN_ID_LEN, DIGEST_LEN,
-        LOG_DEGREE,
+        BATCH_BLOB_VERSIONED_HASH_OFFSET, BATCH_EXTRA_BLOBS_OFFSET, BATCH_VERSION_OFFSET,
+        BATCH_Y_OFFSET, BATCH_Z_OFFSET, BLOB_COMMITMENT_LEN, BLOB_COMMITMENT_Y_OFFSET,
+        BLOB_COMMITMENT_Z_OFFSET, CHAIN_ID_LEN, DIGEST_LEN, L1_QUEUE_INDEX_LEN, LOG_DEGREE,
     },
     util::{assert_conditional_equal, parse_hash_preimage_cells},
     RlcConfig, BATCH_DATA_HASH_OFFSET, BATCH_PARENT_BATCH_HASH, BITS, CHUNK_CHAIN_ID_INDEX,
-    CHUNK_DATA_HASH_INDEX, CHUNK_TX_DATA_HASH_INDEX, LIMBS, POST_STATE_ROOT_INDEX,
-    PREV_STATE_ROOT_INDEX, WITHDRAW_ROOT_INDEX,
+    CHUNK_DATA_HASH_INDEX, CHUNK_TX_DATA_HASH_INDEX, END_L1_QUEUE_INDEX_INDEX, LIMBS,
+    MAX_CHUNK_VKS, POST_L1_MSG_HASH_INDEX, POST_STATE_ROOT_INDEX, PREV_L1_MSG_HASH_INDEX,
+    PREV_STATE_ROOT_INDEX, START_L1_QUEUE_INDEX_INDEX, WITHDRAW_ROOT_INDEX,
 };
 
 /// Subroutine for the witness generations.
@@ -146,6 +163,105 @@ pub fn extract_proof_and_instances_with_pairing_check(
     Ok((as_proof, acc_instances))
 }
 
+/// Aggregate the chunk snarks in-circuit, like [`snark_verifier_sdk::aggregate`], except that the
+/// preprocessed part of each snark's protocol is loaded as witness rather than as constant. The
+/// digest of the preprocessed part is constrained to be the whitelisted chunk vk digest at the
+/// snark's index, so that the snarks may be verified against any of the whitelisted chunk vks,
+/// as long as the vks are of circuits of the same shape.
+///
+/// The whitelisted chunk vk digests are loaded as witness, to be exposed as public inputs, so that
+/// the batch circuit's vk does not depend on the whitelist.
+///
+/// Returns the instances of each snark, the new accumulator, the indices of the chunk vks
+/// packed in base MAX_CHUNK_VKS, the first snark's index being the least significant digit, and
+/// the whitelisted chunk vk digests.
+#[cfg(not(feature = "disable_proof_aggregation"))]
+pub(crate) fn aggregate_with_chunk_vks<'a>(
+    svk: &KzgSuccinctVerifyingKey<G1Affine>,
+    loader: &Rc<Halo2Loader<'a>>,
+    gate: &FlexGateConfig<Fr>,
+    snarks: &[SnarkWitness],
+    chunk_vk_digests: &[Fr; MAX_CHUNK_VKS],
+    chunk_vk_indices: &[usize],
+    as_proof: Value<&'_ [u8]>,
+) -> (
+    Vec<Vec<AssignedValue<Fr>>>,
+    KzgAccumulator<G1Affine, Rc<Halo2Loader<'a>>>,
+    AssignedValue<Fr>,
+    Vec<AssignedValue<Fr>>,
+) {
+    let mut instances = Vec::with_capacity(snarks.len());
+    let mut accumulators = vec![];
+    let mut assigned_indices = Vec::with_capacity(snarks.len());
+    let assigned_chunk_vk_digests = gate.assign_witnesses(
+        &mut loader.ctx_mut(),
+        chunk_vk_digests.iter().map(|&digest| Value::known(digest)),
+    );
+    for (snark, &index) in snarks.iter().zip_eq(chunk_vk_indices) {
+        let chunk_vk_digest = {
+            let mut ctx = loader.ctx_mut();
+            let index = gate
+                .assign_witnesses(&mut ctx, [Value::known(Fr::from(index as u64))])
+                .pop()
+                .expect("one witness");
+            assigned_indices.push(index);
+            // an index out of range selects 0, which is not the digest of any vk.
+            gate.select_from_idx(
+                &mut ctx,
+                assigned_chunk_vk_digests
+                    .iter()
+                    .map(|digest| QuantumCell::Existing(*digest))
+                    .collect_vec(),
+                QuantumCell::Existing(index),
+            )
+        };
+        let (snark_instances, snark_accumulators) =
+            dynamic_verify::<Kzg<Bn256, Bdfg21>>(svk, loader, snark, Some(chunk_vk_digest));
+        instances.extend(snark_instances);
+        accumulators.extend(snark_accumulators);
+    }
+
+    let accumulator = {
+        let mut transcript = PoseidonTranscript::<Rc<Halo2Loader>, _>::new(loader, as_proof);
+        let proof = KzgAs::<Kzg<Bn256, Bdfg21>>::read_proof(
+            &Default::default(),
+            &accumulators,
+            &mut transcript,
+        )
+        .unwrap();
+        KzgAs::<Kzg<Bn256, Bdfg21>>::verify(&Default::default(), &accumulators, &proof).unwrap()
+    };
+
+    let packed_indices = gate.inner_product(
+        &mut loader.ctx_mut(),
+        assigned_indices.into_iter().map(QuantumCell::Existing),
+        std::iter::successors(Some(Fr::one()), |power| {
+            Some(Fr::from(MAX_CHUNK_VKS as u64) * power)
+        })
+        .take(snarks.len())
+        .map(QuantumCell::Constant)
+        .collect_vec(),
+    );
+
+    (
+        instances,
+        accumulator,
+        packed_indices,
+        assigned_chunk_vk_digests,
+    )
+}
+
+/// Pack the indices of the chunk vks in base MAX_CHUNK_VKS, as done by
+/// [`aggregate_with_chunk_vks`].
+pub(crate) fn pack_chunk_vk_indices(chunk_vk_indices: &[usize]) -> Fr {
+    chunk_vk_indices
+        .iter()
+        .rev()
+        .fold(Fr::zero(), |acc, &index| {
+            acc * Fr::from(MAX_CHUNK_VKS as u64) + Fr::from(index as u64)
+        })
+}
+
 /// Extracted hash cells. Including the padded ones so that the circuit is static.
 pub(crate) struct ExtractedHashCells<const N_SNARKS: usize> {
     inputs: Vec<Vec<AssignedCell<Fr, Fr>>>,
@@ -155,6 +271,7 @@ pub(crate) struct ExtractedHashCells<const N_SNARKS: usize> {
     data_lens: Vec<AssignedCell<Fr, Fr>>,
     num_valid_snarks: AssignedCell<Fr, Fr>,
     chunks_are_padding: Vec<AssignedCell<Fr, Fr>>,
+    extra_blobs_are_valid: Vec<AssignedCell<Fr, Fr>>,
 }
 
 // Computed cells to be constrained against public input. These cells are processed into hi/lo format from ExtractedHashCells.
@@ -174,9 +291,13 @@ impl<const N_SNARKS: usize> ExtractedHashCells<N_SNARKS> {
         evm_word_challenge: &AssignedCell<Fr, Fr>,
         num_valid_chunks: usize,
         preimages: &[Vec<u8>],
+        padded_batch_hash_preimage: &[u8],
+        batch_hash_preimage_flags: &[AssignedCell<Fr, Fr>],
+        batch_hash_preimage_len: AssignedCell<Fr, Fr>,
         chunk_is_valid_cell32s: &[AssignedCell<Fr, Fr>],
         num_valid_snarks: AssignedCell<Fr, Fr>,
         chunks_are_padding: Vec<AssignedCell<Fr, Fr>>,
+        extra_blobs_are_valid: Vec<AssignedCell<Fr, Fr>>,
     ) -> Result<Self, halo2_proofs::plonk::Error> {
         let mut inputs = vec![];
         let mut input_rlcs = vec![];
@@ -185,14 +306,55 @@ impl<const N_SNARKS: usize> ExtractedHashCells<N_SNARKS> {
         let mut data_lens = vec![];
 
         // preimages are padded as follows
-        // - the first hash is batch_hash
+        // - the first hash is batch_hash, its input is padded to the size for
+        //   MAX_BLOBS_PER_BATCH blobs
         // - the next hashes are chunk\[i\].piHash, we padded it to N_SNARKS by repeating the last
         //   chunk
         // - the last hash is batch_data_hash, its input is padded to 32*N_SNARKS
         log::trace!("preimage len: {}", preimages.len());
+        assert!(padded_batch_hash_preimage.starts_with(&preimages[0]));
+        {
+            let batch_hash_digest = keccak256(&preimages[0]);
+
+            {
+                let mut preimage_cells = vec![];
+                for input in padded_batch_hash_preimage.iter() {
+                    let v = Fr::from(*input as u64);
+                    let cell = plonk_config.load_private(region, &v, offset)?;
+                    preimage_cells.push(cell);
+                }
+                // the commitments to the empty blobs past the batch data are excluded.
+                let input_rlc = plonk_config.rlc_with_flag(
+                    region,
+                    &preimage_cells,
+                    keccak_input_challenge,
+                    batch_hash_preimage_flags,
+                    offset,
+                )?;
+                inputs.push(preimage_cells);
+                input_rlcs.push(input_rlc);
+            }
+
+            {
+                let mut digest_cells = vec![];
+                for output in batch_hash_digest.iter() {
+                    let v = Fr::from(*output as u64);
+                    let cell = plonk_config.load_private(region, &v, offset)?;
+                    digest_cells.push(cell);
+                }
+                let output_rlc =
+                    plonk_config.rlc(region, &digest_cells, evm_word_challenge, offset)?;
+                outputs.push(digest_cells);
+                output_rlcs.push(output_rlc)
+            }
+
+            data_lens.push(batch_hash_preimage_len);
+        }
+
         for preimage in preimages
             .iter()
-            .take(num_valid_chunks + 1)
+            .skip(1)
+            .take(num_valid_chunks)
             .chain(repeat(&preimages[num_valid_chunks]).take(N_SNARKS - num_valid_chunks))
         {
             {
@@ -289,6 +451,7 @@ impl<const N_SNARKS: usize> ExtractedHashCells<N_SNARKS> {
             data_lens,
             num_valid_snarks,
             chunks_are_padding,
+            extra_blobs_are_valid,
         })
     }
 
@@ -322,16 +485,21 @@ impl<const N_SNARKS: usize> ExtractedHashCells<N_SNARKS> {
     }
 }
 
+/// The cells of the batch hash preimage that commit to the blobs, for each of the
+/// MAX_BLOBS_PER_BATCH blobs.
 #[derive(Default)]
 pub(crate) struct ExpectedBlobCells {
-    pub(crate) z: Vec<AssignedCell<Fr, Fr>>,
-    pub(crate) y: Vec<AssignedCell<Fr, Fr>>,
-    pub(crate) versioned_hash: Vec<AssignedCell<Fr, Fr>>,
+    pub(crate) z: Vec<Vec<AssignedCell<Fr, Fr>>>,
+    pub(crate) y: Vec<Vec<AssignedCell<Fr, Fr>>>,
+    pub(crate) versioned_hash: Vec<Vec<AssignedCell<Fr, Fr>>>,
+    /// Whether each blob but the first holds batch data, i.e. is committed in the batch hash.
+    pub(crate) extra_blobs_are_valid: Vec<AssignedCell<Fr, Fr>>,
     pub(crate) chunk_tx_data_digests: Vec<Vec<AssignedCell<Fr, Fr>>>,
 }
 
 pub(crate) struct AssignedBatchHash {
     pub(crate) hash_output: Vec<Vec<AssignedCell<Fr, Fr>>>,
+    pub(crate) batch_version: AssignedCell<Fr, Fr>,
     pub(crate) blob: ExpectedBlobCells,
     pub(crate) num_valid_snarks: AssignedCell<Fr, Fr>,
     pub(crate) chunks_are_padding: Vec<AssignedCell<Fr, Fr>>,
@@ -347,13 +515,13 @@ pub(crate) struct AssignedBatchHash {
 //
 // 1. batch_data_hash digest is reused for batch hash
 // 3. batch_data_hash and chunk[i].pi_hash use a same chunk[i].data_hash when chunk[i] is not padded
-// 4. chunks are continuous: they are linked via the state roots
+// 4. chunks are continuous: they are linked via the state roots and the L1 message queue
 // 5. batch and all its chunks use a same chain id
 // 6. chunk[i]'s chunk_pi_hash_rlc_cells == chunk[i-1].chunk_pi_hash_rlc_cells when chunk[i] is
 // padded
 // 7. the hash input length are correct
 // - hashes[0] has 200 bytes
-// - hashes[1..N_SNARKS+1] has 168 bytes input
+// - hashes[1..N_SNARKS+1] has 248 bytes input, 280 with the receipt-circuit feature
 // - batch's data_hash length is 32 * number_of_valid_snarks
 // 8. batch data hash is correct w.r.t. its RLCs
 // 9. is_final_cells are set correctly
@@ -365,7 +533,9 @@ pub(crate) fn assign_batch_hashes<const N_SNARKS: usize>(
     challenges: Challenges<Value<Fr>>,
     chunks_are_valid: &[bool],
     num_valid_chunks: usize,
+    num_blobs: usize,
     preimages: &[Vec<u8>],
+    padded_batch_hash_preimage: &[u8],
 ) -> Result<AssignedBatchHash, Error> {
     // assign the hash table
     assign_keccak_table(keccak_config, layouter, challenges, preimages)?;
@@ -373,7 +543,7 @@ pub(crate) fn assign_batch_hashes<const N_SNARKS: usize>(
     // 1. batch_data_hash digest is reused for public input hash
     // 3. batch_data_hash and chunk[i].pi_hash use a same chunk[i].data_hash when chunk[i] is not
     // padded
-    // 4. chunks are continuous: they are linked via the state roots
+    // 4. chunks are continuous: they are linked via the state roots and the L1 message queue
     // 6. chunk[i]'s chunk_pi_hash_rlc_cells == chunk[i-1].chunk_pi_hash_rlc_cells when chunk[i] is
     // padded
     // 7. batch data hash is correct w.r.t. its RLCs
@@ -384,16 +554,46 @@ pub(crate) fn assign_batch_hashes<const N_SNARKS: usize>(
             challenges,
             chunks_are_valid,
             num_valid_chunks,
+            num_blobs,
             preimages,
+            padded_batch_hash_preimage,
         )?;
 
     let batch_hash_input = &extracted_hash_cells.inputs[0]; //[0..INPUT_LEN_PER_ROUND * 2];
+                                                            // the offsets of the versioned hash, z and y of each blob in the batch hash preimage.
+    let blob_offsets = (0..MAX_BLOBS_PER_BATCH)
+        .map(|blob_idx| {
+            if blob_idx == 0 {
+                (
+                    BATCH_BLOB_VERSIONED_HASH_OFFSET,
+                    BATCH_Z_OFFSET,
+                    BATCH_Y_OFFSET,
+                )
+            } else {
+                let offset = BATCH_EXTRA_BLOBS_OFFSET + (blob_idx - 1) * BLOB_COMMITMENT_LEN;
+                (
+                    offset,
+                    offset + BLOB_COMMITMENT_Z_OFFSET,
+                    offset + BLOB_COMMITMENT_Y_OFFSET,
+                )
+            }
+        })
+        .collect::<Vec<_>>();
+    let digest_at = |offset: usize| batch_hash_input[offset..offset + DIGEST_LEN].to_vec();
     let expected_blob_cells = ExpectedBlobCells {
-        z: batch_hash_input[BATCH_Z_OFFSET..BATCH_Z_OFFSET + DIGEST_LEN].to_vec(),
-        y: batch_hash_input[BATCH_Y_OFFSET..BATCH_Y_OFFSET + DIGEST_LEN].to_vec(),
-        versioned_hash: batch_hash_input
-            [BATCH_BLOB_VERSIONED_HASH_OFFSET..BATCH_BLOB_VERSIONED_HASH_OFFSET + DIGEST_LEN]
-            .to_vec(),
+        z: blob_offsets
+            .iter()
+            .map(|&(_, z_offset, _)| digest_at(z_offset))
+            .collect(),
+        y: blob_offsets
+            .iter()
+            .map(|&(_, _, y_offset)| digest_at(y_offset))
+            .collect(),
+        versioned_hash: blob_offsets
+            .iter()
+            .map(|&(versioned_hash_offset, _, _)| digest_at(versioned_hash_offset))
+            .collect(),
+        extra_blobs_are_valid: extracted_hash_cells.extra_blobs_are_valid.clone(),
         chunk_tx_data_digests: (0..N_SNARKS)
             .map(|i| {
                 extracted_hash_cells.inputs[i + 1]
@@ -404,6 +604,7 @@ pub(crate) fn assign_batch_hashes<const N_SNARKS: usize>(
     };
 
     Ok(AssignedBatchHash {
+        batch_version: batch_hash_input[BATCH_VERSION_OFFSET].clone(),
         hash_output: extracted_hash_cells.outputs,
         blob: expected_blob_cells,
         num_valid_snarks: extracted_hash_cells.num_valid_snarks,
@@ -419,7 +620,8 @@ pub(crate) fn assign_keccak_table(
     challenges: Challenges<Value<Fr>>,
     preimages: &[Vec<u8>],
 ) -> Result<(), Error> {
-    let keccak_capacity = KeccakCircuit::<Fr>::capacity_for_row(1 << LOG_DEGREE);
+    let keccak_capacity =
+        KeccakCircuit::<Fr>::capacity_for_row(1 << LOG_DEGREE, config.num_rows_per_round);
 
     let timer = start_timer!(|| ("multi keccak").to_string());
     // preimages consists of the following parts
@@ -436,12 +638,19 @@ pub(crate) fn assign_keccak_table(
     // (2) chunk[i].piHash preimage =
     //      (chain id ||
     //      chunk[i].prevStateRoot || chunk[i].postStateRoot ||
-    //      chunk[i].withdrawRoot || chunk[i].datahash || chunk[i].txdatahash)
+    //      chunk[i].withdrawRoot || chunk[i].datahash || chunk[i].txdatahash ||
+    //      chunk[i].startL1QueueIndex || chunk[i].endL1QueueIndex ||
+    //      chunk[i].prevL1MsgHash || chunk[i].postL1MsgHash)
     // (3) batchDataHash preimage =
     //      (chunk[0].dataHash || ... || chunk[k-1].dataHash)
     // each part of the preimage is mapped to image by Keccak256
-    let witness = multi_keccak(preimages, challenges, keccak_capacity)
-        .map_err(|e| Error::AssertionFailure(format!("multi keccak assignment failed: {e:?}")))?;
+    let witness = multi_keccak(
+        preimages,
+        challenges,
+        keccak_capacity,
+        config.num_rows_per_round,
+    )
+    .map_err(|e| Error::AssertionFailure(format!("multi keccak assignment failed: {e:?}")))?;
     end_timer!(timer);
 
     layouter
@@ -465,12 +674,12 @@ pub(crate) fn assign_keccak_table(
 // This function asserts the following constraints on the hashes
 // 1. batch_data_hash digest is reused for batch hash
 // 3. batch_data_hash and chunk[i].pi_hash use a same chunk[i].data_hash when chunk[i] is not padded
-// 4. chunks are continuous: they are linked via the state roots
+// 4. chunks are continuous: they are linked via the state roots and the L1 message queue
 // 6. chunk[i]'s chunk_pi_hash_rlc_cells == chunk[i-1].chunk_pi_hash_rlc_cells when chunk[i] is
 // padded
 // 7. the hash input length are correct
 // - hashes[0] has 200 bytes
-// - hashes[1..N_SNARKS+1] has 168 bytes input
+// - hashes[1..N_SNARKS+1] has 248 bytes input, 280 with the receipt-circuit feature
 // - batch's data_hash length is 32 * number_of_valid_snarks
 // 8. batch data hash is correct w.r.t. its RLCs
 // 9. is_final_cells are set correctly
@@ -481,7 +690,9 @@ pub(crate) fn conditional_constraints<const N_SNARKS: usize>(
     challenges: Challenges<Value<Fr>>,
     chunks_are_valid: &[bool],
     num_valid_chunks: usize,
+    num_blobs: usize,
     preimages: &[Vec<u8>],
+    padded_batch_hash_preimage: &[u8],
 ) -> Result<(ExtractedHashCells<N_SNARKS>, HashDerivedPublicInputCells), Error> {
     layouter
         .assign_region(
@@ -530,6 +741,78 @@ pub(crate) fn conditional_constraints<const N_SNARKS: usize>(
 
                 log::trace!("number of valid chunks: {:?}", num_valid_snarks.value());
 
+                // ====================================================
+                // build the flags to indicate the extra blobs hold batch data or not
+                // ====================================================
+                let one = {
+                    let one = rlc_config.load_private(&mut region, &Fr::one(), &mut offset)?;
+                    let one_cell = rlc_config.one_cell(one.cell().region_index);
+                    region.constrain_equal(one.cell(), one_cell)?;
+                    one
+                };
+                let extra_blobs_are_valid = (1..MAX_BLOBS_PER_BATCH)
+                    .map(|blob_idx| -> Result<_, halo2_proofs::plonk::Error> {
+                        let is_valid = rlc_config.load_private(
+                            &mut region,
+                            &Fr::from((blob_idx < num_blobs) as u64),
+                            &mut offset,
+                        )?;
+                        rlc_config.enforce_binary(&mut region, &is_valid, &mut offset)?;
+                        Ok(is_valid)
+                    })
+                    .collect::<Result<Vec<_>, halo2_proofs::plonk::Error>>()?;
+                let batch_hash_preimage_flags = repeat(&one)
+                    .take(BATCH_EXTRA_BLOBS_OFFSET)
+                    .chain(
+                        extra_blobs_are_valid
+                            .iter()
+                            .flat_map(|is_valid| repeat(is_valid).take(BLOB_COMMITMENT_LEN)),
+                    )
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                // len(batch_hash_preimage) = 193 + 96 * number of extra blobs
+                const _: () = assert!(
+                    BATCH_EXTRA_BLOBS_OFFSET == 32 * 6 + 1 && BLOB_COMMITMENT_LEN == 32 * 3
+                );
+                let batch_hash_preimage_len = {
+                    let thirty_two =
+                        rlc_config.load_private(&mut region, &Fr::from(32), &mut offset)?;
+                    let thirty_two_cell =
+                        rlc_config.thirty_two_cell(thirty_two.cell().region_index);
+                    region.constrain_equal(thirty_two.cell(), thirty_two_cell)?;
+                    let mut small_constants = Vec::with_capacity(2);
+                    for i in [3, 6] {
+                        let i_cell = rlc_config.load_private(
+                            &mut region,
+                            &Fr::from(i as u64),
+                            &mut offset,
+                        )?;
+                        let i_fixed_cell = rlc_config
+                            .fixed_up_to_max_agg_snarks_cell(i_cell.cell().region_index, i);
+                        region.constrain_equal(i_cell.cell(), i_fixed_cell)?;
+                        small_constants.push(i_cell);
+                    }
+                    let (three, six) = (&small_constants[0], &small_constants[1]);
+
+                    let fixed_len =
+                        rlc_config.mul_add(&mut region, &thirty_two, six, &one, &mut offset)?;
+                    let blob_commitment_len =
+                        rlc_config.mul(&mut region, &thirty_two, three, &mut offset)?;
+                    let mut num_extra_blobs = extra_blobs_are_valid[0].clone();
+                    for is_valid in extra_blobs_are_valid.iter().skip(1) {
+                        num_extra_blobs =
+                            rlc_config.add(&mut region, &num_extra_blobs, is_valid, &mut offset)?;
+                    }
+                    rlc_config.mul_add(
+                        &mut region,
+                        &num_extra_blobs,
+                        &blob_commitment_len,
+                        &fixed_len,
+                        &mut offset,
+                    )?
+                };
+
                 // ====================================================
                 // extract the hash cells from the witnesses and check against the lookup table
                 // ====================================================
@@ -541,9 +824,13 @@ pub(crate) fn conditional_constraints<const N_SNARKS: usize>(
                     &evm_word_challenge,
                     num_valid_chunks,
                     preimages,
+                    padded_batch_hash_preimage,
+                    &batch_hash_preimage_flags,
+                    batch_hash_preimage_len,
                     &chunk_is_valid_cell32s,
                     num_valid_snarks,
                     chunks_are_padding.clone(),
+                    extra_blobs_are_valid,
                 )?;
                 assigned_hash_cells.check_against_lookup_table(
                     rlc_config,
@@ -698,7 +985,9 @@ pub(crate) fn conditional_constraints<const N_SNARKS: usize>(
                 // keccak(
                 //        chain id ||
                 //        chunk[i].prevStateRoot || chunk[i].postStateRoot || chunk[i].withdrawRoot
-                //        || chunk[i].datahash || chunk[i].tx_data_hash)
+                //        || chunk[i].datahash || chunk[i].tx_data_hash
+                //        || chunk[i].startL1QueueIndex || chunk[i].endL1QueueIndex
+                //        || chunk[i].prevL1MsgHash || chunk[i].postL1MsgHash)
                 for i in 0..N_SNARKS - 1 {
                     for j in 0..DIGEST_LEN {
                         // sanity check
@@ -752,6 +1041,45 @@ pub(crate) fn conditional_constraints<const N_SNARKS: usize>(
                     }
                 }
 
+                // ====================================================
+                // 4.c  __valid__ chunks are continuous: they are linked via the L1 message queue
+                // ====================================================
+                // chunk[i+1].startL1QueueIndex == chunk[i].endL1QueueIndex
+                // chunk[i+1].prevL1MsgHash == chunk[i].postL1MsgHash
+                for i in 0..N_SNARKS - 1 {
+                    for (prev_index, post_index, len) in [
+                        (
+                            START_L1_QUEUE_INDEX_INDEX,
+                            END_L1_QUEUE_INDEX_INDEX,
+                            L1_QUEUE_INDEX_LEN,
+                        ),
+                        (PREV_L1_MSG_HASH_INDEX, POST_L1_MSG_HASH_INDEX, DIGEST_LEN),
+                    ] {
+                        for j in 0..len {
+                            // sanity check
+                            assert_conditional_equal(
+                                &chunk_pi_hash_preimages[i + 1][prev_index + j],
+                                &chunk_pi_hash_preimages[i][post_index + j],
+                                &chunk_is_valid_cells[i + 1],
+                                format!(
+                                    "chunk_{i} is not continuous (l1 msg queue): {:?} {:?} {:?}",
+                                    &chunk_pi_hash_preimages[i + 1][prev_index + j].value(),
+                                    &chunk_pi_hash_preimages[i][post_index + j].value(),
+                                    &chunk_is_valid_cells[i + 1].value(),
+                                )
+                                .as_str(),
+                            )?;
+                            rlc_config.conditional_enforce_equal(
+                                &mut region,
+                                &chunk_pi_hash_preimages[i + 1][prev_index + j],
+                                &chunk_pi_hash_preimages[i][post_index + j],
+                                &chunk_is_valid_cells[i + 1],
+                                &mut offset,
+                            )?;
+                        }
+                    }
+                }
+
                 // ====================================================
                 // 6. chunk[i]'s chunk_pi_hash_rlc_cells == chunk[i-1].chunk_pi_hash_rlc_cells when
                 // chunk[i] is padded
@@ -820,6 +1148,8 @@ pub(crate) fn conditional_constraints<const N_SNARKS: usize>(
                 // - current batch hash ..
                 // - chain id (1 Fr cell)
                 // - current withdraw root ..
+                // - prev l1 msg hash ..
+                // - post l1 msg hash ..
 
                 // pi.parent_state_root = chunks[0].prev_state_root
                 let chunk_prev_state_hi = rlc_config.rlc(
@@ -886,23 +1216,50 @@ pub(crate) fn conditional_constraints<const N_SNARKS: usize>(
                     &mut offset,
                 )?;
 
+                // pi.prev_l1_msg_hash = chunks[0].prev_l1_msg_hash
+                // pi.post_l1_msg_hash = chunks[N_SNARKS - 1].post_l1_msg_hash
+                // Note: the padded chunks have the same preimage as the last valid chunk (6.), and
+                // the valid chunks are chained via their L1 msg hashes (4.c).
+                let mut l1_msg_hash_cells = vec![];
+                for (chunk_index, hash_index) in [
+                    (0, PREV_L1_MSG_HASH_INDEX),
+                    (N_SNARKS - 1, POST_L1_MSG_HASH_INDEX),
+                ] {
+                    for half in [
+                        hash_index..hash_index + DIGEST_LEN / 2,
+                        hash_index + DIGEST_LEN / 2..hash_index + DIGEST_LEN,
+                    ] {
+                        l1_msg_hash_cells.push(rlc_config.rlc(
+                            &mut region,
+                            chunk_pi_hash_preimages[chunk_index][half].as_ref(),
+                            &byte_accumulator,
+                            &mut offset,
+                        )?);
+                    }
+                }
+
                 log::trace!("rlc chip uses {} rows", offset);
 
                 Ok((
                     assigned_hash_cells,
-                    HashDerivedPublicInputCells(vec![
-                        batch_parent_batch_hash_hi,
-                        batch_parent_batch_hash_lo,
-                        batch_hash_hi,
-                        batch_hash_lo,
-                        chunk_prev_state_hi,
-                        chunk_prev_state_lo,
-                        chunk_current_state_hi,
-                        chunk_current_state_lo,
-                        chunk_current_withdraw_root_hi,
-                        chunk_current_withdraw_root_lo,
-                        chunk_chain_id,
-                    ]),
+                    HashDerivedPublicInputCells(
+                        vec![
+                            batch_parent_batch_hash_hi,
+                            batch_parent_batch_hash_lo,
+                            batch_hash_hi,
+                            batch_hash_lo,
+                            chunk_prev_state_hi,
+                            chunk_prev_state_lo,
+                            chunk_current_state_hi,
+                            chunk_current_state_lo,
+                            chunk_current_withdraw_root_hi,
+                            chunk_current_withdraw_root_lo,
+                            chunk_chain_id,
+                        ]
+                        .into_iter()
+                        .chain(l1_msg_hash_cells)
+                        .collect(),
+                    ),
                 ))
             },
         )
@@ -0,0 +1,565 @@
+use eth_types::{Address, H256, U256};
+use ethers_core::{
+    types::Transaction,
+    utils::{
+        keccak256,
+        rlp::{Decodable, Rlp},
+    },
+};
+use halo2_proofs::{circuit::Value, halo2curves::bn256::Fr};
+use serde::Serialize;
+use std::{
+    io::{Error, ErrorKind, Result},
+    panic::catch_unwind,
+};
+
+use crate::{
+    aggregation::witgen::process,
+    blob::{
+        blob_to_versioned_hash, BatchData, PointEvaluationAssignments, BLOB_WIDTH, N_BLOB_BYTES,
+        N_BYTES_U256, N_ROWS_NUM_CHUNKS,
+    },
+    BlobCodec, CodecTag, ZstdCodec,
+};
+
+/// A structured view of the batch data carried in the blobs of a batch, as recomputed by the
+/// prover. Serialized to JSON, it is meant to be diffed against the sequencer's view of the batch.
+#[derive(Clone, Debug, Serialize)]
+pub struct BlobReport {
+    /// The version of the batch, which selects the layout of the blob envelope.
+    pub batch_version: u8,
+    /// The codec that the batch data bytes are encoded with.
+    pub codec: CodecTag,
+    /// The size of the encoded batch data bytes, excluding the codec tag and the blob padding.
+    pub encoded_len: usize,
+    /// The size of the batch data bytes, i.e. metadata and chunk data.
+    pub batch_data_len: usize,
+    /// The number of valid chunks in the batch.
+    pub num_valid_chunks: u16,
+    /// The size of each chunk, as per the metadata section.
+    pub chunk_sizes: Vec<u32>,
+    /// The keccak digest of the metadata section.
+    pub metadata_digest: H256,
+    /// The valid chunks of the batch.
+    pub chunks: Vec<ChunkReport>,
+    /// The blobs that the batch data spans.
+    pub blobs: Vec<BlobEvaluationReport>,
+    /// Why the zstd payload cannot be proven, if it uses zstd features that the zstd decoder
+    /// circuit does not support. The batch data is then decoded with the reference zstd
+    /// implementation only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decoder_error: Option<String>,
+}
+
+/// The L2 transactions of a chunk.
+#[derive(Clone, Debug, Serialize)]
+pub struct ChunkReport {
+    /// The index of the chunk in the batch.
+    pub index: usize,
+    /// The number of chunk data bytes.
+    pub size: usize,
+    /// The keccak digest of the chunk data bytes, as committed to in the challenge digest.
+    pub chunk_data_digest: H256,
+    /// The L2 transactions of the chunk, in order.
+    pub txs: Vec<TxReport>,
+}
+
+/// An L2 transaction decoded from the chunk data.
+#[derive(Clone, Debug, Serialize)]
+pub struct TxReport {
+    /// The transaction hash, i.e. the keccak digest of its signed RLP encoding.
+    pub hash: H256,
+    /// The EIP-2718 transaction type, 0 for legacy transactions.
+    pub tx_type: u64,
+    /// The sender, as recovered from the signature.
+    pub from: Address,
+    /// The recipient, if not a contract creation.
+    pub to: Option<Address>,
+    /// The sender's nonce.
+    pub nonce: U256,
+    /// The size of the signed RLP encoding.
+    pub len: usize,
+}
+
+/// The commitment and point evaluation of a blob.
+#[derive(Clone, Debug, Serialize)]
+pub struct BlobEvaluationReport {
+    /// The versioned hash of the blob recomputed from the batch data.
+    pub versioned_hash: H256,
+    /// The versioned hash of the blob as given, if inspecting blobs rather than batch data bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_versioned_hash: Option<H256>,
+    /// The challenge digest, i.e. keccak(metadata_digest || chunk data digests || versioned_hash).
+    pub challenge_digest: U256,
+    /// The challenge point z, i.e. challenge_digest % BLS_MODULUS.
+    pub challenge: U256,
+    /// The evaluation y of the blob polynomial at z.
+    pub evaluation: U256,
+}
+
+impl BlobReport {
+    /// Inspect the 4844 blobs of a batch of the given version, in order.
+    ///
+    /// The batch data is decoded with the codec that the blob envelope is tagged with, or zstd for
+    /// batch versions without the codec tag, where zstd payloads are decoded with the witness
+    /// generator of the zstd decoder and cross-checked against the reference zstd implementation.
+    /// Zstd payloads that the zstd decoder circuit does not support are still reported, along with
+    /// a [`BlobReport::decoder_error`].
+    pub fn from_blobs<const N_SNARKS: usize>(blobs: &[Vec<u8>], batch_version: u8) -> Result<Self> {
+        let envelope = blobs
+            .iter()
+            .map(|blob| blob_to_bytes(blob))
+            .collect::<Result<Vec<_>>>()?
+            .concat();
+        let (codec, payload) = CodecTag::split_envelope(batch_version, &envelope)?;
+
+        let (batch_data_bytes, frame_len, decoder_error) = match codec {
+            CodecTag::Uncompressed => (payload.to_vec(), None, None),
+            CodecTag::Zstd => {
+                let (frame_len, unsupported) = zstd_frame_len(payload)?;
+                let frame = &payload[..frame_len];
+                let batch_data_bytes = ZstdCodec.decode(frame)?;
+                let unsupported = match unsupported {
+                    Some(unsupported) => Some(unsupported),
+                    None => decode_with_witgen(frame, &batch_data_bytes)?,
+                };
+                (
+                    batch_data_bytes,
+                    Some(frame_len),
+                    unsupported
+                        .map(|reason| format!("unsupported by the decoder circuit: {reason}")),
+                )
+            }
+        };
+
+        let mut report =
+            Self::from_batch_data_bytes::<N_SNARKS>(&batch_data_bytes, batch_version, codec)?;
+        if let Some(frame_len) = frame_len {
+            report.encoded_len = frame_len;
+        }
+        report.decoder_error = decoder_error;
+        if report.blobs.len() != blobs.len() {
+            return Err(invalid(format!(
+                "batch data spans {} blobs, {} given",
+                report.blobs.len(),
+                blobs.len()
+            )));
+        }
+        for (blob_report, blob) in report.blobs.iter_mut().zip(blobs) {
+            blob_report.input_versioned_hash = Some(blob_to_versioned_hash(blob));
+        }
+        Ok(report)
+    }
+
+    /// Inspect the batch data bytes, i.e. metadata || chunk data, as encoded in the blobs of a
+    /// batch of the given version with the given codec. For the uncompressed codec, the bytes may
+    /// be right-padded with zeros.
+    pub fn from_batch_data_bytes<const N_SNARKS: usize>(
+        batch_data_bytes: &[u8],
+        batch_version: u8,
+        codec: CodecTag,
+    ) -> Result<Self> {
+        codec.check_batch_version(batch_version)?;
+        let n_bytes_metadata = N_ROWS_NUM_CHUNKS + 4 * N_SNARKS;
+        if batch_data_bytes.len() < n_bytes_metadata {
+            return Err(invalid("batch data shorter than the metadata section"));
+        }
+        let (metadata, mut data) = batch_data_bytes.split_at(n_bytes_metadata);
+
+        let num_valid_chunks = u16::from_be_bytes([metadata[0], metadata[1]]);
+        if num_valid_chunks == 0 || num_valid_chunks as usize > N_SNARKS {
+            return Err(invalid(format!(
+                "number of valid chunks {num_valid_chunks} not in [1, {N_SNARKS}]"
+            )));
+        }
+        let chunk_sizes = metadata[N_ROWS_NUM_CHUNKS..]
+            .chunks(4)
+            .map(|size| u32::from_be_bytes(size.try_into().expect("4 bytes")))
+            .collect::<Vec<_>>();
+        if chunk_sizes[num_valid_chunks as usize..]
+            .iter()
+            .any(|&size| size != 0)
+        {
+            return Err(invalid("padded chunks with non-zero chunk size"));
+        }
+
+        let mut chunks = Vec::with_capacity(num_valid_chunks as usize);
+        for (index, &size) in chunk_sizes[..num_valid_chunks as usize].iter().enumerate() {
+            if data.len() < size as usize {
+                return Err(invalid(format!("chunk {index} exceeds the batch data")));
+            }
+            let (chunk, rest) = data.split_at(size as usize);
+            chunks.push(chunk.to_vec());
+            data = rest;
+        }
+        // only the uncompressed codec carries the blob padding along.
+        if data.iter().any(|&byte| byte != 0) || (codec == CodecTag::Zstd && !data.is_empty()) {
+            return Err(invalid(format!(
+                "{} trailing bytes after the last chunk",
+                data.len()
+            )));
+        }
+        let batch_data_len = batch_data_bytes.len() - data.len();
+        if batch_data_len - n_bytes_metadata > BatchData::<N_SNARKS>::n_rows_data() {
+            return Err(invalid("too many bytes in chunk data"));
+        }
+
+        let chunk_reports = chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                Ok(ChunkReport {
+                    index,
+                    size: chunk.len(),
+                    chunk_data_digest: H256(keccak256(chunk)),
+                    txs: decode_txs(chunk).map_err(|e| invalid(format!("chunk {index}: {e}")))?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let batch_data = BatchData::<N_SNARKS>::from(&chunks).with_codec(batch_version, codec);
+        let encoded_len =
+            batch_data.get_envelope_bytes().len() - usize::from(batch_data.has_codec_tag());
+        let blobs = (0..batch_data.num_blobs())
+            .map(|blob_idx| {
+                let point_evaluation = PointEvaluationAssignments::new(&batch_data, blob_idx);
+                BlobEvaluationReport {
+                    versioned_hash: batch_data.get_versioned_hash(blob_idx),
+                    input_versioned_hash: None,
+                    challenge_digest: point_evaluation.challenge_digest,
+                    challenge: point_evaluation.challenge,
+                    evaluation: point_evaluation.evaluation,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            batch_version,
+            codec,
+            encoded_len,
+            batch_data_len,
+            num_valid_chunks,
+            chunk_sizes,
+            metadata_digest: H256(keccak256(metadata)),
+            chunks: chunk_reports,
+            blobs,
+            decoder_error: None,
+        })
+    }
+}
+
+/// Get the bytes packed in a 4844 blob, i.e. the 31 least-significant bytes of each of its
+/// BLOB_WIDTH coefficients.
+pub fn blob_to_bytes(blob: &[u8]) -> Result<Vec<u8>> {
+    if blob.len() != BLOB_WIDTH * N_BYTES_U256 {
+        return Err(invalid(format!(
+            "blob of {} bytes, expected {}",
+            blob.len(),
+            BLOB_WIDTH * N_BYTES_U256
+        )));
+    }
+    let mut bytes = Vec::with_capacity(N_BLOB_BYTES);
+    for coeff in blob.chunks(N_BYTES_U256) {
+        if coeff[0] != 0 {
+            return Err(invalid(
+                "blob coefficient with non-zero most-significant byte",
+            ));
+        }
+        bytes.extend_from_slice(&coeff[1..]);
+    }
+    Ok(bytes)
+}
+
+/// Get the size of the zstd frame (without the magic number) at the start of the payload, i.e.
+/// excluding the zero bytes that pad the blob, along with the first feature of the frame that the
+/// zstd decoder circuit does not support, if any.
+///
+/// The zstd decoder only supports single-segment frames without a dictionary or a content
+/// checksum, made of compressed blocks with raw literals.
+fn zstd_frame_len(payload: &[u8]) -> Result<(usize, Option<String>)> {
+    let eof = || Error::new(ErrorKind::UnexpectedEof, "truncated zstd frame");
+    let mut unsupported = None;
+    let mut set_unsupported = |reason: String| {
+        unsupported.get_or_insert(reason);
+    };
+
+    let descriptor = *payload.first().ok_or_else(eof)?;
+    if descriptor & 0b0000_1000 != 0 {
+        return Err(invalid(
+            "reserved bit set in the zstd frame header descriptor",
+        ));
+    }
+    let is_single_segment = descriptor & 0b0010_0000 != 0;
+    let has_checksum = descriptor & 0b0000_0100 != 0;
+    let n_bytes_dictionary_id = [0, 1, 2, 4][(descriptor & 0b11) as usize];
+    if !is_single_segment || has_checksum || n_bytes_dictionary_id != 0 {
+        set_unsupported(format!("zstd frame header descriptor {descriptor:#010b}"));
+    }
+    let n_bytes_fcs = match descriptor >> 6 {
+        0 => usize::from(is_single_segment),
+        flag => [2, 4, 8][flag as usize - 1],
+    };
+
+    let mut offset = 1 + usize::from(!is_single_segment) + n_bytes_dictionary_id + n_bytes_fcs;
+    loop {
+        let header = payload.get(offset..offset + 3).ok_or_else(eof)?;
+        let header = u32::from_le_bytes([header[0], header[1], header[2], 0]);
+        let is_last_block = header & 1 == 1;
+        let block_size = (header >> 3) as usize;
+        let n_bytes_content = match (header >> 1) & 0b11 {
+            0 => {
+                set_unsupported("raw zstd block".to_string());
+                block_size
+            }
+            1 => {
+                set_unsupported("RLE zstd block".to_string());
+                1
+            }
+            2 => {
+                let literals_header = *payload.get(offset + 3).ok_or_else(eof)?;
+                if literals_header & 0b11 != 0 {
+                    set_unsupported(format!(
+                        "zstd literals section of type {}",
+                        literals_header & 0b11
+                    ));
+                }
+                block_size
+            }
+            _ => return Err(invalid("reserved zstd block type")),
+        };
+        offset += 3 + n_bytes_content;
+        if is_last_block {
+            break;
+        }
+    }
+    if has_checksum {
+        offset += 4;
+    }
+    if offset > payload.len() {
+        return Err(eof());
+    }
+    Ok((offset, unsupported))
+}
+
+/// Decode a zstd frame with the witness generator of the zstd decoder, cross-checked against the
+/// bytes decoded by the reference zstd implementation. Returns why the zstd decoder does not
+/// support the frame if its witness generator fails on it.
+fn decode_with_witgen(encoded: &[u8], expected: &[u8]) -> Result<Option<String>> {
+    // the randomness only affects the RLCs in the witness rows, which are not inspected.
+    let Ok(result) = catch_unwind(|| process::<Fr>(encoded, Value::known(Fr::from(0x100)))) else {
+        return Ok(Some("zstd decoder witness generation failed".to_string()));
+    };
+    let decoded = result
+        .sequence_exec_results
+        .into_iter()
+        .flat_map(|res| res.recovered_bytes)
+        .collect::<Vec<_>>();
+    if decoded != expected {
+        return Err(invalid(format!(
+            "zstd decoder witgen recovered {} bytes, mismatching the {} bytes decoded by zstd",
+            decoded.len(),
+            expected.len()
+        )));
+    }
+    Ok(None)
+}
+
+/// Decode the concatenated signed RLP encodings of the L2 transactions in a chunk.
+fn decode_txs(mut chunk: &[u8]) -> Result<Vec<TxReport>> {
+    let mut txs = vec![];
+    while !chunk.is_empty() {
+        // typed transactions are prefixed with their type, legacy transactions are RLP lists.
+        let n_bytes_type = match chunk[0] {
+            0..=0x7f => 1,
+            0xc0..=0xff => 0,
+            byte => return Err(invalid(format!("unexpected tx prefix {byte:#04x}"))),
+        };
+        let payload_info = Rlp::new(&chunk[n_bytes_type..])
+            .payload_info()
+            .map_err(|e| invalid(format!("tx {}: {e}", txs.len())))?;
+        let len = n_bytes_type + payload_info.total();
+        if len > chunk.len() {
+            return Err(invalid(format!("tx {} exceeds the chunk", txs.len())));
+        }
+        let (tx_bytes, rest) = chunk.split_at(len);
+
+        let tx = Transaction::decode(&Rlp::new(tx_bytes))
+            .map_err(|e| invalid(format!("tx {}: {e}", txs.len())))?;
+        let from = tx
+            .recover_from()
+            .map_err(|e| invalid(format!("tx {}: {e}", txs.len())))?;
+        txs.push(TxReport {
+            hash: H256(keccak256(tx_bytes)),
+            tx_type: tx.transaction_type.map_or(0, |tx_type| tx_type.as_u64()),
+            from,
+            to: tx.to,
+            nonce: tx.nonce,
+            len,
+        });
+        chunk = rest;
+    }
+    Ok(txs)
+}
+
+fn invalid(msg: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BATCH_VERSION_CODEC_TAG, MAX_AGG_SNARKS};
+    use eth_types::ToBigEndian;
+    use std::{io::Write, str::FromStr};
+    use zstd_encoder::zstd::stream::Encoder;
+
+    // https://etherscan.io/getRawTx?tx=0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060
+    const RAW_TX: &str = "f86780862d79883d2000825208945df9b87991262f6ba471f09758cde1c0fc1de734827a69801ca088ff6cf0fefd94db46111149ae4bfc179e9b94721fffd821d38d16464b3f71d0a045e0aff800961cfce805daef7016b9b675c137a6a41a548f7b60a3484c06a33a";
+
+    fn to_blobs(batch_data: &BatchData<MAX_AGG_SNARKS>) -> Vec<Vec<u8>> {
+        (0..batch_data.num_blobs())
+            .map(|blob_idx| {
+                batch_data
+                    .get_coefficients(blob_idx)
+                    .iter()
+                    .flat_map(|coeff| coeff.to_be_bytes())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inspect_blobs() -> Result<()> {
+        let tx = hex::decode(RAW_TX).unwrap();
+        let chunks = vec![[tx.clone(), tx.clone()].concat(), vec![], tx];
+
+        for (batch_version, codec) in [
+            (BATCH_VERSION_CODEC_TAG, CodecTag::Uncompressed),
+            (BATCH_VERSION_CODEC_TAG, CodecTag::Zstd),
+            (4, CodecTag::Zstd),
+        ] {
+            let batch_data =
+                BatchData::<MAX_AGG_SNARKS>::from(&chunks).with_codec(batch_version, codec);
+            let report =
+                BlobReport::from_blobs::<MAX_AGG_SNARKS>(&to_blobs(&batch_data), batch_version)?;
+
+            assert_eq!(report.codec, codec);
+            assert_eq!(report.decoder_error, None);
+            assert_eq!(
+                report.encoded_len,
+                codec
+                    .codec()
+                    .encode(&batch_data.get_batch_data_bytes())
+                    .len()
+            );
+            assert_eq!(
+                report.batch_data_len,
+                batch_data.get_batch_data_bytes().len()
+            );
+            assert_eq!(report.num_valid_chunks, 3);
+            assert_eq!(
+                report
+                    .chunks
+                    .iter()
+                    .map(|c| c.txs.len())
+                    .collect::<Vec<_>>(),
+                [2, 0, 1]
+            );
+            let tx_report = &report.chunks[2].txs[0];
+            assert_eq!(
+                tx_report.hash,
+                H256::from_str(
+                    "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060"
+                )
+                .unwrap()
+            );
+            assert_eq!(tx_report.tx_type, 0);
+
+            let point_evaluation = PointEvaluationAssignments::from(&batch_data);
+            assert_eq!(report.blobs.len(), 1);
+            assert_eq!(
+                Some(report.blobs[0].versioned_hash),
+                report.blobs[0].input_versioned_hash
+            );
+            assert_eq!(report.blobs[0].challenge, point_evaluation.challenge);
+            assert_eq!(report.blobs[0].evaluation, point_evaluation.evaluation);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn inspect_unsupported_zstd_frame() -> Result<()> {
+        let tx = hex::decode(RAW_TX).unwrap();
+        let batch_data = BatchData::<MAX_AGG_SNARKS>::from(&vec![tx.clone(), tx]);
+        let batch_data_bytes = batch_data.get_batch_data_bytes();
+
+        // a frame with a content checksum, which the zstd decoder does not support.
+        let mut encoder = Encoder::new(Vec::new(), 3)?;
+        encoder.include_checksum(true)?;
+        encoder.include_magicbytes(false)?;
+        encoder.write_all(&batch_data_bytes)?;
+        let frame = encoder.finish()?;
+
+        let envelope = [&[CodecTag::Zstd as u8], frame.as_slice()].concat();
+        let mut blob = vec![0; BLOB_WIDTH * N_BYTES_U256];
+        for (coeff, bytes) in blob
+            .chunks_mut(N_BYTES_U256)
+            .zip(envelope.chunks(N_BYTES_U256 - 1))
+        {
+            coeff[1..=bytes.len()].copy_from_slice(bytes);
+        }
+
+        let report = BlobReport::from_blobs::<MAX_AGG_SNARKS>(&[blob], BATCH_VERSION_CODEC_TAG)?;
+        assert!(report
+            .decoder_error
+            .is_some_and(|e| e.starts_with("unsupported by the decoder circuit")));
+        assert_eq!(report.encoded_len, frame.len());
+        assert_eq!(report.batch_data_len, batch_data_bytes.len());
+        assert_eq!(
+            report
+                .chunks
+                .iter()
+                .map(|c| c.txs.len())
+                .collect::<Vec<_>>(),
+            [1, 1]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn inspect_invalid() {
+        let batch_data = BatchData::<MAX_AGG_SNARKS>::default();
+        let bytes = batch_data.get_batch_data_bytes();
+
+        // no valid chunks.
+        let mut invalid_bytes = bytes.clone();
+        invalid_bytes[1] = 0;
+        assert!(BlobReport::from_batch_data_bytes::<MAX_AGG_SNARKS>(
+            &invalid_bytes,
+            4,
+            CodecTag::Zstd
+        )
+        .is_err());
+        // trailing bytes.
+        let invalid_bytes = [bytes, vec![0]].concat();
+        assert!(BlobReport::from_batch_data_bytes::<MAX_AGG_SNARKS>(
+            &invalid_bytes,
+            4,
+            CodecTag::Zstd
+        )
+        .is_err());
+        // uncompressed batch data in a batch version without the codec tag.
+        assert!(BlobReport::from_batch_data_bytes::<MAX_AGG_SNARKS>(
+            &batch_data.get_batch_data_bytes(),
+            4,
+            CodecTag::Uncompressed
+        )
+        .is_err());
+        // non-canonical blob.
+        let mut blobs = to_blobs(&batch_data);
+        blobs[0][0] = 1;
+        assert!(BlobReport::from_blobs::<MAX_AGG_SNARKS>(&blobs, 4).is_err());
+    }
+}
@@ -6,6 +6,8 @@ mod aggregation;
 mod batch;
 /// blob struct and constants
 mod blob;
+/// codecs for the batch data carried in a blob
+mod codec;
 /// Config to recursive aggregate multiple aggregations
 mod recursion;
 // This module implements `Chunk` related data types.
@@ -17,21 +19,30 @@ mod compression;
 mod constants;
 /// Core module for circuit assignment
 mod core;
+/// Inspection of the batch data carried in blobs
+mod inspect;
 /// Parameters for compression circuit
 mod param;
 /// utilities
 mod util;
+/// Inclusion proofs of withdrawal messages in the withdraw trie
+mod withdraw;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::core::extract_proof_and_instances_with_pairing_check;
 pub use aggregation::*;
-pub use batch::{BatchHash, BatchHeader};
-pub use blob::BatchData;
+pub use batch::{BatchHash, BatchHeader, BatchHeaderCodec, BlobCommitment};
+pub use blob::{BatchData, MAX_BLOBS_PER_BATCH};
 pub use chunk::ChunkInfo;
+pub use codec::{
+    has_codec_tag, BlobCodec, CodecTag, UncompressedCodec, ZstdCodec, BATCH_VERSION_CODEC_TAG,
+};
 pub use compression::*;
-pub use constants::MAX_AGG_SNARKS;
 pub(crate) use constants::*;
+pub use constants::{MAX_AGG_SNARKS, MAX_CHUNK_VKS};
+pub use inspect::{blob_to_bytes, BlobEvaluationReport, BlobReport, ChunkReport, TxReport};
 pub use param::*;
 pub use recursion::*;
+pub use withdraw::{prove_withdrawal, WithdrawProofError, WithdrawTrie, WithdrawalProof};
@@ -17,6 +17,7 @@ mod util;
 
 pub use circuit::RecursionCircuit;
 pub(crate) use common::dynamic_verify;
+pub use common::preprocessed_digest;
 pub use util::{gen_recursion_pk, initial_recursion_snark};
 
 use halo2_proofs::{
@@ -113,4 +114,18 @@ pub trait StateTransition: Sized {
         let end = Self::num_instance();
         (start..end).collect()
     }
+
+    /// The indices, among the additional instance cells, of the cells that must hold the same
+    /// value in every round of recursion.
+    fn invariant_indices() -> Vec<usize> {
+        vec![]
+    }
+
+    /// The pairs of indices, among the additional instance cells, of a field before and after
+    /// the transition, e.g. a rolling hash that the transition updates. The field before each
+    /// transition must be the field after the previous one, and the recursion circuit exposes the
+    /// field before the first transition.
+    fn chained_indices() -> Vec<(usize, usize)> {
+        vec![]
+    }
 }
@@ -3,6 +3,7 @@ use std::{fs::File, iter, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
     circuit::{Cell, Layouter, SimpleFloorPlanner, Value},
+    halo2curves::group::ff::PrimeField,
     poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
 };
 use snark_verifier::{
@@ -11,10 +12,7 @@ use snark_verifier::{
         kzg::{Bdfg21, Kzg, KzgAccumulator, KzgAs, KzgSuccinctVerifyingKey},
         AccumulationScheme, AccumulationSchemeProver,
     },
-    util::{
-        arithmetic::{fe_to_fe, fe_to_limbs},
-        hash,
-    },
+    util::arithmetic::fe_to_limbs,
 };
 use snark_verifier_sdk::{
     types::{Halo2Loader, Plonk},
@@ -159,28 +157,32 @@ impl<ST: StateTransition> RecursionCircuit<ST> {
                 .collect::<Vec<_>>()
         };
 
+        // The "additional" state follows the initial and the current state. The fields before
+        // the transition among it are propagated from the first round, like the initial state.
+        let index_additional_state = Self::INITIAL_STATE_ROW + 2 * ST::num_transition_instance();
+        let chained_prev_indices = ST::chained_indices()
+            .into_iter()
+            .map(|(prev, _)| prev)
+            .collect_vec();
         let state_instances = ST::state_indices()
             .into_iter()
-            .map(|i| &app.instances[0][i])
+            .map(|i| app.instances[0][i])
             .chain(
                 ST::additional_indices()
                     .into_iter()
-                    .map(|i| &app.instances[0][i]),
+                    .enumerate()
+                    .map(|(offset, i)| {
+                        if round > 0 && chained_prev_indices.contains(&i) {
+                            // pick from prev snark
+                            previous.instances[0][index_additional_state + offset]
+                        } else {
+                            // pick from app
+                            app.instances[0][i]
+                        }
+                    }),
             );
 
-        let preprocessed_digest = {
-            let inputs = previous
-                .protocol
-                .preprocessed
-                .iter()
-                .flat_map(|preprocessed| [preprocessed.x, preprocessed.y])
-                .map(fe_to_fe)
-                .chain(previous.protocol.transcript_initial_state)
-                .collect_vec();
-            let mut hasher = hash::Poseidon::from_spec(&NativeLoader, POSEIDON_SPEC.clone());
-            hasher.update(&inputs);
-            hasher.squeeze()
-        };
+        let preprocessed_digest = preprocessed_digest(&previous.protocol);
 
         let instances = [
             accumulator.lhs.x,
@@ -192,7 +194,7 @@ impl<ST: StateTransition> RecursionCircuit<ST> {
         .flat_map(fe_to_limbs::<_, _, LIMBS, BITS>)
         .chain(iter::once(preprocessed_digest))
         .chain(init_instances)
-        .chain(state_instances.copied())
+        .chain(state_instances)
         .chain(iter::once(Fr::from(round as u64)))
         .collect();
 
@@ -241,6 +243,16 @@ impl<ST: StateTransition> RecursionCircuit<ST> {
         // ]
         4 * LIMBS + 2 * ST::num_transition_instance() + ST::num_additional_instance() + 2
     }
+
+    /// Returns the number of application SNARKs recursed over by the [`RecursionCircuit`] with the
+    /// given instances. Every round of recursion verifies exactly one application SNARK, hence the
+    /// recursion can be finalised after any number of rounds without padding.
+    pub fn num_batches(instances: &[Fr]) -> usize {
+        assert_eq!(instances.len(), Self::num_instance_fixed());
+        let round = instances[Self::num_instance_fixed() - 1].to_repr();
+        assert!(round[8..].iter().all(|&byte| byte == 0), "invalid round");
+        u64::from_le_bytes(round[..8].try_into().expect("8 bytes")) as usize + 1
+    }
 }
 
 impl<ST: StateTransition> Circuit<Fr> for RecursionCircuit<ST> {
@@ -425,20 +437,21 @@ impl<ST: StateTransition> Circuit<Fr> for RecursionCircuit<ST> {
                     .collect::<Vec<_>>();
 
                 // Verify that the current "state" is the same as the state defined in the
-                // application SNARK.
+                // application SNARK, except for the chained fields before the transition, which
+                // are propagated like the initial state.
+                let chained_prev_indices = ST::chained_indices()
+                    .into_iter()
+                    .map(|(prev, _)| prev)
+                    .collect_vec();
                 let verify_app_state = state
                     .iter()
                     .zip_eq(
                         ST::state_indices()
                             .into_iter()
-                            .map(|i| &app_instances[i])
-                            .chain(
-                                ST::additional_indices()
-                                    .into_iter()
-                                    .map(|i| &app_instances[i]),
-                            ),
+                            .chain(ST::additional_indices()),
                     )
-                    .map(|(&st, &app_inst)| ("passing cur state to app", st, app_inst))
+                    .filter(|(_, i)| !chained_prev_indices.contains(i))
+                    .map(|(&st, i)| ("passing cur state to app", st, app_instances[i]))
                     .collect::<Vec<_>>();
 
                 // Verify that the "previous state" (additional state not included) is the same
@@ -460,6 +473,72 @@ impl<ST: StateTransition> Circuit<Fr> for RecursionCircuit<ST> {
                     })
                     .collect::<Vec<_>>();
 
+                // Verify that the invariant fields among the additional state are the same as in
+                // the previous round. This check is meaningful only in subsequent recursion rounds
+                // after the first round.
+                let verify_app_invariant = ST::invariant_indices()
+                    .into_iter()
+                    .map(|i| {
+                        let offset = ST::additional_indices()
+                            .into_iter()
+                            .position(|j| j == i)
+                            .expect("invariant indices are among the additional indices");
+                        (
+                            "invariant state equal to prev_recursion's (not first round)",
+                            main_gate.mul(
+                                &mut ctx,
+                                Existing(app_instances[i]),
+                                Existing(not_first_round),
+                            ),
+                            previous_instances[index_additional_state + offset],
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                // Propagate the chained fields before the transition, and verify that the field
+                // before the transition of the application SNARK is the same as the field after the
+                // transition in the previous round. The latter check is meaningful only in
+                // subsequent recursion rounds after the first round.
+                let additional_offset = |i: usize| {
+                    ST::additional_indices()
+                        .into_iter()
+                        .position(|j| j == i)
+                        .expect("chained indices are among the additional indices")
+                };
+                let verify_app_chained = ST::chained_indices()
+                    .into_iter()
+                    .flat_map(|(prev, post)| {
+                        let prev_offset = additional_offset(prev);
+                        let post_offset = additional_offset(post);
+                        let st = state[ST::num_transition_instance() + prev_offset];
+                        [
+                            (
+                                "chained state equal to app's (first round)",
+                                main_gate.mul(&mut ctx, Existing(st), Existing(first_round)),
+                                main_gate.mul(
+                                    &mut ctx,
+                                    Existing(app_instances[prev]),
+                                    Existing(first_round),
+                                ),
+                            ),
+                            (
+                                "chained state equal to prev_recursion's (not first round)",
+                                main_gate.mul(&mut ctx, Existing(st), Existing(not_first_round)),
+                                previous_instances[index_additional_state + prev_offset],
+                            ),
+                            (
+                                "chain app's state with prev_recursion's (not first round)",
+                                main_gate.mul(
+                                    &mut ctx,
+                                    Existing(app_instances[prev]),
+                                    Existing(not_first_round),
+                                ),
+                                previous_instances[index_additional_state + post_offset],
+                            ),
+                        ]
+                    })
+                    .collect::<Vec<_>>();
+
                 // Finally apply the equality constraints between the (LHS, RHS) values constructed
                 // above.
                 for (comment, lhs, rhs) in [
@@ -488,6 +567,8 @@ impl<ST: StateTransition> Circuit<Fr> for RecursionCircuit<ST> {
                 .chain(initial_state_propagate)
                 .chain(verify_app_state)
                 .chain(verify_app_init_state)
+                .chain(verify_app_invariant)
+                .chain(verify_app_chained)
                 {
                     use halo2_proofs::dev::unwrap_value;
                     debug_assert_eq!(
@@ -3,7 +3,8 @@ use std::rc::Rc;
 use snark_verifier::{
     loader::halo2::EccInstructions,
     pcs::{kzg::KzgAccumulator, MultiOpenScheme, PolynomialCommitmentScheme},
-    util::hash,
+    util::{arithmetic::fe_to_fe, hash},
+    Protocol,
 };
 use snark_verifier_sdk::{
     types::{BaseFieldEccChip, Halo2Loader, Plonk},
@@ -20,6 +21,20 @@ fn poseidon<L: Loader<G1Affine>>(loader: &L, inputs: &[L::LoadedScalar]) -> L::L
     hasher.squeeze()
 }
 
+/// The digest of the preprocessed part of a protocol, i.e. its preprocessed commitments and the
+/// initial state of its transcript, as checked by [`dynamic_verify`]. The digest identifies the
+/// verifying key of a circuit among circuits of the same shape.
+pub fn preprocessed_digest(protocol: &Protocol<G1Affine>) -> Fr {
+    let inputs = protocol
+        .preprocessed
+        .iter()
+        .flat_map(|preprocessed| [preprocessed.x, preprocessed.y])
+        .map(fe_to_fe)
+        .chain(protocol.transcript_initial_state)
+        .collect_vec();
+    poseidon(&NativeLoader, &inputs)
+}
+
 /// It is similar to `succinct_verify` method inside of snark-verifier
 /// but allow it allow loader to load preprocessed part as witness (so ANY circuit)
 /// can be verified.
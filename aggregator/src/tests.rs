@@ -165,6 +165,7 @@ macro_rules! aggregation_layer_snark {
             $previous_snarks.as_ref(),
             &mut rng,
             $chunks.as_ref(),
+            &[$crate::preprocessed_digest(&$previous_snarks[0].protocol)],
         );
 
         let pk = gen_pk(&$param, &batch_circuit, None);
@@ -1,6 +1,7 @@
 use std::{fs, path::Path, process};
 
 use ark_std::{end_timer, start_timer, test_rng};
+use gadgets::util::split_h256;
 use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr, poly::commitment::Params};
 use itertools::Itertools;
 use snark_verifier::loader::halo2::halo2_ecc::halo2_base::utils::fs::gen_srs;
@@ -9,8 +10,12 @@ use snark_verifier_sdk::{gen_pk, gen_snark_shplonk, verify_snark_shplonk, Circui
 use crate::{
     aggregation::BatchCircuit,
     batch::{BatchHash, BatchHeader},
-    constants::MAX_AGG_SNARKS,
+    constants::{
+        MAX_AGG_SNARKS, MAX_CHUNK_VKS, PI_CHUNK_VK_DIGESTS, PI_CHUNK_VK_INDICES,
+        PI_POST_L1_MSG_HASH, PI_PREV_L1_MSG_HASH,
+    },
     layer_0,
+    recursion::preprocessed_digest,
     tests::mock_chunk::MockChunkCircuit,
     ChunkInfo,
 };
@@ -136,10 +141,83 @@ fn test_batch_circuit_variadic() {
     assert_eq!(prover1.permutation(), prover2.permutation());
 }
 
+#[test]
+#[ignore = "it takes too much time"]
+fn test_batch_circuit_chunk_vk_whitelist() {
+    // the chunk vk is not whitelisted
+    assert!(try_build_new_batch_circuit::<2>(1, |_| vec![Fr::one()]).is_err());
+    // too many chunk vks are whitelisted
+    assert!(try_build_new_batch_circuit::<2>(1, |digest| vec![digest; MAX_CHUNK_VKS + 1]).is_err());
+
+    // the index of the chunk vk is committed to for each snark
+    let k = 21;
+    let circuit: BatchCircuit<2> =
+        try_build_new_batch_circuit(1, |digest| vec![Fr::one(), digest]).unwrap();
+    assert_eq!(circuit.chunk_vk_indices, vec![1, 1]);
+    let instance = circuit.instances();
+    assert_eq!(
+        instance[0][PI_CHUNK_VK_INDICES],
+        Fr::from(1 + MAX_CHUNK_VKS as u64)
+    );
+    // the whitelist is public, padded with zeros
+    assert_eq!(
+        instance[0][PI_CHUNK_VK_DIGESTS..PI_CHUNK_VK_DIGESTS + MAX_CHUNK_VKS],
+        circuit.chunk_vk_digests
+    );
+    assert_eq!(
+        circuit.chunk_vk_digests[2..],
+        [Fr::zero(); MAX_CHUNK_VKS - 2]
+    );
+    let mock_prover = MockProver::<Fr>::run(k, &circuit, instance.clone()).unwrap();
+    mock_prover.assert_satisfied_par();
+
+    // the snarks are not verified against a different whitelist
+    let mut instance = instance;
+    instance[0][PI_CHUNK_VK_DIGESTS + 1] = Fr::one();
+    let mock_prover = MockProver::<Fr>::run(k, &circuit, instance).unwrap();
+    assert!(mock_prover.verify_par().is_err());
+}
+
+#[test]
+#[ignore = "it takes too much time"]
+fn test_batch_circuit_l1_msg_hashes() {
+    let k = 21;
+    let circuit: BatchCircuit<4> = build_new_batch_circuit(2, k);
+    let chunks = &circuit.batch_hash.chunks_with_padding;
+    let instance = circuit.instances();
+    // the rolling hash before the first chunk and after the last valid one are public
+    for (pi_index, l1_msg_hash) in [
+        (PI_PREV_L1_MSG_HASH, chunks[0].prev_l1_msg_hash),
+        (PI_POST_L1_MSG_HASH, chunks[1].post_l1_msg_hash),
+    ] {
+        let (hi, lo): (Fr, Fr) = split_h256(l1_msg_hash);
+        assert_eq!(instance[0][pi_index..pi_index + 2], [hi, lo]);
+    }
+    let mock_prover = MockProver::<Fr>::run(k, &circuit, instance.clone()).unwrap();
+    mock_prover.assert_satisfied_par();
+
+    // the public l1 msg hashes are those of the chunks
+    for pi_index in [PI_PREV_L1_MSG_HASH, PI_POST_L1_MSG_HASH + 1] {
+        let mut instance = instance.clone();
+        instance[0][pi_index] += Fr::one();
+        let mock_prover = MockProver::<Fr>::run(k, &circuit, instance).unwrap();
+        assert!(mock_prover.verify_par().is_err());
+    }
+}
+
 fn build_new_batch_circuit<const N_SNARKS: usize>(
     num_real_chunks: usize,
     _k: u32,
 ) -> BatchCircuit<N_SNARKS> {
+    try_build_new_batch_circuit(num_real_chunks, |digest| vec![digest]).unwrap()
+}
+
+/// Build a batch circuit whose chunk vk whitelist is derived from the digest of the mock chunk
+/// circuit's vk.
+fn try_build_new_batch_circuit<const N_SNARKS: usize>(
+    num_real_chunks: usize,
+    chunk_vk_digests: impl FnOnce(Fr) -> Vec<Fr>,
+) -> Result<BatchCircuit<N_SNARKS>, snark_verifier::Error> {
     // inner circuit: Mock circuit
     let k0 = 8;
 
@@ -151,6 +229,13 @@ fn build_new_batch_circuit<const N_SNARKS: usize>(
         .collect_vec();
     for i in 0..num_real_chunks - 1 {
         chunks_without_padding[i + 1].prev_state_root = chunks_without_padding[i].post_state_root;
+        let num_l1_msgs = chunks_without_padding[i + 1].end_l1_queue_index
+            - chunks_without_padding[i + 1].start_l1_queue_index;
+        chunks_without_padding[i + 1].start_l1_queue_index =
+            chunks_without_padding[i].end_l1_queue_index;
+        chunks_without_padding[i + 1].end_l1_queue_index =
+            chunks_without_padding[i].end_l1_queue_index + num_l1_msgs;
+        chunks_without_padding[i + 1].prev_l1_msg_hash = chunks_without_padding[i].post_l1_msg_hash;
     }
     let padded_chunk =
         ChunkInfo::mock_padded_chunk_info_for_testing(&chunks_without_padding[num_real_chunks - 1]);
@@ -186,13 +271,24 @@ fn build_new_batch_circuit<const N_SNARKS: usize>(
     // ==========================
     // batch
     // ==========================
-    let batch_hash = BatchHash::construct(&chunks_with_padding, BatchHeader::default());
+    let batch_header = BatchHeader::construct_from_chunks(
+        3,
+        0,
+        0,
+        0,
+        Default::default(),
+        0,
+        &chunks_with_padding,
+        Default::default(),
+    );
+    let batch_hash = BatchHash::construct(&chunks_with_padding, batch_header);
+    let chunk_vk_digests = chunk_vk_digests(preprocessed_digest(&real_snarks[0].protocol));
 
     BatchCircuit::new(
         &params,
         [real_snarks, padded_snarks].concat().as_ref(),
         rng,
         batch_hash,
+        &chunk_vk_digests,
     )
-    .unwrap()
 }
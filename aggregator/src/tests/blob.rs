@@ -1,10 +1,13 @@
 use crate::{
     aggregation::{
-        AssignedBarycentricEvaluationConfig, BarycentricEvaluationConfig, BlobDataConfig, RlcConfig,
+        AssignedBarycentricEvaluationConfig, AssignedDecoderConfigExports,
+        BarycentricEvaluationConfig, BlobDataConfig, RlcConfig,
+    },
+    blob::{
+        BatchData, PointEvaluationAssignments, MAX_BLOBS_PER_BATCH, N_BLOB_BYTES, N_BYTES_U256,
     },
-    blob::{BatchData, PointEvaluationAssignments, N_BYTES_U256},
     param::ConfigParams,
-    BatchDataConfig, MAX_AGG_SNARKS,
+    BatchDataConfig, CodecTag, ZstdCodec, BATCH_VERSION_CODEC_TAG, MAX_AGG_SNARKS,
 };
 use halo2_base::{
     gates::range::{RangeConfig, RangeStrategy},
@@ -14,7 +17,7 @@ use halo2_proofs::{
     circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
     dev::{MockProver, VerifyFailure},
     halo2curves::bn256::Fr,
-    plonk::{Circuit, ConstraintSystem, Error},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, SecondPhase},
 };
 use zkevm_circuits::{
     table::{KeccakTable, RangeTable, U8Table},
@@ -25,9 +28,12 @@ use zkevm_circuits::{
 struct BlobCircuit {
     data: BatchData<MAX_AGG_SNARKS>,
 
+    overwrite_batch_version: Option<u8>,
+    overwrite_decoded_rlc: bool,
     overwrite_num_valid_chunks: bool,
     overwrite_challenge_digest: Option<usize>,
     overwrite_chunk_data_digests: Option<(usize, usize)>,
+    overwrite_versioned_hashes: Option<(usize, usize)>,
     overwrite_chunk_idx: Option<usize>,
     overwrite_accumulator: Option<usize>,
     overwrite_preimage_rlc: Option<usize>,
@@ -46,6 +52,9 @@ struct BlobConfig {
     batch_data_config: BatchDataConfig<MAX_AGG_SNARKS>,
     blob_data: BlobDataConfig<MAX_AGG_SNARKS>,
     barycentric: BarycentricEvaluationConfig,
+
+    /// Stands in for the zstd decoder's exports.
+    decoder_exports: Column<Advice>,
 }
 
 impl Circuit<Fr> for BlobCircuit {
@@ -86,6 +95,9 @@ impl Circuit<Fr> for BlobCircuit {
         );
         let blob_data = BlobDataConfig::configure(meta, &challenge_expressions, u8_table);
 
+        let decoder_exports = meta.advice_column_in(SecondPhase);
+        meta.enable_equality(decoder_exports);
+
         BlobConfig {
             challenges,
 
@@ -95,6 +107,8 @@ impl Circuit<Fr> for BlobCircuit {
             batch_data_config,
             blob_data,
             barycentric,
+
+            decoder_exports,
         }
     }
 
@@ -112,10 +126,10 @@ impl Circuit<Fr> for BlobCircuit {
         let mut first_pass = halo2_base::SKIP_FIRST_PASS;
         let barycentric_assignments = layouter.assign_region(
             || "barycentric config",
-            |region| -> Result<AssignedBarycentricEvaluationConfig, Error> {
+            |region| -> Result<Vec<AssignedBarycentricEvaluationConfig>, Error> {
                 if first_pass {
                     first_pass = false;
-                    return Ok(AssignedBarycentricEvaluationConfig::default());
+                    return Ok(vec![]);
                 }
 
                 let gate = &config.barycentric.scalar.range.gate;
@@ -128,13 +142,17 @@ impl Circuit<Fr> for BlobCircuit {
                     },
                 );
 
-                let point_eval = PointEvaluationAssignments::from(&self.data);
-                Ok(config.barycentric.assign(
-                    &mut ctx,
-                    &point_eval.coefficients,
-                    point_eval.challenge_digest,
-                    point_eval.evaluation,
-                ))
+                Ok((0..MAX_BLOBS_PER_BATCH)
+                    .map(|blob_idx| {
+                        let point_eval = PointEvaluationAssignments::new(&self.data, blob_idx);
+                        config.barycentric.assign(
+                            &mut ctx,
+                            &point_eval.coefficients,
+                            point_eval.challenge_digest,
+                            point_eval.evaluation,
+                        )
+                    })
+                    .collect())
             },
         )?;
 
@@ -161,15 +179,70 @@ impl Circuit<Fr> for BlobCircuit {
 
         config.batch_data_config.load_range_tables(&mut layouter)?;
 
-        config.blob_data.assign(
+        let (batch_version, decoder_exports) = layouter.assign_region(
+            || "dev: batch version and decoder exports",
+            |mut region| -> Result<(AssignedCell<Fr, Fr>, AssignedDecoderConfigExports), Error> {
+                let rlc_config = &config.rlc;
+                rlc_config.init(&mut region)?;
+                let mut rlc_config_offset = 0;
+
+                let batch_version = self
+                    .overwrite_batch_version
+                    .unwrap_or(self.data.batch_version);
+                let batch_version = rlc_config.load_private(
+                    &mut region,
+                    &Fr::from(batch_version as u64),
+                    &mut rlc_config_offset,
+                )?;
+
+                // what the zstd decoder exports when decoding the batch data bytes, with the
+                // same off by one encoded_len.
+                let batch_data_bytes = self.data.get_batch_data_bytes();
+                let encoded_bytes = ZstdCodec.encode(&batch_data_bytes);
+                let r = challenge_values.keccak_input();
+                let mut decoded_rlc = rlc(&batch_data_bytes, r);
+                if self.overwrite_decoded_rlc {
+                    decoded_rlc = decoded_rlc + Value::known(Fr::one());
+                }
+                let [encoded_rlc, encoded_len, decoded_rlc, decoded_len] = [
+                    rlc(&encoded_bytes, r),
+                    Value::known(Fr::from(encoded_bytes.len() as u64 + 1)),
+                    decoded_rlc,
+                    Value::known(Fr::from(batch_data_bytes.len() as u64)),
+                ]
+                .into_iter()
+                .enumerate()
+                .map(|(offset, value)| {
+                    region.assign_advice(
+                        || "decoder export",
+                        config.decoder_exports,
+                        offset,
+                        || value,
+                    )
+                })
+                .collect::<Result<Vec<_>, Error>>()?
+                .try_into()
+                .unwrap();
+                let decoder_exports = AssignedDecoderConfigExports {
+                    encoded_rlc,
+                    encoded_len,
+                    decoded_rlc,
+                    decoded_len,
+                };
+
+                Ok((batch_version, decoder_exports))
+            },
+        )?;
+
+        let blob_data_exports = config.blob_data.assign(
             &mut layouter,
             challenge_values,
             &config.rlc,
             &self.data,
-            &barycentric_assignments.barycentric_assignments,
+            &barycentric_assignments,
         )?;
 
-        layouter.assign_region(
+        let batch_data_exports = layouter.assign_region(
             || "BatchDataConfig",
             |mut region| {
                 let assigned_rows = config.batch_data_config.assign_rows(
@@ -182,7 +255,8 @@ impl Circuit<Fr> for BlobCircuit {
                     challenge_values,
                     &config.rlc,
                     &chunks_are_padding,
-                    &barycentric_assignments.barycentric_assignments,
+                    &self.data,
+                    &barycentric_assignments,
                     &assigned_rows,
                 )?;
 
@@ -220,12 +294,33 @@ impl Circuit<Fr> for BlobCircuit {
                         &assigned_batch_data_export.chunk_data_digests[i][j],
                     )?;
                 }
-                Ok(())
+                if let Some((i, j)) = self.overwrite_versioned_hashes {
+                    increment_cell(
+                        &mut region,
+                        &assigned_batch_data_export.versioned_hashes[i][j],
+                    )?;
+                }
+                Ok(assigned_batch_data_export)
             },
+        )?;
+
+        config.blob_data.assign_codec_checks(
+            &mut layouter,
+            &config.rlc,
+            &batch_version,
+            &blob_data_exports,
+            &batch_data_exports,
+            &decoder_exports,
         )
     }
 }
 
+fn rlc(bytes: &[u8], r: Value<Fr>) -> Value<Fr> {
+    bytes.iter().fold(Value::known(Fr::zero()), |acc, &byte| {
+        acc * r + Value::known(Fr::from(byte as u64))
+    })
+}
+
 fn increment_cell(
     region: &mut Region<Fr>,
     assigned_cell: &AssignedCell<Fr, Fr>,
@@ -293,6 +388,91 @@ fn blob_circuit_completeness() {
     }
 }
 
+#[test]
+fn blob_circuit_completeness_uncompressed() {
+    let one_chunk = vec![vec![2, 3, 4, 100, 1]];
+    let two_chunks = vec![vec![100; 1000], vec![2, 3, 4, 100, 1]];
+    let all_empty_chunks: Vec<Vec<u8>> = vec![vec![]; MAX_AGG_SNARKS];
+    // the codec tag followed by the uncompressed batch data almost fills the blob.
+    let large_blob = vec![vec![
+        123;
+        N_BLOB_BYTES
+            - 2
+            - BatchData::<MAX_AGG_SNARKS>::n_rows_metadata()
+    ]];
+
+    for blob in [one_chunk, two_chunks, all_empty_chunks, large_blob] {
+        let data =
+            BatchData::from(&blob).with_codec(BATCH_VERSION_CODEC_TAG, CodecTag::Uncompressed);
+        assert_eq!(check_data(data), Ok(()), "{:?}", blob);
+    }
+}
+
+#[test]
+fn blob_circuit_completeness_multi_blob() {
+    // the uncompressed batch data spans two and three blobs, or exactly fills two blobs.
+    let two_blobs = vec![vec![7; N_BLOB_BYTES], vec![9; 1000]];
+    let three_blobs = vec![vec![7; N_BLOB_BYTES], vec![9; N_BLOB_BYTES]];
+    let two_full_blobs = vec![vec![
+        7;
+        2 * N_BLOB_BYTES
+            - 1
+            - BatchData::<MAX_AGG_SNARKS>::n_rows_metadata()
+    ]];
+
+    for (blob, num_blobs) in [(two_blobs, 2), (three_blobs, 3), (two_full_blobs, 2)] {
+        let data =
+            BatchData::from(&blob).with_codec(BATCH_VERSION_CODEC_TAG, CodecTag::Uncompressed);
+        assert_eq!(data.num_blobs(), num_blobs);
+        assert_eq!(check_data(data), Ok(()), "{num_blobs} blobs");
+    }
+}
+
+#[test]
+fn blob_circuit_completeness_batch_versions() {
+    for (batch_version, codec) in [
+        (3, CodecTag::Zstd),
+        (4, CodecTag::Zstd),
+        (BATCH_VERSION_CODEC_TAG, CodecTag::Zstd),
+        (BATCH_VERSION_CODEC_TAG, CodecTag::Uncompressed),
+    ] {
+        let data = generic_batch_data().with_codec(batch_version, codec);
+        assert_eq!(check_data(data), Ok(()), "{batch_version} {codec:?}");
+    }
+}
+
+#[test]
+fn overwrite_batch_version() {
+    // the presence of the codec tag must match the batch version.
+    for (batch_version, claimed_batch_version) in
+        [(4, BATCH_VERSION_CODEC_TAG), (BATCH_VERSION_CODEC_TAG, 4)]
+    {
+        let circuit = BlobCircuit {
+            data: generic_batch_data().with_codec(batch_version, CodecTag::Zstd),
+            overwrite_batch_version: Some(claimed_batch_version),
+            ..Default::default()
+        };
+        assert!(check_circuit(&circuit).is_err())
+    }
+}
+
+#[test]
+fn overwrite_decoded_rlc() {
+    // the decoder is only linked to the batch data for zstd payloads.
+    for (batch_version, codec, linked) in [
+        (4, CodecTag::Zstd, true),
+        (BATCH_VERSION_CODEC_TAG, CodecTag::Zstd, true),
+        (BATCH_VERSION_CODEC_TAG, CodecTag::Uncompressed, false),
+    ] {
+        let circuit = BlobCircuit {
+            data: generic_batch_data().with_codec(batch_version, codec),
+            overwrite_decoded_rlc: true,
+            ..Default::default()
+        };
+        assert_eq!(check_circuit(&circuit).is_err(), linked, "{codec:?}");
+    }
+}
+
 fn generic_batch_data() -> BatchData<MAX_AGG_SNARKS> {
     BatchData::from(&vec![
         vec![3, 100, 24, 30],
@@ -360,6 +540,22 @@ fn overwrite_challenge_digest_byte() {
     }
 }
 
+#[test]
+fn overwrite_versioned_hash_byte() {
+    // the versioned hash of each blob, including the empty blobs past the batch data, is bound
+    // to its challenge digest.
+    let data = BatchData::from(&vec![vec![7; N_BLOB_BYTES], vec![9; 1000]])
+        .with_codec(BATCH_VERSION_CODEC_TAG, CodecTag::Uncompressed);
+    for indices in [(0, 0), (1, 31), (MAX_BLOBS_PER_BATCH - 1, 10)] {
+        let circuit = BlobCircuit {
+            data: data.clone(),
+            overwrite_versioned_hashes: Some(indices),
+            ..Default::default()
+        };
+        assert!(check_circuit(&circuit).is_err())
+    }
+}
+
 #[test]
 fn overwrite_chunk_data_digest_byte() {
     for indices in [(0, 0), (4, 30), (10, 31), (MAX_AGG_SNARKS - 1, 2)] {
@@ -79,6 +79,7 @@ where
         snark.clone(),
         recursion_pk.get_vk()
     ));
+    assert_eq!(RecursionCircuit::<App>::num_batches(&snark.instances[0]), 1);
 
     let app = App::new(next_state);
     let app_snark = gen_snark_shplonk(&app_params, &app_pk, app, &mut rng, None::<String>)
@@ -117,6 +118,7 @@ where
         snark.clone(),
         recursion_pk.get_vk()
     ));
+    assert_eq!(RecursionCircuit::<App>::num_batches(&snark.instances[0]), 2);
 
     snark
 }
@@ -398,3 +400,94 @@ mod app_add_inst {
         test_recursion_impl::<Square>(4, Fr::from(2u64));
     }
 }
+
+mod app_chained_inst {
+    use super::*;
+
+    /// Squares the state, and chains a copy of it in the additional instances.
+    #[derive(Clone, Default)]
+    struct Square(Fr);
+
+    impl Circuit<Fr> for Square {
+        type Config = Selector;
+        type FloorPlanner = SimpleFloorPlanner;
+        #[cfg(feature = "circuit-params")]
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let q = meta.selector();
+            let i = meta.instance_column();
+            meta.create_gate("square", |meta| {
+                let q = meta.query_selector(q);
+                let [i, i_w] = [0, 1].map(|rotation| meta.query_instance(i, Rotation(rotation)));
+                Some(q * (i.clone() * i - i_w))
+            });
+            q
+        }
+
+        fn synthesize(
+            &self,
+            q: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    q.enable(&mut region, 2)
+                },
+            )
+        }
+    }
+
+    impl CircuitExt<Fr> for Square {
+        fn num_instance(&self) -> Vec<usize> {
+            vec![4]
+        }
+
+        fn instances(&self) -> Vec<Vec<Fr>> {
+            vec![vec![self.0, self.0.square(), self.0, self.0.square()]]
+        }
+    }
+
+    impl StateTransition for Square {
+        type Input = Fr;
+        type Circuit = Self;
+
+        fn new(state: Self::Input) -> Self {
+            Self(state)
+        }
+
+        fn num_additional_instance() -> usize {
+            2
+        }
+
+        fn num_transition_instance() -> usize {
+            1
+        }
+
+        fn state_transition(&self, _: usize) -> Self::Input {
+            self.0.square()
+        }
+
+        fn chained_indices() -> Vec<(usize, usize)> {
+            let additional_indices = Self::additional_indices();
+            vec![(additional_indices[0], additional_indices[1])]
+        }
+    }
+
+    #[test]
+    fn test_recursion_circuit() {
+        let snark = test_recursion_impl::<Square>(3, Fr::from(2u64));
+        // the chained field is exposed before the first transition and after the last one
+        let index_additional_state = 12 + 1 + 2 * Square::num_transition_instance();
+        assert_eq!(
+            snark.instances[0][index_additional_state..index_additional_state + 2],
+            [Fr::from(2u64), Fr::from(16u64)]
+        );
+    }
+}
@@ -10,7 +10,7 @@ use snark_verifier_sdk::{gen_pk, gen_snark_shplonk, verify_snark_shplonk, Circui
 use zkevm_circuits::{
     keccak_circuit::{
         keccak_packed_multi::{self, multi_keccak},
-        KeccakCircuit, KeccakCircuitConfig, KeccakCircuitConfigArgs,
+        KeccakCircuit, KeccakCircuitConfig, KeccakCircuitConfigArgs, DEFAULT_KECCAK_ROWS,
     },
     table::{KeccakTable, LookupTable},
     util::{Challenges, SubCircuitConfig},
@@ -51,6 +51,7 @@ impl Circuit<Fr> for DynamicHashCircuit {
             let keccak_circuit_config_args = KeccakCircuitConfigArgs {
                 keccak_table,
                 challenges: challenges_exprs,
+                num_rows_per_round: DEFAULT_KECCAK_ROWS,
             };
 
             KeccakCircuitConfig::new(meta, keccak_circuit_config_args)
@@ -77,7 +78,8 @@ impl Circuit<Fr> for DynamicHashCircuit {
         mut layouter: impl Layouter<Fr>,
     ) -> Result<(), Error> {
         let (config, challenges) = config;
-        let keccak_f_rows = keccak_packed_multi::get_num_rows_per_update();
+        let num_rows_per_round = config.keccak_circuit_config.num_rows_per_round;
+        let keccak_f_rows = keccak_packed_multi::get_num_rows_per_update(num_rows_per_round);
 
         config
             .keccak_circuit_config
@@ -95,7 +97,8 @@ impl Circuit<Fr> for DynamicHashCircuit {
         let witness = multi_keccak(
             &[hash_preimage.clone()],
             challenge,
-            KeccakCircuit::<Fr>::capacity_for_row(1 << LOG_DEGREE),
+            KeccakCircuit::<Fr>::capacity_for_row(1 << LOG_DEGREE, num_rows_per_round),
+            num_rows_per_round,
         )
         .unwrap();
 
@@ -0,0 +1,432 @@
+use eth_types::{l2_predeployed::message_queue, l2_types::BlockTrace, ToBigEndian, H256};
+use ethers_core::utils::keccak256;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, iter};
+
+use crate::ChunkInfo;
+
+/// Error of rebuilding the withdraw trie of a chunk or of proving a withdrawal message in it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WithdrawProofError {
+    /// The prestate of the tx appending the first message of the chunk lacks the storage of the
+    /// L2MessageQueue that the withdraw trie is rebuilt from.
+    MissingPrestate {
+        /// number of the block of the tx
+        block_number: u64,
+        /// index of the tx in its block
+        tx_index: usize,
+    },
+    /// The message isn't appended to the withdraw trie in the chunk.
+    MessageNotFound(H256),
+    /// The withdraw root of the rebuilt trie isn't the one committed by the chunk.
+    RootMismatch {
+        /// withdraw root committed by the chunk
+        expected: H256,
+        /// withdraw root of the rebuilt trie
+        rebuilt: H256,
+    },
+    /// The inclusion proof doesn't verify against the withdraw root.
+    InvalidProof,
+}
+
+impl fmt::Display for WithdrawProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPrestate {
+                block_number,
+                tx_index,
+            } => write!(
+                f,
+                "no L2MessageQueue storage in the prestate of tx {tx_index} of block {block_number}"
+            ),
+            Self::MessageNotFound(hash) => {
+                write!(
+                    f,
+                    "withdrawal message {hash:?} is not appended in the chunk"
+                )
+            }
+            Self::RootMismatch { expected, rebuilt } => write!(
+                f,
+                "rebuilt withdraw root {rebuilt:?} differs from the committed one {expected:?}"
+            ),
+            Self::InvalidProof => write!(f, "the inclusion proof is not verified"),
+        }
+    }
+}
+
+impl std::error::Error for WithdrawProofError {}
+
+/// Proof of inclusion of a withdrawal message in the withdraw trie of the L2MessageQueue, in the
+/// form verified by `WithdrawTrieVerifier.verifyMerkleProof` on L1.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalProof {
+    /// hash of the withdrawal message
+    pub message_hash: H256,
+    /// index of the message in the withdraw trie, i.e. its nonce
+    pub message_index: u64,
+    /// hashes of the siblings of the path from the message to the root
+    pub siblings: Vec<H256>,
+    /// withdraw root the message is proven against
+    pub withdraw_root: H256,
+}
+
+impl WithdrawalProof {
+    /// The proof as passed to `L1ScrollMessenger.relayMessageWithProof`, i.e. the concatenated
+    /// siblings.
+    pub fn proof_bytes(&self) -> Vec<u8> {
+        self.siblings
+            .iter()
+            .flat_map(|sibling| sibling.to_fixed_bytes())
+            .collect()
+    }
+
+    /// Verify the proof as `WithdrawTrieVerifier.verifyMerkleProof` does.
+    pub fn verify(&self) -> bool {
+        let (root, _) = self.siblings.iter().fold(
+            (self.message_hash, self.message_index),
+            |(hash, index), sibling| {
+                let hash = if index % 2 == 0 {
+                    hash_pair(&hash, sibling)
+                } else {
+                    hash_pair(sibling, &hash)
+                };
+                (hash, index / 2)
+            },
+        );
+        root == self.withdraw_root
+    }
+}
+
+/// The withdraw trie of the L2MessageQueue, an append only merkle tree of the withdrawal message
+/// hashes, as far as the messages appended in a chunk can be proven in it.
+///
+/// The trie is rebuilt from the messages appended in the chunk and from the left nodes of the
+/// trie before the chunk, which the L2MessageQueue stores as `branches` and reads to append the
+/// first message of the chunk.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WithdrawTrie {
+    /// index of the first message appended in the chunk
+    start_index: u64,
+    /// the left nodes of the trie before the chunk, at the heights of the set bits of
+    /// `start_index`
+    branches: BTreeMap<usize, H256>,
+    /// hashes of the messages appended in the chunk
+    message_hashes: Vec<H256>,
+}
+
+impl WithdrawTrie {
+    /// Construct the trie holding `start_index` messages, from its left nodes at the heights of
+    /// the set bits of `start_index`.
+    pub fn new(start_index: u64, branches: BTreeMap<usize, H256>) -> Self {
+        Self {
+            start_index,
+            branches,
+            message_hashes: vec![],
+        }
+    }
+
+    /// Rebuild the trie from the block traces of a chunk, whose messages are the ones of the
+    /// successful calls to `appendMessage` of the L2MessageQueue.
+    pub fn from_block_traces(traces: &[BlockTrace]) -> Result<Self, WithdrawProofError> {
+        let selector = &keccak256(message_queue::APPEND_MESSAGE_SIGNATURE)[..4];
+        let mut trie = None;
+        for trace in traces {
+            for (tx_index, result) in trace.execution_results.iter().enumerate() {
+                let message_hashes = result
+                    .call_trace
+                    .successful_call_inputs(&message_queue::ADDRESS)
+                    .into_iter()
+                    .filter(|input| input.len() == 4 + 32 && &input[..4] == selector)
+                    .map(|input| H256::from_slice(&input[4..]))
+                    .collect::<Vec<_>>();
+                if message_hashes.is_empty() {
+                    continue;
+                }
+
+                let trie = match trie.as_mut() {
+                    Some(trie) => trie,
+                    None => {
+                        let missing_prestate = || WithdrawProofError::MissingPrestate {
+                            block_number: trace.header.number.unwrap_or_default().as_u64(),
+                            tx_index,
+                        };
+                        let storage = result
+                            .prestate
+                            .get(&message_queue::ADDRESS)
+                            .and_then(|account| account.storage.as_ref())
+                            .ok_or_else(missing_prestate)?;
+                        let start_index = storage
+                            .get(&message_queue::NEXT_MESSAGE_INDEX_SLOT)
+                            .ok_or_else(missing_prestate)?
+                            .as_u64();
+                        let branches = (0..message_queue::MAX_TREE_HEIGHT)
+                            .filter(|height| (start_index >> height) & 1 == 1)
+                            .map(|height| {
+                                let slot = *message_queue::BRANCHES_SLOT + height;
+                                let branch = storage.get(&slot).ok_or_else(missing_prestate)?;
+                                Ok((height, H256(branch.to_be_bytes())))
+                            })
+                            .collect::<Result<_, _>>()?;
+                        trie.insert(Self::new(start_index, branches))
+                    }
+                };
+                message_hashes
+                    .into_iter()
+                    .for_each(|message_hash| trie.append(message_hash));
+            }
+        }
+        Ok(trie.unwrap_or_default())
+    }
+
+    /// Append a message.
+    pub fn append(&mut self, message_hash: H256) {
+        self.message_hashes.push(message_hash);
+    }
+
+    /// The number of messages in the trie.
+    pub fn num_messages(&self) -> u64 {
+        self.start_index + self.message_hashes.len() as u64
+    }
+
+    /// The root of the trie, as the L2MessageQueue computes it, unless the trie is empty.
+    pub fn root(&self) -> Option<H256> {
+        (self.num_messages() > 0).then(|| self.node(self.height(), 0, &zero_hashes()))
+    }
+
+    /// Prove the inclusion of a message appended in the chunk.
+    pub fn prove(&self, message_hash: H256) -> Result<WithdrawalProof, WithdrawProofError> {
+        let position = self
+            .message_hashes
+            .iter()
+            .position(|hash| *hash == message_hash)
+            .ok_or(WithdrawProofError::MessageNotFound(message_hash))?;
+        let message_index = self.start_index + position as u64;
+
+        let zero_hashes = zero_hashes();
+        let siblings = (0..self.height())
+            .map(|height| self.node(height, (message_index >> height) ^ 1, &zero_hashes))
+            .collect();
+        Ok(WithdrawalProof {
+            message_hash,
+            message_index,
+            siblings,
+            withdraw_root: self.node(self.height(), 0, &zero_hashes),
+        })
+    }
+
+    /// The height of the trie, the smallest one holding all the messages.
+    fn height(&self) -> usize {
+        (u64::BITS - self.num_messages().saturating_sub(1).leading_zeros()) as usize
+    }
+
+    /// The node at `index` of the nodes of `height`.
+    fn node(&self, height: usize, index: u64, zero_hashes: &[H256]) -> H256 {
+        let first = index << height;
+        let end = first + (1 << height);
+        if first >= self.num_messages() {
+            zero_hashes[height]
+        } else if end <= self.start_index {
+            // a subtree filled before the chunk is a left node of the trie before the chunk
+            self.branches[&height]
+        } else if height == 0 {
+            self.message_hashes[(index - self.start_index) as usize]
+        } else {
+            hash_pair(
+                &self.node(height - 1, 2 * index, zero_hashes),
+                &self.node(height - 1, 2 * index + 1, zero_hashes),
+            )
+        }
+    }
+}
+
+/// Prove the inclusion of a withdrawal message appended in a chunk against the withdraw root
+/// committed by the chunk, and verify the proof.
+pub fn prove_withdrawal(
+    traces: &[BlockTrace],
+    chunk: &ChunkInfo,
+    message_hash: H256,
+) -> Result<WithdrawalProof, WithdrawProofError> {
+    let proof = WithdrawTrie::from_block_traces(traces)?.prove(message_hash)?;
+    if proof.withdraw_root != chunk.withdraw_root {
+        return Err(WithdrawProofError::RootMismatch {
+            expected: chunk.withdraw_root,
+            rebuilt: proof.withdraw_root,
+        });
+    }
+    if !proof.verify() {
+        return Err(WithdrawProofError::InvalidProof);
+    }
+    Ok(proof)
+}
+
+fn hash_pair(left: &H256, right: &H256) -> H256 {
+    H256(keccak256([left.as_bytes(), right.as_bytes()].concat()))
+}
+
+/// The roots of the empty subtrees of each height.
+fn zero_hashes() -> Vec<H256> {
+    iter::successors(Some(H256::zero()), |hash| Some(hash_pair(hash, hash)))
+        .take(message_queue::MAX_TREE_HEIGHT)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth_types::{l2_types::ExecutionResult, Address};
+    use serde_json::json;
+
+    fn message_hashes(n: u8) -> Vec<H256> {
+        (1..=n).map(|i| H256(keccak256([i]))).collect()
+    }
+
+    /// The root and the branches after appending the messages as
+    /// `AppendOnlyMerkleTree._appendMessageHash` does.
+    fn append_messages(message_hashes: &[H256]) -> (H256, Vec<H256>) {
+        let zero_hashes = zero_hashes();
+        let mut branches = vec![H256::zero(); message_queue::MAX_TREE_HEIGHT];
+        let mut root = H256::zero();
+        for (index, message_hash) in message_hashes.iter().enumerate() {
+            let (mut index, mut hash, mut height) = (index, *message_hash, 0);
+            while index != 0 {
+                if index % 2 == 0 {
+                    branches[height] = hash;
+                    hash = hash_pair(&hash, &zero_hashes[height]);
+                } else {
+                    hash = hash_pair(&branches[height], &hash);
+                }
+                height += 1;
+                index >>= 1;
+            }
+            branches[height] = hash;
+            root = hash;
+        }
+        (root, branches)
+    }
+
+    #[test]
+    fn withdraw_trie_matches_message_queue() {
+        let message_hashes = message_hashes(11);
+        for start_index in [0, 1, 3, 4, 6] {
+            let (_, branches) = append_messages(&message_hashes[..start_index]);
+            let mut trie = WithdrawTrie::new(
+                start_index as u64,
+                (0..message_queue::MAX_TREE_HEIGHT)
+                    .filter(|height| (start_index >> height) & 1 == 1)
+                    .map(|height| (height, branches[height]))
+                    .collect(),
+            );
+            for (i, message_hash) in message_hashes.iter().enumerate().skip(start_index) {
+                trie.append(*message_hash);
+                let (root, _) = append_messages(&message_hashes[..=i]);
+                assert_eq!(trie.root(), Some(root));
+            }
+
+            for (index, message_hash) in message_hashes.iter().enumerate() {
+                let proof = trie.prove(*message_hash);
+                if index < start_index {
+                    assert_eq!(
+                        proof,
+                        Err(WithdrawProofError::MessageNotFound(*message_hash))
+                    );
+                } else {
+                    let proof = proof.unwrap();
+                    assert_eq!(proof.message_index, index as u64);
+                    assert_eq!(proof.proof_bytes().len(), 32 * 4);
+                    assert!(proof.verify());
+                }
+            }
+        }
+        assert_eq!(WithdrawTrie::default().root(), None);
+    }
+
+    #[test]
+    fn prove_withdrawal_of_chunk() {
+        let message_hashes = message_hashes(7);
+        let (prev_root, branches) = append_messages(&message_hashes[..5]);
+        let (root, _) = append_messages(&message_hashes);
+
+        let messenger = Address::repeat_byte(0x11);
+        let selector = hex::encode(&keccak256(message_queue::APPEND_MESSAGE_SIGNATURE)[..4]);
+        let append_message = |message_hash: &H256, error: Option<&str>| {
+            json!({
+                "type": "CALL",
+                "from": messenger,
+                "to": *message_queue::ADDRESS,
+                "gasUsed": "0x0",
+                "input": format!("0x{selector}{}", hex::encode(message_hash)),
+                "error": error,
+            })
+        };
+        let result = |calls: Vec<serde_json::Value>, prestate: serde_json::Value| {
+            serde_json::from_value::<ExecutionResult>(json!({
+                "gas": 0,
+                "failed": false,
+                "structLogs": [],
+                "callTrace": {
+                    "type": "CALL",
+                    "from": Address::repeat_byte(0x22),
+                    "to": messenger,
+                    "gasUsed": "0x0",
+                    "calls": calls,
+                },
+                "prestate": prestate,
+            }))
+            .unwrap()
+        };
+        // 5 messages were appended before the chunk, whose left nodes are at heights 0 and 2
+        let prestate = json!({
+            format!("{:?}", *message_queue::ADDRESS): {
+                "storage": {
+                    format!("{:#x}", *message_queue::NEXT_MESSAGE_INDEX_SLOT): "0x5",
+                    format!("{:#x}", *message_queue::BRANCHES_SLOT): format!("{:?}", branches[0]),
+                    format!("{:#x}", *message_queue::BRANCHES_SLOT + 2): format!("{:?}", branches[2]),
+                    format!("{:#x}", message_queue::WITHDRAW_TRIE_ROOT_SLOT): format!("{prev_root:?}"),
+                },
+            },
+        });
+        let traces = [BlockTrace {
+            execution_results: vec![
+                result(vec![], json!({})),
+                result(
+                    vec![
+                        append_message(&message_hashes[5], None),
+                        // reverted
+                        append_message(&H256::repeat_byte(0xff), Some("execution reverted")),
+                    ],
+                    prestate,
+                ),
+                result(vec![append_message(&message_hashes[6], None)], json!({})),
+            ],
+            withdraw_trie_root: root,
+            ..Default::default()
+        }];
+        let chunk = ChunkInfo {
+            withdraw_root: root,
+            ..Default::default()
+        };
+
+        let proof = prove_withdrawal(&traces, &chunk, message_hashes[6]).unwrap();
+        assert_eq!(proof.message_index, 6);
+        assert_eq!(proof.withdraw_root, root);
+        assert_eq!(
+            prove_withdrawal(&traces, &chunk, message_hashes[4]),
+            Err(WithdrawProofError::MessageNotFound(message_hashes[4]))
+        );
+        assert_eq!(
+            prove_withdrawal(&traces, &chunk, H256::repeat_byte(0xff)),
+            Err(WithdrawProofError::MessageNotFound(H256::repeat_byte(0xff)))
+        );
+        let chunk = ChunkInfo {
+            withdraw_root: prev_root,
+            ..Default::default()
+        };
+        assert_eq!(
+            prove_withdrawal(&traces, &chunk, message_hashes[5]),
+            Err(WithdrawProofError::RootMismatch {
+                expected: prev_root,
+                rebuilt: root
+            })
+        );
+    }
+}
@@ -9,6 +9,8 @@ mod call;
 pub mod curie;
 mod execution;
 mod input_state_ref;
+/// L1 fee models
+pub mod l1_fee;
 #[cfg(feature = "scroll")]
 mod l2;
 #[cfg(all(feature = "tracer-tests", feature = "enable-memory", test))]
@@ -42,6 +44,8 @@ pub use execution::{
 pub use input_state_ref::CircuitInputStateRef;
 use itertools::Itertools;
 #[cfg(feature = "scroll")]
+pub use l2::L2TraceStream;
+#[cfg(feature = "scroll")]
 use mpt_zktrie::state::ZktrieState;
 use std::collections::BTreeMap;
 pub use transaction::{
@@ -301,44 +305,56 @@ impl<'a> CircuitInputBuilder {
             eth_block.number,
             eth_block.transactions.len()
         );
-        for (tx_index, tx) in eth_block.transactions.iter().enumerate() {
-            let chunk_tx_idx = self.block.txs.len();
-            if self.block.txs.len() >= self.block.circuits_params.max_txs {
-                log::error!(
-                    "tx num overflow, MAX_TX limit {}, {}th tx(inner idx: {}) {:?}",
-                    self.block.circuits_params.max_txs,
-                    chunk_tx_idx,
-                    tx.transaction_index.unwrap_or_default(),
-                    tx.hash
-                );
-                return Err(Error::InternalError("tx num overflow"));
-            }
-            let geth_trace = &geth_traces[tx_index];
-            log::info!(
-                "handling {}th tx(inner idx: {}): {:?} rwc {:?}, to: {:?}, input_len {:?}",
+        for (tx_index, _) in eth_block.transactions.iter().enumerate() {
+            self.handle_block_tx(eth_block, tx_index, &geth_traces[tx_index])?;
+        }
+        log::info!(
+            "handle_block_inner, total gas {:?}",
+            self.block_ctx.cumulative_gas_used
+        );
+        Ok(())
+    }
+
+    /// Handle the `tx_index`-th transaction of `eth_block`, which must be the
+    /// next one after the transactions handled so far.
+    pub fn handle_block_tx(
+        &mut self,
+        eth_block: &EthBlock,
+        tx_index: usize,
+        geth_trace: &eth_types::GethExecTrace,
+    ) -> Result<(), Error> {
+        let tx = &eth_block.transactions[tx_index];
+        let chunk_tx_idx = self.block.txs.len();
+        if self.block.txs.len() >= self.block.circuits_params.max_txs {
+            log::error!(
+                "tx num overflow, MAX_TX limit {}, {}th tx(inner idx: {}) {:?}",
+                self.block.circuits_params.max_txs,
                 chunk_tx_idx,
                 tx.transaction_index.unwrap_or_default(),
-                tx.hash,
-                self.block_ctx.rwc,
-                tx.to,
-                tx.input.len(),
+                tx.hash
             );
-            let mut tx = tx.clone();
-            // Chunk can contain multi blocks, so transaction_index needs to be updated
-            tx.transaction_index = Some(self.block.txs.len().into());
-            self.handle_tx(&tx, geth_trace)?;
-            log::debug!(
-                "after handle {}th tx: rwc {:?}, total gas {:?}",
-                chunk_tx_idx,
-                self.block_ctx.rwc,
-                self.block_ctx.cumulative_gas_used
-            );
-            self.check_post_state(&geth_trace.account_after);
+            return Err(Error::InternalError("tx num overflow"));
         }
         log::info!(
-            "handle_block_inner, total gas {:?}",
+            "handling {}th tx(inner idx: {}): {:?} rwc {:?}, to: {:?}, input_len {:?}",
+            chunk_tx_idx,
+            tx.transaction_index.unwrap_or_default(),
+            tx.hash,
+            self.block_ctx.rwc,
+            tx.to,
+            tx.input.len(),
+        );
+        let mut tx = tx.clone();
+        // Chunk can contain multi blocks, so transaction_index needs to be updated
+        tx.transaction_index = Some(self.block.txs.len().into());
+        self.handle_tx(&tx, geth_trace)?;
+        log::debug!(
+            "after handle {}th tx: rwc {:?}, total gas {:?}",
+            chunk_tx_idx,
+            self.block_ctx.rwc,
             self.block_ctx.cumulative_gas_used
         );
+        self.check_post_state(&geth_trace.account_after);
         Ok(())
     }
 
@@ -437,18 +453,12 @@ impl<'a> CircuitInputBuilder {
 
     /// Build the EndBlock step, fill needed rws like reading withdraw root
     pub fn set_end_block(&mut self) -> Result<(), Error> {
-        use crate::l2_predeployed::message_queue::{
-            ADDRESS as MESSAGE_QUEUE, WITHDRAW_TRIE_ROOT_SLOT,
-        };
+        use crate::l2_predeployed::message_queue::{self, WITHDRAW_TRIE_ROOT_SLOT};
 
-        let withdraw_root = *self
-            .sdb
-            .get_storage(&MESSAGE_QUEUE, &WITHDRAW_TRIE_ROOT_SLOT)
-            .1;
-        let withdraw_root_before = *self
-            .sdb
-            .get_committed_storage(&MESSAGE_QUEUE, &WITHDRAW_TRIE_ROOT_SLOT)
-            .1;
+        let message_queue = message_queue::predeploy();
+        let withdraw_root = message_queue.storage(&self.sdb, &WITHDRAW_TRIE_ROOT_SLOT);
+        let withdraw_root_before =
+            message_queue.committed_storage(&self.sdb, &WITHDRAW_TRIE_ROOT_SLOT);
 
         let max_rws = self.block.circuits_params.max_rws;
         let mut padding_step = self.block.block_steps.padding_step.clone();
@@ -489,7 +499,7 @@ impl<'a> CircuitInputBuilder {
             &mut end_block_step,
             RW::READ,
             StorageOp::new(
-                *MESSAGE_QUEUE,
+                message_queue.address,
                 WITHDRAW_TRIE_ROOT_SLOT,
                 withdraw_root,
                 withdraw_root,
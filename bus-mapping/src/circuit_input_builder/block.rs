@@ -178,6 +178,8 @@ pub struct Blocks {
     pub chain_id: u64,
     /// start_l1_queue_index
     pub start_l1_queue_index: u64,
+    /// Rolling hash of the L1 messages popped before these blocks
+    pub prev_l1_msg_hash: H256,
     /// IO to/from the precompiled contract calls.
     pub precompile_events: PrecompileEvents,
     /// circuit capacity counter
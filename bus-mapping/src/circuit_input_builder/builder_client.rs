@@ -3,7 +3,8 @@ use eth_types::{
     geth_types::{self, Account, BlockConstants},
     state_db::{self, CodeDB, StateDB},
     utils::hash_code_keccak,
-    Address, EthBlock, GethExecTrace, ToWord, Word, H256, KECCAK_CODE_HASH_EMPTY,
+    Address, EthBlock, GethExecTrace, GethPrestateTrace, ToWord, Word, H256,
+    KECCAK_CODE_HASH_EMPTY,
 };
 use ethers_providers::JsonRpcClient;
 use hex::decode_to_slice;
@@ -54,6 +55,79 @@ pub fn build_state_code_db(
     (sdb, code_db)
 }
 
+/// Merge the prestates of txs executed in sequence into the state before the first one, i.e. the
+/// first value reported for each account and storage slot, as the accounts and codes that step 4
+/// builds the StateDB and CodeDB from.
+#[allow(clippy::type_complexity)]
+pub fn merge_prestates(
+    prestates: impl Iterator<Item = HashMap<Address, GethPrestateTrace>>,
+) -> (
+    Vec<eth_types::EIP1186ProofResponse>,
+    HashMap<Address, Vec<u8>>,
+) {
+    let mut account_set =
+        HashMap::<Address, (eth_types::EIP1186ProofResponse, HashMap<Word, Word>)>::new();
+    let mut code_set = HashMap::new();
+
+    for trace in prestates {
+        for (addr, prestate) in trace.into_iter() {
+            let (_, storages) = account_set.entry(addr).or_insert_with(|| {
+                let code_size = Word::from(prestate.code.as_ref().map(|bt| bt.len()).unwrap_or(0));
+                let (code_hash, keccak_code_hash) = if let Some(bt) = prestate.code {
+                    let h = CodeDB::hash(&bt);
+                    // only require for L2
+                    let keccak_h = if cfg!(feature = "scroll") {
+                        hash_code_keccak(&bt)
+                    } else {
+                        h
+                    };
+                    code_set.insert(addr, Vec::from(bt.as_ref()));
+                    (h, keccak_h)
+                } else {
+                    (CodeDB::empty_code_hash(), *KECCAK_CODE_HASH_EMPTY)
+                };
+
+                (
+                    eth_types::EIP1186ProofResponse {
+                        address: addr,
+                        balance: prestate.balance.unwrap_or_default(),
+                        nonce: prestate.nonce.unwrap_or_default().into(),
+                        code_hash,
+                        keccak_code_hash,
+                        code_size,
+                        ..Default::default()
+                    },
+                    HashMap::new(),
+                )
+            });
+
+            if let Some(stg) = prestate.storage {
+                for (k, v) in stg {
+                    storages.entry(k).or_insert(v);
+                }
+            }
+        }
+    }
+
+    (
+        account_set
+            .into_iter()
+            .map(|(_, (mut acc_resp, storage_proofs))| {
+                acc_resp.storage_proof = storage_proofs
+                    .into_iter()
+                    .map(|(key, value)| eth_types::StorageProof {
+                        key,
+                        value,
+                        ..Default::default()
+                    })
+                    .collect();
+                acc_resp
+            })
+            .collect::<Vec<_>>(),
+        code_set,
+    )
+}
+
 impl<P: JsonRpcClient> BuilderClient<P> {
     /// Create a new BuilderClient
     pub async fn new(
@@ -188,68 +262,7 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         ),
         Error,
     > {
-        let mut account_set =
-            HashMap::<Address, (eth_types::EIP1186ProofResponse, HashMap<Word, Word>)>::new();
-        let mut code_set = HashMap::new();
-
-        for trace in traces.map(|tr| tr.prestate.clone()) {
-            for (addr, prestate) in trace.into_iter() {
-                let (_, storages) = account_set.entry(addr).or_insert_with(|| {
-                    let code_size =
-                        Word::from(prestate.code.as_ref().map(|bt| bt.len()).unwrap_or(0));
-                    let (code_hash, keccak_code_hash) = if let Some(bt) = prestate.code {
-                        let h = CodeDB::hash(&bt);
-                        // only require for L2
-                        let keccak_h = if cfg!(feature = "scroll") {
-                            hash_code_keccak(&bt)
-                        } else {
-                            h
-                        };
-                        code_set.insert(addr, Vec::from(bt.as_ref()));
-                        (h, keccak_h)
-                    } else {
-                        (CodeDB::empty_code_hash(), *KECCAK_CODE_HASH_EMPTY)
-                    };
-
-                    (
-                        eth_types::EIP1186ProofResponse {
-                            address: addr,
-                            balance: prestate.balance.unwrap_or_default(),
-                            nonce: prestate.nonce.unwrap_or_default().into(),
-                            code_hash,
-                            keccak_code_hash,
-                            code_size,
-                            ..Default::default()
-                        },
-                        HashMap::new(),
-                    )
-                });
-
-                if let Some(stg) = prestate.storage {
-                    for (k, v) in stg {
-                        storages.entry(k).or_insert(v);
-                    }
-                }
-            }
-        }
-
-        Ok((
-            account_set
-                .into_iter()
-                .map(|(_, (mut acc_resp, storage_proofs))| {
-                    acc_resp.storage_proof = storage_proofs
-                        .into_iter()
-                        .map(|(key, value)| eth_types::StorageProof {
-                            key,
-                            value,
-                            ..Default::default()
-                        })
-                        .collect();
-                    acc_resp
-                })
-                .collect::<Vec<_>>(),
-            code_set,
-        ))
+        Ok(merge_prestates(traces.map(|tr| tr.prestate.clone())))
     }
 
     /// Yet-another Step 3-1. (hacking?) replenish the pre state proof
@@ -258,31 +271,81 @@ impl<P: JsonRpcClient> BuilderClient<P> {
     pub async fn complete_prestate(
         &self,
         eth_block: &EthBlock,
+        proofs: Vec<eth_types::EIP1186ProofResponse>,
+    ) -> Result<Vec<eth_types::EIP1186ProofResponse>, Error> {
+        self.complete_prestate_before(eth_block, [eth_block], proofs)
+            .await
+    }
+
+    /// Replenish the pre state proofs with the coinbases of `eth_blocks`, queried at the state
+    /// before `first_block`.
+    async fn complete_prestate_before<'a>(
+        &self,
+        first_block: &EthBlock,
+        eth_blocks: impl IntoIterator<Item = &'a EthBlock>,
         mut proofs: Vec<eth_types::EIP1186ProofResponse>,
     ) -> Result<Vec<eth_types::EIP1186ProofResponse>, Error> {
-        // a hacking? since the coinbase address is not touch in prestate
-        let coinbase_addr = eth_block
-            .author
-            .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?;
-        let block_num = eth_block
+        let block_num = first_block
             .number
             .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?;
-        assert_ne!(
-            block_num.as_u64(),
-            0,
-            "is not expected to access genesis block"
-        );
-
-        if !proofs.iter().any(|pr| pr.address == coinbase_addr) {
-            let coinbase_proof = self
-                .cli
-                .get_proof(coinbase_addr, Vec::new(), (block_num - 1).into())
-                .await?;
-            proofs.push(coinbase_proof);
+        let parent_num = block_num
+            .as_u64()
+            .checked_sub(1)
+            .ok_or(Error::InternalError(
+                "is not expected to access genesis block",
+            ))?;
+
+        for eth_block in eth_blocks {
+            // a hacking? since the coinbase address is not touch in prestate
+            let coinbase_addr = eth_block
+                .author
+                .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?;
+            if !proofs.iter().any(|pr| pr.address == coinbase_addr) {
+                let coinbase_proof = self
+                    .cli
+                    .get_proof(coinbase_addr, Vec::new(), parent_num.into())
+                    .await?;
+                proofs.push(coinbase_proof);
+            }
         }
         Ok(proofs)
     }
 
+    /// Steps 2 and 3 for a range of blocks. Build the account states and codes before the first
+    /// block from the prestates already included in the exec traces of the blocks, rather than
+    /// querying geth for the proof of each accessed account.
+    ///
+    /// The prestate tracer doesn't report the coinbases, whose states are queried via
+    /// `eth_getProof`.
+    #[allow(clippy::type_complexity)]
+    pub async fn get_blocks_pre_state(
+        &self,
+        blocks_and_traces: &[(EthBlock, Vec<eth_types::GethExecTrace>)],
+    ) -> Result<
+        (
+            Vec<eth_types::EIP1186ProofResponse>,
+            HashMap<Address, Vec<u8>>,
+        ),
+        Error,
+    > {
+        let Some((first_block, _)) = blocks_and_traces.first() else {
+            return Ok(Default::default());
+        };
+        let (proofs, codes) = self.get_pre_state(
+            blocks_and_traces
+                .iter()
+                .flat_map(|(_, geth_traces)| geth_traces.iter()),
+        )?;
+        let proofs = self
+            .complete_prestate_before(
+                first_block,
+                blocks_and_traces.iter().map(|(eth_block, _)| eth_block),
+                proofs,
+            )
+            .await?;
+        Ok((proofs, codes))
+    }
+
     /// Step 4. Build a partial StateDB from step 3
     pub fn build_state_code_db(
         proofs: Vec<eth_types::EIP1186ProofResponse>,
@@ -389,14 +452,11 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         block_num_end: u64,
     ) -> Result<CircuitInputBuilder, Error> {
         let mut blocks_and_traces = Vec::new();
-        let mut access_set = AccessSet::default();
         for block_num in block_num_begin..block_num_end {
             let (eth_block, geth_traces, _, _) = self.get_block(block_num).await?;
-            let mut access_list = self.get_state_accesses(&eth_block).await?;
-            access_set.extend(&mut access_list);
             blocks_and_traces.push((eth_block, geth_traces));
         }
-        let (proofs, codes) = self.get_state(block_num_begin, access_set).await?;
+        let (proofs, codes) = self.get_blocks_pre_state(&blocks_and_traces).await?;
         let (state_db, code_db) = Self::build_state_code_db(proofs, codes);
         let builder =
             self.gen_inputs_from_state_multi_blocks(state_db, code_db, &blocks_and_traces)?;
@@ -556,3 +616,132 @@ impl<P: JsonRpcClient> BuilderClient<P> {
     }
     */
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::ReplayClient;
+    use ethers_providers::RpcError;
+    use std::collections::HashSet;
+
+    #[test]
+    fn merge_prestates_keeps_first_values() {
+        let addr = Address::repeat_byte(1);
+        let prestate = |balance: u64, slots: &[(u64, u64)]| {
+            HashMap::from([(
+                addr,
+                GethPrestateTrace {
+                    balance: Some(balance.into()),
+                    nonce: Some(1),
+                    code: Some(vec![0x60, 0x00].into()),
+                    storage: Some(
+                        slots
+                            .iter()
+                            .map(|&(key, value)| (key.into(), value.into()))
+                            .collect(),
+                    ),
+                },
+            )])
+        };
+        // the second tx sees the state changed by the first one, and a newly accessed slot.
+        let (proofs, codes) =
+            merge_prestates([prestate(10, &[(1, 1)]), prestate(7, &[(1, 2), (2, 3)])].into_iter());
+
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(proofs[0].balance, 10.into());
+        assert_eq!(proofs[0].code_size, 2.into());
+        let storage = proofs[0]
+            .storage_proof
+            .iter()
+            .map(|proof| (proof.key.as_u64(), proof.value.as_u64()))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(storage, HashMap::from([(1, 1), (2, 3)]));
+        assert_eq!(codes[&addr], vec![0x60, 0x00]);
+    }
+
+    /// A client replaying the exchanges with a node for a block of a single transfer, from 0xa1
+    /// to 0xb2, whose coinbase is 0xc0.
+    async fn replay_client() -> BuilderClient<ReplayClient> {
+        let fixture = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/testdata/rpc_fixture_transfer.json"
+        );
+        let client = GethClient::new(ReplayClient::load(fixture).unwrap());
+        BuilderClient::new(client, CircuitsParams::default())
+            .await
+            .unwrap()
+    }
+
+    // The params of the struct logger depend on these features.
+    #[cfg(not(any(
+        feature = "enable-memory",
+        feature = "enable-stack",
+        feature = "enable-storage"
+    )))]
+    #[tokio::test]
+    async fn get_block_from_replay() {
+        let client = replay_client().await;
+        assert_eq!(client.chain_id, 534352);
+
+        let (eth_block, geth_traces, history_hashes, prev_state_root) =
+            client.get_block(16).await.unwrap();
+        assert_eq!(eth_block.number.map(|number| number.as_u64()), Some(16));
+        assert_eq!(eth_block.transactions.len(), 1);
+        assert_eq!(geth_traces.len(), 1);
+        assert!(!geth_traces[0].failed);
+        assert_eq!(geth_traces[0].gas.0, 21000);
+        // the prestate of the mux tracer is merged into the trace
+        let sender = Address::from_low_u64_be(0xa1);
+        assert_eq!(geth_traces[0].prestate[&sender].nonce, Some(0));
+        assert!(history_hashes.is_empty());
+        assert_eq!(prev_state_root, Word::zero());
+    }
+
+    #[tokio::test]
+    async fn get_state_accesses_from_replay() {
+        let client = replay_client().await;
+        let eth_block = client.cli.get_block_by_number(16u64.into()).await.unwrap();
+
+        let access_set = client.get_state_accesses(&eth_block).await.unwrap();
+        let accounts = [0xa1, 0xb2, 0xc0]
+            .map(Address::from_low_u64_be)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            access_set.state.keys().copied().collect::<HashSet<_>>(),
+            accounts
+        );
+        assert!(access_set.state.values().all(|keys| keys.is_empty()));
+        assert_eq!(access_set.code, accounts);
+    }
+
+    #[cfg(not(any(
+        feature = "enable-memory",
+        feature = "enable-stack",
+        feature = "enable-storage"
+    )))]
+    #[tokio::test]
+    async fn trace_block_by_number_from_replay() {
+        let client = replay_client().await;
+        assert_eq!(
+            client
+                .cli
+                .trace_block_by_number(16u64.into())
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // the error response of the node is replayed
+        match client.cli.trace_block_by_number(17u64.into()).await {
+            Err(Error::JSONRpcError(e)) => assert_eq!(
+                e.as_error_response().map(|e| e.message.as_str()),
+                Some("block #17 not found")
+            ),
+            result => panic!("expected an error response, got {result:?}"),
+        }
+        // a block that isn't recorded
+        assert!(client.get_block(18).await.is_err());
+    }
+}
@@ -1,6 +1,7 @@
 // Adapted from https://github.com/scroll-tech/go-ethereum/blob/8dc419a70b94f5ca185dcf818a48a3bd2eefc392/consensus/misc/curie.go
 
 use eth_types::{
+    forks::{hardfork_block, HardforkId},
     utils::{hash_code, hash_code_keccak},
     ToWord, Word,
 };
@@ -15,12 +16,7 @@ use super::{CircuitInputStateRef, ExecStep};
 
 /// Get curie fork block height
 pub fn get_curie_fork_block(chain_id: u64) -> u64 {
-    for (fork, fork_chain_id, fork_blk) in eth_types::forks::hardfork_heights() {
-        if fork == eth_types::forks::HardforkId::Curie && chain_id == fork_chain_id {
-            return fork_blk;
-        }
-    }
-    0
+    hardfork_block(HardforkId::Curie, chain_id)
 }
 
 /// Whether this blk has enabled curie fork
@@ -81,18 +77,7 @@ pub fn apply_curie(state: &mut CircuitInputStateRef, step: &mut ExecStep) -> Res
         },
     )?;
 
-    for (slot, value) in [
-        (*l1_gas_price_oracle::IS_CURIE_SLOT, Word::from(1)),
-        (*l1_gas_price_oracle::L1_BLOB_BASEFEE_SLOT, Word::from(1)),
-        (
-            *l1_gas_price_oracle::COMMIT_SCALAR_SLOT,
-            *l1_gas_price_oracle::INITIAL_COMMIT_SCALAR,
-        ),
-        (
-            *l1_gas_price_oracle::BLOB_SCALAR_SLOT,
-            *l1_gas_price_oracle::INITIAL_BLOB_SCALAR,
-        ),
-    ] {
+    for (slot, value) in *l1_gas_price_oracle::CURIE_STORAGE {
         state.push_op(
             step,
             RW::WRITE,
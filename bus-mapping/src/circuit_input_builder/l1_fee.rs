@@ -0,0 +1,127 @@
+//! The formulas pricing the L1 data fee of a transaction from the parameters of the
+//! L1GasPriceOracle, one per fork that changed them.
+//!
+//! A new formula implements [`L1FeeModel`] and is returned by [`l1_fee_model`] from the fork
+//! activating it. The EVM circuit constrains the same formulas, see `TxL1FeeGadget`.
+
+use super::{curie::is_curie_enabled, TxL1Fee, TX_L1_COMMIT_EXTRA_COST, TX_L1_FEE_PRECISION};
+use std::fmt::Debug;
+
+/// A formula pricing the L1 data fee of a transaction.
+pub trait L1FeeModel: Debug + Sync {
+    /// Name of the formula
+    fn name(&self) -> &'static str;
+
+    /// The L1 fee of the transaction scaled by [`TX_L1_FEE_PRECISION`].
+    fn scaled_fee(&self, params: &TxL1Fee, tx_data_gas_cost: u64, tx_rlp_signed_len: u64) -> u128;
+
+    /// The L1 fee of the transaction and the remainder of its scaled value.
+    fn fee(&self, params: &TxL1Fee, tx_data_gas_cost: u64, tx_rlp_signed_len: u64) -> (u64, u64) {
+        let scaled_fee = self.scaled_fee(params, tx_data_gas_cost, tx_rlp_signed_len);
+        log::debug!(
+            "{} l1 fee: params {:?} tx_data_gas_cost {} tx_rlp_signed_len {} scaled fee {}",
+            self.name(),
+            params,
+            tx_data_gas_cost,
+            tx_rlp_signed_len,
+            scaled_fee
+        );
+        (
+            (scaled_fee / TX_L1_FEE_PRECISION as u128) as u64,
+            (scaled_fee % TX_L1_FEE_PRECISION as u128) as u64,
+        )
+    }
+}
+
+/// The formula before curie, pricing the data gas of the transaction:
+/// `fee_scalar * base_fee * (tx_data_gas_cost + TX_L1_COMMIT_EXTRA_COST + fee_overhead)`
+///
+/// <https://github.com/scroll-tech/go-ethereum/blob/49192260a177f1b63fc5ea3b872fb904f396260c/rollup/fees/rollup_fee.go#L118>
+#[derive(Clone, Copy, Debug)]
+pub struct PreCurieL1FeeModel;
+
+impl L1FeeModel for PreCurieL1FeeModel {
+    fn name(&self) -> &'static str {
+        "pre-curie"
+    }
+
+    fn scaled_fee(&self, params: &TxL1Fee, tx_data_gas_cost: u64, _: u64) -> u128 {
+        let tx_l1_gas = tx_data_gas_cost + params.fee_overhead + TX_L1_COMMIT_EXTRA_COST;
+        params.fee_scalar as u128 * params.base_fee as u128 * tx_l1_gas as u128
+    }
+}
+
+/// The formula since curie, pricing the commit transaction and the blob space of the rlp
+/// encoded transaction:
+/// `commit_scalar * base_fee + blob_scalar * tx_rlp_signed_len * l1_blob_basefee`
+///
+/// <https://github.com/scroll-tech/go-ethereum/blob/develop/rollup/fees/rollup_fee.go#L165>
+#[derive(Clone, Copy, Debug)]
+pub struct CurieL1FeeModel;
+
+impl L1FeeModel for CurieL1FeeModel {
+    fn name(&self) -> &'static str {
+        "curie"
+    }
+
+    fn scaled_fee(&self, params: &TxL1Fee, _: u64, tx_rlp_signed_len: u64) -> u128 {
+        params.commit_scalar as u128 * params.base_fee as u128
+            + params.blob_scalar as u128
+                * tx_rlp_signed_len as u128
+                * params.l1_blob_basefee as u128
+    }
+}
+
+/// The L1 fee model of the fork active at the block.
+pub fn l1_fee_model(chain_id: u64, block_number: u64) -> &'static dyn L1FeeModel {
+    if is_curie_enabled(chain_id, block_number) {
+        &CurieL1FeeModel
+    } else {
+        &PreCurieL1FeeModel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth_types::forks::SCROLL_DEVNET_CHAIN_ID;
+
+    // refer to tests in <https://github.com/scroll-tech/go-ethereum/blob/develop/rollup/fees/rollup_fee_test.go>
+    #[test]
+    fn l1_fee_models() {
+        let params = TxL1Fee {
+            chain_id: SCROLL_DEVNET_CHAIN_ID,
+            block_number: 0,
+            base_fee: 15_000_000,
+            fee_overhead: 100,
+            fee_scalar: 10,
+            l1_blob_basefee: 150_000_000,
+            commit_scalar: 10,
+            blob_scalar: 10,
+        };
+        assert_eq!(PreCurieL1FeeModel.fee(&params, 40, 4), (30, 600_000_000));
+
+        let params = TxL1Fee {
+            base_fee: 1_500_000_000,
+            ..params
+        };
+        assert_eq!(CurieL1FeeModel.fee(&params, 40, 4), (21, 0));
+        assert_eq!(CurieL1FeeModel.fee(&params, 40, 5), (22, 500_000_000));
+    }
+
+    #[test]
+    fn l1_fee_model_by_fork() {
+        // curie is activated at block 5 of devnet
+        assert_eq!(l1_fee_model(SCROLL_DEVNET_CHAIN_ID, 4).name(), "pre-curie");
+        assert_eq!(l1_fee_model(SCROLL_DEVNET_CHAIN_ID, 5).name(), "curie");
+        let params = TxL1Fee {
+            chain_id: SCROLL_DEVNET_CHAIN_ID,
+            block_number: 4,
+            base_fee: 15_000_000,
+            fee_overhead: 100,
+            fee_scalar: 10,
+            ..Default::default()
+        };
+        assert_eq!(params.tx_l1_fee(40, 4), (30, 600_000_000));
+    }
+}
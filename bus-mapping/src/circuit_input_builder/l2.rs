@@ -5,12 +5,17 @@ use crate::{
 };
 use eth_types::{
     self,
-    l2_types::{trace::collect_codes, BlockTrace, StorageTrace},
+    l2_types::{
+        stream::{deserialize_block_trace, BlockTraceVisitor},
+        trace::{collect_codes, collect_execution_result_codes},
+        BlockTrace, ExecutionResult, StorageTrace,
+    },
     state_db::{self, CodeDB, StateDB},
     Address, EthBlock, ToWord, Word, H256,
 };
 use ethers_core::types::Bytes;
 use mpt_zktrie::state::ZktrieState;
+use serde::Deserializer;
 use std::collections::hash_map::HashMap;
 
 fn dump_code_db(cdb: &CodeDB) {
@@ -29,29 +34,13 @@ impl CircuitInputBuilder {
         //self.sdb.list_accounts();
         //dump_code_db(&self.code_db);
 
-        let eth_block = EthBlock::from(&block_trace);
-        log::trace!("eth_block block number {:?}", eth_block.number);
+        let eth_block = self.begin_l2_block(&block_trace)?;
+        let block_num = eth_block.number.unwrap_or_default().as_u64();
         let geth_trace: Vec<eth_types::GethExecTrace> = block_trace
             .execution_results
             .into_iter()
             .map(From::from)
             .collect();
-        assert_eq!(
-            self.block.chain_id, block_trace.chain_id,
-            "unexpected chain id in new block_trace"
-        );
-        // Scroll EVM disables BLOCKHASH opcode, so here we don't need any hashes.
-        let mut block = Block::new_with_l1_queue_index(
-            self.block.chain_id,
-            block_trace.start_l1_queue_index,
-            Vec::new(),
-            &eth_block,
-        )?;
-        // override zeroed minder field with additional "coinbase" field in blocktrace
-        block.coinbase = block_trace.coinbase.address;
-        let block_num = block.number.as_u64();
-        // TODO: should be check the block number is in sequence?
-        self.block.add_block(block);
         // note the actions when `handle_rwc_reversion` argument (the 4th one)
         // is true is executing outside this closure
         self.handle_block_inner(&eth_block, &geth_trace)?;
@@ -72,6 +61,29 @@ impl CircuitInputBuilder {
         Ok(())
     }
 
+    /// Add the block of `block_trace` to the chunk, without handling any of
+    /// its transactions.
+    fn begin_l2_block(&mut self, block_trace: &BlockTrace) -> Result<EthBlock, Error> {
+        let eth_block = EthBlock::from(block_trace);
+        log::trace!("eth_block block number {:?}", eth_block.number);
+        assert_eq!(
+            self.block.chain_id, block_trace.chain_id,
+            "unexpected chain id in new block_trace"
+        );
+        // Scroll EVM disables BLOCKHASH opcode, so here we don't need any hashes.
+        let mut block = Block::new_with_l1_queue_index(
+            self.block.chain_id,
+            block_trace.start_l1_queue_index,
+            Vec::new(),
+            &eth_block,
+        )?;
+        // override zeroed minder field with additional "coinbase" field in blocktrace
+        block.coinbase = block_trace.coinbase.address;
+        // TODO: should be check the block number is in sequence?
+        self.block.add_block(block);
+        Ok(eth_block)
+    }
+
     fn collect_account_proofs(
         storage_trace: &StorageTrace,
     ) -> impl Iterator<Item = (&Address, impl IntoIterator<Item = &[u8]>)> + Clone {
@@ -114,6 +126,18 @@ impl CircuitInputBuilder {
         circuits_params: CircuitsParams,
         l2_trace: BlockTrace,
         light_mode: bool,
+    ) -> Result<Self, Error> {
+        let mut builder = Self::init_from_l2_trace(circuits_params, &l2_trace, light_mode)?;
+        builder.apply_l2_trace(l2_trace)?;
+        Ok(builder)
+    }
+
+    /// Create a CircuitInputBuilder with the state before `l2_trace`, without
+    /// applying any of its blocks.
+    fn init_from_l2_trace(
+        circuits_params: CircuitsParams,
+        l2_trace: &BlockTrace,
+        light_mode: bool,
     ) -> Result<Self, Error> {
         let chain_id = l2_trace.chain_id;
 
@@ -166,7 +190,7 @@ impl CircuitInputBuilder {
         let mut code_db = CodeDB::new();
         code_db.insert(Vec::new());
 
-        let codes = collect_codes(&l2_trace, Some(&sdb))?;
+        let codes = collect_codes(l2_trace, Some(&sdb))?;
         for (hash, code) in codes {
             code_db.insert_with_hash(hash, code);
         }
@@ -174,20 +198,24 @@ impl CircuitInputBuilder {
         let mut builder_block = circuit_input_builder::Blocks::init(chain_id, circuits_params);
         builder_block.prev_state_root = old_root;
         builder_block.start_l1_queue_index = l2_trace.start_l1_queue_index;
-        let mut builder = Self {
+        Ok(Self {
             sdb,
             code_db,
             block: builder_block,
             block_ctx: BlockContext::new(),
             mpt_init_state,
-        };
-
-        builder.apply_l2_trace(l2_trace)?;
-        Ok(builder)
+        })
     }
 
     /// Apply more l2 traces
     pub fn add_more_l2_trace(&mut self, l2_trace: BlockTrace) -> Result<(), Error> {
+        self.update_from_l2_trace(&l2_trace)?;
+        self.apply_l2_trace(l2_trace)
+    }
+
+    /// Merge the state and codes accessed by `l2_trace` into the builder,
+    /// without applying any of its blocks.
+    fn update_from_l2_trace(&mut self, l2_trace: &BlockTrace) -> Result<(), Error> {
         // update init state new data from storage
         if let Some(mpt_init_state) = &mut self.mpt_init_state {
             mpt_init_state.update_from_trace(
@@ -240,12 +268,110 @@ impl CircuitInputBuilder {
             *self.sdb.get_storage_mut(&addr, &key).1 = val;
         }
 
-        let codes = collect_codes(&l2_trace, Some(&self.sdb))?;
+        let codes = collect_codes(l2_trace, Some(&self.sdb))?;
         for (hash, code) in codes {
             self.code_db.insert_with_hash(hash, code);
         }
+        Ok(())
+    }
+}
+
+/// Builds a [`CircuitInputBuilder`] from block traces whose execution results
+/// are deserialized and handled one transaction at a time, so that the struct
+/// logs of a whole chunk never have to be held in memory.
+#[derive(Debug)]
+pub struct L2TraceStream {
+    circuits_params: CircuitsParams,
+    light_mode: bool,
+    builder: Option<CircuitInputBuilder>,
+    // the block whose transactions are being streamed
+    eth_block: Option<EthBlock>,
+}
 
-        self.apply_l2_trace(l2_trace)?;
+impl L2TraceStream {
+    /// Create a stream for a chunk with the given `circuits_params`
+    pub fn new(circuits_params: CircuitsParams, light_mode: bool) -> Self {
+        Self {
+            circuits_params,
+            light_mode,
+            builder: None,
+            eth_block: None,
+        }
+    }
+
+    /// Deserialize the next block trace of the chunk and apply it to the
+    /// builder, returning the trace without its execution results.
+    pub fn add_block_trace<'de, D>(&mut self, deserializer: D) -> Result<BlockTrace, Error>
+    where
+        D: Deserializer<'de, Error = serde_json::Error>,
+    {
+        let is_first_block = self.builder.is_none();
+        let block_trace = deserialize_block_trace(deserializer, self).map_err(Error::SerdeError)?;
+        let builder = self.builder.as_mut().ok_or(Error::InternalError(
+            "block trace without execution results",
+        ))?;
+        self.eth_block = None;
+
+        // the l1 queue index is listed after the execution results
+        let block_num = block_trace.header.number.unwrap_or_default().as_u64();
+        if let Some(block) = builder.block.blocks.get_mut(&block_num) {
+            block.start_l1_queue_index = block_trace.start_l1_queue_index;
+        }
+        if is_first_block {
+            builder.block.start_l1_queue_index = block_trace.start_l1_queue_index;
+        }
+        log::debug!("streamed l2 trace of block {block_num}");
+        Ok(block_trace)
+    }
+
+    /// Return the builder with all the block traces applied
+    pub fn finish(self) -> Result<CircuitInputBuilder, Error> {
+        self.builder
+            .ok_or(Error::InternalError("no block trace in l2 trace stream"))
+    }
+}
+
+impl BlockTraceVisitor for L2TraceStream {
+    type Error = Error;
+
+    fn visit_block(&mut self, block: &BlockTrace) -> Result<(), Self::Error> {
+        match self.builder.as_mut() {
+            Some(builder) => builder.update_from_l2_trace(block)?,
+            None => {
+                self.builder = Some(CircuitInputBuilder::init_from_l2_trace(
+                    self.circuits_params,
+                    block,
+                    self.light_mode,
+                )?)
+            }
+        }
+        let builder = self.builder.as_mut().expect("builder is initialized");
+        self.eth_block = Some(builder.begin_l2_block(block)?);
         Ok(())
     }
+
+    fn visit_execution_result(
+        &mut self,
+        block: &BlockTrace,
+        tx_idx: usize,
+        execution_result: ExecutionResult,
+    ) -> Result<(), Self::Error> {
+        let (Some(builder), Some(eth_block)) = (self.builder.as_mut(), self.eth_block.as_ref())
+        else {
+            return Err(Error::InternalError("execution result before block"));
+        };
+        if block.codes.is_empty() {
+            let codes = collect_execution_result_codes(
+                block,
+                tx_idx,
+                &execution_result,
+                Some(&builder.sdb),
+            )?;
+            for (hash, code) in codes {
+                builder.code_db.insert_with_hash(hash, code);
+            }
+        }
+        let geth_trace = eth_types::GethExecTrace::from(execution_result);
+        builder.handle_block_tx(eth_block, tx_idx, &geth_trace)
+    }
 }
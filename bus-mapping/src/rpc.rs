@@ -14,6 +14,9 @@ use std::collections::HashMap;
 
 use crate::util::GETH_TRACE_CHECK_LEVEL;
 
+mod replay;
+pub use replay::{RecordingClient, ReplayClient, RpcExchange, RpcFixtureError};
+
 /// Serialize a type.
 ///
 /// # Panics
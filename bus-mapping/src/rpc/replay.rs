@@ -0,0 +1,330 @@
+//! JSON-RPC clients recording the exchanges of a session with a node to a fixture file and
+//! replaying them from it, so that the RPC-driven parts of the bus mapping, e.g. the
+//! [`BuilderClient`](crate::circuit_input_builder::BuilderClient), can be exercised without a
+//! node.
+
+use async_trait::async_trait;
+use ethers_providers::{JsonRpcClient, JsonRpcError, ProviderError, RpcError};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Debug, Display},
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// A request to a node together with its response, either a result or an error.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcExchange {
+    /// method of the request
+    pub method: String,
+    /// params of the request
+    pub params: serde_json::Value,
+    /// result of the response
+    #[serde(default)]
+    pub result: serde_json::Value,
+    /// error of the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+}
+
+/// Error of the [`RecordingClient`] and the [`ReplayClient`].
+#[derive(Debug)]
+pub enum RpcFixtureError {
+    /// Error of the recorded client.
+    Inner(ProviderError),
+    /// The node responded with an error.
+    Response(JsonRpcError),
+    /// The request wasn't recorded in the fixture.
+    MissingExchange {
+        /// method of the request
+        method: String,
+        /// params of the request
+        params: serde_json::Value,
+    },
+    /// The params or the result failed to (de)serialize.
+    SerdeError(serde_json::Error),
+}
+
+impl Display for RpcFixtureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inner(e) => write!(f, "{e}"),
+            Self::Response(e) => write!(f, "{e}"),
+            Self::MissingExchange { method, params } => {
+                write!(f, "no recorded response to {method} with params {params}")
+            }
+            Self::SerdeError(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for RpcFixtureError {}
+
+impl RpcError for RpcFixtureError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            Self::Inner(e) => e.as_error_response(),
+            Self::Response(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            Self::SerdeError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<RpcFixtureError> for ProviderError {
+    fn from(e: RpcFixtureError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(e))
+    }
+}
+
+impl From<serde_json::Error> for RpcFixtureError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::SerdeError(e)
+    }
+}
+
+/// A client forwarding the requests to a node and recording every exchange, to be saved to a
+/// fixture file that the [`ReplayClient`] serves from. The error responses of the node are
+/// recorded too, while the transport errors aren't.
+///
+/// The fixture is saved when the client is dropped, so that it may be moved into a
+/// [`GethClient`](crate::rpc::GethClient) for a whole session.
+#[derive(Debug)]
+pub struct RecordingClient<P: JsonRpcClient> {
+    inner: P,
+    path: PathBuf,
+    exchanges: Mutex<Vec<RpcExchange>>,
+}
+
+impl<P: JsonRpcClient> RecordingClient<P> {
+    /// Record the exchanges with the inner client to the fixture file at path.
+    pub fn new(inner: P, path: impl AsRef<Path>) -> Self {
+        Self {
+            inner,
+            path: path.as_ref().to_path_buf(),
+            exchanges: Mutex::new(vec![]),
+        }
+    }
+
+    /// The exchanges recorded so far.
+    pub fn exchanges(&self) -> Vec<RpcExchange> {
+        self.exchanges.lock().unwrap().clone()
+    }
+
+    /// Merge the exchanges recorded so far into the fixture file, so that several clients may
+    /// record a session to the same file.
+    pub fn save(&self) -> Result<(), crate::Error> {
+        static SAVE_LOCK: Mutex<()> = Mutex::new(());
+        let _guard = SAVE_LOCK.lock().unwrap();
+
+        let mut exchanges = if self.path.exists() {
+            read_fixture(&self.path)?
+        } else {
+            vec![]
+        };
+        let mut recorded = exchanges
+            .iter()
+            .map(|exchange| (exchange.method.clone(), exchange.params.to_string()))
+            .collect::<HashSet<_>>();
+        for exchange in self.exchanges() {
+            if recorded.insert((exchange.method.clone(), exchange.params.to_string())) {
+                exchanges.push(exchange);
+            }
+        }
+
+        let file = File::create(&self.path).map_err(crate::Error::IoError)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &exchanges)
+            .map_err(crate::Error::SerdeError)
+    }
+}
+
+impl<P: JsonRpcClient> Drop for RecordingClient<P> {
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            log::error!("failed to save rpc fixture {}: {e:?}", self.path.display());
+        }
+    }
+}
+
+#[async_trait]
+impl<P: JsonRpcClient> JsonRpcClient for RecordingClient<P> {
+    type Error = RpcFixtureError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params)?;
+        let response: Result<serde_json::Value, _> = self.inner.request(method, &params).await;
+        let (result, error) = match response {
+            Ok(result) => (result, None),
+            Err(e) => match e.as_error_response() {
+                Some(error) => (serde_json::Value::Null, Some(error.clone())),
+                None => return Err(RpcFixtureError::Inner(e.into())),
+            },
+        };
+        self.exchanges.lock().unwrap().push(RpcExchange {
+            method: method.to_string(),
+            params,
+            result: result.clone(),
+            error: error.clone(),
+        });
+        match error {
+            Some(error) => Err(RpcFixtureError::Response(error)),
+            None => Ok(serde_json::from_value(result)?),
+        }
+    }
+}
+
+/// A client serving the responses recorded by a [`RecordingClient`], deterministically and
+/// without a node. Requests are matched by method and params, and a repeated request gets the
+/// same response.
+#[derive(Debug, Default)]
+pub struct ReplayClient {
+    responses: HashMap<(String, String), Result<serde_json::Value, JsonRpcError>>,
+}
+
+impl ReplayClient {
+    /// Serve the responses of the exchanges.
+    pub fn new(exchanges: Vec<RpcExchange>) -> Self {
+        let responses = exchanges
+            .into_iter()
+            .map(|exchange| {
+                let response = match exchange.error {
+                    Some(error) => Err(error),
+                    None => Ok(exchange.result),
+                };
+                ((exchange.method, exchange.params.to_string()), response)
+            })
+            .collect();
+        Self { responses }
+    }
+
+    /// Serve the responses of the fixture file at path.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, crate::Error> {
+        Ok(Self::new(read_fixture(path.as_ref())?))
+    }
+}
+
+fn read_fixture(path: &Path) -> Result<Vec<RpcExchange>, crate::Error> {
+    let file = File::open(path).map_err(crate::Error::IoError)?;
+    serde_json::from_reader(BufReader::new(file)).map_err(crate::Error::SerdeError)
+}
+
+#[async_trait]
+impl JsonRpcClient for ReplayClient {
+    type Error = RpcFixtureError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params)?;
+        let response = self
+            .responses
+            .get(&(method.to_string(), params.to_string()))
+            .ok_or_else(|| RpcFixtureError::MissingExchange {
+                method: method.to_string(),
+                params,
+            })?;
+        match response {
+            Ok(result) => Ok(serde_json::from_value(result.clone())?),
+            Err(error) => Err(RpcFixtureError::Response(error.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::GethClient;
+    use eth_types::{Address, Bytes, U64};
+    use serde_json::json;
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn record_and_replay() {
+        let l1_gas_price_oracle =
+            Address::from_str("0x5300000000000000000000000000000000000002").unwrap();
+        let header_not_found = JsonRpcError {
+            code: -32000,
+            message: "header not found".into(),
+            data: None,
+        };
+        let node = ReplayClient::new(vec![
+            RpcExchange {
+                method: "eth_chainId".into(),
+                params: json!(null),
+                result: json!("0x82750"),
+                error: None,
+            },
+            RpcExchange {
+                method: "eth_getCode".into(),
+                params: json!(["0x5300000000000000000000000000000000000002", "latest"]),
+                result: json!("0x6080"),
+                error: None,
+            },
+            RpcExchange {
+                method: "eth_getCode".into(),
+                params: json!(["0x5300000000000000000000000000000000000002", "0x11"]),
+                result: json!(null),
+                error: Some(header_not_found.clone()),
+            },
+        ]);
+        let path = std::env::temp_dir().join(format!("rpc_fixture_{}.json", std::process::id()));
+
+        let recorder = GethClient::new(RecordingClient::new(node, &path));
+        assert_eq!(recorder.get_chain_id().await.unwrap(), 534352);
+        assert_eq!(recorder.get_chain_id().await.unwrap(), 534352);
+        // the node doesn't answer it, so that it isn't recorded
+        assert!(recorder.get_coinbase().await.is_err());
+        // the node answers it with an error, which is recorded
+        assert!(recorder
+            .get_code(l1_gas_price_oracle, 17u64.into())
+            .await
+            .is_err());
+        assert_eq!(recorder.0.exchanges().len(), 3);
+        drop(recorder);
+        // a later session is merged into the fixture
+        let recorder = GethClient::new(RecordingClient::new(
+            ReplayClient::load(&path).unwrap(),
+            &path,
+        ));
+        assert_eq!(recorder.get_chain_id().await.unwrap(), 534352);
+        drop(recorder);
+
+        // the repeated exchanges are recorded once
+        assert_eq!(read_fixture(&path).unwrap().len(), 2);
+
+        let replay = GethClient::new(ReplayClient::load(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.get_chain_id().await.unwrap(), 534352);
+        let chain_id: U64 = replay.0.request("eth_chainId", ()).await.unwrap();
+        assert_eq!(chain_id.as_u64(), 534352);
+        assert!(matches!(
+            replay.0.request::<_, U64>("eth_blockNumber", ()).await,
+            Err(RpcFixtureError::MissingExchange { .. })
+        ));
+        // the error response is replayed
+        let error = replay
+            .0
+            .request::<_, Bytes>(
+                "eth_getCode",
+                json!(["0x5300000000000000000000000000000000000002", "0x11"]),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error.as_error_response(), Some(&header_not_found));
+    }
+}
@@ -3,7 +3,7 @@
 use crate::constants::read_env_var;
 
 /// Hardfork ID for scroll networks
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum HardforkId {
     /// Bernoulli hardfork
     Bernoulli = 2,
@@ -38,3 +38,11 @@ pub fn hardfork_heights() -> Vec<(HardforkId, u64, u64)> {
         ), // mainnet
     ]
 }
+
+/// Get the block height of a hardfork of a Scroll network, which is 0 for other chains.
+pub fn hardfork_block(fork: HardforkId, chain_id: u64) -> u64 {
+    hardfork_heights()
+        .into_iter()
+        .find(|(fork_id, fork_chain_id, _)| *fork_id == fork && *fork_chain_id == chain_id)
+        .map_or(0, |(_, _, fork_block)| fork_block)
+}
@@ -2,19 +2,198 @@
 
 // Copied from https://github.com/scroll-tech/go-ethereum/blob/8dc419a70b94f5ca185dcf818a48a3bd2eefc392/rollup/rcfg/config.go#L42
 
-use crate::Address;
+use crate::{
+    forks::{hardfork_block, HardforkId},
+    state_db::StateDB,
+    Address, Word, U256,
+};
+use std::sync::LazyLock;
+
+/// An L2 system contract, predeployed at genesis.
+#[derive(Debug)]
+pub struct Predeploy {
+    /// name of the contract
+    pub name: &'static str,
+    /// address of the contract
+    pub address: Address,
+    /// storage slots read or written by the node or the provers, named as in l2geth `rcfg`
+    pub slots: Vec<(&'static str, U256)>,
+    /// bytecode versions, each with the hardfork deploying it or `None` for genesis, in the
+    /// order of the hardforks
+    pub bytecodes: Vec<(Option<HardforkId>, &'static [u8])>,
+}
+
+impl Predeploy {
+    /// Get the storage slot named `name`.
+    pub fn slot(&self, name: &str) -> Option<U256> {
+        self.slots
+            .iter()
+            .find(|(slot_name, _)| *slot_name == name)
+            .map(|(_, slot)| *slot)
+    }
+
+    /// Get the bytecode deployed at the block, if known.
+    pub fn bytecode(&self, chain_id: u64, block_number: u64) -> Option<&'static [u8]> {
+        self.bytecodes
+            .iter()
+            .rev()
+            .find(|(fork, _)| {
+                fork.map_or(true, |fork| block_number >= hardfork_block(fork, chain_id))
+            })
+            .map(|(_, bytecode)| *bytecode)
+    }
+
+    /// Get the storage value at `slot`, including the writes of the current tx.
+    pub fn storage(&self, sdb: &StateDB, slot: &U256) -> Word {
+        *sdb.get_storage(&self.address, slot).1
+    }
+
+    /// Get the storage value at `slot` before the current tx.
+    pub fn committed_storage(&self, sdb: &StateDB, slot: &U256) -> Word {
+        *sdb.get_committed_storage(&self.address, slot).1
+    }
+
+    /// Set the storage value at `slot` in the current tx.
+    pub fn set_storage(&self, sdb: &mut StateDB, slot: &U256, value: Word) {
+        sdb.set_storage(&self.address, slot, &value);
+    }
+}
+
+/// The L2 system contracts.
+pub static PREDEPLOYS: LazyLock<Vec<Predeploy>> = LazyLock::new(|| {
+    vec![
+        Predeploy {
+            name: "L2MessageQueue",
+            address: *message_queue::ADDRESS,
+            slots: vec![
+                (
+                    "WithdrawTrieRootSlot",
+                    message_queue::WITHDRAW_TRIE_ROOT_SLOT,
+                ),
+                (
+                    "NextMessageIndexSlot",
+                    *message_queue::NEXT_MESSAGE_INDEX_SLOT,
+                ),
+                ("BranchesSlot", *message_queue::BRANCHES_SLOT),
+            ],
+            bytecodes: vec![],
+        },
+        Predeploy {
+            name: "L1GasPriceOracle",
+            address: *l1_gas_price_oracle::ADDRESS,
+            slots: vec![
+                ("L1BaseFeeSlot", *l1_gas_price_oracle::BASE_FEE_SLOT),
+                ("OverheadSlot", *l1_gas_price_oracle::OVERHEAD_SLOT),
+                ("ScalarSlot", *l1_gas_price_oracle::SCALAR_SLOT),
+                (
+                    "L1BlobBaseFeeSlot",
+                    *l1_gas_price_oracle::L1_BLOB_BASEFEE_SLOT,
+                ),
+                ("CommitScalarSlot", *l1_gas_price_oracle::COMMIT_SCALAR_SLOT),
+                ("BlobScalarSlot", *l1_gas_price_oracle::BLOB_SCALAR_SLOT),
+                ("IsCurieSlot", *l1_gas_price_oracle::IS_CURIE_SLOT),
+            ],
+            bytecodes: vec![
+                (None, l1_gas_price_oracle::V1_BYTECODE.as_slice()),
+                (
+                    Some(HardforkId::Curie),
+                    l1_gas_price_oracle::V2_BYTECODE.as_slice(),
+                ),
+            ],
+        },
+        Predeploy {
+            name: "Whitelist",
+            address: *whitelist::ADDRESS,
+            slots: vec![],
+            bytecodes: vec![],
+        },
+        Predeploy {
+            name: "WETH",
+            address: *weth::ADDRESS,
+            slots: vec![],
+            bytecodes: vec![],
+        },
+        Predeploy {
+            name: "L2TxFeeVault",
+            address: *fee_vault::ADDRESS,
+            slots: vec![],
+            bytecodes: vec![],
+        },
+    ]
+});
+
+/// Get the L2 system contract at `address`.
+pub fn predeploy(address: &Address) -> Option<&'static Predeploy> {
+    PREDEPLOYS
+        .iter()
+        .find(|predeploy| predeploy.address == *address)
+}
+
+/// Whether an L2 system contract is at `address`.
+pub fn is_predeploy(address: &Address) -> bool {
+    predeploy(address).is_some()
+}
 
 /// helper for L2MessageQueue contract
 pub mod message_queue {
     use super::*;
-    use crate::U256;
-    use std::{str::FromStr, sync::LazyLock};
+    use std::str::FromStr;
 
     /// address of L2MessageQueue predeploy
     pub static ADDRESS: LazyLock<Address> =
         LazyLock::new(|| Address::from_str("0x5300000000000000000000000000000000000000").unwrap());
     /// the slot of withdraw root in L2MessageQueue
     pub static WITHDRAW_TRIE_ROOT_SLOT: U256 = U256::zero();
+
+    // The withdraw trie is the append only merkle tree of the appended message hashes, with the
+    // storage layout of
+    // <https://github.com/scroll-tech/scroll/blob/develop/contracts/src/libraries/common/AppendOnlyMerkleTree.sol>
+    /// max height of the withdraw trie
+    pub const MAX_TREE_HEIGHT: usize = 40;
+    /// the slot of the index of the next appended message in L2MessageQueue
+    pub static NEXT_MESSAGE_INDEX_SLOT: LazyLock<U256> = LazyLock::new(|| U256::from(1));
+    /// the slot of `branches[0]` in L2MessageQueue, where `branches[h]` is the last left node at
+    /// height `h` of the withdraw trie
+    pub static BRANCHES_SLOT: LazyLock<U256> =
+        LazyLock::new(|| U256::from(2 + MAX_TREE_HEIGHT as u64));
+    /// signature of `appendMessage(bytes32 messageHash)`, called by L2ScrollMessenger
+    pub const APPEND_MESSAGE_SIGNATURE: &str = "appendMessage(bytes32)";
+
+    /// L2MessageQueue in the [`PREDEPLOYS`](super::PREDEPLOYS)
+    pub fn predeploy() -> &'static Predeploy {
+        super::predeploy(&ADDRESS).expect("L2MessageQueue is predeployed")
+    }
+}
+
+/// Helper for Whitelist contract
+pub mod whitelist {
+    use super::*;
+    use std::str::FromStr;
+
+    /// address of Whitelist predeploy
+    pub static ADDRESS: LazyLock<Address> =
+        LazyLock::new(|| Address::from_str("0x5300000000000000000000000000000000000003").unwrap());
+}
+
+/// Helper for L2 WETH contract
+pub mod weth {
+    use super::*;
+    use std::str::FromStr;
+
+    /// address of WETH predeploy
+    pub static ADDRESS: LazyLock<Address> =
+        LazyLock::new(|| Address::from_str("0x5300000000000000000000000000000000000004").unwrap());
+}
+
+/// Helper for L2TxFeeVault contract
+pub mod fee_vault {
+    use super::*;
+    use std::str::FromStr;
+
+    /// address of L2TxFeeVault predeploy, which receives the L2 fees unless the chain config
+    /// sets another fee vault
+    pub static ADDRESS: LazyLock<Address> =
+        LazyLock::new(|| Address::from_str("0x5300000000000000000000000000000000000005").unwrap());
 }
 
 /// Helper for L1GasPriceOracle contract
@@ -22,6 +201,7 @@ pub mod message_queue {
 pub mod l1_gas_price_oracle {
     use revm_primitives::HashMap;
 
+    use super::Predeploy;
     use crate::{geth_types::Account, Address, U256};
     use std::{str::FromStr, sync::LazyLock};
 
@@ -62,6 +242,21 @@ pub mod l1_gas_price_oracle {
         hex::decode(include_str!("./data/v2_l1_oracle_bytecode.txt")).expect("decode v2 bytecode")
     });
 
+    /// Storage written by the curie upgrade of the contract, along with its bytecode
+    pub static CURIE_STORAGE: LazyLock<[(U256, U256); 4]> = LazyLock::new(|| {
+        [
+            (*IS_CURIE_SLOT, U256::from(1u64)),
+            (*L1_BLOB_BASEFEE_SLOT, U256::from(1u64)),
+            (*COMMIT_SCALAR_SLOT, *INITIAL_COMMIT_SCALAR),
+            (*BLOB_SCALAR_SLOT, *INITIAL_BLOB_SCALAR),
+        ]
+    });
+
+    /// L1GasPriceOracle in the [`PREDEPLOYS`](super::PREDEPLOYS)
+    pub fn predeploy() -> &'static Predeploy {
+        super::predeploy(&ADDRESS).expect("L1GasPriceOracle is predeployed")
+    }
+
     /// Default contract state for testing
     pub fn default_contract_account() -> Account {
         let storages: Vec<(U256, U256)> = vec![
@@ -79,3 +274,60 @@ pub mod l1_gas_price_oracle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::forks::SCROLL_DEVNET_CHAIN_ID;
+
+    #[test]
+    fn predeploy_registry() {
+        let oracle = l1_gas_price_oracle::predeploy();
+        assert_eq!(oracle.name, "L1GasPriceOracle");
+        assert_eq!(
+            oracle.slot("CommitScalarSlot"),
+            Some(*l1_gas_price_oracle::COMMIT_SCALAR_SLOT)
+        );
+        assert_eq!(oracle.slot("UnknownSlot"), None);
+        assert!(is_predeploy(&fee_vault::ADDRESS));
+        assert!(!is_predeploy(&Address::zero()));
+
+        // curie is activated at block 5 of devnet
+        assert_eq!(
+            oracle.bytecode(SCROLL_DEVNET_CHAIN_ID, 4),
+            Some(l1_gas_price_oracle::V1_BYTECODE.as_slice())
+        );
+        assert_eq!(
+            oracle.bytecode(SCROLL_DEVNET_CHAIN_ID, 5),
+            Some(l1_gas_price_oracle::V2_BYTECODE.as_slice())
+        );
+        assert_eq!(
+            message_queue::predeploy().bytecode(SCROLL_DEVNET_CHAIN_ID, 5),
+            None
+        );
+    }
+
+    #[test]
+    fn predeploy_storage() {
+        let oracle = l1_gas_price_oracle::predeploy();
+        let slot = *l1_gas_price_oracle::COMMIT_SCALAR_SLOT;
+        let mut sdb = StateDB::new();
+        sdb.get_account_mut(&oracle.address)
+            .1
+            .storage
+            .insert(slot, *l1_gas_price_oracle::INITIAL_COMMIT_SCALAR);
+        assert_eq!(
+            oracle.storage(&sdb, &slot),
+            *l1_gas_price_oracle::INITIAL_COMMIT_SCALAR
+        );
+
+        oracle.set_storage(&mut sdb, &slot, Word::from(10));
+        assert_eq!(oracle.storage(&sdb, &slot), Word::from(10));
+        assert_eq!(
+            oracle.committed_storage(&sdb, &slot),
+            *l1_gas_price_oracle::INITIAL_COMMIT_SCALAR
+        );
+        sdb.commit_tx();
+        assert_eq!(oracle.committed_storage(&sdb, &slot), Word::from(10));
+    }
+}
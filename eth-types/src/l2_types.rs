@@ -14,6 +14,10 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use trace::collect_codes;
 
+/// Compact binary encoding of block traces
+pub mod codec;
+/// Streaming deserialization of block traces
+pub mod stream;
 /// Trace related helpers
 pub mod trace;
 
@@ -0,0 +1,807 @@
+//! Compact binary encoding of block traces.
+//!
+//! The JSON traces produced by l2geth spell every word as a hex string and
+//! every struct log field by name, which makes them large and slow to parse.
+//! The encoding here stores the same data with bincode, using raw bytes for
+//! hashes, addresses and words and varints for integers.
+//!
+//! An encoded trace starts with [`TRACE_CODEC_MAGIC`], followed by the schema
+//! version as a little endian `u16` and a byte telling which kind of trace
+//! follows. Decoding rejects any version other than [`TRACE_CODEC_VERSION`],
+//! so the schema below must be bumped whenever it changes.
+//!
+//! The block header, the call traces and the prestates have serde
+//! representations that only work with self describing formats, so they are
+//! embedded as JSON. Struct log errors are stored as a tagged enum, since their
+//! serde representation drops the payload of some errors.
+
+use super::{
+    AccountTrace, BlockTrace, BlockTraceV2, BytecodeTrace, ExecStep, ExecutionResult, ExtraData,
+    StorageTrace, TransactionTrace,
+};
+use crate::{evm_types::OpcodeId, GethExecError, ToBigEndian, H256};
+use bincode::Options;
+use ethers_core::types::{transaction::eip2930::AccessListItem, Address, Bytes, U256, U64};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// Leading bytes of every encoded trace.
+pub const TRACE_CODEC_MAGIC: [u8; 4] = *b"L2BT";
+/// Version of the schema written by this module.
+pub const TRACE_CODEC_VERSION: u16 = 1;
+
+const HEADER_LEN: usize = TRACE_CODEC_MAGIC.len() + 3;
+const KIND_BLOCK_TRACE: u8 = 0;
+const KIND_BLOCK_TRACE_V2: u8 = 1;
+
+/// Error while encoding or decoding a trace.
+#[derive(Debug)]
+pub enum CodecError {
+    /// The input does not start with [`TRACE_CODEC_MAGIC`].
+    InvalidMagic,
+    /// The input was written with another schema version.
+    UnsupportedVersion(u16),
+    /// The input holds another kind of trace.
+    UnexpectedKind(u8),
+    /// bincode de/serialization error.
+    Bincode(bincode::Error),
+    /// Error in one of the fields embedded as JSON.
+    Json(serde_json::Error),
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{self:?}")
+    }
+}
+
+impl StdError for CodecError {}
+
+impl From<bincode::Error> for CodecError {
+    fn from(e: bincode::Error) -> Self {
+        Self::Bincode(e)
+    }
+}
+
+impl From<serde_json::Error> for CodecError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// Encode a block trace.
+pub fn encode_block_trace(trace: &BlockTrace) -> Result<Vec<u8>, CodecError> {
+    encode(KIND_BLOCK_TRACE, &CompactBlockTrace::try_from(trace)?)
+}
+
+/// Decode a block trace written by [`encode_block_trace`].
+pub fn decode_block_trace(bytes: &[u8]) -> Result<BlockTrace, CodecError> {
+    decode::<CompactBlockTrace>(KIND_BLOCK_TRACE, bytes)?.try_into()
+}
+
+/// Encode a block trace without execution results.
+pub fn encode_block_trace_v2(trace: &BlockTraceV2) -> Result<Vec<u8>, CodecError> {
+    encode(KIND_BLOCK_TRACE_V2, &CompactBlockTraceV2::try_from(trace)?)
+}
+
+/// Decode a block trace written by [`encode_block_trace_v2`].
+pub fn decode_block_trace_v2(bytes: &[u8]) -> Result<BlockTraceV2, CodecError> {
+    decode::<CompactBlockTraceV2>(KIND_BLOCK_TRACE_V2, bytes)?.try_into()
+}
+
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+fn encode<T: Serialize>(kind: u8, value: &T) -> Result<Vec<u8>, CodecError> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(&TRACE_CODEC_MAGIC);
+    bytes.extend_from_slice(&TRACE_CODEC_VERSION.to_le_bytes());
+    bytes.push(kind);
+    bincode_options().serialize_into(&mut bytes, value)?;
+    Ok(bytes)
+}
+
+// A decoded value never takes more bytes than its encoding, so the input length
+// bounds the lengths bincode may allocate for.
+fn decode<T: DeserializeOwned>(kind: u8, bytes: &[u8]) -> Result<T, CodecError> {
+    if bytes.len() < HEADER_LEN || bytes[..TRACE_CODEC_MAGIC.len()] != TRACE_CODEC_MAGIC {
+        return Err(CodecError::InvalidMagic);
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != TRACE_CODEC_VERSION {
+        return Err(CodecError::UnsupportedVersion(version));
+    }
+    if bytes[6] != kind {
+        return Err(CodecError::UnexpectedKind(bytes[6]));
+    }
+    let bytes = &bytes[HEADER_LEN..];
+    Ok(bincode_options()
+        .with_limit(bytes.len() as u64)
+        .deserialize(bytes)?)
+}
+
+// Words are stored big endian without leading zeros.
+fn encode_word(word: &U256) -> Vec<u8> {
+    let bytes = word.to_be_bytes();
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    bytes[start..].to_vec()
+}
+
+fn decode_word(bytes: &[u8]) -> U256 {
+    U256::from_big_endian(bytes)
+}
+
+fn encode_codes(codes: &[Bytes]) -> Vec<Vec<u8>> {
+    codes.iter().map(|code| code.to_vec()).collect()
+}
+
+fn decode_codes(codes: Vec<Vec<u8>>) -> Vec<Bytes> {
+    codes.into_iter().map(Bytes::from).collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct CompactBlockTrace {
+    chain_id: u64,
+    coinbase: CompactAccountTrace,
+    header: Vec<u8>,
+    transactions: Vec<CompactTransactionTrace>,
+    execution_results: Vec<CompactExecutionResult>,
+    codes: Vec<([u8; 32], Vec<u8>)>,
+    storage_trace: CompactStorageTrace,
+    tx_storage_trace: Vec<CompactStorageTrace>,
+    start_l1_queue_index: u64,
+    withdraw_trie_root: [u8; 32],
+}
+
+impl TryFrom<&BlockTrace> for CompactBlockTrace {
+    type Error = CodecError;
+
+    fn try_from(trace: &BlockTrace) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_id: trace.chain_id,
+            coinbase: (&trace.coinbase).into(),
+            header: serde_json::to_vec(&trace.header)?,
+            transactions: trace.transactions.iter().map(Into::into).collect(),
+            execution_results: trace
+                .execution_results
+                .iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            codes: trace
+                .codes
+                .iter()
+                .map(|c| (c.hash.0, c.code.to_vec()))
+                .collect(),
+            storage_trace: (&trace.storage_trace).into(),
+            tx_storage_trace: trace.tx_storage_trace.iter().map(Into::into).collect(),
+            start_l1_queue_index: trace.start_l1_queue_index,
+            withdraw_trie_root: trace.withdraw_trie_root.0,
+        })
+    }
+}
+
+impl TryFrom<CompactBlockTrace> for BlockTrace {
+    type Error = CodecError;
+
+    fn try_from(trace: CompactBlockTrace) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_id: trace.chain_id,
+            coinbase: trace.coinbase.into(),
+            header: serde_json::from_slice(&trace.header)?,
+            transactions: trace.transactions.into_iter().map(Into::into).collect(),
+            execution_results: trace
+                .execution_results
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            codes: trace
+                .codes
+                .into_iter()
+                .map(|(hash, code)| BytecodeTrace {
+                    hash: H256(hash),
+                    code: code.into(),
+                })
+                .collect(),
+            storage_trace: trace.storage_trace.into(),
+            tx_storage_trace: trace.tx_storage_trace.into_iter().map(Into::into).collect(),
+            start_l1_queue_index: trace.start_l1_queue_index,
+            withdraw_trie_root: H256(trace.withdraw_trie_root),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CompactBlockTraceV2 {
+    chain_id: u64,
+    coinbase: CompactAccountTrace,
+    header: Vec<u8>,
+    transactions: Vec<CompactTransactionTrace>,
+    codes: Vec<([u8; 32], Vec<u8>)>,
+    storage_trace: CompactStorageTrace,
+    start_l1_queue_index: u64,
+}
+
+impl TryFrom<&BlockTraceV2> for CompactBlockTraceV2 {
+    type Error = CodecError;
+
+    fn try_from(trace: &BlockTraceV2) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_id: trace.chain_id,
+            coinbase: (&trace.coinbase).into(),
+            header: serde_json::to_vec(&trace.header)?,
+            transactions: trace.transactions.iter().map(Into::into).collect(),
+            codes: trace
+                .codes
+                .iter()
+                .map(|c| (c.hash.0, c.code.to_vec()))
+                .collect(),
+            storage_trace: (&trace.storage_trace).into(),
+            start_l1_queue_index: trace.start_l1_queue_index,
+        })
+    }
+}
+
+impl TryFrom<CompactBlockTraceV2> for BlockTraceV2 {
+    type Error = CodecError;
+
+    fn try_from(trace: CompactBlockTraceV2) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_id: trace.chain_id,
+            coinbase: trace.coinbase.into(),
+            header: serde_json::from_slice(&trace.header)?,
+            transactions: trace.transactions.into_iter().map(Into::into).collect(),
+            codes: trace
+                .codes
+                .into_iter()
+                .map(|(hash, code)| BytecodeTrace {
+                    hash: H256(hash),
+                    code: code.into(),
+                })
+                .collect(),
+            storage_trace: trace.storage_trace.into(),
+            start_l1_queue_index: trace.start_l1_queue_index,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CompactAccountTrace {
+    address: [u8; 20],
+    nonce: u64,
+    balance: Vec<u8>,
+    keccak_code_hash: [u8; 32],
+    poseidon_code_hash: [u8; 32],
+    code_size: u64,
+}
+
+impl From<&AccountTrace> for CompactAccountTrace {
+    fn from(account: &AccountTrace) -> Self {
+        Self {
+            address: account.address.0,
+            nonce: account.nonce,
+            balance: encode_word(&account.balance),
+            keccak_code_hash: account.keccak_code_hash.0,
+            poseidon_code_hash: account.poseidon_code_hash.0,
+            code_size: account.code_size,
+        }
+    }
+}
+
+impl From<CompactAccountTrace> for AccountTrace {
+    fn from(account: CompactAccountTrace) -> Self {
+        Self {
+            address: Address::from(account.address),
+            nonce: account.nonce,
+            balance: decode_word(&account.balance),
+            keccak_code_hash: H256(account.keccak_code_hash),
+            poseidon_code_hash: H256(account.poseidon_code_hash),
+            code_size: account.code_size,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CompactTransactionTrace {
+    tx_hash: [u8; 32],
+    type_: u8,
+    nonce: u64,
+    gas: u64,
+    gas_price: Vec<u8>,
+    gas_tip_cap: Option<Vec<u8>>,
+    gas_fee_cap: Option<Vec<u8>>,
+    from: [u8; 20],
+    to: Option<[u8; 20]>,
+    chain_id: Vec<u8>,
+    value: Vec<u8>,
+    data: Vec<u8>,
+    is_create: bool,
+    access_list: Option<Vec<([u8; 20], Vec<[u8; 32]>)>>,
+    v: u64,
+    r: Vec<u8>,
+    s: Vec<u8>,
+}
+
+impl From<&TransactionTrace> for CompactTransactionTrace {
+    fn from(tx: &TransactionTrace) -> Self {
+        Self {
+            tx_hash: tx.tx_hash.0,
+            type_: tx.type_,
+            nonce: tx.nonce,
+            gas: tx.gas,
+            gas_price: encode_word(&tx.gas_price),
+            gas_tip_cap: tx.gas_tip_cap.as_ref().map(encode_word),
+            gas_fee_cap: tx.gas_fee_cap.as_ref().map(encode_word),
+            from: tx.from.0,
+            to: tx.to.map(|to| to.0),
+            chain_id: encode_word(&tx.chain_id),
+            value: encode_word(&tx.value),
+            data: tx.data.to_vec(),
+            is_create: tx.is_create,
+            access_list: tx.access_list.as_ref().map(|access_list| {
+                access_list
+                    .iter()
+                    .map(|item| {
+                        (
+                            item.address.0,
+                            item.storage_keys.iter().map(|key| key.0).collect(),
+                        )
+                    })
+                    .collect()
+            }),
+            v: tx.v.as_u64(),
+            r: encode_word(&tx.r),
+            s: encode_word(&tx.s),
+        }
+    }
+}
+
+impl From<CompactTransactionTrace> for TransactionTrace {
+    fn from(tx: CompactTransactionTrace) -> Self {
+        Self {
+            tx_hash: H256(tx.tx_hash),
+            type_: tx.type_,
+            nonce: tx.nonce,
+            gas: tx.gas,
+            gas_price: decode_word(&tx.gas_price),
+            gas_tip_cap: tx.gas_tip_cap.as_deref().map(decode_word),
+            gas_fee_cap: tx.gas_fee_cap.as_deref().map(decode_word),
+            from: Address::from(tx.from),
+            to: tx.to.map(Address::from),
+            chain_id: decode_word(&tx.chain_id),
+            value: decode_word(&tx.value),
+            data: tx.data.into(),
+            is_create: tx.is_create,
+            access_list: tx.access_list.map(|access_list| {
+                access_list
+                    .into_iter()
+                    .map(|(address, storage_keys)| AccessListItem {
+                        address: Address::from(address),
+                        storage_keys: storage_keys.into_iter().map(H256).collect(),
+                    })
+                    .collect()
+            }),
+            v: U64::from(tx.v),
+            r: decode_word(&tx.r),
+            s: decode_word(&tx.s),
+        }
+    }
+}
+
+// Proof maps are sorted by key so that the encoding of a trace is
+// deterministic.
+#[derive(Serialize, Deserialize)]
+struct CompactStorageTrace {
+    root_before: [u8; 32],
+    root_after: [u8; 32],
+    proofs: Option<Vec<([u8; 20], Vec<Vec<u8>>)>>,
+    storage_proofs: Vec<([u8; 20], Vec<([u8; 32], Vec<Vec<u8>>)>)>,
+    deletion_proofs: Vec<Vec<u8>>,
+}
+
+impl From<&StorageTrace> for CompactStorageTrace {
+    fn from(storage_trace: &StorageTrace) -> Self {
+        Self {
+            root_before: storage_trace.root_before.0,
+            root_after: storage_trace.root_after.0,
+            proofs: storage_trace.proofs.as_ref().map(|proofs| {
+                let mut proofs = proofs
+                    .iter()
+                    .map(|(address, proof)| (address.0, encode_codes(proof)))
+                    .collect::<Vec<_>>();
+                proofs.sort_by_key(|(address, _)| *address);
+                proofs
+            }),
+            storage_proofs: {
+                let mut storage_proofs = storage_trace
+                    .storage_proofs
+                    .iter()
+                    .map(|(address, proofs)| {
+                        let mut proofs = proofs
+                            .iter()
+                            .map(|(key, proof)| (key.0, encode_codes(proof)))
+                            .collect::<Vec<_>>();
+                        proofs.sort_by_key(|(key, _)| *key);
+                        (address.0, proofs)
+                    })
+                    .collect::<Vec<_>>();
+                storage_proofs.sort_by_key(|(address, _)| *address);
+                storage_proofs
+            },
+            deletion_proofs: encode_codes(&storage_trace.deletion_proofs),
+        }
+    }
+}
+
+impl From<CompactStorageTrace> for StorageTrace {
+    fn from(storage_trace: CompactStorageTrace) -> Self {
+        Self {
+            root_before: H256(storage_trace.root_before),
+            root_after: H256(storage_trace.root_after),
+            proofs: storage_trace.proofs.map(|proofs| {
+                proofs
+                    .into_iter()
+                    .map(|(address, proof)| (Address::from(address), decode_codes(proof)))
+                    .collect()
+            }),
+            storage_proofs: storage_trace
+                .storage_proofs
+                .into_iter()
+                .map(|(address, proofs)| {
+                    (
+                        Address::from(address),
+                        proofs
+                            .into_iter()
+                            .map(|(key, proof)| (H256(key), decode_codes(proof)))
+                            .collect(),
+                    )
+                })
+                .collect(),
+            deletion_proofs: decode_codes(storage_trace.deletion_proofs),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CompactExecutionResult {
+    l1_fee: Vec<u8>,
+    gas: u64,
+    failed: bool,
+    return_value: String,
+    from: Option<CompactAccountTrace>,
+    to: Option<CompactAccountTrace>,
+    account_after: Vec<CompactAccountTrace>,
+    account_created: Option<CompactAccountTrace>,
+    code_hash: Option<[u8; 32]>,
+    byte_code: Option<String>,
+    exec_steps: Vec<CompactExecStep>,
+    call_trace: Vec<u8>,
+    prestate: Vec<u8>,
+}
+
+impl TryFrom<&ExecutionResult> for CompactExecutionResult {
+    type Error = CodecError;
+
+    fn try_from(result: &ExecutionResult) -> Result<Self, Self::Error> {
+        Ok(Self {
+            l1_fee: encode_word(&result.l1_fee),
+            gas: result.gas,
+            failed: result.failed,
+            return_value: result.return_value.clone(),
+            from: result.from.as_ref().map(Into::into),
+            to: result.to.as_ref().map(Into::into),
+            account_after: result.account_after.iter().map(Into::into).collect(),
+            account_created: result.account_created.as_ref().map(Into::into),
+            code_hash: result.code_hash.map(|hash| hash.0),
+            byte_code: result.byte_code.clone(),
+            exec_steps: result.exec_steps.iter().map(Into::into).collect(),
+            call_trace: serde_json::to_vec(&result.call_trace)?,
+            // going through a `Value` sorts the accounts and storage slots
+            prestate: serde_json::to_vec(&serde_json::to_value(&result.prestate)?)?,
+        })
+    }
+}
+
+impl TryFrom<CompactExecutionResult> for ExecutionResult {
+    type Error = CodecError;
+
+    fn try_from(result: CompactExecutionResult) -> Result<Self, Self::Error> {
+        Ok(Self {
+            l1_fee: decode_word(&result.l1_fee),
+            gas: result.gas,
+            failed: result.failed,
+            return_value: result.return_value,
+            from: result.from.map(Into::into),
+            to: result.to.map(Into::into),
+            account_after: result.account_after.into_iter().map(Into::into).collect(),
+            account_created: result.account_created.map(Into::into),
+            code_hash: result.code_hash.map(H256),
+            byte_code: result.byte_code,
+            exec_steps: result.exec_steps.into_iter().map(Into::into).collect(),
+            call_trace: serde_json::from_slice(&result.call_trace)?,
+            prestate: serde_json::from_slice(&result.prestate)?,
+        })
+    }
+}
+
+// The stack, memory and storage are always part of the schema, so that
+// encodings do not depend on the enabled features. They are left empty when
+// the corresponding feature is disabled.
+#[derive(Serialize, Deserialize)]
+struct CompactExecStep {
+    pc: u64,
+    op: u8,
+    gas: u64,
+    gas_cost: u64,
+    refund: u64,
+    depth: i64,
+    error: Option<CompactExecError>,
+    stack: Option<Vec<Vec<u8>>>,
+    memory: Option<Vec<Vec<u8>>>,
+    storage: Option<Vec<(Vec<u8>, Vec<u8>)>>,
+    extra_data: Option<Option<Vec<Vec<u8>>>>,
+}
+
+impl From<&ExecStep> for CompactExecStep {
+    fn from(step: &ExecStep) -> Self {
+        #[cfg(feature = "enable-stack")]
+        let stack = step
+            .stack
+            .as_ref()
+            .map(|stack| stack.iter().map(encode_word).collect());
+        #[cfg(not(feature = "enable-stack"))]
+        let stack = None;
+        #[cfg(feature = "enable-memory")]
+        let memory = step
+            .memory
+            .as_ref()
+            .map(|memory| memory.iter().map(encode_word).collect());
+        #[cfg(not(feature = "enable-memory"))]
+        let memory = None;
+        #[cfg(feature = "enable-storage")]
+        let storage = step.storage.as_ref().map(|storage| {
+            let mut storage = storage
+                .iter()
+                .map(|(key, value)| (encode_word(key), encode_word(value)))
+                .collect::<Vec<_>>();
+            storage.sort();
+            storage
+        });
+        #[cfg(not(feature = "enable-storage"))]
+        let storage = None;
+
+        Self {
+            pc: step.pc,
+            op: step.op.as_u8(),
+            gas: step.gas,
+            gas_cost: step.gas_cost,
+            refund: step.refund,
+            depth: step.depth as i64,
+            error: step.error.map(Into::into),
+            stack,
+            memory,
+            storage,
+            extra_data: step
+                .extra_data
+                .as_ref()
+                .map(|extra_data| extra_data.code_list.as_deref().map(encode_codes)),
+        }
+    }
+}
+
+impl From<CompactExecStep> for ExecStep {
+    fn from(step: CompactExecStep) -> Self {
+        Self {
+            pc: step.pc,
+            op: step.op.into(),
+            gas: step.gas,
+            gas_cost: step.gas_cost,
+            refund: step.refund,
+            depth: step.depth as isize,
+            error: step.error.map(Into::into),
+            #[cfg(feature = "enable-stack")]
+            stack: step
+                .stack
+                .map(|stack| stack.iter().map(|word| decode_word(word)).collect()),
+            #[cfg(feature = "enable-memory")]
+            memory: step
+                .memory
+                .map(|memory| memory.iter().map(|word| decode_word(word)).collect()),
+            #[cfg(feature = "enable-storage")]
+            storage: step.storage.map(|storage| {
+                storage
+                    .iter()
+                    .map(|(key, value)| (decode_word(key), decode_word(value)))
+                    .collect()
+            }),
+            extra_data: step.extra_data.map(|code_list| ExtraData {
+                code_list: code_list.map(decode_codes),
+            }),
+        }
+    }
+}
+
+// Variants are only ever appended, as bincode stores their index.
+#[derive(Serialize, Deserialize)]
+enum CompactExecError {
+    OutOfGas,
+    CodeStoreOutOfGas,
+    Depth,
+    InsufficientBalance,
+    ContractAddressCollision,
+    ExecutionReverted,
+    MaxInitCodeSizeExceeded,
+    MaxCodeSizeExceeded,
+    InvalidJump,
+    WriteProtection,
+    ReturnDataOutOfBounds,
+    GasUintOverflow,
+    InvalidCode,
+    NonceUintOverflow,
+    StackUnderflow { stack_len: u64, required: u64 },
+    StackOverflow { stack_len: u64, limit: u64 },
+    InvalidOpcode(u8),
+}
+
+impl From<GethExecError> for CompactExecError {
+    fn from(error: GethExecError) -> Self {
+        match error {
+            GethExecError::OutOfGas => Self::OutOfGas,
+            GethExecError::CodeStoreOutOfGas => Self::CodeStoreOutOfGas,
+            GethExecError::Depth => Self::Depth,
+            GethExecError::InsufficientBalance => Self::InsufficientBalance,
+            GethExecError::ContractAddressCollision => Self::ContractAddressCollision,
+            GethExecError::ExecutionReverted => Self::ExecutionReverted,
+            GethExecError::MaxInitCodeSizeExceeded => Self::MaxInitCodeSizeExceeded,
+            GethExecError::MaxCodeSizeExceeded => Self::MaxCodeSizeExceeded,
+            GethExecError::InvalidJump => Self::InvalidJump,
+            GethExecError::WriteProtection => Self::WriteProtection,
+            GethExecError::ReturnDataOutOfBounds => Self::ReturnDataOutOfBounds,
+            GethExecError::GasUintOverflow => Self::GasUintOverflow,
+            GethExecError::InvalidCode => Self::InvalidCode,
+            GethExecError::NonceUintOverflow => Self::NonceUintOverflow,
+            GethExecError::StackUnderflow {
+                stack_len,
+                required,
+            } => Self::StackUnderflow {
+                stack_len,
+                required,
+            },
+            GethExecError::StackOverflow { stack_len, limit } => {
+                Self::StackOverflow { stack_len, limit }
+            }
+            GethExecError::InvalidOpcode(op) => Self::InvalidOpcode(op.as_u8()),
+        }
+    }
+}
+
+impl From<CompactExecError> for GethExecError {
+    fn from(error: CompactExecError) -> Self {
+        match error {
+            CompactExecError::OutOfGas => Self::OutOfGas,
+            CompactExecError::CodeStoreOutOfGas => Self::CodeStoreOutOfGas,
+            CompactExecError::Depth => Self::Depth,
+            CompactExecError::InsufficientBalance => Self::InsufficientBalance,
+            CompactExecError::ContractAddressCollision => Self::ContractAddressCollision,
+            CompactExecError::ExecutionReverted => Self::ExecutionReverted,
+            CompactExecError::MaxInitCodeSizeExceeded => Self::MaxInitCodeSizeExceeded,
+            CompactExecError::MaxCodeSizeExceeded => Self::MaxCodeSizeExceeded,
+            CompactExecError::InvalidJump => Self::InvalidJump,
+            CompactExecError::WriteProtection => Self::WriteProtection,
+            CompactExecError::ReturnDataOutOfBounds => Self::ReturnDataOutOfBounds,
+            CompactExecError::GasUintOverflow => Self::GasUintOverflow,
+            CompactExecError::InvalidCode => Self::InvalidCode,
+            CompactExecError::NonceUintOverflow => Self::NonceUintOverflow,
+            CompactExecError::StackUnderflow {
+                stack_len,
+                required,
+            } => Self::StackUnderflow {
+                stack_len,
+                required,
+            },
+            CompactExecError::StackOverflow { stack_len, limit } => {
+                Self::StackOverflow { stack_len, limit }
+            }
+            CompactExecError::InvalidOpcode(op) => Self::InvalidOpcode(OpcodeId::from(op)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_json<T: Serialize>(value: &T) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn block_trace_round_trip() {
+        let json = include_str!("../testdata/trace_v1_5224657.json");
+        let trace: BlockTrace = serde_json::from_str(json).unwrap();
+
+        let bytes = encode_block_trace(&trace).unwrap();
+        assert!(bytes.len() < json.len() / 4);
+        let decoded = decode_block_trace(&bytes).unwrap();
+        assert_eq!(to_json(&decoded), to_json(&trace));
+
+        // the encoding is deterministic
+        assert_eq!(encode_block_trace(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn block_trace_v2_round_trip() {
+        let json = include_str!("../testdata/trace_v2_5224657.json");
+        let trace: BlockTraceV2 = serde_json::from_str(json).unwrap();
+
+        let bytes = encode_block_trace_v2(&trace).unwrap();
+        assert!(bytes.len() < json.len());
+        let decoded = decode_block_trace_v2(&bytes).unwrap();
+        assert_eq!(to_json(&decoded), to_json(&trace));
+    }
+
+    #[test]
+    fn exec_errors_round_trip() {
+        let json = include_str!("../testdata/trace_v1_5224657.json");
+        let mut trace: BlockTrace = serde_json::from_str(json).unwrap();
+
+        let errors = [
+            GethExecError::StackUnderflow {
+                stack_len: 1,
+                required: 2,
+            },
+            GethExecError::StackOverflow {
+                stack_len: 1024,
+                limit: 1023,
+            },
+            GethExecError::InvalidOpcode(OpcodeId::INVALID(0xfe)),
+            GethExecError::InvalidOpcode(OpcodeId::BASEFEE),
+            GethExecError::OutOfGas,
+        ];
+        let steps = &mut trace.execution_results[0].exec_steps;
+        assert!(steps.len() >= errors.len());
+        for (step, error) in steps.iter_mut().zip(errors) {
+            step.error = Some(error);
+        }
+
+        let decoded = decode_block_trace(&encode_block_trace(&trace).unwrap()).unwrap();
+        let decoded_errors = decoded.execution_results[0]
+            .exec_steps
+            .iter()
+            .take(errors.len())
+            .map(|step| step.error)
+            .collect::<Vec<_>>();
+        assert_eq!(decoded_errors, errors.map(Some));
+    }
+
+    #[test]
+    fn reject_invalid_header() {
+        let json = include_str!("../testdata/trace_v2_5224657.json");
+        let trace: BlockTraceV2 = serde_json::from_str(json).unwrap();
+        let bytes = encode_block_trace_v2(&trace).unwrap();
+
+        assert!(matches!(
+            decode_block_trace_v2(&bytes[..3]),
+            Err(CodecError::InvalidMagic)
+        ));
+        assert!(matches!(
+            decode_block_trace(&bytes),
+            Err(CodecError::UnexpectedKind(KIND_BLOCK_TRACE_V2))
+        ));
+
+        let mut future = bytes.clone();
+        future[4..6].copy_from_slice(&(TRACE_CODEC_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            decode_block_trace_v2(&future),
+            Err(CodecError::UnsupportedVersion(v)) if v == TRACE_CODEC_VERSION + 1
+        ));
+
+        assert!(matches!(
+            decode_block_trace_v2(&bytes[..bytes.len() - 1]),
+            Err(CodecError::Bincode(_))
+        ));
+    }
+}
@@ -0,0 +1,328 @@
+//! Streaming deserialization of [`BlockTrace`]s.
+//!
+//! The execution results of a block trace hold the struct logs of every
+//! transaction and dominate its size. Instead of collecting them into
+//! [`BlockTrace::execution_results`], the deserializer here hands them to a
+//! [`BlockTraceVisitor`] one transaction at a time, so that only a single
+//! [`ExecutionResult`] has to be kept in memory.
+
+use super::{
+    AccountTrace, BlockTrace, BytecodeTrace, ExecutionResult, StorageTrace, TransactionTrace,
+};
+use crate::{EthBlock, H256};
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserializer,
+};
+use std::fmt;
+
+/// Receives the parts of a [`BlockTrace`] while it is being deserialized.
+pub trait BlockTraceVisitor {
+    /// Error returned by the visitor, reported as a deserialization error.
+    type Error: fmt::Display;
+
+    /// Called once before the first execution result, with all the fields
+    /// that precede `executionResults` in the input. The execution results of
+    /// `block` are always empty, and fields that come later in the input (the
+    /// l1 queue index and the withdraw root in l2geth's output) are left at
+    /// their default values.
+    fn visit_block(&mut self, block: &BlockTrace) -> Result<(), Self::Error>;
+
+    /// Called for every execution result in transaction order.
+    fn visit_execution_result(
+        &mut self,
+        block: &BlockTrace,
+        tx_idx: usize,
+        execution_result: ExecutionResult,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Deserialize a block trace, either bare or wrapped in a JSON-RPC response,
+/// feeding its execution results to `visitor` instead of collecting them.
+///
+/// The returned trace has all the other fields set and empty
+/// `execution_results`. The input must list `executionResults` after the
+/// chain id, the header, the transactions and the storage trace, as l2geth
+/// does. A chain id listed later is an error unless it matches the one the
+/// execution results were handled with.
+pub fn deserialize_block_trace<'de, D, V>(
+    deserializer: D,
+    visitor: &mut V,
+) -> Result<BlockTrace, D::Error>
+where
+    D: Deserializer<'de>,
+    V: BlockTraceVisitor,
+{
+    deserializer.deserialize_map(BlockTraceSeed { visitor })
+}
+
+struct BlockTraceSeed<'a, V> {
+    visitor: &'a mut V,
+}
+
+impl<'de, 'a, V: BlockTraceVisitor> DeserializeSeed<'de> for BlockTraceSeed<'a, V> {
+    type Value = BlockTrace;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+/// The fields of a [`BlockTrace`] seen so far.
+#[derive(Default)]
+struct PartialBlockTrace {
+    chain_id: Option<u64>,
+    coinbase: Option<AccountTrace>,
+    header: Option<EthBlock>,
+    transactions: Option<Vec<TransactionTrace>>,
+    codes: Option<Vec<BytecodeTrace>>,
+    storage_trace: Option<StorageTrace>,
+    tx_storage_trace: Option<Vec<StorageTrace>>,
+    start_l1_queue_index: Option<u64>,
+    withdraw_trie_root: Option<H256>,
+}
+
+impl PartialBlockTrace {
+    /// Take the fields needed to apply the execution results. Scalar fields
+    /// are copied, since they may still be overridden by later input.
+    fn take_block<E: de::Error>(&mut self) -> Result<BlockTrace, E> {
+        Ok(BlockTrace {
+            chain_id: self.chain_id.unwrap_or_default(),
+            coinbase: self
+                .coinbase
+                .take()
+                .ok_or_else(|| E::missing_field("coinbase"))?,
+            header: self
+                .header
+                .take()
+                .ok_or_else(|| E::missing_field("header"))?,
+            transactions: self
+                .transactions
+                .take()
+                .ok_or_else(|| E::missing_field("transactions"))?,
+            execution_results: Vec::new(),
+            codes: self.codes.take().unwrap_or_default(),
+            storage_trace: self
+                .storage_trace
+                .take()
+                .ok_or_else(|| E::missing_field("storageTrace"))?,
+            tx_storage_trace: self.tx_storage_trace.take().unwrap_or_default(),
+            start_l1_queue_index: self.start_l1_queue_index.unwrap_or_default(),
+            withdraw_trie_root: self.withdraw_trie_root.unwrap_or_default(),
+        })
+    }
+}
+
+impl<'de, 'a, V: BlockTraceVisitor> Visitor<'de> for BlockTraceSeed<'a, V> {
+    type Value = BlockTrace;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a block trace")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut fields = PartialBlockTrace::default();
+        // set once the execution results have been streamed
+        let mut block: Option<BlockTrace> = None;
+        // set when the trace is wrapped in a JSON-RPC response
+        let mut result: Option<BlockTrace> = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "result" => {
+                    result = Some(map.next_value_seed(BlockTraceSeed {
+                        visitor: &mut *self.visitor,
+                    })?)
+                }
+                "chainID" => {
+                    let chain_id = map.next_value()?;
+                    // the execution results were applied under the chain id known then
+                    if block
+                        .as_ref()
+                        .is_some_and(|block| block.chain_id != chain_id)
+                    {
+                        return Err(de::Error::custom(
+                            "chainID must be listed before executionResults",
+                        ));
+                    }
+                    fields.chain_id = Some(chain_id);
+                }
+                "coinbase" => fields.coinbase = Some(map.next_value()?),
+                "header" => fields.header = Some(map.next_value()?),
+                "transactions" => fields.transactions = Some(map.next_value()?),
+                "codes" => fields.codes = Some(map.next_value()?),
+                "storageTrace" => fields.storage_trace = Some(map.next_value()?),
+                "txStorageTraces" => fields.tx_storage_trace = Some(map.next_value()?),
+                "startL1QueueIndex" => fields.start_l1_queue_index = Some(map.next_value()?),
+                "withdraw_trie_root" => fields.withdraw_trie_root = Some(map.next_value()?),
+                "executionResults" => {
+                    if block.is_some() {
+                        return Err(de::Error::duplicate_field("executionResults"));
+                    }
+                    let partial = fields.take_block()?;
+                    self.visitor
+                        .visit_block(&partial)
+                        .map_err(de::Error::custom)?;
+                    map.next_value_seed(ExecutionResultsSeed {
+                        block: &partial,
+                        visitor: &mut *self.visitor,
+                    })?;
+                    block = Some(partial);
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        if let Some(result) = result {
+            return Ok(result);
+        }
+        let mut block = block.ok_or_else(|| de::Error::missing_field("executionResults"))?;
+        // fields listed after the execution results
+        if let Some(codes) = fields.codes {
+            block.codes = codes;
+        }
+        if let Some(tx_storage_trace) = fields.tx_storage_trace {
+            block.tx_storage_trace = tx_storage_trace;
+        }
+        if let Some(start_l1_queue_index) = fields.start_l1_queue_index {
+            block.start_l1_queue_index = start_l1_queue_index;
+        }
+        block.withdraw_trie_root = fields
+            .withdraw_trie_root
+            .ok_or_else(|| de::Error::missing_field("withdraw_trie_root"))?;
+        Ok(block)
+    }
+}
+
+struct ExecutionResultsSeed<'a, V> {
+    block: &'a BlockTrace,
+    visitor: &'a mut V,
+}
+
+impl<'de, 'a, V: BlockTraceVisitor> DeserializeSeed<'de> for ExecutionResultsSeed<'a, V> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a, V: BlockTraceVisitor> Visitor<'de> for ExecutionResultsSeed<'a, V> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of execution results")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut tx_idx = 0;
+        while let Some(execution_result) = seq.next_element::<ExecutionResult>()? {
+            self.visitor
+                .visit_execution_result(self.block, tx_idx, execution_result)
+                .map_err(de::Error::custom)?;
+            tx_idx += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct CollectingVisitor {
+        num_blocks: usize,
+        execution_results: Vec<ExecutionResult>,
+    }
+
+    impl BlockTraceVisitor for CollectingVisitor {
+        type Error = String;
+
+        fn visit_block(&mut self, block: &BlockTrace) -> Result<(), Self::Error> {
+            assert!(block.execution_results.is_empty());
+            self.num_blocks += 1;
+            Ok(())
+        }
+
+        fn visit_execution_result(
+            &mut self,
+            block: &BlockTrace,
+            tx_idx: usize,
+            execution_result: ExecutionResult,
+        ) -> Result<(), Self::Error> {
+            if tx_idx >= block.transactions.len() {
+                return Err(format!("unexpected execution result {tx_idx}"));
+            }
+            self.execution_results.push(execution_result);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn streamed_trace_matches_full_trace() {
+        let json = include_str!("../testdata/trace_v1_5224657.json");
+        let expected: BlockTrace = serde_json::from_str(json).unwrap();
+
+        let mut visitor = CollectingVisitor::default();
+        let mut deserializer = serde_json::Deserializer::from_reader(json.as_bytes());
+        let block = deserialize_block_trace(&mut deserializer, &mut visitor).unwrap();
+
+        assert_eq!(visitor.num_blocks, 1);
+        assert!(block.execution_results.is_empty());
+        assert_eq!(block.start_l1_queue_index, expected.start_l1_queue_index);
+        assert_eq!(block.withdraw_trie_root, expected.withdraw_trie_root);
+        assert_eq!(block.transactions.len(), expected.transactions.len());
+        assert_eq!(
+            visitor
+                .execution_results
+                .iter()
+                .map(|er| er.exec_steps.len())
+                .collect::<Vec<_>>(),
+            expected
+                .execution_results
+                .iter()
+                .map(|er| er.exec_steps.len())
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn chain_id_after_execution_results() {
+        let json = include_str!("../testdata/trace_v1_5224657.json");
+        let listed = r#""chainID": 534352,"#;
+        assert!(json.contains(listed));
+        // move the chain id to the end of the trace
+        let moved = |chain_id: u64| {
+            let json = json.replacen(listed, "", 1);
+            let end = json.rfind('}').unwrap();
+            format!(r#"{}, "chainID": {chain_id} }}"#, json[..end].trim_end())
+        };
+
+        // the execution results were handled with chain id 0
+        let mut visitor = CollectingVisitor::default();
+        let mut deserializer = serde_json::Deserializer::from_str(&moved(534352));
+        let err = deserialize_block_trace(&mut deserializer, &mut visitor).unwrap_err();
+        assert!(err.to_string().contains("chainID"));
+
+        let mut visitor = CollectingVisitor::default();
+        let mut deserializer = serde_json::Deserializer::from_str(&moved(0));
+        let block = deserialize_block_trace(&mut deserializer, &mut visitor).unwrap();
+        assert_eq!(block.chain_id, 0);
+    }
+
+    #[test]
+    fn streamed_trace_in_json_rpc_response() {
+        let json = include_str!("../testdata/trace_v1_5224657.json");
+        let wrapped = format!(r#"{{"jsonrpc":"2.0","id":1,"result":{json}}}"#);
+
+        let mut visitor = CollectingVisitor::default();
+        let mut deserializer = serde_json::Deserializer::from_str(&wrapped);
+        let block = deserialize_block_trace(&mut deserializer, &mut visitor).unwrap();
+
+        assert_eq!(visitor.num_blocks, 1);
+        assert_eq!(visitor.execution_results.len(), block.transactions.len());
+    }
+}
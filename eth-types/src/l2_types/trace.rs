@@ -8,7 +8,7 @@ use crate::{
 use ethers_core::types::Bytes;
 use itertools::Itertools;
 
-use super::ExecStep;
+use super::{ExecStep, ExecutionResult};
 
 /// Update codedb from statedb and trace
 pub fn collect_codes(
@@ -30,102 +30,122 @@ pub fn collect_codes(
     }
     let mut codes = Vec::new();
     for (er_idx, execution_result) in block.execution_results.iter().enumerate() {
-        if let Some(bytecode) = &execution_result.byte_code {
-            if let Some(to) = &execution_result.to {
-                // Not contract deployment
-                let bytecode = decode_bytecode(bytecode)?.to_vec();
-                let code_hash = to.poseidon_code_hash;
-                // code hash 0 means non-existed account
-                if !code_hash.is_zero() {
-                    codes.push((code_hash, bytecode));
-                }
-                //log::debug!("inserted tx bytecode {:?} {:?}", code_hash, hash);
+        codes.extend(collect_execution_result_codes(
+            block,
+            er_idx,
+            execution_result,
+            sdb,
+        )?);
+    }
+
+    log::debug!("collect codes done");
+    Ok(codes)
+}
+
+/// Collect the codes used by a single transaction of `block`. Only the header
+/// and transactions of `block` are read, so it can be used while the
+/// execution results are streamed.
+pub fn collect_execution_result_codes(
+    block: &BlockTrace,
+    er_idx: usize,
+    execution_result: &ExecutionResult,
+    sdb: Option<&StateDB>,
+) -> Result<Vec<(H256, Vec<u8>)>, Error> {
+    let mut codes = Vec::new();
+    if let Some(bytecode) = &execution_result.byte_code {
+        if let Some(to) = &execution_result.to {
+            // Not contract deployment
+            let bytecode = decode_bytecode(bytecode)?.to_vec();
+            let code_hash = to.poseidon_code_hash;
+            // code hash 0 means non-existed account
+            if !code_hash.is_zero() {
+                codes.push((code_hash, bytecode));
             }
+            //log::debug!("inserted tx bytecode {:?} {:?}", code_hash, hash);
         }
+    }
 
-        // filter all precompile calls, empty calls and create
-        let mut call_trace = execution_result
-            .call_trace
-            .flatten_trace(&execution_result.prestate)
-            .into_iter()
-            .filter(|call| {
-                let is_call_to_precompile = call.to.as_ref().map(is_precompiled).unwrap_or(false);
-                let is_call_to_empty = call.gas_used.is_zero()
-                    && !call.call_type.is_create()
-                    && call.is_callee_code_empty;
-                !(is_call_to_precompile || is_call_to_empty || call.call_type.is_create())
-            })
-            .collect::<Vec<_>>();
-        //log::trace!("call_trace: {call_trace:?}");
+    // filter all precompile calls, empty calls and create
+    let mut call_trace = execution_result
+        .call_trace
+        .flatten_trace(&execution_result.prestate)
+        .into_iter()
+        .filter(|call| {
+            let is_call_to_precompile = call.to.as_ref().map(is_precompiled).unwrap_or(false);
+            let is_call_to_empty =
+                call.gas_used.is_zero() && !call.call_type.is_create() && call.is_callee_code_empty;
+            !(is_call_to_precompile || is_call_to_empty || call.call_type.is_create())
+        })
+        .collect::<Vec<_>>();
+    //log::trace!("call_trace: {call_trace:?}");
 
-        for (idx, step) in execution_result.exec_steps.iter().enumerate().rev() {
-            if step.op.is_create() {
-                continue;
-            }
-            let call = if step.op.is_call() {
-                // filter call to empty/precompile/!precheck_ok
-                if let Some(next_step) = execution_result.exec_steps.get(idx + 1) {
-                    // the call doesn't have inner steps, it could be:
-                    // - a call to a precompiled contract
-                    // - a call to an empty account
-                    // - a call that !is_precheck_ok
-                    if next_step.depth != step.depth + 1 {
-                        log::trace!("skip call step due to no inner step, curr: {step:?}, next: {next_step:?}");
-                        continue;
-                    }
-                } else {
-                    // this is the final step, no inner steps
-                    log::trace!("skip call step due this is the final step: {step:?}");
+    for (idx, step) in execution_result.exec_steps.iter().enumerate().rev() {
+        if step.op.is_create() {
+            continue;
+        }
+        let call = if step.op.is_call() {
+            // filter call to empty/precompile/!precheck_ok
+            if let Some(next_step) = execution_result.exec_steps.get(idx + 1) {
+                // the call doesn't have inner steps, it could be:
+                // - a call to a precompiled contract
+                // - a call to an empty account
+                // - a call that !is_precheck_ok
+                if next_step.depth != step.depth + 1 {
+                    log::trace!(
+                        "skip call step due to no inner step, curr: {step:?}, next: {next_step:?}"
+                    );
                     continue;
                 }
-                let call = call_trace.pop();
-                //log::trace!("call_trace pop: {call:?}, current step: {step:?}");
-                call
             } else {
-                None
-            };
+                // this is the final step, no inner steps
+                log::trace!("skip call step due this is the final step: {step:?}");
+                continue;
+            }
+            let call = call_trace.pop();
+            //log::trace!("call_trace pop: {call:?}, current step: {step:?}");
+            call
+        } else {
+            None
+        };
 
-            if let Some(data) = &step.extra_data {
-                match step.op {
-                    OpcodeId::CALL
-                    | OpcodeId::CALLCODE
-                    | OpcodeId::DELEGATECALL
-                    | OpcodeId::STATICCALL => {
-                        let call = call.unwrap();
-                        assert_eq!(call.call_type, step.op, "{call:?}");
-                        let code_idx = if block.transactions[er_idx].to.is_none() {
-                            0
-                        } else {
-                            1
-                        };
-                        let callee_code = data.get_code_at(code_idx);
-                        let addr = call.to.unwrap();
-                        trace_code(
-                            &mut codes,
-                            callee_code.unwrap_or_default(),
-                            step,
-                            Some(addr),
-                            sdb,
-                            block,
-                        );
-                    }
-                    OpcodeId::EXTCODECOPY => {
-                        let code = data.get_code_at(0);
-                        if code.is_none() {
-                            log::warn!("unable to fetch code from step. {step:?}");
-                            continue;
-                        }
-                        log::info!("trace extcodecopy! block {:?}", block.header.number);
-                        trace_code(&mut codes, code.unwrap(), step, None, sdb, block);
+        if let Some(data) = &step.extra_data {
+            match step.op {
+                OpcodeId::CALL
+                | OpcodeId::CALLCODE
+                | OpcodeId::DELEGATECALL
+                | OpcodeId::STATICCALL => {
+                    let call = call.unwrap();
+                    assert_eq!(call.call_type, step.op, "{call:?}");
+                    let code_idx = if block.transactions[er_idx].to.is_none() {
+                        0
+                    } else {
+                        1
+                    };
+                    let callee_code = data.get_code_at(code_idx);
+                    let addr = call.to.unwrap();
+                    trace_code(
+                        &mut codes,
+                        callee_code.unwrap_or_default(),
+                        step,
+                        Some(addr),
+                        sdb,
+                        block,
+                    );
+                }
+                OpcodeId::EXTCODECOPY => {
+                    let code = data.get_code_at(0);
+                    if code.is_none() {
+                        log::warn!("unable to fetch code from step. {step:?}");
+                        continue;
                     }
-
-                    _ => {}
+                    log::info!("trace extcodecopy! block {:?}", block.header.number);
+                    trace_code(&mut codes, code.unwrap(), step, None, sdb, block);
                 }
+
+                _ => {}
             }
         }
     }
-
-    log::debug!("collect codes done");
     Ok(codes)
 }
 
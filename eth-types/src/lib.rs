@@ -479,6 +479,13 @@ impl FromStr for GethExecError {
         static STACK_OVERFLOW_RE: LazyLock<regex::Regex> =
             LazyLock::new(|| regex::Regex::new(r"^stack limit reached (\d+) \((\d+)\)$").unwrap());
 
+        // the two numbers in `v` captured by `re`
+        fn captured_pair(re: &regex::Regex, v: &str) -> Result<(u64, u64), ()> {
+            re.captures(v)
+                .and_then(|caps| Some((caps[1].parse().ok()?, caps[2].parse().ok()?)))
+                .ok_or_else(|| log::warn!("malformed geth error: {}", v))
+        }
+
         let e = match v {
             "out of gas" => GethExecError::OutOfGas,
             "contract creation code storage out of gas" => GethExecError::CodeStoreOutOfGas,
@@ -495,25 +502,21 @@ impl FromStr for GethExecError {
             "invalid code: must not begin with 0xef" => GethExecError::InvalidCode,
             "nonce uint64 overflow" => GethExecError::NonceUintOverflow,
             _ if v.starts_with("stack underflow") => {
-                let caps = STACK_UNDERFLOW_RE.captures(v).unwrap();
-                let stack_len = caps.get(1).unwrap().as_str().parse::<u64>().unwrap();
-                let required = caps.get(2).unwrap().as_str().parse::<u64>().unwrap();
+                let (stack_len, required) = captured_pair(&STACK_UNDERFLOW_RE, v)?;
                 GethExecError::StackUnderflow {
                     stack_len,
                     required,
                 }
             }
             _ if v.starts_with("stack limit reached") => {
-                let caps = STACK_OVERFLOW_RE.captures(v).unwrap();
-                let stack_len = caps.get(1).unwrap().as_str().parse::<u64>().unwrap();
-                let limit = caps.get(2).unwrap().as_str().parse::<u64>().unwrap();
+                let (stack_len, limit) = captured_pair(&STACK_OVERFLOW_RE, v)?;
                 GethExecError::StackOverflow { stack_len, limit }
             }
             _ if v.starts_with("invalid opcode") => v
                 .strip_prefix("invalid opcode: ")
-                .map(|s| OpcodeId::from_str(s).unwrap())
+                .and_then(|s| OpcodeId::from_str(s).ok())
                 .map(GethExecError::InvalidOpcode)
-                .unwrap(),
+                .ok_or_else(|| log::warn!("malformed geth error: {}", v))?,
             _ => {
                 log::warn!("unknown geth error: {}", v);
                 return Err(());
@@ -729,7 +732,8 @@ pub struct GethCallTrace {
     // gas: U256,
     #[serde(rename = "gasUsed")]
     gas_used: U256,
-    // input: Bytes,
+    #[serde(default)]
+    input: Bytes,
     output: Option<Bytes>,
     to: Option<Address>,
     #[serde(rename = "type")]
@@ -788,6 +792,26 @@ impl GethCallTrace {
         call_is_success
     }
 
+    /// The inputs of the calls to `address` which succeeded and weren't reverted by a caller, in
+    /// the order of execution.
+    pub fn successful_call_inputs(&self, address: &Address) -> Vec<Bytes> {
+        let mut inputs = vec![];
+        self.successful_call_inputs_inner(address, &mut inputs);
+        inputs
+    }
+
+    fn successful_call_inputs_inner(&self, address: &Address, inputs: &mut Vec<Bytes>) {
+        if self.error.is_some() {
+            return;
+        }
+        if self.to.as_ref() == Some(address) {
+            inputs.push(self.input.clone());
+        }
+        for call in &self.calls {
+            call.successful_call_inputs_inner(address, inputs);
+        }
+    }
+
     /// flatten the call trace as it is.
     pub fn flatten_trace(
         &self,
@@ -902,6 +926,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_geth_exec_error() {
+        assert_eq!(
+            "stack underflow (1 <=> 2)".parse(),
+            Ok(GethExecError::StackUnderflow {
+                stack_len: 1,
+                required: 2
+            })
+        );
+        assert_eq!(
+            "invalid opcode: opcode 0xfe not defined".parse(),
+            Ok(GethExecError::InvalidOpcode(OpcodeId::INVALID(0xfe)))
+        );
+        // the string constants of errors with a payload are rejected
+        for v in [
+            "stack underflow",
+            "stack underflow (1 <=> x)",
+            "stack limit reached",
+            "invalid opcode",
+            "invalid opcode: UNKNOWN",
+        ] {
+            assert_eq!(v.parse::<GethExecError>(), Err(()));
+        }
+    }
+
     #[test]
     fn deserialize_geth_exec_trace2() {
         let trace_json = r#"
@@ -1061,6 +1110,7 @@ mod tests {
                     from: address!("0x000000000000000000000000000000000cafe001"),
                     to: None,
                     gas_used: U256::zero(),
+                    input: Bytes::default(),
                     call_type: "CALL".to_string(),
                     output: Some(Bytes::from([0x00]))
                 }
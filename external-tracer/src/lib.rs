@@ -1,13 +1,114 @@
 //! This module generates traces by connecting to an external tracer
+//!
+//! The revm backend only covers the l1 path, i.e. [`trace`] without the `scroll` feature. The l2
+//! traces of the `scroll` feature (`BlockTrace` from `l2trace`) are still only produced by geth,
+//! so that they keep needing the go toolchain.
+
+#[cfg(feature = "revm")]
+mod native;
 
 #[cfg(feature = "scroll")]
 use eth_types::l2_types::BlockTrace;
 use eth_types::{
     geth_types::{Account, BlockConstants, Transaction},
+    l2_predeployed::l1_gas_price_oracle,
     Address, Error, GethExecTrace, Word,
 };
 use serde::Serialize;
-use std::collections::BTreeMap;
+use std::{borrow::Cow, collections::BTreeMap, env, str::FromStr};
+
+/// The tracer that executes a [`TraceConfig`], selected at runtime by the `EXTERNAL_TRACER`
+/// environment variable (`geth` or `revm`).
+///
+/// Both backends emit the traces in the shape of the geth struct logger, call tracer and prestate
+/// tracer, so that their consumers don't tell them apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TracerBackend {
+    /// geth, through the go FFI of `geth-utils` (feature `geth`)
+    Geth,
+    /// revm, natively (feature `revm`)
+    ///
+    /// It traces with the l1 semantics only: it doesn't produce the l2 traces of the `scroll`
+    /// feature, whose storage is proven in the zktrie.
+    Revm,
+}
+
+impl TracerBackend {
+    /// The environment variable selecting the backend.
+    pub const ENV_VAR: &'static str = "EXTERNAL_TRACER";
+
+    /// The backend selected by the environment, geth by default if it's built or if l2 traces
+    /// are needed.
+    pub fn from_env() -> Self {
+        match env::var(Self::ENV_VAR) {
+            Ok(backend) => backend
+                .parse()
+                .unwrap_or_else(|_| panic!("unknown {}: {backend}", Self::ENV_VAR)),
+            Err(_) if cfg!(any(feature = "geth", feature = "scroll")) => Self::Geth,
+            Err(_) => Self::Revm,
+        }
+    }
+
+    /// Execute the config, returning the trace as geth's JSON.
+    fn trace_json(&self, config: &TraceConfig) -> Result<String, Error> {
+        let config = match config.chain_config.as_ref().and_then(|c| c.l1_fee.as_ref()) {
+            Some(l1_fee) => {
+                let mut config = config.clone();
+                l1_fee.apply(&mut config.accounts);
+                Cow::Owned(config)
+            }
+            None => Cow::Borrowed(config),
+        };
+        match self {
+            #[cfg(feature = "geth")]
+            Self::Geth => {
+                check_chain_config(&config, Self::Geth, ChainConfig::unsupported_by_geth)?;
+                let trace_config = &serde_json::to_string_pretty(&config).unwrap();
+                log::trace!("trace config: {}", trace_config);
+                geth_utils::trace(trace_config).map_err(|error| match error {
+                    geth_utils::Error::TracingError(error) => Error::TracingError(error),
+                })
+            }
+            #[cfg(feature = "revm")]
+            Self::Revm => {
+                check_chain_config(&config, Self::Revm, ChainConfig::unsupported_by_revm)?;
+                native::trace(&config).map(|traces| traces.to_string())
+            }
+            #[allow(unreachable_patterns)]
+            backend => Err(Error::TracingError(format!(
+                "external-tracer is built without the {backend:?} backend"
+            ))),
+        }
+    }
+}
+
+/// Fail if the chain config sets fields that the backend would ignore, tracing with its defaults
+/// instead.
+#[cfg(any(feature = "geth", feature = "revm"))]
+fn check_chain_config(
+    config: &TraceConfig,
+    backend: TracerBackend,
+    unsupported: fn(&ChainConfig) -> Vec<&'static str>,
+) -> Result<(), Error> {
+    match config.chain_config.as_ref().map(unsupported) {
+        Some(fields) if !fields.is_empty() => Err(Error::TracingError(format!(
+            "the {backend:?} tracer backend does not support the chain config fields {fields:?}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+impl FromStr for TracerBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "geth" => Ok(Self::Geth),
+            "revm" => Ok(Self::Revm),
+            _ => Err(Error::TracingError(format!("unknown tracer backend: {s}"))),
+        }
+    }
+}
 
 /// Configuration structure for `geth_utlis::trace`
 #[derive(Debug, Default, Clone, Serialize)]
@@ -75,18 +176,172 @@ impl LoggerConfig {
 }
 
 /// Configuration structure for `params.ChainConfig`
+///
+/// The fields left unset keep the defaults of `geth-utils`, which activates every fork at genesis
+/// and sends the fees to the coinbase. A fork is disabled by activating it after the traced block.
+/// The fields unknown to the backend tracing the config are rejected, see
+/// [`ChainConfig::unsupported_by_geth`] and [`ChainConfig::unsupported_by_revm`].
 #[derive(Clone, Debug, Default, Serialize)]
 #[serde(rename_all = "PascalCase")]
-pub struct ChainConfig {}
+pub struct ChainConfig {
+    /// shanghai activation block (l2geth)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shanghai_block: Option<u64>,
+    /// shanghai activation time (l1geth)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shanghai_time: Option<u64>,
+    /// cancun activation time (l1geth)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancun_time: Option<u64>,
+    /// archimedes activation block (l2geth)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archimedes_block: Option<u64>,
+    /// bernoulli activation block (l2geth)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bernoulli_block: Option<u64>,
+    /// curie activation block (l2geth)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub curie_block: Option<u64>,
+    /// darwin activation time (l2geth)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub darwin_time: Option<u64>,
+    /// darwinV2 activation time (l2geth)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub darwin_v2_time: Option<u64>,
+    /// scroll specific config (l2geth)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scroll: Option<ScrollConfig>,
+    /// The L1 fee parameters, which geth reads from the storage of the L1GasPriceOracle
+    /// predeploy rather than from the chain config, so that they are written to its storage
+    /// before tracing.
+    #[serde(skip)]
+    pub l1_fee: Option<L1FeeConfig>,
+}
+
+impl ChainConfig {
+    /// Whether shanghai is active at the given timestamp.
+    pub fn is_shanghai(&self, timestamp: u64) -> bool {
+        self.shanghai_time.map_or(true, |time| timestamp >= time)
+    }
+
+    /// Whether cancun is active at the given timestamp.
+    pub fn is_cancun(&self, timestamp: u64) -> bool {
+        self.cancun_time.map_or(true, |time| timestamp >= time)
+    }
+
+    /// Whether curie is active at the given block.
+    pub fn is_curie(&self, number: u64) -> bool {
+        self.curie_block.map_or(true, |block| number >= block)
+    }
+
+    /// The fields that are set but unknown to the geth linked by `geth-utils`, which would drop
+    /// them: l1geth has no l2 forks, and the l2geth predates darwin and has no time based l1
+    /// forks.
+    pub fn unsupported_by_geth(&self) -> Vec<&'static str> {
+        #[cfg(feature = "scroll")]
+        let fields = [
+            ("ShanghaiTime", self.shanghai_time.is_some()),
+            ("CancunTime", self.cancun_time.is_some()),
+            ("DarwinTime", self.darwin_time.is_some()),
+            ("DarwinV2Time", self.darwin_v2_time.is_some()),
+        ];
+        #[cfg(not(feature = "scroll"))]
+        let fields = self.l2_fields();
+        fields
+            .into_iter()
+            .filter_map(|(field, is_set)| is_set.then_some(field))
+            .collect()
+    }
+
+    /// The fields that are set but ignored by revm, which traces with the l1 semantics only.
+    pub fn unsupported_by_revm(&self) -> Vec<&'static str> {
+        self.l2_fields()
+            .into_iter()
+            .filter_map(|(field, is_set)| is_set.then_some(field))
+            .collect()
+    }
+
+    /// The l2 specific fields, and whether they are set.
+    fn l2_fields(&self) -> [(&'static str, bool); 7] {
+        [
+            ("ShanghaiBlock", self.shanghai_block.is_some()),
+            ("ArchimedesBlock", self.archimedes_block.is_some()),
+            ("BernoulliBlock", self.bernoulli_block.is_some()),
+            ("CurieBlock", self.curie_block.is_some()),
+            ("DarwinTime", self.darwin_time.is_some()),
+            ("DarwinV2Time", self.darwin_v2_time.is_some()),
+            ("Scroll", self.scroll.is_some()),
+        ]
+    }
+}
+
+/// Configuration structure for `params.ScrollConfig`
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ScrollConfig {
+    /// the address receiving the fees, the coinbase by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_vault_address: Option<Address>,
+}
+
+/// The L1 fee parameters stored in the L1GasPriceOracle predeploy, whose defaults are the ones of
+/// [`l1_gas_price_oracle::default_contract_account`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct L1FeeConfig {
+    /// L1 base fee
+    pub base_fee: Word,
+    /// L1 fee overhead, before curie
+    pub overhead: Word,
+    /// L1 fee scalar, before curie
+    pub scalar: Word,
+    /// L1 blob base fee, since curie
+    pub blob_base_fee: Word,
+    /// commit scalar, since curie
+    pub commit_scalar: Word,
+    /// blob scalar, since curie
+    pub blob_scalar: Word,
+}
+
+impl Default for L1FeeConfig {
+    fn default() -> Self {
+        Self {
+            base_fee: Word::one(),
+            overhead: Word::zero(),
+            scalar: Word::zero(),
+            blob_base_fee: Word::one(),
+            commit_scalar: *l1_gas_price_oracle::INITIAL_COMMIT_SCALAR,
+            blob_scalar: *l1_gas_price_oracle::INITIAL_BLOB_SCALAR,
+        }
+    }
+}
+
+impl L1FeeConfig {
+    /// Write the parameters to the storage of the L1GasPriceOracle, which is deployed with its
+    /// default state if missing.
+    pub fn apply(&self, accounts: &mut BTreeMap<Address, Account>) {
+        let oracle = accounts
+            .entry(*l1_gas_price_oracle::ADDRESS)
+            .or_insert_with(l1_gas_price_oracle::default_contract_account);
+        for (slot, value) in [
+            (*l1_gas_price_oracle::BASE_FEE_SLOT, self.base_fee),
+            (*l1_gas_price_oracle::OVERHEAD_SLOT, self.overhead),
+            (*l1_gas_price_oracle::SCALAR_SLOT, self.scalar),
+            (
+                *l1_gas_price_oracle::L1_BLOB_BASEFEE_SLOT,
+                self.blob_base_fee,
+            ),
+            (*l1_gas_price_oracle::COMMIT_SCALAR_SLOT, self.commit_scalar),
+            (*l1_gas_price_oracle::BLOB_SCALAR_SLOT, self.blob_scalar),
+        ] {
+            oracle.storage.insert(slot, value);
+        }
+    }
+}
 
 #[cfg(not(feature = "scroll"))]
 pub fn trace(config: &TraceConfig) -> Result<Vec<GethExecTrace>, Error> {
-    let trace_config = &serde_json::to_string_pretty(&config).unwrap();
-    log::trace!("trace config: {}", trace_config);
     // Get the trace
-    let trace_string = geth_utils::trace(trace_config).map_err(|error| match error {
-        geth_utils::Error::TracingError(error) => Error::TracingError(error),
-    })?;
+    let trace_string = TracerBackend::from_env().trace_json(config)?;
 
     log::trace!("trace: {}", trace_string);
 
@@ -97,15 +352,20 @@ pub fn trace(config: &TraceConfig) -> Result<Vec<GethExecTrace>, Error> {
 }
 
 /// Creates a l2-trace for the specified config
+///
+/// Only the geth backend executes with the l2 semantics and proves the storage in the zktrie, so
+/// that the l2 traces need the `geth` feature and the revm backend is rejected.
 #[cfg(feature = "scroll")]
 pub fn l2trace(config: &TraceConfig) -> Result<BlockTrace, Error> {
-    let l2_config = config.clone();
-    let trace_config = &serde_json::to_string_pretty(&l2_config).unwrap();
-    log::trace!("trace config: {}", trace_config);
+    let backend = TracerBackend::from_env();
+    if backend != TracerBackend::Geth {
+        return Err(Error::TracingError(format!(
+            "l2 traces are only produced by the Geth tracer backend, {} selects {backend:?}",
+            TracerBackend::ENV_VAR
+        )));
+    }
     // Get the trace
-    let trace_string = geth_utils::trace(trace_config).map_err(|error| match error {
-        geth_utils::Error::TracingError(error) => Error::TracingError(error),
-    })?;
+    let trace_string = backend.trace_json(config)?;
 
     log::trace!("trace: {}", trace_string);
 
@@ -125,3 +385,174 @@ pub fn trace(config: &TraceConfig) -> Result<Vec<GethExecTrace>, Error> {
         .map(From::from)
         .collect::<Vec<_>>())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_config_overrides() {
+        let chain_config = ChainConfig {
+            curie_block: Some(10),
+            scroll: Some(ScrollConfig {
+                fee_vault_address: Some(Address::repeat_byte(0xfe)),
+            }),
+            l1_fee: Some(L1FeeConfig {
+                blob_scalar: 7.into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        // only the overridden fields are passed to geth
+        assert_eq!(
+            serde_json::to_value(&chain_config).unwrap(),
+            serde_json::json!({
+                "CurieBlock": 10,
+                "Scroll": {
+                    "FeeVaultAddress": "0xfefefefefefefefefefefefefefefefefefefefe",
+                },
+            })
+        );
+        assert!(!chain_config.is_curie(9));
+        assert!(chain_config.is_curie(10));
+        assert!(chain_config.is_cancun(0));
+
+        let mut accounts = BTreeMap::new();
+        chain_config.l1_fee.unwrap().apply(&mut accounts);
+        let oracle = &accounts[&*l1_gas_price_oracle::ADDRESS];
+        assert_eq!(
+            oracle.storage[&*l1_gas_price_oracle::BLOB_SCALAR_SLOT],
+            7.into()
+        );
+        assert_eq!(
+            oracle.storage[&*l1_gas_price_oracle::COMMIT_SCALAR_SLOT],
+            *l1_gas_price_oracle::INITIAL_COMMIT_SCALAR
+        );
+    }
+
+    #[test]
+    fn darwin_chain_config_is_rejected() {
+        let chain_config = ChainConfig {
+            darwin_time: Some(0),
+            darwin_v2_time: Some(0),
+            ..Default::default()
+        };
+        // neither the l2geth of geth-utils nor revm trace with the darwin rules, so that a
+        // darwin block fails to trace rather than being traced with the rules before darwin
+        assert_eq!(
+            chain_config.unsupported_by_geth(),
+            vec!["DarwinTime", "DarwinV2Time"]
+        );
+        assert_eq!(
+            chain_config.unsupported_by_revm(),
+            vec!["DarwinTime", "DarwinV2Time"]
+        );
+        #[cfg(any(feature = "geth", feature = "revm"))]
+        let config = TraceConfig {
+            chain_config: Some(chain_config),
+            ..Default::default()
+        };
+        #[cfg(feature = "geth")]
+        assert!(TracerBackend::Geth.trace_json(&config).is_err());
+        #[cfg(feature = "revm")]
+        assert!(TracerBackend::Revm.trace_json(&config).is_err());
+
+        // the forks known to the tracers are passed on
+        #[cfg(feature = "scroll")]
+        let chain_config = ChainConfig {
+            curie_block: Some(1),
+            ..Default::default()
+        };
+        #[cfg(not(feature = "scroll"))]
+        let chain_config = ChainConfig {
+            cancun_time: Some(1),
+            ..Default::default()
+        };
+        assert!(chain_config.unsupported_by_geth().is_empty());
+    }
+
+    /// Both backends emit the same traces, i.e. the same struct logs, prestates and call traces,
+    /// for txs calling a contract that logs and for a reverted tx.
+    #[cfg(all(feature = "geth", feature = "revm", not(feature = "scroll")))]
+    #[test]
+    fn revm_traces_as_geth() {
+        let sender = Address::repeat_byte(0xfe);
+        let caller = Address::repeat_byte(0xca);
+        let callee = Address::repeat_byte(0xcc);
+        let reverter = Address::repeat_byte(0xdd);
+
+        // PUSH1 0x01 PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 LOG0 PUSH1 0x20 PUSH1 0x00 RETURN
+        let callee_code = vec![
+            0x60, 0x01, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xa0, 0x60, 0x20, 0x60, 0x00,
+            0xf3,
+        ];
+        // PUSH1 0x00 SLOAD POP
+        // PUSH1 0x20 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH20 callee GAS CALL
+        // PUSH1 0x00 SSTORE STOP
+        let caller_code = [
+            vec![0x60, 0x00, 0x54, 0x50],
+            vec![
+                0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+            ],
+            callee.as_bytes().to_vec(),
+            vec![0x5a, 0xf1, 0x60, 0x00, 0x55, 0x00],
+        ]
+        .concat();
+        // PUSH1 0x00 PUSH1 0x00 REVERT
+        let reverter_code = vec![0x60, 0x00, 0x60, 0x00, 0xfd];
+
+        let mut config = TraceConfig {
+            chain_id: 1,
+            logger_config: LoggerConfig {
+                enable_memory: true,
+                disable_stack: false,
+                disable_storage: false,
+                enable_return_data: true,
+            },
+            ..TraceConfig::default()
+        };
+        config.block_constants.number = 1.into();
+        config.block_constants.gas_limit = 10_000_000.into();
+        for (address, code, storage) in [
+            (caller, caller_code, [(Word::zero(), Word::from(7))].into()),
+            (callee, callee_code, Default::default()),
+            (reverter, reverter_code, Default::default()),
+        ] {
+            config.accounts.insert(
+                address,
+                Account {
+                    address,
+                    code: code.into(),
+                    storage,
+                    ..Account::default()
+                },
+            );
+        }
+        config.accounts.insert(
+            sender,
+            Account {
+                address: sender,
+                balance: Word::from(10).pow(18.into()),
+                ..Account::default()
+            },
+        );
+        for (nonce, to) in [caller, reverter].into_iter().enumerate() {
+            config.transactions.push(Transaction {
+                from: sender,
+                to: Some(to),
+                nonce: Word::from(nonce as u64),
+                gas_limit: 100_000.into(),
+                ..Transaction::default()
+            });
+        }
+
+        let traces = [TracerBackend::Geth, TracerBackend::Revm].map(|backend| {
+            let traces = backend.trace_json(&config).unwrap();
+            serde_json::from_str::<Vec<GethExecTrace>>(&traces).unwrap()
+        });
+        assert_eq!(traces[0].len(), 2);
+        assert!(!traces[0][0].failed);
+        assert!(traces[0][1].failed);
+        assert_eq!(traces[0], traces[1]);
+    }
+}
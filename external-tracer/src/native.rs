@@ -0,0 +1,647 @@
+//! A tracer executing a [`TraceConfig`] with revm, which mimics the tracers of `geth-utils`, i.e.
+//! the struct logger, the call tracer and the prestate tracer of geth, so that no go toolchain is
+//! needed to trace.
+
+use crate::{LoggerConfig, TraceConfig};
+use eth_types::{evm_types::OpcodeId, Address, Bytes, Error, ToBigEndian, Word, H256};
+use revm::{
+    db::{CacheDB, EmptyDB},
+    inspector_handle_register,
+    interpreter::{
+        CallInputs, CallOutcome, CallScheme, CreateInputs, CreateOutcome, CreateScheme,
+        InstructionResult, Interpreter,
+    },
+    primitives::{
+        AccountInfo, Address as RevmAddress, BlobExcessGasAndPrice, Bytecode, ExecutionResult,
+        Output, SpecId, TxKind, B256, U256,
+    },
+    Database, DatabaseCommit, Evm, EvmContext, Inspector,
+};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Execute the transactions of the config one after another, returning their traces as the
+/// `ExecutionResult`s emitted by `geth-utils`.
+pub(crate) fn trace(config: &TraceConfig) -> Result<Value, Error> {
+    let block = &config.block_constants;
+    let txs_gas_limit = config
+        .transactions
+        .iter()
+        .map(|tx| tx.gas_limit.as_u64())
+        .sum::<u64>();
+    if txs_gas_limit > block.gas_limit.as_u64() {
+        return Err(Error::TracingError(format!(
+            "txs total gas: {txs_gas_limit} Exceeds block gas limit: {}",
+            block.gas_limit
+        )));
+    }
+
+    let mut db = CacheDB::new(EmptyDB::default());
+    for (address, account) in config.accounts.iter() {
+        let code = Bytecode::new_raw(account.code.to_vec().into());
+        let info = AccountInfo::new(
+            to_revm_word(&account.balance),
+            account.nonce.as_u64(),
+            code.hash_slow(),
+            code,
+        );
+        db.insert_account_info(to_revm_address(address), info);
+        for (key, value) in account.storage.iter() {
+            db.insert_account_storage(
+                to_revm_address(address),
+                to_revm_word(key),
+                to_revm_word(value),
+            )
+            .map_err(|e| Error::TracingError(format!("{e:?}")))?;
+        }
+    }
+    // The hashes of the 256 most recent blocks, the missing ones being zero as in geth.
+    let number = block.number.as_u64();
+    for n in number.saturating_sub(256)..number {
+        let hash = (config.history_hashes.len() as u64 + n)
+            .checked_sub(number)
+            .and_then(|index| config.history_hashes.get(index as usize))
+            .map(|hash| B256::from(hash.to_be_bytes()))
+            .unwrap_or_default();
+        db.block_hashes.insert(U256::from(n), hash);
+    }
+
+    let chain_config = config.chain_config.clone().unwrap_or_default();
+    let timestamp = block.timestamp.as_u64();
+    let spec_id = if chain_config.is_cancun(timestamp) {
+        SpecId::CANCUN
+    } else if chain_config.is_shanghai(timestamp) {
+        SpecId::SHANGHAI
+    } else {
+        SpecId::MERGE
+    };
+
+    let mut traces = Vec::with_capacity(config.transactions.len());
+    for (i, tx) in config.transactions.iter().enumerate() {
+        let mut logger = StructLogger::new(&config.logger_config);
+        let result = {
+            let mut evm = Evm::builder()
+                .with_db(&mut db)
+                .with_external_context(&mut logger)
+                .modify_cfg_env(|cfg| {
+                    cfg.chain_id = config.chain_id;
+                    cfg.disable_base_fee = true;
+                })
+                .modify_block_env(|env| {
+                    env.number = U256::from(number);
+                    env.coinbase = to_revm_address(&block.coinbase);
+                    env.timestamp = to_revm_word(&block.timestamp);
+                    env.gas_limit = to_revm_word(&block.gas_limit);
+                    env.basefee = to_revm_word(&block.base_fee);
+                    env.difficulty = to_revm_word(&block.difficulty);
+                    env.prevrandao = Some(B256::from(block.difficulty.to_be_bytes()));
+                    if spec_id == SpecId::CANCUN {
+                        env.blob_excess_gas_and_price = Some(BlobExcessGasAndPrice::new(0));
+                    }
+                })
+                .modify_tx_env(|env| {
+                    // geth falls back to the gas price for the fee caps of legacy txs.
+                    let gas_price = tx.gas_price.unwrap_or_default();
+                    env.caller = to_revm_address(&tx.from);
+                    env.transact_to = match tx.to {
+                        Some(to) => TxKind::Call(to_revm_address(&to)),
+                        None => TxKind::Create,
+                    };
+                    env.nonce = Some(tx.nonce.as_u64());
+                    env.gas_limit = tx.gas_limit.as_u64();
+                    env.gas_price = to_revm_word(&tx.gas_fee_cap.unwrap_or(gas_price));
+                    env.gas_priority_fee = Some(to_revm_word(&tx.gas_tip_cap.unwrap_or(gas_price)));
+                    env.value = to_revm_word(&tx.value);
+                    env.data = tx.call_data.to_vec().into();
+                    env.chain_id = Some(config.chain_id);
+                    env.access_list = tx
+                        .access_list
+                        .iter()
+                        .flat_map(|access_list| access_list.0.iter())
+                        .map(|item| {
+                            (
+                                to_revm_address(&item.address),
+                                item.storage_keys
+                                    .iter()
+                                    .map(|key| U256::from_be_bytes(key.to_fixed_bytes()))
+                                    .collect(),
+                            )
+                        })
+                        .collect();
+                })
+                .with_spec_id(spec_id)
+                .append_handler_register(inspector_handle_register)
+                .build();
+            evm.transact().map_err(|e| {
+                Error::TracingError(format!("Failed to apply config.Transactions[{i}]: {e:?}"))
+            })?
+        };
+
+        // The prestate is read before the state changes of the tx are committed.
+        let mut prestate = Map::new();
+        for (address, account) in result.state.iter() {
+            let info = db
+                .basic(*address)
+                .map_err(|e| Error::TracingError(format!("{e:?}")))?
+                .unwrap_or_default();
+            let code = match info.code {
+                Some(code) => code,
+                None => db
+                    .code_by_hash(info.code_hash)
+                    .map_err(|e| Error::TracingError(format!("{e:?}")))?,
+            };
+            let mut account_prestate = Map::new();
+            account_prestate.insert("balance".into(), json!(to_word(info.balance)));
+            if info.nonce != 0 {
+                account_prestate.insert("nonce".into(), json!(info.nonce));
+            }
+            if !code.is_empty() {
+                account_prestate.insert(
+                    "code".into(),
+                    json!(Bytes::from(code.original_bytes().to_vec())),
+                );
+            }
+            if !account.storage.is_empty() {
+                let storage = account
+                    .storage
+                    .iter()
+                    .map(|(key, slot)| {
+                        (
+                            format!("{:?}", H256::from(key.to_be_bytes::<32>())),
+                            json!(H256::from(slot.original_value().to_be_bytes::<32>())),
+                        )
+                    })
+                    .collect::<Map<_, _>>();
+                account_prestate.insert("storage".into(), Value::Object(storage));
+            }
+            prestate.insert(
+                format!("{:?}", to_address(*address)),
+                Value::Object(account_prestate),
+            );
+        }
+        db.commit(result.state);
+
+        let gas_used = result.result.gas_used();
+        let output = match &result.result {
+            ExecutionResult::Success { output, .. } => match output {
+                Output::Call(bytes) | Output::Create(bytes, _) => bytes.to_vec(),
+            },
+            ExecutionResult::Revert { output, .. } => output.to_vec(),
+            ExecutionResult::Halt { .. } => vec![],
+        };
+        let mut call_trace = logger
+            .root
+            .take()
+            .expect("the tx is executed in a call frame");
+        // geth reports the gas used by the tx for the root call.
+        call_trace.gas_used = gas_used;
+
+        traces.push(json!({
+            "gas": gas_used,
+            "failed": !result.result.is_success(),
+            "returnValue": output.iter().map(|b| format!("{b:02x}")).collect::<String>(),
+            "structLogs": logger.struct_logs,
+            "prestate": prestate,
+            "callTrace": call_trace.into_json(),
+        }));
+    }
+
+    Ok(Value::Array(traces))
+}
+
+/// A frame of geth's call tracer.
+#[derive(Debug)]
+struct CallFrame {
+    call_type: &'static str,
+    from: RevmAddress,
+    to: Option<RevmAddress>,
+    gas_limit: u64,
+    gas_used: u64,
+    output: Vec<u8>,
+    error: Option<String>,
+    calls: Vec<CallFrame>,
+}
+
+impl CallFrame {
+    fn new(call_type: &'static str, from: RevmAddress, to: Option<RevmAddress>, gas: u64) -> Self {
+        Self {
+            call_type,
+            from,
+            to,
+            gas_limit: gas,
+            gas_used: 0,
+            output: vec![],
+            error: None,
+            calls: vec![],
+        }
+    }
+
+    fn into_json(self) -> Value {
+        let mut frame = Map::new();
+        frame.insert("type".into(), json!(self.call_type));
+        frame.insert("from".into(), json!(to_address(self.from)));
+        if let Some(to) = self.to {
+            frame.insert("to".into(), json!(to_address(to)));
+        }
+        frame.insert("gasUsed".into(), json!(Word::from(self.gas_used)));
+        if !self.output.is_empty() {
+            frame.insert("output".into(), json!(Bytes::from(self.output)));
+        }
+        if let Some(error) = self.error {
+            frame.insert("error".into(), json!(error));
+        }
+        if !self.calls.is_empty() {
+            let calls = self.calls.into_iter().map(Self::into_json).collect();
+            frame.insert("calls".into(), Value::Array(calls));
+        }
+        Value::Object(frame)
+    }
+}
+
+/// The struct log of the step being executed, which is completed once the step is done.
+#[derive(Debug)]
+struct PendingStep {
+    address: RevmAddress,
+    op: OpcodeId,
+    gas: u64,
+    stack: Vec<U256>,
+    log: Map<String, Value>,
+}
+
+/// An inspector recording the struct logs and the call frames of a tx, as geth's struct logger and
+/// call tracer do.
+#[derive(Debug)]
+struct StructLogger {
+    config: LoggerConfig,
+    struct_logs: Vec<Value>,
+    step: Option<PendingStep>,
+    /// The refund counter of each frame of the call stack, whose sum is the refund counter of the
+    /// tx, as the refunds of a frame are merged into its caller's once it returns successfully.
+    refunds: Vec<i64>,
+    /// The storage slots accessed by each contract so far.
+    storage: HashMap<RevmAddress, BTreeMap<U256, U256>>,
+    frames: Vec<CallFrame>,
+    root: Option<CallFrame>,
+}
+
+impl StructLogger {
+    fn new(config: &LoggerConfig) -> Self {
+        Self {
+            config: config.clone(),
+            struct_logs: vec![],
+            step: None,
+            refunds: vec![],
+            storage: HashMap::new(),
+            frames: vec![],
+            root: None,
+        }
+    }
+
+    fn enter(&mut self, frame: CallFrame) {
+        self.frames.push(frame);
+    }
+
+    fn exit(&mut self, result: InstructionResult, gas_remaining: u64, output: &[u8]) {
+        let mut frame = self.frames.pop().expect("frame entered");
+        frame.gas_used = frame.gas_limit.saturating_sub(gas_remaining);
+        frame.output = output.to_vec();
+        frame.error = frame_error(result);
+        match self.frames.last_mut() {
+            Some(caller) => caller.calls.push(frame),
+            None => self.root = Some(frame),
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for StructLogger {
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        let depth = context.journaled_state.depth() as usize;
+        self.refunds.resize(depth, 0);
+        if let Some(refund) = self.refunds.last_mut() {
+            *refund = interp.gas.refunded();
+        }
+        let refund = self.refunds.iter().sum::<i64>().max(0) as u64;
+
+        let op = OpcodeId::from(interp.current_opcode());
+        let mut log = Map::new();
+        log.insert("pc".into(), json!(interp.program_counter()));
+        log.insert("op".into(), json!(geth_op_name(op)));
+        log.insert("gas".into(), json!(interp.gas.remaining()));
+        log.insert("depth".into(), json!(depth));
+        if refund != 0 {
+            log.insert("refund".into(), json!(refund));
+        }
+        if !self.config.disable_stack {
+            let stack = interp
+                .stack
+                .data()
+                .iter()
+                .map(|value| json!(to_word(*value)))
+                .collect();
+            log.insert("stack".into(), Value::Array(stack));
+        }
+        if self.config.enable_memory {
+            let memory = interp
+                .shared_memory
+                .context_memory()
+                .chunks_exact(32)
+                .map(|chunk| json!(format!("{:x}", H256::from_slice(chunk))))
+                .collect();
+            log.insert("memory".into(), Value::Array(memory));
+        }
+
+        self.step = Some(PendingStep {
+            address: interp.contract.target_address,
+            op,
+            gas: interp.gas.remaining(),
+            stack: interp.stack.data().clone(),
+            log,
+        });
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        let Some(PendingStep {
+            address,
+            op,
+            gas,
+            stack,
+            mut log,
+        }) = self.step.take()
+        else {
+            return;
+        };
+        log.insert(
+            "gasCost".into(),
+            json!(gas.saturating_sub(interp.gas.remaining())),
+        );
+        if let Some(error) = step_error(interp.instruction_result, op, stack.len()) {
+            log.insert("error".into(), json!(error));
+        }
+
+        if !self.config.disable_storage && matches!(op, OpcodeId::SLOAD | OpcodeId::SSTORE) {
+            // The loaded value is read from the stack after the step, as reading it from the
+            // database would warm the slot up.
+            let slot = match (op, stack.as_slice(), interp.stack.data().last()) {
+                (OpcodeId::SLOAD, [.., key], Some(value)) if interp.instruction_result.is_ok() => {
+                    Some((*key, *value))
+                }
+                (OpcodeId::SSTORE, [.., value, key], _) => Some((*key, *value)),
+                _ => None,
+            };
+            if let Some((key, value)) = slot {
+                let storage = self.storage.entry(address).or_default();
+                storage.insert(key, value);
+                let storage = storage
+                    .iter()
+                    .map(|(key, value)| {
+                        (
+                            format!("{:x}", H256::from(key.to_be_bytes::<32>())),
+                            json!(format!("{:x}", H256::from(value.to_be_bytes::<32>()))),
+                        )
+                    })
+                    .collect();
+                log.insert("storage".into(), Value::Object(storage));
+            }
+        }
+
+        self.struct_logs.push(Value::Object(log));
+    }
+
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        let call_type = match inputs.scheme {
+            CallScheme::CallCode => "CALLCODE",
+            CallScheme::DelegateCall => "DELEGATECALL",
+            CallScheme::StaticCall => "STATICCALL",
+            _ => "CALL",
+        };
+        // geth reports the callee's code address for delegate calls.
+        let to = match inputs.scheme {
+            CallScheme::CallCode | CallScheme::DelegateCall => inputs.bytecode_address,
+            _ => inputs.target_address,
+        };
+        self.enter(CallFrame::new(
+            call_type,
+            inputs.caller,
+            Some(to),
+            inputs.gas_limit,
+        ));
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.exit(
+            outcome.result.result,
+            outcome.result.gas.remaining(),
+            &outcome.result.output,
+        );
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        let call_type = match inputs.scheme {
+            CreateScheme::Create2 { .. } => "CREATE2",
+            _ => "CREATE",
+        };
+        self.enter(CallFrame::new(
+            call_type,
+            inputs.caller,
+            None,
+            inputs.gas_limit,
+        ));
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.to = outcome.address;
+        }
+        self.exit(
+            outcome.result.result,
+            outcome.result.gas.remaining(),
+            &outcome.result.output,
+        );
+        outcome
+    }
+
+    fn selfdestruct(&mut self, contract: RevmAddress, target: RevmAddress, _value: U256) {
+        if let Some(caller) = self.frames.last_mut() {
+            caller
+                .calls
+                .push(CallFrame::new("SELFDESTRUCT", contract, Some(target), 0));
+        }
+    }
+}
+
+/// The name of the opcode as printed by geth.
+fn geth_op_name(op: OpcodeId) -> String {
+    match op {
+        OpcodeId::INVALID(0xfe) => "INVALID".to_string(),
+        OpcodeId::INVALID(byte) => format!("opcode {byte:#x} not defined"),
+        op => op.to_string(),
+    }
+}
+
+/// The error of a step as reported by geth's struct logger, given the stack length before the
+/// step. The errors that geth only reports for the call frame, e.g. reverts or failed prechecks of
+/// calls, are not reported for the step.
+fn step_error(result: InstructionResult, op: OpcodeId, stack_len: usize) -> Option<String> {
+    let (min_stack_ptr, max_stack_ptr) = op.valid_stack_ptr_range();
+    let error = match result {
+        InstructionResult::OutOfGas
+        | InstructionResult::MemoryOOG
+        | InstructionResult::MemoryLimitOOG
+        | InstructionResult::InvalidOperandOOG => "out of gas".to_string(),
+        InstructionResult::OpcodeNotFound
+        | InstructionResult::NotActivated
+        | InstructionResult::InvalidFEOpcode => {
+            format!("invalid opcode: {}", geth_op_name(op))
+        }
+        InstructionResult::InvalidJump => "invalid jump destination".to_string(),
+        InstructionResult::StateChangeDuringStaticCall
+        | InstructionResult::CallNotAllowedInsideStatic => "write protection".to_string(),
+        InstructionResult::OutOfOffset => "return data out of bounds".to_string(),
+        InstructionResult::StackUnderflow => {
+            format!("stack underflow ({stack_len} <=> {})", 1024 - max_stack_ptr)
+        }
+        InstructionResult::StackOverflow => {
+            format!("stack limit reached {stack_len} ({})", 1024 - min_stack_ptr)
+        }
+        _ => return None,
+    };
+    Some(error)
+}
+
+/// The error of a call frame as reported by geth's call tracer.
+fn frame_error(result: InstructionResult) -> Option<String> {
+    let error = match result {
+        _ if result.is_ok() => return None,
+        InstructionResult::Revert => "execution reverted",
+        InstructionResult::CallTooDeep => "max call depth exceeded",
+        InstructionResult::OutOfFunds => "insufficient balance for transfer",
+        InstructionResult::CreateCollision => "contract address collision",
+        InstructionResult::NonceOverflow => "nonce uint64 overflow",
+        InstructionResult::CreateContractSizeLimit => "max code size exceeded",
+        InstructionResult::CreateInitCodeSizeLimit => "max initcode size exceeded",
+        InstructionResult::CreateContractStartingWithEF => "invalid code: must not begin with 0xef",
+        InstructionResult::InvalidJump => "invalid jump destination",
+        InstructionResult::StateChangeDuringStaticCall
+        | InstructionResult::CallNotAllowedInsideStatic => "write protection",
+        InstructionResult::OutOfOffset => "return data out of bounds",
+        InstructionResult::StackUnderflow => "stack underflow",
+        InstructionResult::StackOverflow => "stack limit reached",
+        InstructionResult::OpcodeNotFound
+        | InstructionResult::NotActivated
+        | InstructionResult::InvalidFEOpcode => "invalid opcode",
+        _ => "out of gas",
+    };
+    Some(error.to_string())
+}
+
+fn to_revm_address(address: &Address) -> RevmAddress {
+    RevmAddress::from(address.to_fixed_bytes())
+}
+
+fn to_revm_word(word: &Word) -> U256 {
+    U256::from_be_bytes(word.to_be_bytes())
+}
+
+fn to_address(address: RevmAddress) -> Address {
+    Address::from(address.into_array())
+}
+
+fn to_word(word: U256) -> Word {
+    Word::from_big_endian(&word.to_be_bytes::<32>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth_types::{
+        geth_types::{Account, Transaction},
+        GethExecTrace,
+    };
+
+    #[test]
+    fn trace_struct_logs() {
+        // PUSH1 0x2a PUSH1 0x00 SSTORE STOP
+        let contract = Address::repeat_byte(0xcc);
+        let sender = Address::repeat_byte(0xfe);
+        let mut config = TraceConfig {
+            chain_id: 1,
+            logger_config: LoggerConfig {
+                disable_stack: false,
+                disable_storage: false,
+                ..LoggerConfig::default()
+            },
+            ..TraceConfig::default()
+        };
+        config.block_constants.number = 1.into();
+        config.block_constants.gas_limit = 10_000_000.into();
+        config.accounts.insert(
+            contract,
+            Account {
+                address: contract,
+                code: vec![0x60, 0x2a, 0x60, 0x00, 0x55, 0x00].into(),
+                ..Account::default()
+            },
+        );
+        config.accounts.insert(
+            sender,
+            Account {
+                address: sender,
+                balance: Word::from(10).pow(18.into()),
+                ..Account::default()
+            },
+        );
+        config.transactions.push(Transaction {
+            from: sender,
+            to: Some(contract),
+            gas_limit: 100_000.into(),
+            ..Transaction::default()
+        });
+
+        let traces: Vec<GethExecTrace> = serde_json::from_value(trace(&config).unwrap()).unwrap();
+        let trace = &traces[0];
+        assert!(!trace.failed);
+        let ops = trace
+            .struct_logs
+            .iter()
+            .map(|step| (step.op, step.gas_cost.0, step.depth))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ops,
+            vec![
+                (OpcodeId::PUSH1, 3, 1),
+                (OpcodeId::PUSH1, 3, 1),
+                (OpcodeId::SSTORE, 22100, 1),
+                (OpcodeId::STOP, 0, 1),
+            ]
+        );
+        assert_eq!(trace.gas.0, 21000 + 3 + 3 + 22100);
+        assert_eq!(
+            trace.prestate[&contract]
+                .code
+                .as_ref()
+                .map(|code| code.len()),
+            Some(6)
+        );
+    }
+}
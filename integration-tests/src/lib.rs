@@ -4,19 +4,21 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 #![deny(missing_docs)]
 
-use bus_mapping::rpc::GethClient;
+use async_trait::async_trait;
+use bus_mapping::rpc::{GethClient, RecordingClient, ReplayClient, RpcFixtureError};
 use env_logger::Env;
 use eth_types::Address;
 use ethers::{
     abi,
     core::{k256::ecdsa::SigningKey, types::Bytes},
-    providers::{Http, Provider},
+    providers::{Http, JsonRpcClient, Provider},
     signers::{coins_bip39::English, MnemonicBuilder, Signer, Wallet},
 };
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     collections::HashMap,
     env::{self, VarError},
+    fmt::Debug,
     fs::File,
     sync::{LazyLock, Once},
     time::Duration,
@@ -72,6 +74,21 @@ pub static CIRCUIT: LazyLock<String> = LazyLock::new(|| match env::var("CIRCUIT"
     Err(e) => panic!("Error in CIRCUIT env var: {e:?}"),
 });
 
+/// Fixture file of the exchanges with geth0. If set, the [`get_client`] clients replay the
+/// exchanges from it instead of connecting to geth0, or record them to it with `RPC_RECORD`.
+pub static RPC_FIXTURE: LazyLock<Option<String>> =
+    LazyLock::new(|| match env::var("RPC_FIXTURE") {
+        Ok(val) => Some(val),
+        Err(VarError::NotPresent) => None,
+        Err(e) => panic!("Error in RPC_FIXTURE env var: {e:?}"),
+    });
+/// Whether to record the exchanges with geth0 to `RPC_FIXTURE` rather than replaying them.
+pub static RPC_RECORD: LazyLock<bool> = LazyLock::new(|| match env::var("RPC_RECORD") {
+    Ok(val) => !matches!(val.as_str(), "" | "0" | "false"),
+    Err(VarError::NotPresent) => false,
+    Err(e) => panic!("Error in RPC_RECORD env var: {e:?}"),
+});
+
 static LOG_INIT: Once = Once::new();
 
 /// Initialize log
@@ -81,9 +98,49 @@ pub fn log_init() {
     });
 }
 
+/// Transport of the integration test [`GethClient`], see [`RPC_FIXTURE`].
+#[derive(Debug)]
+pub enum TestTransport {
+    /// Connected to geth0
+    Live(Http),
+    /// Connected to geth0, recording the exchanges to the fixture
+    Record(RecordingClient<Http>),
+    /// Replaying the exchanges of the fixture
+    Replay(ReplayClient),
+}
+
+#[async_trait]
+impl JsonRpcClient for TestTransport {
+    type Error = RpcFixtureError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            Self::Live(http) => http
+                .request(method, params)
+                .await
+                .map_err(|e| RpcFixtureError::Inner(e.into())),
+            Self::Record(client) => client.request(method, params).await,
+            Self::Replay(client) => client.request(method, params).await,
+        }
+    }
+}
+
 /// Get the integration test [`GethClient`]
-pub fn get_client() -> GethClient<Http> {
-    let transport = Http::new(Url::parse(&GETH0_URL).expect("invalid url"));
+pub fn get_client() -> GethClient<TestTransport> {
+    let http = || Http::new(Url::parse(&GETH0_URL).expect("invalid url"));
+    let transport = match RPC_FIXTURE.as_ref() {
+        None => TestTransport::Live(http()),
+        Some(fixture) if *RPC_RECORD => {
+            TestTransport::Record(RecordingClient::new(http(), fixture))
+        }
+        Some(fixture) => {
+            TestTransport::Replay(ReplayClient::load(fixture).expect("cannot load rpc fixture"))
+        }
+    };
     GethClient::new(transport)
 }
 
@@ -15,7 +15,7 @@ use zkevm_circuits::{
     bytecode_circuit::circuit::BytecodeCircuit,
     copy_circuit::CopyCircuit,
     evm_circuit::{witness::block_convert, EvmCircuit},
-    keccak_circuit::keccak_packed_multi::multi_keccak,
+    keccak_circuit::{keccak_packed_multi::multi_keccak, DEFAULT_KECCAK_ROWS},
     mpt_circuit::MptCircuit,
     rlp_circuit_fsm::RlpCircuit,
     state_circuit::StateCircuit,
@@ -212,7 +212,7 @@ async fn test_print_circuits_size() {
             Value::known(mock_randomness),
         );
         let keccak_inputs = keccak_inputs(&block).unwrap();
-        let keccak_rows = multi_keccak(&keccak_inputs, challenges, None)
+        let keccak_rows = multi_keccak(&keccak_inputs, challenges, None, DEFAULT_KECCAK_ROWS)
             .unwrap()
             .len();
         log::info!(
@@ -16,7 +16,7 @@ use external_tracer::TraceConfig;
 use helpers::*;
 use itertools::Itertools;
 
-pub use external_tracer::LoggerConfig;
+pub use external_tracer::{ChainConfig, L1FeeConfig, LoggerConfig, ScrollConfig};
 
 /// TestContext is a type that contains all the information from a block
 /// required to build the circuit inputs.
@@ -136,6 +136,32 @@ impl<const NACC: usize, const NTX: usize> TestContext<NACC, NTX> {
         func_block: Fb,
         logger_config: LoggerConfig,
     ) -> Result<Self, Error>
+    where
+        FTx: FnOnce(Vec<&mut MockTransaction>, [MockAccount; NACC]),
+        Fb: FnOnce(&mut MockBlock, Vec<MockTransaction>) -> &mut MockBlock,
+        FAcc: FnOnce([&mut MockAccount; NACC]),
+    {
+        Self::new_with_chain_config(
+            history_hashes,
+            acc_fns,
+            func_tx,
+            func_block,
+            logger_config,
+            None,
+        )
+    }
+
+    /// Create a new TestContext as [`TestContext::new_with_logger_config`] does, tracing the
+    /// block under the forks, fee vault and L1 fee parameters of the chain config rather than
+    /// the defaults of the tracer.
+    pub fn new_with_chain_config<FAcc, FTx, Fb>(
+        history_hashes: Option<Vec<Word>>,
+        acc_fns: FAcc,
+        func_tx: FTx,
+        func_block: Fb,
+        logger_config: LoggerConfig,
+        chain_config: Option<ChainConfig>,
+    ) -> Result<Self, Error>
     where
         FTx: FnOnce(Vec<&mut MockTransaction>, [MockAccount; NACC]),
         Fb: FnOnce(&mut MockBlock, Vec<MockTransaction>) -> &mut MockBlock,
@@ -208,6 +234,7 @@ impl<const NACC: usize, const NTX: usize> TestContext<NACC, NTX> {
                 .collect_vec(),
             history_hashes.clone(),
             logger_config,
+            chain_config,
         )?;
 
         #[cfg(feature = "scroll")]
@@ -292,6 +319,7 @@ pub fn gen_trace_config(
     accounts: Vec<Account>,
     history_hashes: Option<Vec<Word>>,
     logger_config: LoggerConfig,
+    chain_config: Option<ChainConfig>,
 ) -> Result<TraceConfig, Error> {
     Ok(TraceConfig {
         chain_id,
@@ -307,7 +335,7 @@ pub fn gen_trace_config(
             .map(eth_types::geth_types::Transaction::from)
             .collect(),
         logger_config,
-        chain_config: None,
+        chain_config,
         #[cfg(feature = "scroll")]
         l1_queue_index: 0,
     })
@@ -1,18 +1,24 @@
-use std::{env, iter::repeat};
+use std::iter::repeat;
 
-use aggregator::{BatchHash, BatchHeader, ChunkInfo, MAX_AGG_SNARKS};
+use aggregator::{preprocessed_digest, BatchHash, BatchHeader, ChunkInfo, MAX_AGG_SNARKS};
 use anyhow::{bail, Result};
 use eth_types::H256;
+use halo2_proofs::halo2curves::bn256::{Fr, G1Affine};
 use sha2::{Digest, Sha256};
+use snark_verifier::Protocol;
 use snark_verifier_sdk::Snark;
 
 use crate::{
     common,
     config::{LayerId, AGG_DEGREES},
-    consts::{BATCH_KECCAK_ROW, BATCH_VK_FILENAME, BUNDLE_VK_FILENAME, CHUNK_PROTOCOL_FILENAME},
+    consts::{
+        BATCH_VK_FILENAME, BUNDLE_VK_FILENAME, CHUNK_LEGACY_PROTOCOL_FILENAME,
+        CHUNK_PROTOCOL_FILENAME,
+    },
     io::{force_to_read, try_to_read},
     proof::BundleProof,
     types::BundleProvingTask,
+    utils::gen_rng,
     BatchProof, BatchProvingTask, ChunkProof,
 };
 
@@ -21,17 +27,18 @@ pub struct Prover {
     // Make it public for testing with inner functions (unnecessary for FFI).
     pub prover_impl: common::Prover,
     pub chunk_protocol: Vec<u8>,
+    // Protocol of the previous chunk circuit version, whose proofs are accepted alongside those
+    // of the current version, e.g. while rolling out a new chunk circuit.
+    pub legacy_chunk_protocol: Option<Vec<u8>>,
     raw_vk_batch: Option<Vec<u8>>,
     raw_vk_bundle: Option<Vec<u8>>,
 }
 
 impl Prover {
     pub fn from_dirs(params_dir: &str, assets_dir: &str) -> Self {
-        log::debug!("set env KECCAK_ROWS={}", BATCH_KECCAK_ROW.to_string());
-        env::set_var("KECCAK_ROWS", BATCH_KECCAK_ROW.to_string());
-
         let prover_impl = common::Prover::from_params_dir(params_dir, &AGG_DEGREES);
         let chunk_protocol = force_to_read(assets_dir, &CHUNK_PROTOCOL_FILENAME);
+        let legacy_chunk_protocol = try_to_read(assets_dir, &CHUNK_LEGACY_PROTOCOL_FILENAME);
 
         let raw_vk_batch = try_to_read(assets_dir, &BATCH_VK_FILENAME);
         let raw_vk_bundle = try_to_read(assets_dir, &BUNDLE_VK_FILENAME);
@@ -53,15 +60,34 @@ impl Prover {
         Self {
             prover_impl,
             chunk_protocol,
+            legacy_chunk_protocol,
             raw_vk_batch,
             raw_vk_bundle,
         }
     }
 
-    // Return true if chunk proofs are valid (same protocol), false otherwise.
+    // The protocols of the chunk circuit versions whose proofs are accepted, the current one
+    // first.
+    pub fn chunk_protocols(&self) -> impl Iterator<Item = &Vec<u8>> {
+        std::iter::once(&self.chunk_protocol).chain(self.legacy_chunk_protocol.as_ref())
+    }
+
+    // The digests of the chunk vks that the batch circuit verifies the chunk proofs against.
+    pub fn chunk_vk_digests(&self) -> Result<Vec<Fr>> {
+        self.chunk_protocols()
+            .map(|protocol| {
+                let protocol = serde_json::from_slice::<Protocol<G1Affine>>(protocol)?;
+                Ok(preprocessed_digest(&protocol))
+            })
+            .collect()
+    }
+
+    // Return true if chunk proofs are valid (of an accepted protocol), false otherwise.
     pub fn check_protocol_of_chunks(&self, chunk_proofs: &[ChunkProof]) -> bool {
         chunk_proofs.iter().enumerate().all(|(i, proof)| {
-            let result = proof.protocol == self.chunk_protocol;
+            let result = self
+                .chunk_protocols()
+                .any(|protocol| proof.protocol == *protocol);
             if !result {
                 log::error!(
                     "Non-match protocol of chunk-proof index-{}: expected = {:x}, actual = {:x}",
@@ -135,6 +161,17 @@ impl Prover {
         if !self.check_protocol_of_chunks(&batch.chunk_proofs) {
             bail!("non-match-chunk-protocol: {name}");
         }
+        // the batch circuit lays out its public inputs as per the v3 batch header.
+        if !batch
+            .batch_header
+            .header_codec()?
+            .is_supported_by_batch_circuit()
+        {
+            bail!(
+                "batch header version {} is not supported by the batch circuit: {name}",
+                batch.batch_header.version
+            );
+        }
         let mut chunk_hashes: Vec<_> = batch
             .chunk_proofs
             .iter()
@@ -165,13 +202,14 @@ impl Prover {
             batch.batch_header.parent_batch_hash,
             batch.batch_header.last_block_timestamp,
             &chunk_hashes,
+            batch.batch_header.codec,
         );
 
         // sanity check between:
         // - BatchHeader supplied from infra
         // - BatchHeader re-constructed by circuits
         //
-        // for the fields data_hash, z, y, blob_versioned_hash.
+        // for the fields data_hash, z, y, blob_versioned_hash, and the l1 msg hashes.
         assert_eq!(
             batch_header.data_hash, batch.batch_header.data_hash,
             "BatchHeader(sanity) mismatch data_hash expected={}, got={}",
@@ -192,8 +230,23 @@ impl Prover {
             "BatchHeader(sanity) mismatch blob versioned hash expected={}, got={}",
             batch.batch_header.blob_versioned_hash, batch_header.blob_versioned_hash,
         );
+        assert_eq!(
+            batch_header.extra_blobs, batch.batch_header.extra_blobs,
+            "BatchHeader(sanity) mismatch extra blobs expected={:?}, got={:?}",
+            batch.batch_header.extra_blobs, batch_header.extra_blobs,
+        );
+        assert_eq!(
+            batch_header.prev_l1_msg_hash, batch.batch_header.prev_l1_msg_hash,
+            "BatchHeader(sanity) mismatch prev l1 msg hash expected={}, got={}",
+            batch.batch_header.prev_l1_msg_hash, batch_header.prev_l1_msg_hash,
+        );
+        assert_eq!(
+            batch_header.post_l1_msg_hash, batch.batch_header.post_l1_msg_hash,
+            "BatchHeader(sanity) mismatch post l1 msg hash expected={}, got={}",
+            batch.batch_header.post_l1_msg_hash, batch_header.post_l1_msg_hash,
+        );
 
-        let batch_hash = batch_header.batch_hash();
+        let batch_hash = batch_header.batch_hash()?;
         let batch_info: BatchHash<N_SNARKS> = BatchHash::construct(&chunk_hashes, batch_header);
 
         let layer3_snark = self.prover_impl.load_or_gen_agg_snark(
@@ -202,6 +255,7 @@ impl Prover {
             LayerId::Layer3.degree(),
             batch_info,
             &layer2_snarks,
+            &self.chunk_vk_digests()?,
             output_dir,
         )?;
         log::info!("Got aggregation snark (layer-3): {name}");
@@ -218,6 +272,9 @@ impl Prover {
         name: Option<&str>,
         output_dir: Option<&str>,
     ) -> Result<BundleProof> {
+        if bundle.batch_proofs.is_empty() {
+            bail!("bundle-prover: no batch proofs in bundle");
+        }
         let name = name.map_or_else(|| bundle.identifier(), |name| name.to_string());
 
         let bundle_snarks = bundle
@@ -234,8 +291,45 @@ impl Prover {
             output_dir,
         )?;
 
+        self.finalize_bundle_proof(&name, layer5_snark, output_dir)
+    }
+
+    // Recurse over the batch proofs (layer-5), continuing from the layer-5 snark of the preceding
+    // batches of the bundle if any. The returned snark proves all the batches recursed over thus
+    // far, so it may be extended with the next batches or finalised into a bundle proof, e.g.
+    // when a deadline for the bundle is reached.
+    pub fn extend_bundle_snark(
+        &mut self,
+        batch_proofs: &[BatchProof],
+        prev_snark: Option<Snark>,
+    ) -> Result<Snark> {
+        if batch_proofs.is_empty() {
+            bail!("bundle-prover: no batch proofs to extend the bundle with");
+        }
+
+        let bundle_snarks = batch_proofs
+            .iter()
+            .map(|proof| proof.into())
+            .collect::<Vec<_>>();
+
+        self.prover_impl.extend_recursion_snark(
+            LayerId::Layer5.id(),
+            LayerId::Layer5.degree(),
+            gen_rng(),
+            prev_snark,
+            &bundle_snarks,
+        )
+    }
+
+    // Given the layer-5 snark of a bundle, of any number of batches, generate the bundle proof.
+    pub fn finalize_bundle_proof(
+        &mut self,
+        name: &str,
+        layer5_snark: Snark,
+        output_dir: Option<&str>,
+    ) -> Result<BundleProof> {
         let layer6_evm_proof = self.prover_impl.load_or_gen_comp_evm_proof(
-            &name,
+            name,
             LayerId::Layer6.id(),
             true,
             LayerId::Layer6.degree(),
@@ -246,6 +340,10 @@ impl Prover {
         self.check_bundle_vk();
 
         let bundle_proof: BundleProof = layer6_evm_proof.proof.into();
+        log::info!(
+            "Got bundle proof of {} batches: {name}",
+            bundle_proof.num_batches()
+        );
         if let Some(output_dir) = output_dir {
             bundle_proof.dump(output_dir, "recursion")?;
         }
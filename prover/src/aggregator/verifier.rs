@@ -3,7 +3,7 @@ use crate::{
     config::{LAYER4_CONFIG_PATH, LAYER4_DEGREE},
     consts::{batch_vk_filename, DEPLOYMENT_CODE_FILENAME},
     io::{force_to_read, try_to_read},
-    proof::BundleProof,
+    proof::{BundleProof, BundleVerifierVersion},
 };
 use aggregator::CompressionCircuit;
 use halo2_proofs::{
@@ -54,6 +54,15 @@ impl Verifier {
     }
 
     pub fn verify_bundle_proof(&self, bundle_proof: BundleProof) -> bool {
+        // the deployment code is generated from the current bundle circuit.
+        if bundle_proof.verifier_version() != BundleVerifierVersion::CURRENT {
+            log::warn!(
+                "bundle proof for verifier {:?}, expected {:?}",
+                bundle_proof.verifier_version(),
+                BundleVerifierVersion::CURRENT
+            );
+            return false;
+        }
         if let Some(deployment_code) = self.deployment_code.clone() {
             verify_evm_calldata(deployment_code, bundle_proof.calldata())
         } else {
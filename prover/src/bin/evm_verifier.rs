@@ -0,0 +1,65 @@
+//! Generate the EVM verifier of a layer, verify an EVM proof of that layer with it in a local EVM
+//! and print a JSON report of the verification gas, e.g.
+//!
+//! cargo run --release --bin evm_verifier -- --layer layer6 --params-dir params \
+//!     --vk vk_bundle.vkey --proof evm_layer6_bundle.json --output-dir output
+use anyhow::{bail, Result};
+use clap::Parser;
+use prover::{
+    common::Verifier,
+    config::LayerId,
+    io::{from_json_file, read_all},
+    utils::init_env_and_log,
+    CompressionCircuit, EvmProof,
+};
+use std::env;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Layer of the proof, one of the EVM-verifiable layers (layer4, layer6)
+    #[clap(long)]
+    layer: LayerId,
+
+    /// Directory of the KZG params
+    #[clap(long, default_value = "params")]
+    params_dir: String,
+
+    /// File of the layer's verifying key
+    #[clap(long)]
+    vk: String,
+
+    /// File of the EVM proof (JSON) to verify
+    #[clap(long)]
+    proof: String,
+
+    /// Directory to dump the YUL source and the deployment bytecode of the verifier to, the
+    /// output directory of the logs by default
+    #[clap(long)]
+    output_dir: Option<String>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if !args.layer.is_evm_verifiable() {
+        bail!("{} is not an EVM-verifiable layer", args.layer);
+    }
+    let log_dir = init_env_and_log("evm_verifier");
+    let output_dir = args.output_dir.unwrap_or(log_dir);
+
+    env::set_var("COMPRESSION_CONFIG", args.layer.config_path());
+    let verifier = Verifier::<CompressionCircuit>::from_params_dir(
+        &args.params_dir,
+        args.layer.degree(),
+        &read_all(&args.vk),
+    );
+    let evm_proof: EvmProof = from_json_file(&args.proof)?;
+
+    let report = verifier.evm_gas_report(&evm_proof, Some(&output_dir));
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    if !report.verified {
+        bail!("the proof is not verified by the EVM verifier");
+    }
+
+    Ok(())
+}
@@ -6,6 +6,7 @@ use crate::{
 };
 use aggregator::{BatchCircuit, BatchHash};
 use anyhow::{anyhow, Result};
+use halo2_proofs::halo2curves::bn256::Fr;
 use rand::Rng;
 use snark_verifier_sdk::Snark;
 use std::env;
@@ -18,12 +19,18 @@ impl Prover {
         mut rng: impl Rng + Send,
         batch_info: BatchHash<N_SNARKS>,
         previous_snarks: &[Snark],
+        chunk_vk_digests: &[Fr],
     ) -> Result<Snark> {
         env::set_var("AGGREGATION_CONFIG", layer_config_path(id));
 
-        let circuit: BatchCircuit<N_SNARKS> =
-            BatchCircuit::new(self.params(degree), previous_snarks, &mut rng, batch_info)
-                .map_err(|err| anyhow!("Failed to construct aggregation circuit: {err:?}"))?;
+        let circuit: BatchCircuit<N_SNARKS> = BatchCircuit::new(
+            self.params(degree),
+            previous_snarks,
+            &mut rng,
+            batch_info,
+            chunk_vk_digests,
+        )
+        .map_err(|err| anyhow!("Failed to construct aggregation circuit: {err:?}"))?;
 
         self.gen_snark(id, degree, &mut rng, circuit, "gen_agg_snark")
     }
@@ -35,6 +42,7 @@ impl Prover {
         degree: u32,
         batch_info: BatchHash<N_SNARKS>,
         previous_snarks: &[Snark],
+        chunk_vk_digests: &[Fr],
         output_dir: Option<&str>,
     ) -> Result<Snark> {
         let file_path = format!(
@@ -48,7 +56,14 @@ impl Prover {
             Some(snark) => Ok(snark),
             None => {
                 let rng = gen_rng();
-                let result = self.gen_agg_snark(id, degree, rng, batch_info, previous_snarks);
+                let result = self.gen_agg_snark(
+                    id,
+                    degree,
+                    rng,
+                    batch_info,
+                    previous_snarks,
+                    chunk_vk_digests,
+                );
                 if let (Some(_), Ok(snark)) = (output_dir, &result) {
                     write_snark(&file_path, snark);
                 }
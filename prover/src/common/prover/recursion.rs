@@ -16,10 +16,24 @@ use super::Prover;
 
 impl Prover {
     pub fn gen_recursion_snark(
+        &mut self,
+        id: &str,
+        degree: u32,
+        rng: impl Rng + Send,
+        batch_snarks: &[Snark],
+    ) -> Result<Snark> {
+        self.extend_recursion_snark(id, degree, rng, None, batch_snarks)
+    }
+
+    /// Recurse over the batch snarks, continuing from the recursion snark of the preceding batches
+    /// of the bundle if any. Every recursion snark proves the bundle of the batches recursed over
+    /// thus far, so a bundle can be extended as batches are proven and finalised at any point.
+    pub fn extend_recursion_snark(
         &mut self,
         id: &str,
         degree: u32,
         mut rng: impl Rng + Send,
+        prev_snark: Option<Snark>,
         batch_snarks: &[Snark],
     ) -> Result<Snark> {
         // We should at least have a single snark.
@@ -45,17 +59,27 @@ impl Prover {
         );
         let (params, pk) = self.params_and_pk(id, degree, &circuit_for_pk)?;
 
-        // Using the above generated PK, we can now construct the legitimate starting state.
-        let mut cur_snark = initial_recursion_snark::<RecursionTask<MAX_AGG_SNARKS>>(
-            params,
-            Some(pk.get_vk()),
-            &mut rng,
-        );
-
-        // The recursion task is initialised with all the snarks, and the we are at the 0th round
-        // of recursion at the start.
+        // Using the above generated PK, we can now construct the legitimate starting state, unless
+        // we continue from the batches already recursed over.
+        let (mut cur_snark, mut n_rounds) = match prev_snark {
+            Some(prev_snark) => {
+                let n_rounds = RecursionCircuit::<RecursionTask<MAX_AGG_SNARKS>>::num_batches(
+                    &prev_snark.instances[0],
+                );
+                (prev_snark, n_rounds)
+            }
+            None => (
+                initial_recursion_snark::<RecursionTask<MAX_AGG_SNARKS>>(
+                    params,
+                    Some(pk.get_vk()),
+                    &mut rng,
+                ),
+                0,
+            ),
+        };
+
+        // The recursion task is initialised with all the snarks to recurse over.
         let mut task = RecursionTask::<MAX_AGG_SNARKS>::new(batch_snarks);
-        let mut n_rounds = 0;
 
         while !task.completed() {
             log::debug!("construct recursion circuit for round {}", n_rounds);
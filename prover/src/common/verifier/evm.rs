@@ -1,5 +1,8 @@
 use super::Verifier;
-use crate::EvmProof;
+use crate::{
+    evm::{EvmGasReport, EvmVerifier},
+    EvmProof,
+};
 use halo2_proofs::halo2curves::bn256::Fr;
 use snark_verifier_sdk::CircuitExt;
 
@@ -7,4 +10,16 @@ impl<C: CircuitExt<Fr>> Verifier<C> {
     pub fn gen_evm_verifier(&self, evm_proof: &EvmProof, output_dir: Option<&str>) {
         crate::evm::gen_evm_verifier::<C>(&self.params, &self.vk, evm_proof, output_dir)
     }
+
+    /// Generate the EVM verifier, dumping it to output_dir if any, and report the gas used to
+    /// verify the proof with it.
+    pub fn evm_gas_report(&self, evm_proof: &EvmProof, output_dir: Option<&str>) -> EvmGasReport {
+        let verifier =
+            EvmVerifier::new::<C>(&self.params, &self.vk, evm_proof.num_instance.clone());
+        if let Some(dir) = output_dir {
+            verifier.dump(dir);
+        }
+
+        verifier.gas_report(&evm_proof.proof)
+    }
 }
@@ -1,6 +1,6 @@
 use crate::utils::read_env_var;
 use aggregator::ConfigParams;
-use std::{collections::HashSet, fmt, fs::File, path::Path, sync::LazyLock};
+use std::{collections::HashSet, fmt, fs::File, path::Path, str::FromStr, sync::LazyLock};
 
 pub static INNER_DEGREE: LazyLock<u32> =
     LazyLock::new(|| read_env_var("SCROLL_PROVER_INNER_DEGREE", 20));
@@ -69,6 +69,23 @@ impl fmt::Display for LayerId {
     }
 }
 
+impl FromStr for LayerId {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        match id {
+            "inner" => Ok(Self::Inner),
+            "layer1" => Ok(Self::Layer1),
+            "layer2" => Ok(Self::Layer2),
+            "layer3" => Ok(Self::Layer3),
+            "layer4" => Ok(Self::Layer4),
+            "layer5" => Ok(Self::Layer5),
+            "layer6" => Ok(Self::Layer6),
+            _ => Err(format!("Wrong layer id-{id}")),
+        }
+    }
+}
+
 impl LayerId {
     pub fn id(&self) -> &str {
         match self {
@@ -94,6 +111,12 @@ impl LayerId {
         }
     }
 
+    /// Whether the layer generates EVM proofs, i.e. it is a compression layer whose proofs may be
+    /// verified on-chain.
+    pub fn is_evm_verifiable(&self) -> bool {
+        matches!(self, Self::Layer4 | Self::Layer6)
+    }
+
     pub fn config_path(&self) -> &str {
         match self {
             Self::Layer1 => &LAYER1_CONFIG_PATH,
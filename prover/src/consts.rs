@@ -15,6 +15,13 @@ pub fn chunk_vk_filename() -> String {
 
 pub static CHUNK_PROTOCOL_FILENAME: LazyLock<String> =
     LazyLock::new(|| read_env_var("CHUNK_PROTOCOL_FILENAME", "chunk.protocol".to_string()));
+// Protocol of the previous chunk circuit version, whose proofs are still accepted in batches.
+pub static CHUNK_LEGACY_PROTOCOL_FILENAME: LazyLock<String> = LazyLock::new(|| {
+    read_env_var(
+        "CHUNK_LEGACY_PROTOCOL_FILENAME",
+        "chunk_legacy.protocol".to_string(),
+    )
+});
 
 pub static CHUNK_VK_FILENAME: LazyLock<String> = LazyLock::new(chunk_vk_filename);
 pub static BATCH_VK_FILENAME: LazyLock<String> = LazyLock::new(batch_vk_filename);
@@ -22,7 +29,3 @@ pub static BUNDLE_VK_FILENAME: LazyLock<String> = LazyLock::new(bundle_vk_filena
 
 pub static DEPLOYMENT_CODE_FILENAME: LazyLock<String> =
     LazyLock::new(|| read_env_var("DEPLOYMENT_CODE_FILENAME", "evm_verifier.bin".to_string()));
-
-// For our k=21 agg circuit, 12 means it can include 2**21 / (12 * 25) * 136.0 = 0.95M bytes
-pub static BATCH_KECCAK_ROW: LazyLock<usize> =
-    LazyLock::new(|| read_env_var("BATCH_KECCAK_ROW", 12));
@@ -1,12 +1,214 @@
-use crate::{io::write_file, EvmProof};
+use crate::{io::write_file, EvmProof, Proof};
 use halo2_proofs::{
     halo2curves::bn256::{Bn256, Fr, G1Affine},
     plonk::VerifyingKey,
     poly::kzg::commitment::ParamsKZG,
 };
-use snark_verifier::pcs::kzg::{Bdfg21, Kzg};
+use serde_derive::{Deserialize, Serialize};
+use snark_verifier::{
+    loader::evm::{encode_calldata, Address, ExecutorBuilder},
+    pcs::kzg::{Bdfg21, Kzg},
+};
 use snark_verifier_sdk::CircuitExt;
-use std::{path::PathBuf, str::FromStr};
+use std::{fs, path::PathBuf, str::FromStr};
+
+// Gas prices of the bn254 precompiles as per EIP-1108.
+const EC_ADD_GAS: u64 = 150;
+const EC_MUL_GAS: u64 = 6000;
+const PAIRING_BASE_GAS: u64 = 45000;
+const PAIRING_PER_PAIR_GAS: u64 = 34000;
+// Size of the input of a pairing check for each (G1, G2) pair.
+const PAIRING_PAIR_BYTES: u64 = 192;
+// Gas prices of the keccak256 opcode.
+const KECCAK_BASE_GAS: u64 = 30;
+const KECCAK_WORD_GAS: u64 = 6;
+// Intrinsic gas of a transaction, and of its calldata as per EIP-2028.
+const TX_BASE_GAS: u64 = 21000;
+const CALLDATA_ZERO_BYTE_GAS: u64 = 4;
+const CALLDATA_NON_ZERO_BYTE_GAS: u64 = 16;
+
+/// The EVM verifier of a circuit, i.e. its YUL source and the deployment bytecode compiled from
+/// it (using `solc` in PATH).
+#[derive(Clone, Debug)]
+pub struct EvmVerifier {
+    pub yul_code: String,
+    pub deployment_code: Vec<u8>,
+}
+
+impl EvmVerifier {
+    pub fn new<C: CircuitExt<Fr>>(
+        params: &ParamsKZG<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+        num_instance: Vec<usize>,
+    ) -> Self {
+        // The YUL source is only dumped to a file by the sdk.
+        let yul_file = tempfile::Builder::new()
+            .prefix("evm_verifier")
+            .suffix(".yul")
+            .tempfile()
+            .unwrap();
+        let deployment_code = snark_verifier_sdk::gen_evm_verifier::<C, Kzg<Bn256, Bdfg21>>(
+            params,
+            vk,
+            num_instance,
+            Some(yul_file.path()),
+        );
+        let yul_code = fs::read_to_string(yul_file.path()).unwrap();
+
+        Self {
+            yul_code,
+            deployment_code,
+        }
+    }
+
+    /// Dump the YUL source and the deployment bytecode to output_dir.
+    pub fn dump(&self, output_dir: &str) {
+        let mut dir = PathBuf::from_str(output_dir).unwrap();
+        write_file(&mut dir, "evm_verifier.yul", self.yul_code.as_bytes());
+        write_file(&mut dir, "evm_verifier.bin", &self.deployment_code);
+    }
+
+    /// Verify the proof by the verifier deployed in a local EVM, and report the gas used.
+    pub fn gas_report(&self, proof: &Proof) -> EvmGasReport {
+        let calldata = encode_calldata(&proof.instances(), proof.proof());
+
+        let (verified, total) = {
+            let mut evm = ExecutorBuilder::default()
+                .with_gas_limit(u64::MAX.into())
+                .build();
+            let caller = Address::from_low_u64_be(0xfe);
+            match evm
+                .deploy(caller, self.deployment_code.clone().into(), 0.into())
+                .address
+            {
+                Some(verifier) => {
+                    let result = evm.call_raw(caller, verifier, calldata.clone().into(), 0.into());
+                    (!result.reverted, result.gas_used)
+                }
+                None => {
+                    log::error!("failed to deploy the EVM verifier");
+                    (false, 0)
+                }
+            }
+        };
+
+        let intrinsic = TX_BASE_GAS
+            + calldata
+                .iter()
+                .map(|&byte| {
+                    if byte == 0 {
+                        CALLDATA_ZERO_BYTE_GAS
+                    } else {
+                        CALLDATA_NON_ZERO_BYTE_GAS
+                    }
+                })
+                .sum::<u64>();
+
+        // The verifier is straight-line code, so that every precompile call and keccak256 in its
+        // YUL source is executed exactly once.
+        let num_ec_add = yul_calls(&self.yul_code, "staticcall(gas(), 0x06,").len() as u64;
+        let num_ec_mul = yul_calls(&self.yul_code, "staticcall(gas(), 0x07,").len() as u64;
+        let pairings = yul_calls(&self.yul_code, "staticcall(gas(), 0x08,")
+            .into_iter()
+            .map(|args| {
+                yul_literal(&args[3]).unwrap_or(2 * PAIRING_PAIR_BYTES) / PAIRING_PAIR_BYTES
+            })
+            .collect::<Vec<_>>();
+        let keccaks = yul_calls(&self.yul_code, "keccak256(")
+            .into_iter()
+            .map(|args| yul_literal(&args[1]).unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        let msm = num_ec_add * EC_ADD_GAS + num_ec_mul * EC_MUL_GAS;
+        let pairing = pairings
+            .iter()
+            .map(|num_pairs| PAIRING_BASE_GAS + num_pairs * PAIRING_PER_PAIR_GAS)
+            .sum();
+        let transcript = keccaks
+            .iter()
+            .map(|len| KECCAK_BASE_GAS + len.div_ceil(32) * KECCAK_WORD_GAS)
+            .sum();
+
+        EvmGasReport {
+            verified,
+            deployment_code_size: self.deployment_code.len(),
+            calldata_size: calldata.len(),
+            total,
+            intrinsic,
+            pairing,
+            msm,
+            transcript,
+            other: total.saturating_sub(intrinsic + pairing + msm + transcript),
+            num_ec_add,
+            num_ec_mul,
+            num_pairings: pairings.iter().sum(),
+            num_keccaks: keccaks.len() as u64,
+        }
+    }
+}
+
+/// Gas used to verify a proof by an EVM verifier, broken down by the parts of the verification.
+///
+/// The total is measured by executing the verifier, while the breakdown is derived from the
+/// precompile calls and keccak256 in the verifier's YUL source, priced as per EIP-1108.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct EvmGasReport {
+    /// Whether the proof is verified.
+    pub verified: bool,
+    pub deployment_code_size: usize,
+    pub calldata_size: usize,
+    /// Gas used by the verification transaction.
+    pub total: u64,
+    /// Transaction base gas and calldata gas.
+    pub intrinsic: u64,
+    /// Gas of the final pairing check(s).
+    pub pairing: u64,
+    /// Gas of the ecAdd and ecMul precompile calls, i.e. the multi-scalar multiplications.
+    pub msm: u64,
+    /// Gas of the keccak256 that the Fiat-Shamir transcript squeezes challenges with.
+    pub transcript: u64,
+    /// The rest, i.e. field arithmetic, inversions (modexp precompile) and memory.
+    pub other: u64,
+    pub num_ec_add: u64,
+    pub num_ec_mul: u64,
+    pub num_pairings: u64,
+    pub num_keccaks: u64,
+}
+
+/// The arguments of each call in the YUL source that starts with the pattern, which ends with the
+/// call's opening parenthesis or first comma.
+fn yul_calls(yul_code: &str, pattern: &str) -> Vec<Vec<String>> {
+    let name_len = pattern.find('(').expect("pattern of a call") + 1;
+    yul_code
+        .match_indices(pattern)
+        .map(|(start, _)| {
+            let mut args = vec![String::new()];
+            let mut depth = 0;
+            for c in yul_code[start + name_len..].chars() {
+                match c {
+                    '(' => depth += 1,
+                    ')' if depth == 0 => break,
+                    ')' => depth -= 1,
+                    ',' if depth == 0 => {
+                        args.push(String::new());
+                        continue;
+                    }
+                    _ => {}
+                }
+                args.last_mut().unwrap().push(c);
+            }
+            args.iter().map(|arg| arg.trim().to_string()).collect()
+        })
+        .collect()
+}
+
+/// Parse a decimal or hex YUL literal.
+fn yul_literal(literal: &str) -> Option<u64> {
+    match literal.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => literal.parse().ok(),
+    }
+}
 
 /// Dump YUL and binary bytecode(use `solc` in PATH) to output_dir.
 /// Panic if error encountered.
@@ -16,26 +218,130 @@ pub fn gen_evm_verifier<C: CircuitExt<Fr>>(
     evm_proof: &EvmProof,
     output_dir: Option<&str>,
 ) {
-    let yul_file_path = output_dir.map(|dir| {
-        let mut path = PathBuf::from_str(dir).unwrap();
-        path.push("evm_verifier.yul");
-        path
-    });
-
-    // Generate deployment code and dump YUL file.
-    let deployment_code = snark_verifier_sdk::gen_evm_verifier::<C, Kzg<Bn256, Bdfg21>>(
-        params,
-        vk,
-        evm_proof.num_instance.clone(),
-        yul_file_path.as_deref(),
-    );
+    let verifier = EvmVerifier::new::<C>(params, vk, evm_proof.num_instance.clone());
 
     if let Some(dir) = output_dir {
-        // Dump bytecode.
-        let mut dir = PathBuf::from_str(dir).unwrap();
-        write_file(&mut dir, "evm_verifier.bin", &deployment_code);
+        verifier.dump(dir);
     }
 
-    let success = evm_proof.proof.evm_verify(deployment_code);
+    let success = evm_proof.proof.evm_verify(verifier.deployment_code);
     assert!(success);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+        poly::commitment::ParamsProver,
+    };
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+    use snark_verifier_sdk::{gen_evm_proof_shplonk, gen_pk};
+
+    /// A circuit that copies a witness to its only instance.
+    #[derive(Clone, Default)]
+    struct CopyCircuit(Fr);
+
+    impl Circuit<Fr> for CopyCircuit {
+        type Config = (Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            (advice, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, instance): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let cell = layouter.assign_region(
+                || "witness",
+                |mut region| region.assign_advice(|| "x", advice, 0, || Value::known(self.0)),
+            )?;
+            layouter.constrain_instance(cell.cell(), instance, 0)
+        }
+    }
+
+    impl CircuitExt<Fr> for CopyCircuit {
+        fn num_instance(&self) -> Vec<usize> {
+            vec![1]
+        }
+
+        fn instances(&self) -> Vec<Vec<Fr>> {
+            vec![vec![self.0]]
+        }
+    }
+
+    #[test]
+    #[ignore = "requires solc in PATH"]
+    fn gas_report_of_generated_verifier() {
+        let mut rng = XorShiftRng::seed_from_u64(0x1234);
+        let params = ParamsKZG::<Bn256>::setup(8, &mut rng);
+        let circuit = CopyCircuit(Fr::from(42));
+        let instances = circuit.instances();
+        let pk = gen_pk(&params, &circuit, None);
+        let proof =
+            gen_evm_proof_shplonk(&params, &pk, circuit.clone(), instances.clone(), &mut rng);
+        let proof = Proof::new(proof, &instances, None);
+
+        let verifier =
+            EvmVerifier::new::<CopyCircuit>(&params, pk.get_vk(), circuit.num_instance());
+        let report = verifier.gas_report(&proof);
+        assert!(report.verified);
+
+        // The breakdown relies on the generated verifier being straight-line code, and on its
+        // precompile calls being written as matched by gas_report.
+        assert!(!verifier.yul_code.contains("for {"));
+        let num_precompile_calls = ["0x05", "0x06", "0x07", "0x08"]
+            .map(|address| {
+                yul_calls(&verifier.yul_code, &format!("staticcall(gas(), {address},")).len()
+            })
+            .iter()
+            .sum::<usize>();
+        assert_eq!(
+            num_precompile_calls,
+            verifier.yul_code.matches("staticcall(").count()
+        );
+
+        // The KZG decider checks a single pairing of 2 pairs.
+        assert_eq!(report.num_pairings, 2);
+        assert_eq!(report.pairing, PAIRING_BASE_GAS + 2 * PAIRING_PER_PAIR_GAS);
+        assert!(report.num_ec_mul > 0);
+        assert!(report.num_keccaks > 0);
+        assert!(report.intrinsic + report.pairing + report.msm + report.transcript < report.total);
+    }
+
+    #[test]
+    fn yul_calls_args() {
+        let yul_code = "
+            success := and(eq(staticcall(gas(), 0x06, 0x80, 0x80, 0x80, 0x40), 1), success)
+            success := and(eq(staticcall(gas(), 0x08, 0x100, 0x180, 0x00, 0x20), 1), success)
+            mstore(0x20, keccak256(0x40, 160))
+            mstore(0x20, keccak256(add(0x40, 0x20), 0x40))
+        ";
+        let ec_adds = yul_calls(yul_code, "staticcall(gas(), 0x06,");
+        assert_eq!(
+            ec_adds,
+            vec![vec!["gas()", "0x06", "0x80", "0x80", "0x80", "0x40"]]
+        );
+        let pairings = yul_calls(yul_code, "staticcall(gas(), 0x08,");
+        assert_eq!(yul_literal(&pairings[0][3]), Some(2 * PAIRING_PAIR_BYTES));
+        let keccaks = yul_calls(yul_code, "keccak256(")
+            .into_iter()
+            .map(|args| yul_literal(&args[1]))
+            .collect::<Vec<_>>();
+        assert_eq!(keccaks, vec![Some(160), Some(0x40)]);
+        assert!(yul_calls(yul_code, "staticcall(gas(), 0x07,").is_empty());
+    }
+}
@@ -18,7 +18,7 @@ mod chunk;
 mod evm;
 
 pub use batch::BatchProof;
-pub use bundle::BundleProof;
+pub use bundle::{BundleProof, BundleVerifierVersion};
 pub use chunk::{compare_chunk_info, ChunkProof};
 pub use evm::EvmProof;
 
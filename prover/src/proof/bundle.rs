@@ -1,37 +1,95 @@
 use super::{dump_as_json, dump_data, dump_vk, serialize_instance};
 use crate::{utils::short_git_version, Proof};
+use aggregator::MAX_CHUNK_VKS;
 use anyhow::Result;
 use serde_derive::{Deserialize, Serialize};
 
 // 3 limbs per field element, 4 field elements
 const ACC_LEN: usize = 12;
 
-// - Accmulator (4*LIMBS)
-// - PREPROCESS_DIGEST, ROUND
-// - (hi, lo) finalised state root
-// - (hi, lo) finalised batch hash
-// - (hi, lo) pending state root
-// - (hi, lo) pending batch hash
-// - chain id
-// - (hi, lo) pending withdraw root
-// - bundle count
+const ACC_BYTES: usize = ACC_LEN * 32;
 
-const PI_LEN: usize = 13;
+/// The version of the L1 bundle verifier, which fixes the public inputs, i.e. the layout of the
+/// calldata given to the verifier after the accumulator.
+///
+/// Only the chunk vk indices of the pending batch are exposed from V1: the recursion circuit
+/// enforces the same whitelist across the bundle, so the chunks of every batch were verified
+/// against one of the exposed digests, and the indices of the earlier batches are public in their
+/// batch proofs. The recursion circuit also chains the l1 msg hashes of consecutive batches, and
+/// exposes the one before the first batch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BundleVerifierVersion {
+    /// 13 public inputs:
+    /// - PREPROCESS_DIGEST
+    /// - (hi, lo) finalised state root
+    /// - (hi, lo) finalised batch hash
+    /// - (hi, lo) pending state root
+    /// - (hi, lo) pending batch hash
+    /// - chain id
+    /// - (hi, lo) pending withdraw root
+    /// - round, i.e. the number of batches in the bundle - 1
+    #[default]
+    V0,
+    /// 18 + MAX_CHUNK_VKS public inputs:
+    /// - PREPROCESS_DIGEST
+    /// - (hi, lo) finalised state root
+    /// - (hi, lo) finalised batch hash
+    /// - (hi, lo) pending state root
+    /// - (hi, lo) pending batch hash
+    /// - chain id
+    /// - (hi, lo) pending withdraw root
+    /// - chunk vk indices of the pending batch
+    /// - chunk vk digests (MAX_CHUNK_VKS), the whitelist every batch of the bundle is verified
+    ///   against
+    /// - (hi, lo) finalised l1 msg hash, i.e. the rolling hash of the L1 messages before the bundle
+    /// - (hi, lo) pending l1 msg hash
+    /// - round, i.e. the number of batches in the bundle - 1
+    V1,
+}
 
-const ACC_BYTES: usize = ACC_LEN * 32;
-const PI_BYTES: usize = PI_LEN * 32;
+impl BundleVerifierVersion {
+    /// The version of the verifier generated from the current bundle circuit.
+    pub const CURRENT: Self = Self::V1;
+
+    /// The number of public inputs, excluding the accumulator.
+    pub const fn pi_len(&self) -> usize {
+        match self {
+            Self::V0 => 13,
+            Self::V1 => 18 + MAX_CHUNK_VKS,
+        }
+    }
+
+    const fn pi_bytes(&self) -> usize {
+        self.pi_len() * 32
+    }
+
+    fn from_pi_len(pi_len: usize) -> Option<Self> {
+        [Self::V0, Self::V1]
+            .into_iter()
+            .find(|version| version.pi_len() == pi_len)
+    }
+}
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct BundleProof {
     #[serde(flatten)]
     on_chain_proof: Proof,
+    /// Bundle proofs serialized before the verifier was versioned are of V0.
+    #[serde(default)]
+    verifier_version: BundleVerifierVersion,
 }
 
 impl From<Proof> for BundleProof {
     fn from(proof: Proof) -> Self {
         let instances = proof.instances();
         assert_eq!(instances.len(), 1);
-        assert_eq!(instances[0].len(), ACC_LEN + PI_LEN);
+        let verifier_version = BundleVerifierVersion::from_pi_len(instances[0].len() - ACC_LEN)
+            .unwrap_or_else(|| {
+                panic!(
+                    "no bundle verifier version with {} public inputs",
+                    instances[0].len()
+                )
+            });
 
         let vk = proof.vk;
         let git_version = proof.git_version;
@@ -52,13 +110,21 @@ impl From<Proof> for BundleProof {
                 vk,
                 git_version,
             },
+            verifier_version,
         }
     }
 }
 
 impl BundleProof {
+    /// Returns the version of the L1 verifier that the calldata is laid out for.
+    pub fn verifier_version(&self) -> BundleVerifierVersion {
+        self.verifier_version
+    }
+
     /// Returns the calldata given to YUL verifier.
-    /// Format: Accumulator(12x32bytes) || PI(13x32bytes) || Proof
+    /// Format: Accumulator(12x32bytes) || PI(Nx32bytes) || Proof, where the public inputs are laid
+    /// out as per [`Self::verifier_version`], e.g. N = 22 for
+    /// [`BundleVerifierVersion::V1`].
     pub fn calldata(self) -> Vec<u8> {
         let proof = self.proof_to_verify();
 
@@ -69,6 +135,13 @@ impl BundleProof {
         calldata
     }
 
+    /// Returns the number of batches proven by the bundle proof.
+    pub fn num_batches(&self) -> u64 {
+        let pi_bytes = self.verifier_version.pi_bytes();
+        let round = &self.on_chain_proof.instances[pi_bytes - 32..];
+        u64::from_be_bytes(round[24..].try_into().unwrap()) + 1
+    }
+
     pub fn dump(&self, dir: &str, name: &str) -> Result<()> {
         let filename = format!("bundle_{name}");
 
@@ -94,7 +167,10 @@ impl BundleProof {
         // raw.proof is accumulator + proof
         assert!(self.on_chain_proof.proof.len() > ACC_BYTES);
         // raw.instances is PI
-        assert_eq!(self.on_chain_proof.instances.len(), PI_BYTES);
+        assert_eq!(
+            self.on_chain_proof.instances.len(),
+            self.verifier_version.pi_bytes()
+        );
 
         // instances = raw_proof[..12] (acc) + raw_instances (pi_data)
         // proof = raw_proof[12..]
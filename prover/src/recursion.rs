@@ -1,6 +1,6 @@
 use halo2_proofs::halo2curves::bn256::Fr;
 
-use aggregator::{BatchCircuit, StateTransition};
+use aggregator::{BatchCircuit, StateTransition, MAX_CHUNK_VKS};
 use snark_verifier_sdk::Snark;
 
 /// 4 fields for 2 hashes (Hi, Lo)
@@ -9,7 +9,17 @@ const ST_INSTANCE: usize = 4;
 /// Additional public inputs, specifically:
 /// - withdraw root (hi, lo)
 /// - chain ID
-const ADD_INSTANCE: usize = 3;
+/// - chunk vk indices
+/// - chunk vk digests
+/// - prev l1 msg hash (hi, lo)
+/// - post l1 msg hash (hi, lo)
+const ADD_INSTANCE: usize = 8 + MAX_CHUNK_VKS;
+
+/// The offset among the additional public inputs of the chunk vk digests.
+const CHUNK_VK_DIGESTS_OFFSET: usize = 4;
+
+/// The offset among the additional public inputs of the l1 msg hashes.
+const L1_MSG_HASHES_OFFSET: usize = CHUNK_VK_DIGESTS_OFFSET + MAX_CHUNK_VKS;
 
 /// Number of public inputs to describe the state.
 const NUM_INSTANCES: usize = ST_INSTANCE + ADD_INSTANCE;
@@ -67,4 +77,24 @@ impl<'a, const N_SNARK: usize> StateTransition for RecursionTask<'a, N_SNARK> {
     fn num_additional_instance() -> usize {
         ADD_INSTANCE
     }
+
+    /// The chunk vk whitelist, i.e. the chunk vk digests, is the same for all the batches in a
+    /// bundle.
+    fn invariant_indices() -> Vec<usize> {
+        Self::additional_indices()[CHUNK_VK_DIGESTS_OFFSET..CHUNK_VK_DIGESTS_OFFSET + MAX_CHUNK_VKS]
+            .to_vec()
+    }
+
+    /// The rolling hash of the L1 messages before a batch is the one after the previous batch.
+    fn chained_indices() -> Vec<(usize, usize)> {
+        let additional_indices = Self::additional_indices();
+        let prev_l1_msg_hash = &additional_indices[L1_MSG_HASHES_OFFSET..L1_MSG_HASHES_OFFSET + 2];
+        let post_l1_msg_hash =
+            &additional_indices[L1_MSG_HASHES_OFFSET + 2..L1_MSG_HASHES_OFFSET + 4];
+        prev_l1_msg_hash
+            .iter()
+            .copied()
+            .zip(post_l1_msg_hash.iter().copied())
+            .collect()
+    }
 }
@@ -1,7 +1,9 @@
 mod batch;
 mod chunk;
 mod inner;
+mod minimize;
 
 pub use batch::{batch_prove, bundle_prove};
 pub use chunk::chunk_prove;
 pub use inner::inner_prove;
+pub use minimize::{minimize_block_trace, mock_prove_failure, write_trace_fixture};
@@ -0,0 +1,222 @@
+//! Delta debugging of failing block traces.
+//!
+//! Given a trace on which the prover fails and a predicate recognizing the
+//! failure, [`minimize_block_trace`] greedily removes transactions, storage
+//! proofs and bytecodes for as long as the predicate keeps holding. The result
+//! is usually small enough to be checked in as a regression fixture with
+//! [`write_trace_fixture`].
+
+use crate::{
+    inner::Prover,
+    zkevm::circuit::{block_traces_to_witness_block, SuperCircuit},
+};
+use anyhow::Result;
+use eth_types::l2_types::BlockTrace;
+use std::{
+    fs::File,
+    io::BufWriter,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+/// Shrink `trace` to a trace on which `still_fails` holds, by removing
+/// transactions (together with their execution results and storage traces),
+/// account and storage proofs, deletion proofs and bytecodes.
+///
+/// `still_fails` must hold on `trace` itself. It should recognize one specific
+/// failure, as removing parts of a trace easily introduces unrelated ones.
+pub fn minimize_block_trace<F>(trace: BlockTrace, mut still_fails: F) -> BlockTrace
+where
+    F: FnMut(&BlockTrace) -> bool,
+{
+    assert!(still_fails(&trace), "the predicate must hold on the input");
+    let mut trace = trace;
+
+    let num_txs = trace.transactions.len();
+    let txs = ddmin((0..num_txs).collect(), |txs| {
+        still_fails(&retain_txs(&trace, txs))
+    });
+    trace = retain_txs(&trace, &txs);
+    log::info!("kept {} of {num_txs} txs", txs.len());
+
+    if let Some(proofs) = trace.storage_trace.proofs.clone() {
+        let num_proofs = proofs.len();
+        let proofs = ddmin(proofs.into_iter().collect(), |proofs| {
+            let mut candidate = trace.clone();
+            candidate.storage_trace.proofs = Some(proofs.iter().cloned().collect());
+            still_fails(&candidate)
+        });
+        log::info!("kept {} of {num_proofs} account proofs", proofs.len());
+        trace.storage_trace.proofs = Some(proofs.into_iter().collect());
+    }
+
+    let storage_proofs = trace
+        .storage_trace
+        .storage_proofs
+        .iter()
+        .flat_map(|(address, proofs)| {
+            proofs
+                .iter()
+                .map(move |(key, proof)| (*address, *key, proof.clone()))
+        })
+        .collect::<Vec<_>>();
+    let num_storage_proofs = storage_proofs.len();
+    let storage_proofs = ddmin(storage_proofs, |storage_proofs| {
+        let mut candidate = trace.clone();
+        candidate.storage_trace.storage_proofs = Default::default();
+        for (address, key, proof) in storage_proofs {
+            candidate
+                .storage_trace
+                .storage_proofs
+                .entry(*address)
+                .or_default()
+                .insert(*key, proof.clone());
+        }
+        still_fails(&candidate)
+    });
+    log::info!(
+        "kept {} of {num_storage_proofs} storage proofs",
+        storage_proofs.len()
+    );
+    trace.storage_trace.storage_proofs = Default::default();
+    for (address, key, proof) in storage_proofs {
+        trace
+            .storage_trace
+            .storage_proofs
+            .entry(address)
+            .or_default()
+            .insert(key, proof);
+    }
+
+    let num_deletion_proofs = trace.storage_trace.deletion_proofs.len();
+    trace.storage_trace.deletion_proofs = ddmin(
+        trace.storage_trace.deletion_proofs.clone(),
+        |deletion_proofs| {
+            let mut candidate = trace.clone();
+            candidate.storage_trace.deletion_proofs = deletion_proofs.to_vec();
+            still_fails(&candidate)
+        },
+    );
+    log::info!(
+        "kept {} of {num_deletion_proofs} deletion proofs",
+        trace.storage_trace.deletion_proofs.len()
+    );
+
+    let num_codes = trace.codes.len();
+    trace.codes = ddmin(trace.codes.clone(), |codes| {
+        let mut candidate = trace.clone();
+        candidate.codes = codes.to_vec();
+        still_fails(&candidate)
+    });
+    log::info!("kept {} of {num_codes} codes", trace.codes.len());
+
+    trace
+}
+
+/// Describe why building the witness of `trace` or mock proving the super
+/// circuit on it fails, or return `None` if it passes. Panics during witness
+/// generation are reported as failures too.
+///
+/// This is meant to build predicates for [`minimize_block_trace`], e.g.
+/// `|trace| mock_prove_failure(trace).is_some_and(|e| e.contains("..."))`.
+pub fn mock_prove_failure(trace: &BlockTrace) -> Option<String> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let witness_block = block_traces_to_witness_block(vec![trace.clone()])?;
+        Prover::<SuperCircuit>::mock_prove_witness_block(&witness_block)
+    }));
+    match result {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(format!("{e:#}")),
+        Err(panic) => Some(
+            panic
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_else(|| "panic".to_string()),
+        ),
+    }
+}
+
+/// Write `trace` as pretty printed JSON, readable by
+/// [`get_block_trace_from_file`](crate::utils::get_block_trace_from_file).
+pub fn write_trace_fixture<P: AsRef<Path>>(trace: &BlockTrace, path: P) -> Result<()> {
+    let f = File::create(path)?;
+    serde_json::to_writer_pretty(BufWriter::new(f), trace)?;
+    Ok(())
+}
+
+/// Keep the transactions at the (sorted) indices `txs`.
+fn retain_txs(trace: &BlockTrace, txs: &[usize]) -> BlockTrace {
+    let pick = |len: usize| txs.iter().copied().filter(move |idx| *idx < len);
+    let mut candidate = trace.clone();
+    candidate.transactions = pick(trace.transactions.len())
+        .map(|idx| trace.transactions[idx].clone())
+        .collect();
+    candidate.execution_results = pick(trace.execution_results.len())
+        .map(|idx| trace.execution_results[idx].clone())
+        .collect();
+    candidate.tx_storage_trace = pick(trace.tx_storage_trace.len())
+        .map(|idx| trace.tx_storage_trace[idx].clone())
+        .collect();
+    candidate
+}
+
+/// Zeller's ddmin, restricted to removals: returns a subsequence of `items`
+/// on which `test` holds and from which no single chunk of the final
+/// granularity can be removed.
+fn ddmin<T: Clone>(mut items: Vec<T>, mut test: impl FnMut(&[T]) -> bool) -> Vec<T> {
+    if test(&[]) {
+        return vec![];
+    }
+    let mut num_chunks = 2;
+    while items.len() > 1 {
+        let chunk_size = (items.len() + num_chunks - 1) / num_chunks;
+        let mut reduced = false;
+        let mut start = 0;
+        while start < items.len() {
+            let end = (start + chunk_size).min(items.len());
+            let complement = items[..start]
+                .iter()
+                .chain(&items[end..])
+                .cloned()
+                .collect::<Vec<_>>();
+            if test(&complement) {
+                items = complement;
+                reduced = true;
+                // retry the same position, which now holds the next chunk
+            } else {
+                start = end;
+            }
+        }
+        if reduced {
+            num_chunks = (num_chunks - 1).max(2);
+        } else if chunk_size == 1 {
+            break;
+        } else {
+            num_chunks = (num_chunks * 2).min(items.len());
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ddmin;
+
+    #[test]
+    fn ddmin_finds_failure_inducing_items() {
+        let mut num_tests = 0;
+        let items = ddmin((0..100).collect(), |items| {
+            num_tests += 1;
+            items.contains(&17) && items.contains(&58)
+        });
+        assert_eq!(items, vec![17, 58]);
+        assert!(num_tests < 100);
+
+        let items = ddmin((0..10).collect(), |_| true);
+        assert!(items.is_empty());
+
+        let items = ddmin((0..10).collect(), |items| items.len() == 10);
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+    }
+}
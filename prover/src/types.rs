@@ -1,5 +1,5 @@
 use aggregator::{BatchHeader, ChunkInfo, MAX_AGG_SNARKS};
-use eth_types::l2_types::BlockTrace;
+use eth_types::{l2_types::BlockTrace, H256};
 use serde::{Deserialize, Serialize};
 use zkevm_circuits::evm_circuit::witness::Block;
 
@@ -18,13 +18,16 @@ pub struct ChunkProvingTask {
     /// Prover can check `chunk_info` is consistent with block traces
     pub chunk_info: Option<ChunkInfo>,
     pub block_traces: Vec<BlockTrace>,
+    /// Rolling hash of the L1 messages popped before this chunk
+    pub prev_l1_msg_hash: H256,
 }
 
 impl ChunkProvingTask {
-    pub fn from(block_traces: Vec<BlockTrace>) -> Self {
+    pub fn from(block_traces: Vec<BlockTrace>, prev_l1_msg_hash: H256) -> Self {
         Self {
             block_traces,
             chunk_info: None,
+            prev_l1_msg_hash,
         }
     }
     pub fn is_empty(&self) -> bool {
@@ -1,9 +1,12 @@
 #![allow(deprecated)]
 use crate::{
     types::BlockTraceJsonRpcResult,
-    zkevm::circuit::{block_traces_to_witness_block, print_chunk_stats},
+    zkevm::circuit::{
+        block_traces_to_witness_block, finalize_builder, print_chunk_stats, validite_block_traces,
+    },
 };
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
+use bus_mapping::circuit_input_builder::L2TraceStream;
 use chrono::Utc;
 use eth_types::l2_types::BlockTrace;
 use git_version::git_version;
@@ -26,7 +29,9 @@ use std::{
     str::FromStr,
     sync::Once,
 };
-use zkevm_circuits::evm_circuit::witness::Block;
+use zkevm_circuits::{
+    evm_circuit::witness::Block, super_circuit::params::get_super_circuit_params,
+};
 
 pub static LOGGER: Once = Once::new();
 
@@ -166,6 +171,36 @@ pub fn chunk_trace_to_witness_block(chunk_trace: Vec<BlockTrace>) -> Result<Bloc
     block_traces_to_witness_block(chunk_trace)
 }
 
+/// Build the witness block of a chunk from block trace files. The execution
+/// results of each trace are handed to the circuit input builder one tx at a
+/// time, so peak memory is bounded by the largest tx instead of the chunk.
+pub fn chunk_trace_files_to_witness_block<P: AsRef<Path>>(paths: &[P]) -> Result<Block> {
+    if paths.is_empty() {
+        bail!("Empty chunk trace");
+    }
+    let mut stream = L2TraceStream::new(get_super_circuit_params(), false);
+    // block traces without their execution results
+    let mut block_traces = Vec::with_capacity(paths.len());
+    for path in paths {
+        let f = File::open(path)?;
+        let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(f));
+        deserializer.disable_recursion_limit();
+        let deserializer = serde_stacker::Deserializer::new(&mut deserializer);
+        let block_trace = stream
+            .add_block_trace(deserializer)
+            .map_err(|e| anyhow!("unable to stream BlockTrace from {:?}: {e}", path.as_ref()))?;
+        block_traces.push(block_trace);
+    }
+    print_chunk_stats(&block_traces);
+    validite_block_traces(&block_traces)?;
+
+    let mut builder = stream.finish()?;
+    let witness_block = finalize_builder(&mut builder)?;
+    // send to other thread to drop
+    std::thread::spawn(move || drop(builder.block));
+    Ok(witness_block)
+}
+
 // Return the output dir.
 pub fn init_env_and_log(id: &str) -> String {
     dotenvy::dotenv().ok();
@@ -235,3 +270,35 @@ pub fn short_git_version() -> String {
         commit_version[1..8].to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE_PATH: &str = "../eth-types/src/testdata/trace_v1_5224657.json";
+
+    #[test]
+    fn streamed_witness_block_matches_full_trace() {
+        let expected = chunk_trace_to_witness_block(vec![get_block_trace_from_file(TRACE_PATH)])
+            .expect("witness block from the full trace");
+        let streamed = chunk_trace_files_to_witness_block(&[TRACE_PATH])
+            .expect("witness block from the streamed trace");
+
+        assert_eq!(streamed.chain_id, expected.chain_id);
+        assert_eq!(streamed.start_l1_queue_index, expected.start_l1_queue_index);
+        assert_eq!(streamed.withdraw_root, expected.withdraw_root);
+        assert_eq!(streamed.prev_state_root, expected.prev_state_root);
+        assert_eq!(streamed.post_state_root(), expected.post_state_root());
+        assert_eq!(streamed.txs, expected.txs);
+        assert_eq!(
+            streamed.rws.table_assignments(),
+            expected.rws.table_assignments()
+        );
+        assert_eq!(
+            streamed.bytecodes.keys().collect::<Vec<_>>(),
+            expected.bytecodes.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(streamed.copy_events.len(), expected.copy_events.len());
+        assert_eq!(streamed.sha3_inputs, expected.sha3_inputs);
+    }
+}
@@ -79,7 +79,8 @@ impl Prover {
         {
             Some(proof) => Ok(proof),
             None => {
-                let witness_block = chunk_trace_to_witness_block(chunk.block_traces)?;
+                let mut witness_block = chunk_trace_to_witness_block(chunk.block_traces)?;
+                witness_block.prev_l1_msg_hash = chunk.prev_l1_msg_hash;
                 let row_usage = calculate_row_usage_of_witness_block(&witness_block)?;
                 log::info!("Got witness block");
 
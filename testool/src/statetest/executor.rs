@@ -1,5 +1,8 @@
 use super::{AccountMatch, StateTest, StateTestResult};
-use crate::{config::TestSuite, utils::ETH_CHAIN_ID};
+use crate::{
+    config::TestSuite,
+    utils::{ETH_CHAIN_ID, TEST_FORK},
+};
 use bus_mapping::circuit_input_builder::{CircuitInputBuilder, CircuitsParams, PrecompileEcParams};
 use eth_types::{
     geth_types, state_db::CodeDB, Address, Bytes, GethExecTrace, ToBigEndian, ToWord, H256, U256,
@@ -215,6 +218,7 @@ fn into_traceconfig(st: StateTest) -> (String, TraceConfig, StateTestResult) {
                     || bus_mapping::util::GETH_TRACE_CHECK_LEVEL.should_check()),
                 ..Default::default()
             },
+            chain_config: Some(TEST_FORK.chain_config()),
             ..Default::default()
         },
         st.result,
@@ -643,7 +647,9 @@ pub fn run_test(
         #[cfg(feature = "chunk-prove")]
         {
             eth_types::constants::set_env_coinbase(&st.env.current_coinbase);
-            prover::test::chunk_prove(&test_id, prover::ChunkProvingTask::from(vec![scroll_trace]));
+            // a state test is a chunk of its own, with no L1 messages popped before it
+            let chunk = prover::ChunkProvingTask::from(vec![scroll_trace], H256::zero());
+            prover::test::chunk_prove(&test_id, chunk);
         }
 
         #[cfg(not(any(feature = "inner-prove", feature = "chunk-prove")))]
@@ -2,6 +2,7 @@ use std::str::FromStr;
 
 use anyhow::{bail, Result};
 use eth_types::{bytecode::OpcodeWithData, Bytecode, GethExecTrace};
+use external_tracer::ChainConfig;
 use log::{error, info};
 use prettytable::Table;
 use std::process::{Command, Stdio};
@@ -64,6 +65,21 @@ impl FromStr for MainnetFork {
 }
 
 impl MainnetFork {
+    /// The chain config tracing under this fork, disabling the forks after it that the tracer
+    /// knows about. The forks before shanghai are always active in the tracer, and the l2geth
+    /// activates shanghai by block and doesn't know cancun.
+    pub fn chain_config(&self) -> ChainConfig {
+        let disabled = |fork: MainnetFork| (*self < fork).then_some(u64::MAX);
+        let mut chain_config = ChainConfig::default();
+        if cfg!(feature = "scroll") {
+            chain_config.shanghai_block = disabled(Self::Shanghai);
+        } else {
+            chain_config.shanghai_time = disabled(Self::Shanghai);
+            chain_config.cancun_time = disabled(Self::Cancun);
+        }
+        chain_config
+    }
+
     pub fn in_network_range(expect: &[String]) -> Result<bool, anyhow::Error> {
         let in_network = if expect.is_empty() {
             true
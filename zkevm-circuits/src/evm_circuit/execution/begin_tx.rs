@@ -1470,6 +1470,59 @@ mod test {
         CircuitTestBuilder::new_from_test_ctx(ctx).run();
     }
 
+    #[cfg(feature = "scroll")]
+    #[test]
+    fn begin_tx_l1_fee_before_and_after_curie() {
+        use bus_mapping::circuit_input_builder::curie::get_curie_fork_block;
+        use eth_types::forks::SCROLL_DEVNET_CHAIN_ID;
+        use mock::test_ctx::{ChainConfig, L1FeeConfig, LoggerConfig};
+
+        let curie_block = get_curie_fork_block(SCROLL_DEVNET_CHAIN_ID);
+        // refer to test in <https://github.com/scroll-tech/go-ethereum/blob/develop/rollup/fees/rollup_fee_test.go>
+        let l1_fee_config = L1FeeConfig {
+            base_fee: Word::from(1_500_000_000u64),
+            overhead: Word::from(100),
+            scalar: Word::from(10),
+            blob_base_fee: Word::from(150_000_000u64),
+            commit_scalar: Word::from(10),
+            blob_scalar: Word::from(10),
+        };
+
+        for l1_fee in [L1FeeConfig::default(), l1_fee_config] {
+            let mut tx_l1_fees = vec![];
+            for block_number in [curie_block - 1, curie_block + 1] {
+                let ctx = TestContext::<2, 1>::new_with_chain_config(
+                    None,
+                    |accs| {
+                        accs[0].address(MOCK_ACCOUNTS[0]).balance(eth(10));
+                        accs[1].address(MOCK_ACCOUNTS[1]).balance(eth(10));
+                    },
+                    |mut txs, _accs| {
+                        txs[0]
+                            .from(MOCK_ACCOUNTS[1])
+                            .to(MOCK_ACCOUNTS[0])
+                            .gas_price(gwei(2))
+                            .input(vec![1, 2, 3, 4, 0, 0, 0, 0].into())
+                            .value(eth(1));
+                    },
+                    |block, _tx| block.chain_id(SCROLL_DEVNET_CHAIN_ID).number(block_number),
+                    LoggerConfig::default(),
+                    Some(ChainConfig {
+                        curie_block: Some(curie_block),
+                        l1_fee: Some(l1_fee.clone()),
+                        ..Default::default()
+                    }),
+                )
+                .unwrap();
+                tx_l1_fees.push(ctx.geth_traces[0].l1_fee);
+
+                CircuitTestBuilder::new_from_test_ctx(ctx).run();
+            }
+            // the fee is priced by a different formula on each side of the fork
+            assert_ne!(tx_l1_fees[0], tx_l1_fees[1]);
+        }
+    }
+
     fn begin_tx_deploy(nonce: u64) {
         let code = bytecode! {
             // [ADDRESS, STOP]
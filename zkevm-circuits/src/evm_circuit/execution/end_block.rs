@@ -146,18 +146,7 @@ impl<F: Field> ExecutionGadget<F> for EndBlockGadget<F> {
                 None,
             );
             // State changes
-            for (slot, value) in [
-                (*l1_gas_price_oracle::IS_CURIE_SLOT, eth_types::Word::from(1u64)),
-                (*l1_gas_price_oracle::L1_BLOB_BASEFEE_SLOT, eth_types::Word::from(1u64)),
-                (
-                    *l1_gas_price_oracle::COMMIT_SCALAR_SLOT,
-                    *l1_gas_price_oracle::INITIAL_COMMIT_SCALAR,
-                ),
-                (
-                    *l1_gas_price_oracle::BLOB_SCALAR_SLOT,
-                    *l1_gas_price_oracle::INITIAL_BLOB_SCALAR,
-                ),
-            ] {
+            for (slot, value) in *l1_gas_price_oracle::CURIE_STORAGE {
                 cb.account_storage_write(
                     l1_fee_address.expr(),
                     cb.word_rlc_constant(slot),
@@ -12,15 +12,76 @@ use crate::{
     util::{Expr, Field},
 };
 use bus_mapping::{
-    circuit_input_builder::{TxL1Fee, TX_L1_COMMIT_EXTRA_COST, TX_L1_FEE_PRECISION},
+    circuit_input_builder::{
+        l1_fee::{CurieL1FeeModel, L1FeeModel, PreCurieL1FeeModel},
+        TxL1Fee, TX_L1_COMMIT_EXTRA_COST, TX_L1_FEE_PRECISION,
+    },
     l2_predeployed::l1_gas_price_oracle,
 };
 use eth_types::{ToLittleEndian, U256};
-use gadgets::util::not;
+use gadgets::util::{not, sum};
 use gadgets::ToScalar;
 use halo2_proofs::plonk::{Error, Expression};
 
-/// Transaction L1 fee gadget for L1GasPriceOracle contract
+/// The L1GasPriceOracle parameters read by the [`TxL1FeeGadget`], and the values of the
+/// transaction priced by the L1 fee models.
+pub(crate) struct L1FeeParams<F> {
+    pub(crate) base_fee: Expression<F>,
+    pub(crate) fee_overhead: Expression<F>,
+    pub(crate) fee_scalar: Expression<F>,
+    pub(crate) l1_blob_basefee: Expression<F>,
+    pub(crate) commit_scalar: Expression<F>,
+    pub(crate) blob_scalar: Expression<F>,
+    pub(crate) tx_data_gas_cost: Expression<F>,
+    pub(crate) tx_signed_length: Expression<F>,
+}
+
+/// The constraints of an [`L1FeeModel`], which the [`TxL1FeeGadget`] applies when the fork of
+/// the model is active at the block, as selected by
+/// [`l1_fee_model`](bus_mapping::circuit_input_builder::l1_fee::l1_fee_model).
+pub(crate) trait L1FeeModelConstraints<F: Field>: L1FeeModel {
+    /// Name of the constraint of the formula
+    fn constraint_name(&self) -> &'static str;
+
+    /// The L1 fee scaled by `TX_L1_FEE_PRECISION`, as [`L1FeeModel::scaled_fee`] computes it.
+    fn scaled_fee_expr(&self, params: &L1FeeParams<F>) -> Expression<F>;
+}
+
+impl<F: Field> L1FeeModelConstraints<F> for PreCurieL1FeeModel {
+    fn constraint_name(&self) -> &'static str {
+        "fee_scalar * base_fee * tx_l1_gas == tx_l1_fee * 10e9 + remainder"
+    }
+
+    fn scaled_fee_expr(&self, params: &L1FeeParams<F>) -> Expression<F> {
+        let tx_l1_gas = params.tx_data_gas_cost.expr()
+            + TX_L1_COMMIT_EXTRA_COST.expr()
+            + params.fee_overhead.expr();
+        params.fee_scalar.expr() * params.base_fee.expr() * tx_l1_gas
+    }
+}
+
+impl<F: Field> L1FeeModelConstraints<F> for CurieL1FeeModel {
+    fn constraint_name(&self) -> &'static str {
+        "commitScalar * l1BaseFee + blobScalar * _data.length * l1BlobBaseFee == tx_l1_fee * 10e9 + remainder"
+    }
+
+    fn scaled_fee_expr(&self, params: &L1FeeParams<F>) -> Expression<F> {
+        params.commit_scalar.expr() * params.base_fee.expr()
+            + params.blob_scalar.expr()
+                * params.tx_signed_length.expr()
+                * params.l1_blob_basefee.expr()
+    }
+}
+
+/// Transaction L1 fee gadget for L1GasPriceOracle contract.
+///
+/// The fee parameters are read from the storage of the L1GasPriceOracle, and the fee is
+/// constrained to be the quotient by `TX_L1_FEE_PRECISION` of the formula of the
+/// [`L1FeeModel`] active at the block:
+/// - before curie, [`PreCurieL1FeeModel`]
+/// - since curie, [`CurieL1FeeModel`]
+///
+/// All values are u64, so that neither side of the equation overflows the field.
 #[derive(Clone, Debug)]
 pub(crate) struct TxL1FeeGadget<F> {
     /// Transaction L1 fee
@@ -39,11 +100,11 @@ pub(crate) struct TxL1FeeGadget<F> {
     fee_scalar_word: U64Word<F>,
     /// Current value of L1 blob base fee
     l1_blob_basefee_word: U64Word<F>,
-    /// Current value of L1 scalar fee
+    /// Current value of L1 commit scalar
     commit_scalar_word: U64Word<F>,
-    /// Current value of L1 blob scalar fee
+    /// Current value of L1 blob scalar
     blob_scalar_word: U64Word<F>,
-    /// Current value of L1 base fee
+    /// Committed value of L1 base fee
     base_fee_committed: Cell<F>,
     /// Committed value of L1 fee overhead
     fee_overhead_committed: Cell<F>,
@@ -51,9 +112,9 @@ pub(crate) struct TxL1FeeGadget<F> {
     fee_scalar_committed: Cell<F>,
     /// Committed value of L1 blob base fee
     l1_blob_basefee_committed: Cell<F>,
-    /// Committed value of L1 scalar fee
+    /// Committed value of L1 commit scalar
     commit_scalar_committed: Cell<F>,
-    /// Committed value of L1 blob scalar fee
+    /// Committed value of L1 blob scalar
     blob_scalar_committed: Cell<F>,
 }
 
@@ -262,6 +323,10 @@ impl<F: Field> TxL1FeeGadget<F> {
         let blob_scalar_word = cb.query_word_rlc();
 
         let tx_l1_fee = from_bytes::expr(&tx_l1_fee_word.cells[..N_BYTES_U64]);
+        cb.require_zero(
+            "tx_l1_fee fits in u64",
+            sum::expr(&tx_l1_fee_word.cells[N_BYTES_U64..]),
+        );
         let [remainder, base_fee, fee_overhead, fee_scalar] = [
             &remainder_word,
             &base_fee_word,
@@ -286,22 +351,29 @@ impl<F: Field> TxL1FeeGadget<F> {
 
         // For curie and non-curie, see l2geth side implementation:
         // <https://github.com/scroll-tech/go-ethereum/blob/36d7325ea1cb6749f353f84df7e9903f93aa903b/rollup/fees/rollup_fee.go#L76>
-        cb.condition(is_curie.expr(), |cb| {
-            cb.require_equal(
-                "commitScalar * l1BaseFee + blobScalar * _data.length * l1BlobBaseFee == tx_l1_fee * 10e9 + remainder",
-                commit_scalar * base_fee.clone() + blob_scalar * tx_signed_length * l1_blob_basefee,
-                tx_l1_fee.clone() * TX_L1_FEE_PRECISION.expr() + remainder.clone(),
-            );
-        });
-
-        cb.condition(not::expr(is_curie.expr()), |cb| {
-            let tx_l1_gas = tx_data_gas_cost + TX_L1_COMMIT_EXTRA_COST.expr() + fee_overhead;
-            cb.require_equal(
-                "fee_scalar * base_fee * tx_l1_gas == tx_l1_fee * 10e9 + remainder",
-                fee_scalar * base_fee * tx_l1_gas,
-                tx_l1_fee * TX_L1_FEE_PRECISION.expr() + remainder,
-            );
-        });
+        let params = L1FeeParams {
+            base_fee,
+            fee_overhead,
+            fee_scalar,
+            l1_blob_basefee,
+            commit_scalar,
+            blob_scalar,
+            tx_data_gas_cost,
+            tx_signed_length,
+        };
+        let models: [(Expression<F>, &dyn L1FeeModelConstraints<F>); 2] = [
+            (not::expr(is_curie.expr()), &PreCurieL1FeeModel),
+            (is_curie, &CurieL1FeeModel),
+        ];
+        for (is_active, model) in models {
+            cb.condition(is_active, |cb| {
+                cb.require_equal(
+                    model.constraint_name(),
+                    model.scaled_fee_expr(&params),
+                    tx_l1_fee.expr() * TX_L1_FEE_PRECISION.expr() + remainder.expr(),
+                );
+            });
+        }
 
         let base_fee_committed = cb.query_cell_phase2();
         let fee_overhead_committed = cb.query_cell_phase2();
@@ -413,6 +485,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tx_l1_fee_with_remainder_after_curie() {
+        // 10 * 1_500_000_000 + 10 * 5 * 150_000_000 = 22 * 1e9 + 500_000_000
+        for (tx_l1_fee, ok) in [(22, true), (21, false), (23, false)] {
+            let witnesses = [
+                TEST_AFTER_CURIE.into(),
+                TEST_BASE_FEE_BEFORE_CURIE.into(),
+                TEST_FEE_OVERHEAD.into(),
+                TEST_FEE_SCALAR.into(),
+                TEST_TX_DATA_GAS_COST.into(),
+                TEST_TX_L1_FEE_BEFORE_CURIE,
+                // Curie fields
+                TEST_BASE_FEE_AFTER_CURIE.into(),
+                L1_BLOB_BASEFEE.into(),
+                COMMIT_SCALAR.into(),
+                BLOB_SCALAR.into(),
+                5,
+                tx_l1_fee,
+            ]
+            .map(U256::from);
+
+            try_test!(TxL1FeeGadgetTestContainer<Fr>, witnesses, ok);
+        }
+    }
+
     #[derive(Clone)]
     struct TxL1FeeGadgetTestContainer<F> {
         is_curie: Cell<F>,
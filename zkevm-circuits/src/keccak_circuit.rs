@@ -14,6 +14,7 @@ mod test;
 
 #[cfg(any(feature = "test", test, feature = "test-circuits"))]
 pub use dev::KeccakCircuit as TestKeccakCircuit;
+pub use param::DEFAULT_KECCAK_ROWS;
 use std::cmp::max;
 
 use std::marker::PhantomData;
@@ -30,7 +31,7 @@ use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
     keccak_circuit::keccak_packed_multi::{
         decode, get_num_bits_per_absorb_lookup, get_num_bits_per_base_chi_lookup,
-        get_num_bits_per_rho_pi_lookup, get_num_bits_per_theta_c_lookup, get_num_rows_per_round,
+        get_num_bits_per_rho_pi_lookup, get_num_bits_per_theta_c_lookup, get_num_rows_per_update,
         split, split_uniform, transform, transform_to, Part,
     },
     table::{KeccakTable, LookupTable},
@@ -66,6 +67,8 @@ pub struct KeccakCircuitConfig<F> {
     pack_table: [TableColumn; 2],
     /// The column for enabling copy constraints in aggregator
     pub preimage_column_index: usize,
+    /// The number of rows used by a single inner round of keccak-f
+    pub num_rows_per_round: usize,
     _marker: PhantomData<F>,
 }
 
@@ -76,6 +79,10 @@ pub struct KeccakCircuitConfigArgs<F: Field> {
     pub keccak_table: KeccakTable,
     /// Challenges randomness
     pub challenges: Challenges<Expression<F>>,
+    /// The number of rows used by a single inner round of keccak-f. More rows
+    /// per round means fewer advice columns but fewer keccak-f's for a given
+    /// circuit height, see [`KeccakCircuit::capacity_estimates`].
+    pub num_rows_per_round: usize,
 }
 
 impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
@@ -87,11 +94,12 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         Self::ConfigArgs {
             keccak_table,
             challenges,
+            num_rows_per_round,
         }: Self::ConfigArgs,
     ) -> Self {
         assert!(
-            get_num_rows_per_round() > NUM_BYTES_PER_WORD,
-            "KeccakCircuit requires KECCAK_ROWS>=9"
+            num_rows_per_round > NUM_BYTES_PER_WORD,
+            "KeccakCircuit requires num_rows_per_round >= 9"
         );
         let q_enable = keccak_table.q_enable;
 
@@ -114,7 +122,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         let chi_base_table = array_init::array_init(|_| meta.lookup_table_column());
         let pack_table = array_init::array_init(|_| meta.lookup_table_column());
 
-        let mut cell_manager = CellManager::new(get_num_rows_per_round());
+        let mut cell_manager = CellManager::new(num_rows_per_round);
         let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
         let mut total_lookup_counter = 0;
 
@@ -136,7 +144,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
             for j in 0..5 {
                 let cell = cell_manager.query_cell(meta);
                 s[i][j] = cell.expr();
-                s_next[i][j] = cell.at_offset(meta, get_num_rows_per_round() as i32).expr();
+                s_next[i][j] = cell.at_offset(meta, num_rows_per_round as i32).expr();
             }
         }
         log::debug!("- Post states:");
@@ -149,7 +157,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         let mut absorb_data_next = vec![0u64.expr(); NUM_WORDS_TO_ABSORB];
         let mut absorb_result_next = vec![0u64.expr(); NUM_WORDS_TO_ABSORB];
         for i in 0..NUM_WORDS_TO_ABSORB {
-            let rot = ((i + 1) * get_num_rows_per_round()) as i32;
+            let rot = ((i + 1) * num_rows_per_round) as i32;
             absorb_from_next[i] = absorb_from.at_offset(meta, rot).expr();
             absorb_data_next[i] = absorb_data.at_offset(meta, rot).expr();
             absorb_result_next[i] = absorb_result.at_offset(meta, rot).expr();
@@ -167,7 +175,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         // rlc.
         cell_manager.start_region();
         let mut lookup_counter = 0;
-        let part_size = get_num_bits_per_absorb_lookup();
+        let part_size = get_num_bits_per_absorb_lookup(num_rows_per_round);
         let input = absorb_from.expr() + absorb_data.expr();
         let absorb_fat = split::expr(meta, &mut cell_manager, &mut cb, input, 0, part_size);
         cell_manager.start_region();
@@ -242,7 +250,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         // that allows us to also calculate the rotated value "for free".
         cell_manager.start_region();
         let mut lookup_counter = 0;
-        let part_size_c = get_num_bits_per_theta_c_lookup();
+        let part_size_c = get_num_bits_per_theta_c_lookup(num_rows_per_round);
         let mut c_parts = Vec::new();
         for s in s.iter() {
             // Calculate c and split into parts
@@ -300,7 +308,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         // `s[j][2 * i + 3 * j) % 5] = normalize(rot(s[i][j], RHOM[i][j]))`.
         cell_manager.start_region();
         let mut lookup_counter = 0;
-        let part_size = get_num_bits_per_base_chi_lookup();
+        let part_size = get_num_bits_per_base_chi_lookup(num_rows_per_round);
         // To combine the rho/pi/chi steps we have to ensure a specific layout so
         // query those cells here first.
         // For chi we have to do `s[i][j] ^ ((~s[(i+1)%5][j]) & s[(i+2)%5][j])`. `j`
@@ -330,7 +338,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
                     if row_idx == 0 {
                         num_columns += 1;
                     }
-                    row_idx = (((row_idx as usize) + 1) % get_num_rows_per_round()) as i32;
+                    row_idx = (((row_idx as usize) + 1) % num_rows_per_round) as i32;
                 }
             }
         }
@@ -384,7 +392,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         // s[(i+2)%5][j])` five times, on each row (no selector needed).
         // This is calculated by making use of `CHI_BASE_LOOKUP_TABLE`.
         let mut lookup_counter = 0;
-        let part_size_base = get_num_bits_per_base_chi_lookup();
+        let part_size_base = get_num_bits_per_base_chi_lookup(num_rows_per_round);
         for idx in 0..num_columns {
             // First fetch the cells we wan to use
             let mut input: [Expression<F>; 5] = array_init::array_init(|_| 0.expr());
@@ -433,7 +441,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         // iota
         // Simply do the single xor on state [0][0].
         cell_manager.start_region();
-        let part_size = get_num_bits_per_absorb_lookup();
+        let part_size = get_num_bits_per_absorb_lookup(num_rows_per_round);
         let input = s[0][0].clone() + round_cst_expr.clone();
         let iota_parts = split::expr(meta, &mut cell_manager, &mut cb, input, 0, part_size);
         cell_manager.start_region();
@@ -466,7 +474,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         let squeeze_from = cell_manager.query_cell(meta);
         let mut squeeze_from_prev = vec![0u64.expr(); NUM_WORDS_TO_SQUEEZE];
         for (idx, squeeze_from_prev) in squeeze_from_prev.iter_mut().enumerate() {
-            let rot = (-(idx as i32) - 1) * get_num_rows_per_round() as i32;
+            let rot = (-(idx as i32) - 1) * num_rows_per_round as i32;
             *squeeze_from_prev = squeeze_from.at_offset(meta, rot).expr();
         }
         // Squeeze
@@ -546,7 +554,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         let mut hash_bytes = Vec::new();
         for i in 0..NUM_WORDS_TO_SQUEEZE {
             for byte in squeeze_bytes.iter() {
-                let rot = (-(i as i32) - 1) * get_num_rows_per_round() as i32;
+                let rot = (-(i as i32) - 1) * num_rows_per_round as i32;
                 hash_bytes.push(byte.cell.at_offset(meta, rot).expr());
             }
         }
@@ -607,7 +615,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         // Enforce logic for when this block is the last block for a hash
         let last_is_padding_in_block = is_paddings.last().unwrap().at_offset(
             meta,
-            -(((NUM_ROUNDS + 1 - NUM_WORDS_TO_ABSORB) * get_num_rows_per_round()) as i32),
+            -(((NUM_ROUNDS + 1 - NUM_WORDS_TO_ABSORB) * num_rows_per_round) as i32),
         );
         meta.create_gate("is final", |meta| {
             let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
@@ -625,7 +633,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
             );
             // For all the rows of a round, only the first row can have `is_final == 1`.
             cb.condition(
-                (1..get_num_rows_per_round() as i32)
+                (1..num_rows_per_round as i32)
                     .map(|i| meta.query_fixed(q_enable, Rotation(-i)))
                     .fold(0.expr(), |acc, elem| acc + elem),
                 |cb| {
@@ -644,7 +652,7 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         let prev_is_padding = is_paddings
             .last()
             .unwrap()
-            .at_offset(meta, -(get_num_rows_per_round() as i32));
+            .at_offset(meta, -(num_rows_per_round as i32));
         meta.create_gate("padding", |meta| {
             let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
             let q_padding = meta.query_fixed(q_padding, Rotation::cur());
@@ -734,13 +742,10 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
             let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
 
             let q_padding = meta.query_fixed(q_padding, Rotation::cur());
-            let start_new_hash_prev =
-                start_new_hash(meta, Rotation(-(get_num_rows_per_round() as i32)));
-            let length_prev =
-                meta.query_advice(length, Rotation(-(get_num_rows_per_round() as i32)));
+            let start_new_hash_prev = start_new_hash(meta, Rotation(-(num_rows_per_round as i32)));
+            let length_prev = meta.query_advice(length, Rotation(-(num_rows_per_round as i32)));
             let length = meta.query_advice(length, Rotation::cur());
-            let data_rlc_prev =
-                meta.query_advice(data_rlc, Rotation(-(get_num_rows_per_round() as i32)));
+            let data_rlc_prev = meta.query_advice(data_rlc, Rotation(-(num_rows_per_round as i32)));
             let data_rlcs: Vec<_> = (0..NUM_BYTES_PER_WORD + 1)
                 .map(|i| meta.query_advice(data_rlc, Rotation(i as i32)))
                 .collect();
@@ -837,18 +842,33 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
         log::debug!("Total Lookups: {}", total_lookup_counter);
         log::debug!("Total Columns: {}", cell_manager.get_width());
         log::debug!("num unused cells: {}", cell_manager.get_num_unused_cells());
-        log::debug!("part_size absorb: {}", get_num_bits_per_absorb_lookup());
-        log::debug!("part_size theta: {}", get_num_bits_per_theta_c_lookup());
+        log::debug!(
+            "part_size absorb: {}",
+            get_num_bits_per_absorb_lookup(num_rows_per_round)
+        );
+        log::debug!(
+            "part_size theta: {}",
+            get_num_bits_per_theta_c_lookup(num_rows_per_round)
+        );
         log::debug!(
             "part_size theta c: {}",
-            get_num_bits_per_lookup(THETA_C_LOOKUP_RANGE)
+            get_num_bits_per_lookup(THETA_C_LOOKUP_RANGE, num_rows_per_round)
+        );
+        log::debug!(
+            "part_size theta t: {}",
+            get_num_bits_per_lookup(4, num_rows_per_round)
+        );
+        log::debug!(
+            "part_size rho/pi: {}",
+            get_num_bits_per_rho_pi_lookup(num_rows_per_round)
+        );
+        log::debug!(
+            "part_size chi base: {}",
+            get_num_bits_per_base_chi_lookup(num_rows_per_round)
         );
-        log::debug!("part_size theta t: {}", get_num_bits_per_lookup(4));
-        log::debug!("part_size rho/pi: {}", get_num_bits_per_rho_pi_lookup());
-        log::debug!("part_size chi base: {}", get_num_bits_per_base_chi_lookup());
         log::debug!(
             "uniform part sizes: {:?}",
-            target_part_sizes(get_num_bits_per_theta_c_lookup())
+            target_part_sizes(get_num_bits_per_theta_c_lookup(num_rows_per_round))
         );
 
         KeccakCircuitConfig {
@@ -868,12 +888,19 @@ impl<F: Field> SubCircuitConfig<F> for KeccakCircuitConfig<F> {
             chi_base_table,
             pack_table,
             preimage_column_index,
+            num_rows_per_round,
             _marker: PhantomData,
         }
     }
 }
 
 impl<F: Field> KeccakCircuitConfig<F> {
+    /// The number of rows at the end of the circuit that cannot be used with
+    /// the layout of this config.
+    pub fn unusable_rows(&self) -> usize {
+        keccak_unusable_rows(self.num_rows_per_round)
+    }
+
     /// Assign the circuit for hash function
     pub(crate) fn assign(
         &self,
@@ -969,14 +996,33 @@ impl<F: Field> KeccakCircuitConfig<F> {
 
     /// Load the auxiliary tables for keccak circuit
     pub fn load_aux_tables(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        load_normalize_table(layouter, "normalize_6", &self.normalize_6, 6u64)?;
-        load_normalize_table(layouter, "normalize_4", &self.normalize_4, 4u64)?;
-        load_normalize_table(layouter, "normalize_3", &self.normalize_3, 3u64)?;
+        let num_rows_per_round = self.num_rows_per_round;
+        load_normalize_table(
+            layouter,
+            "normalize_6",
+            &self.normalize_6,
+            6u64,
+            num_rows_per_round,
+        )?;
+        load_normalize_table(
+            layouter,
+            "normalize_4",
+            &self.normalize_4,
+            4u64,
+            num_rows_per_round,
+        )?;
+        load_normalize_table(
+            layouter,
+            "normalize_3",
+            &self.normalize_3,
+            3u64,
+            num_rows_per_round,
+        )?;
         load_lookup_table(
             layouter,
             "chi base",
             &self.chi_base_table,
-            get_num_bits_per_base_chi_lookup(),
+            get_num_bits_per_base_chi_lookup(num_rows_per_round),
             &CHI_BASE_LOOKUP_TABLE,
         )?;
         load_pack_table(layouter, &self.pack_table)
@@ -1009,8 +1055,11 @@ pub struct KeccakCircuit<F: Field> {
 impl<F: Field> SubCircuit<F> for KeccakCircuit<F> {
     type Config = KeccakCircuitConfig<F>;
 
+    /// The unusable rows of the [`DEFAULT_KECCAK_ROWS`] layout. A config built
+    /// with another `num_rows_per_round` reports its own in
+    /// [`KeccakCircuitConfig::unusable_rows`].
     fn unusable_rows() -> usize {
-        keccak_unusable_rows()
+        keccak_unusable_rows(DEFAULT_KECCAK_ROWS)
     }
 
     /// The `block.circuits_params.keccak_padding` parameter, when enabled, sets
@@ -1023,27 +1072,10 @@ impl<F: Field> SubCircuit<F> for KeccakCircuit<F> {
         )
     }
 
-    /// Return the minimum number of rows required to prove the block
+    /// Return the minimum number of rows required to prove the block with the
+    /// [`DEFAULT_KECCAK_ROWS`] layout, see [`KeccakCircuit::min_num_rows_block_for`].
     fn min_num_rows_block(block: &witness::Block) -> (usize, usize) {
-        let rows_per_chunk = (NUM_ROUNDS + 1) * get_num_rows_per_round();
-        let aux_tables_rows = [
-            normalize_table_size(6),
-            normalize_table_size(4),
-            normalize_table_size(3),
-            lookup_table_size(CHI_BASE_LOOKUP_TABLE.len()),
-        ];
-        (
-            keccak_inputs(block)
-                .unwrap()
-                .iter()
-                .map(|bytes| (bytes.len() as f64 / 136.0).ceil() as usize * rows_per_chunk)
-                .sum::<usize>()
-                + get_num_rows_per_round(), // reserved for first 12 dummy rows
-            max(
-                block.circuits_params.max_keccak_rows,
-                *(aux_tables_rows.iter().max().unwrap()),
-            ),
-        )
+        Self::min_num_rows_block_for(block, DEFAULT_KECCAK_ROWS)
     }
 
     /// Make the assignments to the KeccakCircuit
@@ -1054,11 +1086,25 @@ impl<F: Field> SubCircuit<F> for KeccakCircuit<F> {
         layouter: &mut impl Layouter<F>,
     ) -> Result<(), Error> {
         config.load_aux_tables(layouter)?;
-        let witness = self.generate_witness(*challenges);
+        let witness = self.generate_witness(*challenges, config.num_rows_per_round);
         config.assign(layouter, witness.as_slice())
     }
 }
 
+/// The resources used by the keccak circuit for a given number of rows per
+/// round, see [`KeccakCircuit::capacity_estimates`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeccakCapacityEstimate {
+    /// The number of rows used by a single inner round of keccak-f
+    pub num_rows_per_round: usize,
+    /// The number of advice columns allocated by the cell manager
+    pub num_columns: usize,
+    /// The number of rows at the end of the circuit that cannot be used
+    pub unusable_rows: usize,
+    /// The number of keccak_f's that fit into the circuit, if any
+    pub capacity: Option<usize>,
+}
+
 impl<F: Field> KeccakCircuit<F> {
     /// Creates a new circuit instance
     pub fn new(num_rows: usize, inputs: Vec<Vec<u8>>) -> Self {
@@ -1070,24 +1116,96 @@ impl<F: Field> KeccakCircuit<F> {
     }
 
     /// The number of keccak_f's that can be done in this circuit
-    pub fn capacity(&self) -> Option<usize> {
-        Self::capacity_for_row(self.num_rows)
+    pub fn capacity(&self, num_rows_per_round: usize) -> Option<usize> {
+        Self::capacity_for_row(self.num_rows, num_rows_per_round)
     }
 
     /// The number of keccak_f's that can be done for
-    /// a particular row number depending on current Keccak params
-    pub fn capacity_for_row(num_rows: usize) -> Option<usize> {
+    /// a particular row number depending on the number of rows per round
+    pub fn capacity_for_row(num_rows: usize, num_rows_per_round: usize) -> Option<usize> {
         if num_rows > 0 {
             // Subtract two for unusable rows
-            Some(num_rows / ((NUM_ROUNDS + 1) * get_num_rows_per_round()) - 2)
+            Some(num_rows / get_num_rows_per_update(num_rows_per_round) - 2)
         } else {
             None
         }
     }
 
+    /// The number of rows required to hash `inputs`, including the dummy
+    /// round at the start of the circuit.
+    pub fn num_rows_for_inputs(inputs: &[Vec<u8>], num_rows_per_round: usize) -> usize {
+        inputs
+            .iter()
+            // the padding takes at least one byte, so an input filling its last block needs
+            // another one
+            .map(|bytes| (bytes.len() / RATE + 1) * get_num_rows_per_update(num_rows_per_round))
+            .sum::<usize>()
+            + num_rows_per_round
+    }
+
+    /// Return the minimum number of rows required to prove the block with
+    /// `num_rows_per_round` rows per round.
+    pub fn min_num_rows_block_for(
+        block: &witness::Block,
+        num_rows_per_round: usize,
+    ) -> (usize, usize) {
+        let aux_tables_rows = [
+            normalize_table_size(6, num_rows_per_round),
+            normalize_table_size(4, num_rows_per_round),
+            normalize_table_size(3, num_rows_per_round),
+            lookup_table_size(CHI_BASE_LOOKUP_TABLE.len(), num_rows_per_round),
+        ];
+        (
+            Self::num_rows_for_inputs(&keccak_inputs(block).unwrap(), num_rows_per_round),
+            max(
+                block.circuits_params.max_keccak_rows,
+                *(aux_tables_rows.iter().max().unwrap()),
+            ),
+        )
+    }
+
+    /// Estimate the columns and capacity of a circuit with `num_rows` rows for
+    /// every supported number of rows per round, from the widest to the
+    /// narrowest layout up to `max_num_rows_per_round`.
+    pub fn capacity_estimates(
+        num_rows: usize,
+        max_num_rows_per_round: usize,
+    ) -> Vec<KeccakCapacityEstimate> {
+        (NUM_BYTES_PER_WORD + 1..=max_num_rows_per_round)
+            .map(|num_rows_per_round| {
+                let mut meta = ConstraintSystem::<F>::default();
+                let keccak_table = KeccakTable::construct(&mut meta);
+                let challenges = Challenges::construct(&mut meta).exprs(&mut meta);
+                let config = KeccakCircuitConfig::new(
+                    &mut meta,
+                    KeccakCircuitConfigArgs {
+                        keccak_table,
+                        challenges,
+                        num_rows_per_round,
+                    },
+                );
+                KeccakCapacityEstimate {
+                    num_rows_per_round,
+                    num_columns: config.cell_manager.get_width(),
+                    unusable_rows: config.unusable_rows(),
+                    capacity: Self::capacity_for_row(num_rows, num_rows_per_round),
+                }
+            })
+            .collect()
+    }
+
     /// Sets the witness using the data to be hashed
-    pub(crate) fn generate_witness(&self, challenges: Challenges<Value<F>>) -> Vec<KeccakRow<F>> {
-        multi_keccak(self.inputs.as_slice(), challenges, self.capacity())
-            .expect("Too many inputs for given capacity")
+    pub(crate) fn generate_witness(
+        &self,
+        challenges: Challenges<Value<F>>,
+        num_rows_per_round: usize,
+    ) -> Vec<KeccakRow<F>> {
+        multi_keccak(
+            self.inputs.as_slice(),
+            challenges,
+            self.capacity(num_rows_per_round),
+            num_rows_per_round,
+        )
+        .expect("Too many inputs for given capacity")
     }
 }
@@ -1,7 +1,7 @@
 pub use super::KeccakCircuit;
 
 use crate::{
-    keccak_circuit::{KeccakCircuitConfig, KeccakCircuitConfigArgs},
+    keccak_circuit::{KeccakCircuitConfig, KeccakCircuitConfigArgs, DEFAULT_KECCAK_ROWS},
     table::KeccakTable,
     util::{Challenges, Field, SubCircuit, SubCircuitConfig},
 };
@@ -31,6 +31,7 @@ impl<F: Field> Circuit<F> for KeccakCircuit<F> {
                 KeccakCircuitConfigArgs {
                     keccak_table,
                     challenges,
+                    num_rows_per_round: DEFAULT_KECCAK_ROWS,
                 },
             )
         };
@@ -9,43 +9,30 @@ use halo2_proofs::{
 };
 use log::{debug, trace};
 use rayon::{iter::IntoParallelRefIterator, prelude::ParallelIterator};
-use std::{env::var, vec};
+use std::vec;
 
 const MAX_DEGREE: usize = 9;
 
-/// Obtain the rows required for 1 iteration of f-box's inner round
-/// function (consisting of 5 phases) within Keccak circuit
-pub fn get_num_rows_per_round() -> usize {
-    let r = var("KECCAK_ROWS")
-        .unwrap_or_else(|_| format!("{DEFAULT_KECCAK_ROWS}"))
-        .parse()
-        .expect("Cannot parse KECCAK_ROWS env var as usize");
-    assert!(
-        r > NUM_BYTES_PER_WORD,
-        "env variable KECCAK_ROWS must be greater than (NUM_BYTES_PER_WORD + 1)."
-    );
-    r
-}
 /// Obtain the rows required for 1 iteration of the f-box
 /// function (consisting of nr = 12 + 2*l inner rounds)
 /// within Keccak circuit
-pub fn get_num_rows_per_update() -> usize {
-    get_num_rows_per_round() * (NUM_ROUNDS + 1)
+pub fn get_num_rows_per_update(num_rows_per_round: usize) -> usize {
+    num_rows_per_round * (NUM_ROUNDS + 1)
 }
 /// Obtain the column position of the hash inputs
 /// within cell_manager for an inner round.
 /// This value is determined by the number of rows allocated
 /// to each inner round and target part_size for u64
-pub fn get_input_bytes_col_idx_in_cell_manager() -> usize {
+pub fn get_input_bytes_col_idx_in_cell_manager(num_rows_per_round: usize) -> usize {
     let mut col: usize = 0;
-    let inner_round_num_rows = get_num_rows_per_round();
+    let inner_round_num_rows = num_rows_per_round;
 
     col += NUM_SETUP_VARS_FOR_ROUND / inner_round_num_rows;
     if inner_round_num_rows * col < NUM_SETUP_VARS_FOR_ROUND {
         col += 1;
     }
 
-    let part_size = get_num_bits_per_absorb_lookup();
+    let part_size = get_num_bits_per_absorb_lookup(num_rows_per_round);
     let part_length = WordParts::new(part_size, 0, false).parts.len();
 
     let mut absorb_parts_col = part_length / inner_round_num_rows;
@@ -56,31 +43,37 @@ pub fn get_input_bytes_col_idx_in_cell_manager() -> usize {
     col + absorb_parts_col * 2 + 1
 }
 
-pub(crate) fn keccak_unusable_rows() -> usize {
+pub(crate) fn keccak_unusable_rows(num_rows_per_round: usize) -> usize {
     const UNUSABLE_ROWS_BY_KECCAK_ROWS: [usize; 24] = [
         53, 67, 63, 59, 45, 79, 77, 75, 73, 71, 69, 67, 65, 63, 61, 59, 57, 71, 89, 107, 107, 107,
         107, 107,
     ];
     UNUSABLE_ROWS_BY_KECCAK_ROWS
-        .get(get_num_rows_per_round() - NUM_BYTES_PER_WORD - 1)
+        .get(num_rows_per_round - NUM_BYTES_PER_WORD - 1)
         .cloned()
         .unwrap_or(107)
 }
 
-pub(crate) fn get_num_bits_per_absorb_lookup() -> usize {
-    get_num_bits_per_lookup(ABSORB_LOOKUP_RANGE)
+pub(crate) fn get_num_bits_per_absorb_lookup(num_rows_per_round: usize) -> usize {
+    get_num_bits_per_lookup(ABSORB_LOOKUP_RANGE, num_rows_per_round)
 }
 
-pub(crate) fn get_num_bits_per_theta_c_lookup() -> usize {
-    get_num_bits_per_lookup(THETA_C_LOOKUP_RANGE)
+pub(crate) fn get_num_bits_per_theta_c_lookup(num_rows_per_round: usize) -> usize {
+    get_num_bits_per_lookup(THETA_C_LOOKUP_RANGE, num_rows_per_round)
 }
 
-pub(crate) fn get_num_bits_per_rho_pi_lookup() -> usize {
-    get_num_bits_per_lookup(CHI_BASE_LOOKUP_RANGE.max(RHO_PI_LOOKUP_RANGE))
+pub(crate) fn get_num_bits_per_rho_pi_lookup(num_rows_per_round: usize) -> usize {
+    get_num_bits_per_lookup(
+        CHI_BASE_LOOKUP_RANGE.max(RHO_PI_LOOKUP_RANGE),
+        num_rows_per_round,
+    )
 }
 
-pub(crate) fn get_num_bits_per_base_chi_lookup() -> usize {
-    get_num_bits_per_lookup(CHI_BASE_LOOKUP_RANGE.max(RHO_PI_LOOKUP_RANGE))
+pub(crate) fn get_num_bits_per_base_chi_lookup(num_rows_per_round: usize) -> usize {
+    get_num_bits_per_lookup(
+        CHI_BASE_LOOKUP_RANGE.max(RHO_PI_LOOKUP_RANGE),
+        num_rows_per_round,
+    )
 }
 
 /// AbsorbData
@@ -534,9 +527,10 @@ pub(crate) mod transform_to {
 pub(crate) fn keccak_rows<F: Field>(
     bytes: &[u8],
     challenges: Challenges<Value<F>>,
+    num_rows_per_round: usize,
 ) -> Vec<KeccakRow<F>> {
     let mut rows = Vec::new();
-    keccak(&mut rows, bytes, challenges);
+    keccak(&mut rows, bytes, challenges, num_rows_per_round);
     rows
 }
 
@@ -544,6 +538,7 @@ pub(crate) fn keccak<F: Field>(
     rows: &mut Vec<KeccakRow<F>>,
     bytes: &[u8],
     challenges: Challenges<Value<F>>,
+    num_rows_per_round: usize,
 ) {
     let mut bits = into_bits(bytes);
     let mut s = [[F::zero(); 5]; 5];
@@ -587,7 +582,7 @@ pub(crate) fn keccak<F: Field>(
         let mut round_lengths = Vec::new();
         let mut round_data_rlcs = Vec::new();
         for round in 0..NUM_ROUNDS + 1 {
-            let mut cell_manager = CellManager::new(get_num_rows_per_round());
+            let mut cell_manager = CellManager::new(num_rows_per_round);
             let mut region = KeccakRegion::new();
 
             let mut absorb_row = AbsorbData::default();
@@ -612,8 +607,8 @@ pub(crate) fn keccak<F: Field>(
             absorb_result.assign(&mut region, 0, absorb_row.result);
 
             // Column padding
-            if get_num_rows_per_round() > 28 {
-                for _ in 28..get_num_rows_per_round() {
+            if num_rows_per_round > 28 {
+                for _ in 28..num_rows_per_round {
                     let padding_cell = cell_manager.query_cell_value();
                     padding_cell.assign(&mut region, 0, F::zero());
                 }
@@ -621,7 +616,7 @@ pub(crate) fn keccak<F: Field>(
 
             // Absorb
             cell_manager.start_region();
-            let part_size = get_num_bits_per_absorb_lookup();
+            let part_size = get_num_bits_per_absorb_lookup(num_rows_per_round);
             let input = absorb_row.from + absorb_row.absorb;
             let absorb_fat = split::value(&mut cell_manager, &mut region, input, 0, part_size);
             cell_manager.start_region();
@@ -644,7 +639,7 @@ pub(crate) fn keccak<F: Field>(
                 transform::value(&mut cell_manager, &mut region, packed, false, |v| *v, true);
             cell_manager.start_region();
             let mut is_paddings = Vec::new();
-            let mut data_rlcs = vec![Value::known(F::zero()); get_num_rows_per_round()];
+            let mut data_rlcs = vec![Value::known(F::zero()); num_rows_per_round];
             for _ in input_bytes.iter() {
                 is_paddings.push(cell_manager.query_cell_value());
             }
@@ -680,7 +675,7 @@ pub(crate) fn keccak<F: Field>(
 
             if round != NUM_ROUNDS {
                 // Theta
-                let part_size = get_num_bits_per_theta_c_lookup();
+                let part_size = get_num_bits_per_theta_c_lookup(num_rows_per_round);
                 let mut bcf = Vec::new();
                 for s in &s {
                     let c = s[0] + s[1] + s[2] + s[3] + s[4];
@@ -713,7 +708,7 @@ pub(crate) fn keccak<F: Field>(
                 cell_manager.start_region();
 
                 // Rho/Pi
-                let part_size = get_num_bits_per_base_chi_lookup();
+                let part_size = get_num_bits_per_base_chi_lookup(num_rows_per_round);
                 let target_word_sizes = target_part_sizes(part_size);
                 let num_word_parts = target_word_sizes.len();
                 let mut rho_pi_chi_cells: [[[Vec<Cell<F>>; 5]; 5]; 3] =
@@ -730,7 +725,7 @@ pub(crate) fn keccak<F: Field>(
                                 rho_pi_chi_cells[p][i][j]
                                     .push(cell_manager.query_cell_value_at_row(row_idx as i32));
                             }
-                            row_idx = (row_idx + 1) % get_num_rows_per_round();
+                            row_idx = (row_idx + 1) % num_rows_per_round;
                         }
                     }
                 }
@@ -761,7 +756,7 @@ pub(crate) fn keccak<F: Field>(
                 cell_manager.start_region();
 
                 // Chi
-                let part_size_base = get_num_bits_per_base_chi_lookup();
+                let part_size_base = get_num_bits_per_base_chi_lookup(num_rows_per_round);
                 let three_packed = pack::<F>(&vec![3u8; part_size_base]);
                 let mut os = [[F::zero(); 5]; 5];
                 for j in 0..5 {
@@ -793,7 +788,7 @@ pub(crate) fn keccak<F: Field>(
                 cell_manager.start_region();
 
                 // iota
-                let part_size = get_num_bits_per_absorb_lookup();
+                let part_size = get_num_bits_per_absorb_lookup(num_rows_per_round);
                 let input = s[0][0] + pack_u64::<F>(ROUND_CST[round]);
                 let iota_parts =
                     split::value::<F>(&mut cell_manager, &mut region, input, 0, part_size);
@@ -851,7 +846,7 @@ pub(crate) fn keccak<F: Field>(
 
         for round in 0..NUM_ROUNDS + 1 {
             let round_cst = pack_u64(ROUND_CST[round]);
-            for row_idx in 0..get_num_rows_per_round() {
+            for row_idx in 0..num_rows_per_round {
                 rows.push(KeccakRow {
                     q_enable: row_idx == 0,
                     q_round: row_idx == 0 && round < NUM_ROUNDS,
@@ -888,21 +883,24 @@ pub(crate) fn keccak<F: Field>(
     }
 }
 
-/// Genenrate keccak circuit witness from preimages
+/// Genenrate keccak circuit witness from preimages, laid out with
+/// `num_rows_per_round` rows per inner round. This must match the value the
+/// [`KeccakCircuitConfig`](super::KeccakCircuitConfig) was configured with.
 pub fn multi_keccak<F: Field>(
     bytes: &[Vec<u8>],
     challenges: Challenges<Value<F>>,
     capacity: Option<usize>,
+    num_rows_per_round: usize,
 ) -> Result<Vec<KeccakRow<F>>, Error> {
     log::info!("multi_keccak assign with capacity: {:?}", capacity);
 
     let mut rows: Vec<KeccakRow<F>> = Vec::new();
     if let Some(capacity) = capacity {
-        rows.reserve((1 + capacity * (NUM_ROUNDS + 1)) * get_num_rows_per_round());
+        rows.reserve((1 + capacity * (NUM_ROUNDS + 1)) * num_rows_per_round);
     }
     // Dummy first row so that the initial data is absorbed
     // The initial data doesn't really matter, `is_final` just needs to be disabled.
-    for idx in 0..get_num_rows_per_round() {
+    for idx in 0..num_rows_per_round {
         rows.push(KeccakRow {
             q_enable: idx == 0,
             q_round: false,
@@ -924,22 +922,22 @@ pub fn multi_keccak<F: Field>(
     // TODO: optimize the `extend` using Iter?
     let real_rows: Vec<_> = bytes
         .par_iter()
-        .flat_map_iter(|bytes| keccak_rows(bytes, challenges))
+        .flat_map_iter(|bytes| keccak_rows(bytes, challenges, num_rows_per_round))
         .collect();
     rows.extend(real_rows);
     debug!("keccak rows len without padding: {}", rows.len());
     if let Some(capacity) = capacity {
         let padding_rows = {
             let mut rows = Vec::new();
-            keccak(&mut rows, &[], challenges);
+            keccak(&mut rows, &[], challenges, num_rows_per_round);
             rows
         };
         // Pad with no data hashes to the expected capacity
-        while rows.len() < (1 + capacity * (NUM_ROUNDS + 1)) * get_num_rows_per_round() {
+        while rows.len() < (1 + capacity * (NUM_ROUNDS + 1)) * num_rows_per_round {
             rows.extend(padding_rows.clone());
         }
         // Check that we are not over capacity
-        if rows.len() > (1 + capacity * (NUM_ROUNDS + 1)) * get_num_rows_per_round() {
+        if rows.len() > (1 + capacity * (NUM_ROUNDS + 1)) * num_rows_per_round {
             return Err(Error::BoundsFailure);
         }
     }
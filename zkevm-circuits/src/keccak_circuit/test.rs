@@ -12,19 +12,30 @@ use std::iter::zip;
 
 use super::util::{target_part_sizes, target_part_sizes_rot, WordParts};
 
-// This needs to be tested independent since it sets the environment variable
-// which might affect other tests.
-#[ignore]
 #[test]
-fn serial_keccak_circuit_unusable_rows() {
-    for keccak_rows in NUM_BYTES_PER_WORD + 1..=50 {
-        std::env::set_var("KECCAK_ROWS", format!("{keccak_rows}"));
-        assert_eq!(
-            KeccakCircuit::<Fr>::unusable_rows(),
-            unusable_rows::<Fr, KeccakCircuit::<Fr>>(),
-        )
+fn keccak_circuit_unusable_rows() {
+    assert_eq!(
+        KeccakCircuit::<Fr>::unusable_rows(),
+        unusable_rows::<Fr, KeccakCircuit::<Fr>>(),
+    )
+}
+
+#[test]
+fn keccak_config_unusable_rows() {
+    for num_rows_per_round in NUM_BYTES_PER_WORD + 1..=50 {
+        let mut meta = ConstraintSystem::<Fr>::default();
+        let keccak_table = KeccakTable::construct(&mut meta);
+        let challenges = Challenges::construct(&mut meta).exprs(&mut meta);
+        let config = KeccakCircuitConfig::new(
+            &mut meta,
+            KeccakCircuitConfigArgs {
+                keccak_table,
+                challenges,
+                num_rows_per_round,
+            },
+        );
+        assert_eq!(config.unusable_rows(), meta.blinding_factors() + 1);
     }
-    std::env::set_var("KECCAK_ROWS", format!("{DEFAULT_KECCAK_ROWS}"));
 }
 
 fn verify<F: Field>(k: u32, inputs: Vec<Vec<u8>>, success: bool) {
@@ -79,6 +90,47 @@ fn variadic_size_check() {
     assert_eq!(prover1.permutation(), prover2.permutation());
 }
 
+#[test]
+fn num_rows_for_inputs_matches_witness() {
+    let inputs = vec![
+        vec![],
+        (0u8..135).collect::<Vec<_>>(),
+        (0u8..136).collect::<Vec<_>>(),
+        (0u8..200).collect::<Vec<_>>(),
+    ];
+    let challenges = Challenges::mock(
+        Value::known(Fr::from(0x100)),
+        Value::known(Fr::from(0x100)),
+        Value::known(Fr::from(0x100)),
+    );
+    for num_rows_per_round in [NUM_BYTES_PER_WORD + 1, DEFAULT_KECCAK_ROWS, 20] {
+        let witness = multi_keccak(&inputs, challenges, None, num_rows_per_round).unwrap();
+        assert_eq!(
+            witness.len(),
+            KeccakCircuit::<Fr>::num_rows_for_inputs(&inputs, num_rows_per_round)
+        );
+    }
+}
+
+#[test]
+fn capacity_estimates_trade_columns_for_rows() {
+    let estimates = KeccakCircuit::<Fr>::capacity_estimates(1 << 20, 24);
+    assert_eq!(estimates.len(), 24 - NUM_BYTES_PER_WORD);
+    // More rows per round never needs more columns, but fits fewer keccak_f's.
+    for pair in estimates.windows(2) {
+        assert!(pair[0].num_columns >= pair[1].num_columns);
+        assert!(pair[0].capacity >= pair[1].capacity);
+    }
+    let default = estimates
+        .iter()
+        .find(|estimate| estimate.num_rows_per_round == DEFAULT_KECCAK_ROWS)
+        .unwrap();
+    assert_eq!(
+        default.capacity,
+        KeccakCircuit::<Fr>::capacity_for_row(1 << 20, DEFAULT_KECCAK_ROWS)
+    );
+}
+
 #[test]
 fn test_target_part_sizes() {
     // Uniform 8 parts of 8 bits each.
@@ -36,6 +36,8 @@ pub mod keccak_circuit;
 pub mod mpt_circuit;
 pub mod pi_circuit;
 pub mod poseidon_circuit;
+#[cfg(feature = "receipt-circuit")]
+pub mod receipt_circuit;
 pub mod rlp_circuit_fsm;
 pub mod sig_circuit;
 // we don't use this for aggregation
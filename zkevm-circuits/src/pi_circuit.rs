@@ -38,9 +38,13 @@ use crate::{
         RPI_LENGTH_ACC_CELL_IDX, RPI_RLC_ACC_CELL_IDX, TIMESTAMP_OFFSET,
     },
     state_circuit::StateCircuitExports,
-    tx_circuit::{CHAIN_ID_OFFSET as CHAIN_ID_OFFSET_IN_TX, TX_LEN},
+    tx_circuit::{TxCircuitExports, CHAIN_ID_OFFSET as CHAIN_ID_OFFSET_IN_TX, TX_LEN},
     witness::{self, Block, BlockContext, BlockContexts, Transaction},
 };
+#[cfg(feature = "receipt-circuit")]
+use crate::{pi_circuit::param::RECEIPTS_BYTES_NUM, receipt_circuit::ReceiptCircuitExports};
+#[cfg(feature = "receipt-circuit")]
+use ethers_core::types::Bloom;
 use gadgets::util::{and, not, select, Expr};
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter, Region, Value},
@@ -81,6 +85,11 @@ pub struct PublicData {
     pub next_state_root: Hash,
     /// Withdraw Trie Root
     pub withdraw_trie_root: Hash,
+    /// Rolling hash of the L1 messages popped before this chunk
+    pub prev_l1_msg_hash: Hash,
+    /// Receipts root and logs bloom of each block, by block number
+    #[cfg(feature = "receipt-circuit")]
+    pub receipts: BTreeMap<u64, (Hash, Bloom)>,
     /// Max number of supported transactions
     pub max_txs: usize,
     /// Max number of supported calldata bytes
@@ -189,6 +198,46 @@ impl PublicData {
         H256(keccak256(self.data_bytes()))
     }
 
+    /// The total number of L1 messages popped after this chunk.
+    fn end_l1_queue_index(&self) -> u64 {
+        self.transactions
+            .iter()
+            .filter(|tx| tx.tx_type.is_l1_msg())
+            // tx.nonce alias for queue_index for l1 msg tx
+            .map(|tx| tx.nonce + 1)
+            .fold(self.start_l1_queue_index, u64::max)
+    }
+
+    /// Compute the bytes for the rolling hash of L1 messages, i.e. the previous rolling hash
+    /// followed by the hashes of the L1 messages included in this chunk.
+    fn l1_msg_hash_bytes(&self) -> Vec<u8> {
+        iter::empty()
+            .chain(self.prev_l1_msg_hash.to_fixed_bytes())
+            .chain(
+                self.transactions
+                    .iter()
+                    .filter(|&tx| tx.tx_type == TxType::L1Msg)
+                    .flat_map(|tx| tx.hash.to_fixed_bytes()),
+            )
+            .collect::<Vec<u8>>()
+    }
+
+    fn get_post_l1_msg_hash(&self) -> H256 {
+        H256(keccak256(self.l1_msg_hash_bytes()))
+    }
+
+    /// Compute the bytes hashed for the receipts of the chunk, i.e. the number, logs bloom and
+    /// receipts root of each block.
+    #[cfg(feature = "receipt-circuit")]
+    fn receipts_bytes(&self) -> Vec<u8> {
+        witness::receipts_bytes(&self.receipts)
+    }
+
+    #[cfg(feature = "receipt-circuit")]
+    fn get_receipts_hash(&self) -> H256 {
+        H256(keccak256(self.receipts_bytes()))
+    }
+
     /// Obtain the l2 tx (not padding; right now padding txs are l2 txs by default) bytes in the
     /// chunk
     fn chunk_txbytes(&self) -> Vec<u8> {
@@ -206,8 +255,13 @@ impl PublicData {
         H256(keccak256(self.chunk_txbytes()))
     }
 
-    fn pi_bytes(&self, data_hash: H256, chunk_txbytes_hash: H256) -> Vec<u8> {
-        iter::empty()
+    fn pi_bytes(
+        &self,
+        data_hash: H256,
+        chunk_txbytes_hash: H256,
+        post_l1_msg_hash: H256,
+    ) -> Vec<u8> {
+        let pi_bytes = iter::empty()
             .chain(self.chain_id.to_be_bytes())
             // state roots
             .chain(self.prev_state_root.to_fixed_bytes())
@@ -216,7 +270,16 @@ impl PublicData {
             // data hash
             .chain(data_hash.to_fixed_bytes())
             .chain(chunk_txbytes_hash.to_fixed_bytes())
-            .collect::<Vec<u8>>()
+            // l1 message queue
+            .chain(self.start_l1_queue_index.to_be_bytes())
+            .chain(self.end_l1_queue_index().to_be_bytes())
+            .chain(self.prev_l1_msg_hash.to_fixed_bytes())
+            .chain(post_l1_msg_hash.to_fixed_bytes());
+        // receipts
+        #[cfg(feature = "receipt-circuit")]
+        let pi_bytes = pi_bytes.chain(self.get_receipts_hash().to_fixed_bytes());
+
+        pi_bytes.collect::<Vec<u8>>()
     }
 
     fn get_pi(&self) -> H256 {
@@ -231,7 +294,19 @@ impl PublicData {
             hex::encode(chunk_txbytes_hash.to_fixed_bytes())
         );
 
-        let pi_bytes = self.pi_bytes(data_hash, chunk_txbytes_hash);
+        let post_l1_msg_hash = self.get_post_l1_msg_hash();
+        log::debug!(
+            "[pi] post l1 msg hash: {}",
+            hex::encode(post_l1_msg_hash.to_fixed_bytes())
+        );
+
+        #[cfg(feature = "receipt-circuit")]
+        log::debug!(
+            "[pi] receipts hash: {}",
+            hex::encode(self.get_receipts_hash().to_fixed_bytes())
+        );
+
+        let pi_bytes = self.pi_bytes(data_hash, chunk_txbytes_hash, post_l1_msg_hash);
         let pi_hash = keccak256(pi_bytes);
 
         H256(pi_hash)
@@ -285,12 +360,64 @@ impl PublicData {
             + self.max_txs * KECCAK_DIGEST_SIZE
     }
 
-    fn q_chunk_txbytes_start_offset(&self) -> usize {
+    fn l1_msg_hashes_start_offset(&self) -> usize {
         self.data_bytes_end_offset()
             + 1 // a row is reserved for the keccak256(rlc(data_bytes)) == data_hash lookup.
             + 1 // new row
     }
 
+    fn q_l1_msg_hashes_start_offset(&self) -> usize {
+        self.l1_msg_hashes_start_offset()
+            + 1 // rlc init row
+            + KECCAK_DIGEST_SIZE // prev_l1_msg_hash
+    }
+
+    fn q_l1_msg_hashes_end_offset(&self) -> usize {
+        self.q_l1_msg_hashes_start_offset() + KECCAK_DIGEST_SIZE * self.max_txs
+    }
+
+    fn l1_msg_hashes_end_offset(&self) -> usize {
+        self.l1_msg_hashes_start_offset()
+            + KECCAK_DIGEST_SIZE // prev_l1_msg_hash
+            + KECCAK_DIGEST_SIZE * self.max_txs
+    }
+
+    #[cfg(feature = "receipt-circuit")]
+    fn receipts_start_offset(&self) -> usize {
+        self.l1_msg_hashes_end_offset()
+            + 1 // a row is reserved for the keccak256(rlc(l1_msg_hash_bytes)) lookup.
+            + 1 // new row
+    }
+
+    #[cfg(feature = "receipt-circuit")]
+    fn q_receipts_start_offset(&self) -> usize {
+        self.receipts_start_offset() + 1 // rlc init row
+    }
+
+    #[cfg(feature = "receipt-circuit")]
+    fn q_receipts_end_offset(&self) -> usize {
+        self.q_receipts_start_offset() + RECEIPTS_BYTES_NUM * self.max_inner_blocks
+    }
+
+    #[cfg(feature = "receipt-circuit")]
+    fn receipts_end_offset(&self) -> usize {
+        self.receipts_start_offset() + RECEIPTS_BYTES_NUM * self.max_inner_blocks
+    }
+
+    #[cfg(feature = "receipt-circuit")]
+    fn q_chunk_txbytes_start_offset(&self) -> usize {
+        self.receipts_end_offset()
+            + 1 // a row is reserved for the keccak256(rlc(receipts_bytes)) lookup.
+            + 1 // new row
+    }
+
+    #[cfg(not(feature = "receipt-circuit"))]
+    fn q_chunk_txbytes_start_offset(&self) -> usize {
+        self.l1_msg_hashes_end_offset()
+            + 1 // a row is reserved for the keccak256(rlc(l1_msg_hash_bytes)) lookup.
+            + 1 // new row
+    }
+
     fn q_chunk_txbytes_end_offset(&self) -> usize {
         self.q_chunk_txbytes_start_offset() // chunk_txbytes section is a singular row
     }
@@ -300,7 +427,13 @@ impl PublicData {
     }
 
     fn pi_bytes_end_offset(&self) -> usize {
-        self.pi_bytes_start_offset() + N_BYTES_U64 + N_BYTES_WORD * 5
+        // the receipts hash is an extra word at the end of the pi bytes
+        let n_words = if cfg!(feature = "receipt-circuit") {
+            8
+        } else {
+            7
+        };
+        self.pi_bytes_start_offset() + N_BYTES_U64 * 3 + N_BYTES_WORD * n_words
     }
 
     fn pi_hash_start_offset(&self) -> usize {
@@ -371,11 +504,13 @@ pub struct PiCircuitConfig<F: Field> {
     rpi_rlc_acc: Column<Advice>, // RLC(rpi) as the input to Keccak table
     rpi_length_acc: Column<Advice>,
 
-    // columns for padding in block context and tx hashes
+    // columns for padding in block context, tx hashes and receipts
     is_rpi_padding: Column<Advice>,
     real_rpi: Column<Advice>,
     q_tx_hashes: Column<Fixed>,
     q_block_context: Column<Fixed>,
+    #[cfg(feature = "receipt-circuit")]
+    q_receipts: Column<Fixed>,
     q_chunk_txbytes: Column<Fixed>, // indicates a single row
 
     // columns for assertion about cum_num_txs in block table
@@ -466,6 +601,8 @@ impl<F: Field> SubCircuitConfig<F> for PiCircuitConfig<F> {
 
         let q_block_context = meta.fixed_column();
         let q_tx_hashes = meta.fixed_column();
+        #[cfg(feature = "receipt-circuit")]
+        let q_receipts = meta.fixed_column();
         let q_chunk_txbytes = meta.fixed_column();
 
         let q_not_end = meta.complex_selector();
@@ -629,6 +766,27 @@ impl<F: Field> SubCircuitConfig<F> for PiCircuitConfig<F> {
             cb.gate(meta.query_fixed(q_tx_hashes, Rotation::cur()))
         });
 
+        #[cfg(feature = "receipt-circuit")]
+        meta.create_gate("padding receipts", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            cb.require_boolean(
+                "is_rpi_padding is boolean",
+                meta.query_advice(is_rpi_padding, Rotation::cur()),
+            );
+
+            // the block numbers are copied from the block context, so that a block is padding iff
+            // its number is 0.
+            cb.condition(meta.query_advice(is_rpi_padding, Rotation::cur()), |cb| {
+                cb.require_zero(
+                    "rpi == 0 for padding",
+                    meta.query_advice(rpi, Rotation::cur()),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_receipts, Rotation::cur()))
+        });
+
         // We reuse the layout for rpi to compute the keccak output.
         // The 32 bytes of keccak output are combined into (hi, lo)
         //  where r = challenges.evm_word().
@@ -654,6 +812,8 @@ impl<F: Field> SubCircuitConfig<F> for PiCircuitConfig<F> {
         // The layout for entire pi circuit looks like
         // data bytes:      |   rpi   | rpi_bytes | rpi_bytes_acc | rpi_rlc_acc | rpi_length_acc |
         //                  |   ..    |     ..    |      ...      |   dbs_rlc   |    input_len   |
+        // l1 msg hashes:   |   ..    |     ..    |      ...      |   lhs_rlc   |    input_len   |
+        // receipts:        |   ..    |     ..    |      ...      |   rcs_rlc   |    input_len   |
         // chunk_txbytes:   |   ..    |     ..    |      ...      |      ...    |       ...      |
         //  chain_id        | chain_id|     ..    |      ...      |     ...     |      ...       |
         // prev_state_root  |   ..    |     ..    |      ...      |     ...     |      ...       |
@@ -756,6 +916,8 @@ impl<F: Field> SubCircuitConfig<F> for PiCircuitConfig<F> {
             is_rpi_padding,
             real_rpi,
             q_tx_hashes,
+            #[cfg(feature = "receipt-circuit")]
+            q_receipts,
             q_field_step,
             q_chunk_txbytes,
             is_field_rlc,
@@ -780,6 +942,11 @@ struct Connections<F: Field> {
     start_state_root: AssignedCell<F, F>,
     end_state_root: AssignedCell<F, F>,
     withdraw_root: AssignedCell<F, F>,
+    start_l1_queue_index: AssignedCell<F, F>,
+    end_l1_queue_index: AssignedCell<F, F>,
+    // [block number, logs bloom, receipts root] of each block
+    #[cfg(feature = "receipt-circuit")]
+    receipts: Vec<[AssignedCell<F, F>; 3]>,
 }
 
 impl<F: Field> PiCircuitConfig<F> {
@@ -811,7 +978,28 @@ impl<F: Field> PiCircuitConfig<F> {
     /// |          |------------------------|--------------------------|
     /// |          | rlc(data_bytes)        | <- q_keccak == 1         |
     /// |----------|------------------------|--------------------------|
-    /// | *PART 2* |                        |                          |
+    /// |          | rpi initialise         |                          |
+    /// | *PART 2* | prev_l1_msg_hash       |                          |
+    /// |          |------------------------|--------------------------|
+    /// | ASSIGN   | tx_hash\[0\]           |                          |
+    /// | L1 MSG   | ...                    |                          |
+    /// | HASHES   | tx_hash\[n\]           | <- q_tx_hashes == 1      |
+    /// |          | DUMMY_TX_HASH          |                          |
+    /// |          | ...                    |                          |
+    /// |          |------------------------|--------------------------|
+    /// |          | rlc(l1_msg_hash_bytes) | <- q_keccak == 1         |
+    /// |----------|------------------------|--------------------------|
+    /// |          | rpi initialise         |                          |
+    /// |          | block\[0\].number      |                          |
+    /// | *PART 3* | logs_bloom\[0\]        |                          |
+    /// |          | receipts_root\[0\]     | <- q_receipts == 1       |
+    /// | ASSIGN   | ...                    |                          |
+    /// | RECEIPTS | PADDING                |                          |
+    /// |          | ...                    |                          |
+    /// |          |------------------------|--------------------------|
+    /// |          | rlc(receipts_bytes)    | <- q_keccak == 1         |
+    /// |----------|------------------------|--------------------------|
+    /// | *PART 4* |                        |                          |
     /// | ASSIGN   |                        |                          |
     /// | CHUNK    | chunk_txbytes_hash_rlc | <- q_chunk_txbytes == 1  |
     /// | TXBYTES  |                        |                          |
@@ -819,19 +1007,24 @@ impl<F: Field> PiCircuitConfig<F> {
     /// |----------|------------------------|--------------------------|
     /// |          | rpi initialise         |                          |
     /// |          | chain_id               |                          |
-    /// | *PART 3* | prev_state_root        |                          |
+    /// | *PART 5* | prev_state_root        |                          |
     /// |          | next_state_root        |                          |
     /// | ASSIGN   | withdraw_trie_root     |                          |
     /// | PI       | data_hash              |                          |
     /// | BYTES    | chunk_txbytes_hash     |                          |
+    /// |          | start_l1_queue_index   |                          |
+    /// |          | end_l1_queue_index     |                          |
+    /// |          | prev_l1_msg_hash       |                          |
+    /// |          | post_l1_msg_hash       |                          |
+    /// |          | receipts_hash          |                          |
     /// |          |------------------------|--------------------------|
     /// |          | rlc(pi_bytes)          | <- q_keccak == 1         |
     /// |----------|------------------------|--------------------------|
-    /// | *PART 4* | rpi initialise         |                          |
+    /// | *PART 6* | rpi initialise         |                          |
     /// | ASSIGN   | pi_hash_hi             |                          |
     /// | PI HASH  | pi_hash_lo             |                          |
     /// |----------|------------------------|--------------------------|
-    /// | *PART 5* | rpi initialise         |                          |
+    /// | *PART 7* | rpi initialise         |                          |
     /// | ASSIGN   | coinbase               |                          |
     /// | CONSTS   | difficulty             |                          |
     /// |----------|------------------------|--------------------------|
@@ -845,6 +1038,9 @@ impl<F: Field> PiCircuitConfig<F> {
     /// of the public input hash, hence we need 16 rows to assign the pi_hash_lo field. In
     /// addition, for those rows represent the field, the `q_field_step` fixed column is enabled.
     ///
+    /// PART 3 and the receipts_hash of PART 5 are only assigned with the `receipt-circuit`
+    /// feature. Without it, PART 4 directly follows PART 2.
+    ///
     /// Since we already know the maximum number of blocks and txs that we will assign in this
     /// layout, all the `q_*` columns are fixed. For blocks and txs, we pad the remaining layout
     /// with a padded field and mark it by the `is_rpi_padding` identifier.
@@ -857,21 +1053,37 @@ impl<F: Field> PiCircuitConfig<F> {
         challenges: &Challenges<Value<F>>,
     ) -> Result<(PiHashExport<F>, Connections<F>), Error> {
         // 1. Assign data bytes.
-        let (offset, data_hash_rlc_cell) = self.assign_data_bytes(
+        let (offset, data_hash_rlc_cell, tx_hash_cells) = self.assign_data_bytes(
             region,
             0, /* offset == 0 */
             public_data,
             block_value_cells,
             challenges,
         )?;
+        debug_assert_eq!(offset, public_data.l1_msg_hashes_start_offset());
+
+        // 2. Assign the rolling hash of L1 messages.
+        let (offset, prev_l1_msg_hash_cell, post_l1_msg_hash_rlc_cell) =
+            self.assign_l1_msg_hashes(region, offset, public_data, &tx_hash_cells, challenges)?;
+
+        // 3. Assign the receipts roots and logs blooms.
+        #[cfg(feature = "receipt-circuit")]
+        let (offset, receipts) = {
+            debug_assert_eq!(offset, public_data.receipts_start_offset());
+            let (offset, receipts_cells, receipts_hash_rlc_cell) =
+                self.assign_receipts(region, offset, public_data, block_value_cells, challenges)?;
+            (offset, Some((receipts_hash_rlc_cell, receipts_cells)))
+        };
+        #[cfg(not(feature = "receipt-circuit"))]
+        let receipts = None;
         debug_assert_eq!(offset, public_data.q_chunk_txbytes_start_offset());
 
-        // 2. Assign chunk tx bytes.
+        // 4. Assign chunk tx bytes.
         let (offset, chunk_txbytes_hash_rlc_cell) =
             self.assign_chunk_txbytes(region, offset, public_data, tx_value_cells, challenges)?;
         debug_assert_eq!(offset, public_data.pi_bytes_start_offset());
 
-        // 3. Assign public input bytes.
+        // 5. Assign public input bytes.
         let (offset, pi_hash_rlc_cell, connections) = self.assign_pi_bytes(
             region,
             offset,
@@ -880,16 +1092,19 @@ impl<F: Field> PiCircuitConfig<F> {
             tx_value_cells,
             &data_hash_rlc_cell,
             &chunk_txbytes_hash_rlc_cell,
+            &prev_l1_msg_hash_cell,
+            &post_l1_msg_hash_rlc_cell,
+            receipts,
             challenges,
         )?;
         debug_assert_eq!(offset, public_data.pi_hash_start_offset());
 
-        // 4. Assign public input hash (hi, lo) decomposition.
+        // 6. Assign public input hash (hi, lo) decomposition.
         let (offset, pi_hash_cells) =
             self.assign_pi_hash(region, offset, public_data, &pi_hash_rlc_cell, challenges)?;
         debug_assert_eq!(offset, public_data.constants_start_offset());
 
-        // 5. Assign block coinbase and difficulty.
+        // 7. Assign block coinbase and difficulty.
         let offset =
             self.assign_constants(region, offset, public_data, block_value_cells, challenges)?;
         debug_assert_eq!(offset, public_data.constants_end_offset() + 1);
@@ -899,6 +1114,10 @@ impl<F: Field> PiCircuitConfig<F> {
 
     /// Assign data bytes, that represent the pre-image to data_hash.
     /// i.e. keccak256(rlc(data_bytes)) == data_hash.
+    ///
+    /// Besides the data_hash rlc cell, the rpi cells of all the tx hash slots (including the
+    /// padding ones) are returned.
+    #[allow(clippy::type_complexity)]
     fn assign_data_bytes(
         &self,
         region: &mut Region<'_, F>,
@@ -906,7 +1125,7 @@ impl<F: Field> PiCircuitConfig<F> {
         public_data: &PublicData,
         block_value_cells: &[AssignedCell<F, F>],
         challenges: &Challenges<Value<F>>,
-    ) -> Result<(usize, AssignedCell<F, F>), Error> {
+    ) -> Result<(usize, AssignedCell<F, F>, Vec<AssignedCell<F, F>>), Error> {
         // Initialise the RLC accumulator and length values.
         let (mut offset, mut rpi_rlc_acc, mut rpi_length) = self.assign_rlc_init(region, offset)?;
 
@@ -1066,6 +1285,7 @@ impl<F: Field> PiCircuitConfig<F> {
             offset = tmp_offset;
             rpi_rlc_acc = tmp_rpi_rlc_acc;
             rpi_length = tmp_rpi_length;
+            tx_copy_cells.push(cells[RPI_CELL_IDX].clone());
 
             if i == (public_data.max_txs - n_txs) - 1 {
                 data_bytes_rlc = Some(cells[RPI_RLC_ACC_CELL_IDX].clone());
@@ -1101,8 +1321,227 @@ impl<F: Field> PiCircuitConfig<F> {
         };
         self.q_keccak.enable(region, offset)?;
 
-        // After the data bytes, an empty header row is provided for the chunk_txbytes section
-        Ok((offset + 1, data_hash_rlc_cell))
+        // After the data bytes, an empty header row is provided for the l1 msg hashes section
+        Ok((offset + 1, data_hash_rlc_cell, tx_copy_cells))
+    }
+
+    /// Assign the bytes that represent the pre-image to the rolling hash of L1 messages,
+    /// i.e. keccak256(rlc(prev_l1_msg_hash || l1_msg_hashes)) == post_l1_msg_hash.
+    ///
+    /// The tx hash slots are laid out exactly as in the data bytes and are copied from there,
+    /// the padding slots do not contribute to the pre-image.
+    #[allow(clippy::type_complexity)]
+    fn assign_l1_msg_hashes(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        public_data: &PublicData,
+        tx_hash_cells: &[AssignedCell<F, F>],
+        challenges: &Challenges<Value<F>>,
+    ) -> Result<(usize, AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        debug_assert_eq!(tx_hash_cells.len(), public_data.max_txs);
+        let (mut offset, mut rpi_rlc_acc, mut rpi_length) = self.assign_rlc_init(region, offset)?;
+
+        // Enable fixed columns for tx hashes.
+        for q_offset in
+            public_data.q_l1_msg_hashes_start_offset()..public_data.q_l1_msg_hashes_end_offset()
+        {
+            region.assign_fixed(
+                || "q_tx_hashes",
+                self.q_tx_hashes,
+                q_offset,
+                || Value::known(F::one()),
+            )?;
+        }
+        // Enable RLC accumulator consistency check throughout the above rows.
+        for q_offset in
+            public_data.l1_msg_hashes_start_offset()..public_data.l1_msg_hashes_end_offset()
+        {
+            self.q_not_end.enable(region, q_offset)?;
+        }
+
+        // Assign prev_l1_msg_hash
+        let mut cells;
+        (offset, rpi_rlc_acc, rpi_length, cells) = self.assign_field(
+            region,
+            offset,
+            &public_data.prev_l1_msg_hash.to_fixed_bytes(),
+            RpiFieldType::DefaultType,
+            false, // no padding in this case
+            rpi_rlc_acc,
+            rpi_length,
+            challenges,
+        )?;
+        let prev_l1_msg_hash_cell = cells[RPI_CELL_IDX].clone();
+        let mut l1_msg_hash_bytes_rlc = cells[RPI_RLC_ACC_CELL_IDX].clone();
+        let mut l1_msg_hash_bytes_length = cells[RPI_LENGTH_ACC_CELL_IDX].clone();
+
+        // Assign the L1Msg hashes followed by the padding hashes
+        let l1_msg_hashes = public_data
+            .transactions
+            .iter()
+            .filter(|&tx| tx.tx_type == TxType::L1Msg)
+            .map(|tx| tx.hash)
+            .collect::<Vec<H256>>();
+        for (i, tx_hash_cell) in tx_hash_cells.iter().enumerate() {
+            let is_rpi_padding = i >= l1_msg_hashes.len();
+            let tx_hash = if is_rpi_padding {
+                get_dummy_tx_hash()
+            } else {
+                l1_msg_hashes[i]
+            };
+            (offset, rpi_rlc_acc, rpi_length, cells) = self.assign_field(
+                region,
+                offset,
+                &tx_hash.to_fixed_bytes(),
+                RpiFieldType::DefaultType,
+                is_rpi_padding,
+                rpi_rlc_acc,
+                rpi_length,
+                challenges,
+            )?;
+            // Copy the tx hash from the same slot in data bytes.
+            region.constrain_equal(tx_hash_cell.cell(), cells[RPI_CELL_IDX].cell())?;
+            l1_msg_hash_bytes_rlc = cells[RPI_RLC_ACC_CELL_IDX].clone();
+            l1_msg_hash_bytes_length = cells[RPI_LENGTH_ACC_CELL_IDX].clone();
+        }
+
+        // Assign row for validating lookup to check:
+        // post_l1_msg_hash == keccak256(rlc(l1_msg_hash_bytes))
+        l1_msg_hash_bytes_rlc.copy_advice(
+            || "l1_msg_hash_bytes_rlc in the rpi col",
+            region,
+            self.raw_public_inputs,
+            offset,
+        )?;
+        l1_msg_hash_bytes_length.copy_advice(
+            || "l1_msg_hash_bytes_length in the rpi_length_acc col",
+            region,
+            self.rpi_length_acc,
+            offset,
+        )?;
+        let post_l1_msg_hash_rlc_cell = {
+            let post_l1_msg_hash_rlc = rlc_be_bytes(
+                &public_data.get_post_l1_msg_hash().to_fixed_bytes(),
+                challenges.evm_word(),
+            );
+            region.assign_advice(
+                || "post_l1_msg_hash_rlc",
+                self.rpi_rlc_acc,
+                offset,
+                || post_l1_msg_hash_rlc,
+            )?
+        };
+        self.q_keccak.enable(region, offset)?;
+
+        // After the l1 msg hashes, an empty header row is provided for the chunk_txbytes section
+        Ok((offset + 1, prev_l1_msg_hash_cell, post_l1_msg_hash_rlc_cell))
+    }
+
+    /// Assign the bytes that represent the pre-image to the receipts hash, i.e. the number, logs
+    /// bloom and receipts root of each block, followed by the padding blocks.
+    ///
+    /// The block numbers are copied from the block table, and the cells of each block are
+    /// returned to be connected with the receipt circuit.
+    #[cfg(feature = "receipt-circuit")]
+    #[allow(clippy::type_complexity)]
+    fn assign_receipts(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        public_data: &PublicData,
+        block_value_cells: &[AssignedCell<F, F>],
+        challenges: &Challenges<Value<F>>,
+    ) -> Result<(usize, Vec<[AssignedCell<F, F>; 3]>, AssignedCell<F, F>), Error> {
+        let (mut offset, mut rpi_rlc_acc, mut rpi_length) = self.assign_rlc_init(region, offset)?;
+
+        // Enable fixed columns for receipts.
+        for q_offset in public_data.q_receipts_start_offset()..public_data.q_receipts_end_offset() {
+            region.assign_fixed(
+                || "q_receipts",
+                self.q_receipts,
+                q_offset,
+                || Value::known(F::one()),
+            )?;
+        }
+        // Enable RLC accumulator consistency check throughout the above rows.
+        for q_offset in public_data.receipts_start_offset()..public_data.receipts_end_offset() {
+            self.q_not_end.enable(region, q_offset)?;
+        }
+
+        let n_blocks = public_data.receipts.len();
+        let mut receipts_cells = vec![];
+        let mut cells = vec![];
+        for (i, (number, (receipts_root, logs_bloom))) in public_data
+            .receipts
+            .iter()
+            .map(|(&number, &receipts)| (number, receipts))
+            .chain(iter::repeat((0, Default::default())))
+            .take(public_data.max_inner_blocks)
+            .enumerate()
+        {
+            let is_rpi_padding = i >= n_blocks;
+            let block_cells = [
+                number.to_be_bytes().to_vec(),
+                logs_bloom.as_bytes().to_vec(),
+                receipts_root.to_fixed_bytes().to_vec(),
+            ]
+            .iter()
+            .map(|value_be_bytes| {
+                (offset, rpi_rlc_acc, rpi_length, cells) = self.assign_field(
+                    region,
+                    offset,
+                    value_be_bytes,
+                    RpiFieldType::DefaultType,
+                    is_rpi_padding,
+                    rpi_rlc_acc,
+                    rpi_length,
+                    challenges,
+                )?;
+                Ok(cells[RPI_CELL_IDX].clone())
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+            // Copy the block number from the block table, where padding blocks have number 0.
+            region.constrain_equal(
+                block_cells[0].cell(),
+                block_value_cells[i * BLOCK_LEN + BLOCK_NUM_OFFSET].cell(),
+            )?;
+            receipts_cells.push(block_cells.try_into().unwrap());
+        }
+        let receipts_bytes_rlc = cells[RPI_RLC_ACC_CELL_IDX].clone();
+        let receipts_bytes_length = cells[RPI_LENGTH_ACC_CELL_IDX].clone();
+
+        // Assign row for validating lookup to check:
+        // receipts_hash == keccak256(rlc(receipts_bytes))
+        receipts_bytes_rlc.copy_advice(
+            || "receipts_bytes_rlc in the rpi col",
+            region,
+            self.raw_public_inputs,
+            offset,
+        )?;
+        receipts_bytes_length.copy_advice(
+            || "receipts_bytes_length in the rpi_length_acc col",
+            region,
+            self.rpi_length_acc,
+            offset,
+        )?;
+        let receipts_hash_rlc_cell = {
+            let receipts_hash_rlc = rlc_be_bytes(
+                &public_data.get_receipts_hash().to_fixed_bytes(),
+                challenges.evm_word(),
+            );
+            region.assign_advice(
+                || "receipts_hash_rlc",
+                self.rpi_rlc_acc,
+                offset,
+                || receipts_hash_rlc,
+            )?
+        };
+        self.q_keccak.enable(region, offset)?;
+
+        // After the receipts, an empty header row is provided for the chunk_txbytes section
+        Ok((offset + 1, receipts_cells, receipts_hash_rlc_cell))
     }
 
     /// Assign chunk txbytes hash
@@ -1160,6 +1599,9 @@ impl<F: Field> PiCircuitConfig<F> {
         tx_value_cells: &[AssignedCell<F, F>],
         data_hash_rlc_cell: &AssignedCell<F, F>,
         chunk_txbytes_hash_rlc_cell: &AssignedCell<F, F>,
+        prev_l1_msg_hash_cell: &AssignedCell<F, F>,
+        post_l1_msg_hash_rlc_cell: &AssignedCell<F, F>,
+        receipts: Option<(AssignedCell<F, F>, Vec<[AssignedCell<F, F>; 3]>)>,
         challenges: &Challenges<Value<F>>,
     ) -> Result<(usize, AssignedCell<F, F>, Connections<F>), Error> {
         let (mut offset, mut rpi_rlc_acc, mut rpi_length) = self.assign_rlc_init(region, offset)?;
@@ -1207,12 +1649,6 @@ impl<F: Field> PiCircuitConfig<F> {
                 tx_value_cells[tx_id * TX_LEN + CHAIN_ID_OFFSET_IN_TX - 1].cell(),
             )?;
         }
-        // connections to be done with other sub-circuits.
-        let connections = Connections {
-            start_state_root: rpi_cells[1].clone(),
-            end_state_root: rpi_cells[2].clone(),
-            withdraw_root: rpi_cells[3].clone(),
-        };
 
         // Assign data_hash
         (offset, rpi_rlc_acc, rpi_length, cells) = self.assign_field(
@@ -1231,7 +1667,7 @@ impl<F: Field> PiCircuitConfig<F> {
         region.constrain_equal(data_hash_rlc_cell.cell(), data_hash_cell.cell())?;
 
         // Assign chunk txbytes hash
-        (offset, _, _, cells) = self.assign_field(
+        (offset, rpi_rlc_acc, rpi_length, cells) = self.assign_field(
             region,
             offset,
             &public_data.get_chunk_txbytes_hash().to_fixed_bytes(),
@@ -1242,8 +1678,6 @@ impl<F: Field> PiCircuitConfig<F> {
             challenges,
         )?;
         let chunk_txbytes_hash_cell = cells[RPI_CELL_IDX].clone();
-        let pi_bytes_rlc = cells[RPI_RLC_ACC_CELL_IDX].clone();
-        let pi_bytes_length = cells[RPI_LENGTH_ACC_CELL_IDX].clone();
 
         // Copy chunk_txbytes_hash value from the previous section.
         region.constrain_equal(
@@ -1251,6 +1685,69 @@ impl<F: Field> PiCircuitConfig<F> {
             chunk_txbytes_hash_rlc_cell.cell(),
         )?;
 
+        // Assign [start_l1_queue_index, end_l1_queue_index, prev_l1_msg_hash, post_l1_msg_hash].
+        let l1_msg_cells = [
+            public_data.start_l1_queue_index.to_be_bytes().to_vec(),
+            public_data.end_l1_queue_index().to_be_bytes().to_vec(),
+            public_data.prev_l1_msg_hash.to_fixed_bytes().to_vec(),
+            public_data.get_post_l1_msg_hash().to_fixed_bytes().to_vec(),
+        ]
+        .iter()
+        .map(|value_be_bytes| {
+            (offset, rpi_rlc_acc, rpi_length, cells) = self.assign_field(
+                region,
+                offset,
+                value_be_bytes,
+                RpiFieldType::DefaultType,
+                false, // no padding in this case.
+                rpi_rlc_acc,
+                rpi_length,
+                challenges,
+            )?;
+            Ok(cells[RPI_CELL_IDX].clone())
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+        // Copy the rolling hashes of L1 messages from the l1 msg hashes section.
+        region.constrain_equal(l1_msg_cells[2].cell(), prev_l1_msg_hash_cell.cell())?;
+        region.constrain_equal(l1_msg_cells[3].cell(), post_l1_msg_hash_rlc_cell.cell())?;
+
+        // Assign receipts_hash
+        #[cfg(feature = "receipt-circuit")]
+        let (offset, cells, receipts_cells) = {
+            let (receipts_hash_rlc_cell, receipts_cells) =
+                receipts.expect("receipts section is assigned with the receipt circuit");
+            let (offset, _, _, cells) = self.assign_field(
+                region,
+                offset,
+                &public_data.get_receipts_hash().to_fixed_bytes(),
+                RpiFieldType::DefaultType,
+                false, // no padding in this case
+                rpi_rlc_acc,
+                rpi_length,
+                challenges,
+            )?;
+
+            // Copy receipts_hash value from the receipts section.
+            region.constrain_equal(cells[RPI_CELL_IDX].cell(), receipts_hash_rlc_cell.cell())?;
+            (offset, cells, receipts_cells)
+        };
+        #[cfg(not(feature = "receipt-circuit"))]
+        debug_assert!(receipts.is_none());
+        let pi_bytes_rlc = cells[RPI_RLC_ACC_CELL_IDX].clone();
+        let pi_bytes_length = cells[RPI_LENGTH_ACC_CELL_IDX].clone();
+
+        // connections to be done with other sub-circuits.
+        let connections = Connections {
+            start_state_root: rpi_cells[1].clone(),
+            end_state_root: rpi_cells[2].clone(),
+            withdraw_root: rpi_cells[3].clone(),
+            start_l1_queue_index: l1_msg_cells[0].clone(),
+            end_l1_queue_index: l1_msg_cells[1].clone(),
+            #[cfg(feature = "receipt-circuit")]
+            receipts: receipts_cells,
+        };
+
         // Assign row for validating lookup to check:
         // pi_hash == keccak256(rlc(pi_bytes))
         pi_bytes_rlc.copy_advice(
@@ -1791,6 +2288,9 @@ impl<F: Field> PiCircuit<F> {
             prev_state_root: prev_state_root_in_trie,
             next_state_root: block.post_state_root(),
             withdraw_trie_root: H256(block.withdraw_root.to_be_bytes()),
+            prev_l1_msg_hash: block.prev_l1_msg_hash,
+            #[cfg(feature = "receipt-circuit")]
+            receipts: block.receipts_roots_and_blooms(),
         };
 
         Self {
@@ -1817,6 +2317,7 @@ impl<F: Field> PiCircuit<F> {
         layouter: &mut impl Layouter<F>,
         state_roots: Option<&StateCircuitExports<Assigned<F>>>,
         withdraw_roots: Option<&EvmCircuitExports<Assigned<F>>>,
+        l1_queue_indices: Option<&TxCircuitExports<Assigned<F>>>,
     ) -> Result<(), Error> {
         let local_conn = self
             .connections
@@ -1860,6 +2361,70 @@ impl<F: Field> PiCircuit<F> {
                     log::warn!("withdraw roots are not set, skip connection with evm circuit");
                 }
 
+                if let Some(l1_queue_indices) = l1_queue_indices {
+                    log::debug!(
+                        "constrain_equal of l1 queue index: {:?} <-> {:?}",
+                        (
+                            &local_conn.start_l1_queue_index,
+                            &local_conn.end_l1_queue_index
+                        ),
+                        (
+                            &l1_queue_indices.start_l1_queue_index,
+                            &l1_queue_indices.end_l1_queue_index
+                        )
+                    );
+                    region.constrain_equal(
+                        local_conn.start_l1_queue_index.cell(),
+                        l1_queue_indices.start_l1_queue_index.0,
+                    )?;
+                    region.constrain_equal(
+                        local_conn.end_l1_queue_index.cell(),
+                        l1_queue_indices.end_l1_queue_index.0,
+                    )?;
+                } else {
+                    log::warn!("l1 queue indices are not set, skip connection with tx circuit");
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    /// Connect the receipts roots and logs blooms exported by the receipt circuit when we are in
+    /// super circuit
+    #[cfg(feature = "receipt-circuit")]
+    pub fn connect_receipts(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        receipts: Option<&ReceiptCircuitExports<Assigned<F>>>,
+    ) -> Result<(), Error> {
+        let local_conn = self
+            .connections
+            .borrow()
+            .clone()
+            .expect("expected to be called after syncthesis");
+
+        layouter.assign_region(
+            || "pi receipts connecting region",
+            |mut region| {
+                if let Some(receipts) = receipts {
+                    log::debug!(
+                        "constrain_equal of receipts: {:?} <-> {:?}",
+                        &local_conn.receipts,
+                        receipts
+                    );
+                    for (i, [block_number, logs_bloom, receipts_root]) in
+                        local_conn.receipts.iter().enumerate()
+                    {
+                        region.constrain_equal(block_number.cell(), receipts.block_numbers[i].0)?;
+                        region.constrain_equal(logs_bloom.cell(), receipts.logs_blooms[i].0)?;
+                        region
+                            .constrain_equal(receipts_root.cell(), receipts.receipts_roots[i].0)?;
+                    }
+                } else {
+                    log::warn!("receipts are not set, skip connection with receipt circuit");
+                }
+
                 Ok(())
             },
         )
@@ -1887,16 +2452,28 @@ impl<F: Field> SubCircuit<F> for PiCircuit<F> {
         let num_rows = 1 + max_inner_blocks * BLOCK_HEADER_BYTES_NUM
             + max_txs * KECCAK_DIGEST_SIZE
             + 1 // for data hash row
+            + 1 // for l1 msg hashes start row
+            + KECCAK_DIGEST_SIZE // prev l1 msg hash
+            + max_txs * KECCAK_DIGEST_SIZE
+            + 1 // for post l1 msg hash row
             + 1 // for chunk txbytes hash row
             + 1 // for pi bytes start row
             + N_BYTES_U64 // chain_id
             + 4 * KECCAK_DIGEST_SIZE // state_roots & data hash
+            + 2 * N_BYTES_U64 // l1 queue indices
+            + 2 * KECCAK_DIGEST_SIZE // l1 msg hashes
             + 1 // for pi hash row
             + 1 // for pi hash bytes start row
             + KECCAK_DIGEST_SIZE // pi hash bytes
             + 1 // for coinbase & difficulty start row
             + N_BYTES_ACCOUNT_ADDRESS
             + N_BYTES_WORD;
+        #[cfg(feature = "receipt-circuit")]
+        let num_rows = num_rows
+            + 1 // for receipts start row
+            + max_inner_blocks * RECEIPTS_BYTES_NUM
+            + 1 // for receipts hash row
+            + KECCAK_DIGEST_SIZE; // receipts hash
 
         (
             (tx_usage * block.circuits_params.max_vertical_circuit_rows as f32).ceil() as usize,
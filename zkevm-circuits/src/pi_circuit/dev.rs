@@ -30,6 +30,9 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize, const MAX_INNER_
                 prev_state_root: H256::zero(),
                 next_state_root: H256::zero(),
                 withdraw_trie_root: H256::zero(),
+                prev_l1_msg_hash: H256::zero(),
+                #[cfg(feature = "receipt-circuit")]
+                receipts: Default::default(),
                 block_ctxs: Default::default(),
             },
             connections: Default::default(),
@@ -123,16 +126,21 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize, const MAX_INNER_
         )?;
         // assign keccak table
         let data_bytes = self.0.public_data.data_bytes();
+        let l1_msg_hash_bytes = self.0.public_data.l1_msg_hash_bytes();
         let chunk_txbytes = self.0.public_data.chunk_txbytes();
         let pi_bytes = self.0.public_data.pi_bytes(
             self.0.public_data.get_data_hash(),
             self.0.public_data.get_chunk_txbytes_hash(),
+            self.0.public_data.get_post_l1_msg_hash(),
         );
-        config.keccak_table.dev_load(
-            &mut layouter,
-            vec![&data_bytes, &chunk_txbytes, &pi_bytes],
-            &challenges,
-        )?;
+        let keccak_inputs = vec![&data_bytes, &l1_msg_hash_bytes, &chunk_txbytes, &pi_bytes];
+        #[cfg(feature = "receipt-circuit")]
+        let receipts_bytes = self.0.public_data.receipts_bytes();
+        #[cfg(feature = "receipt-circuit")]
+        let keccak_inputs = [keccak_inputs, vec![&receipts_bytes]].concat();
+        config
+            .keccak_table
+            .dev_load(&mut layouter, keccak_inputs, &challenges)?;
 
         self.0.import_tx_values(tx_value_cells);
         self.0.synthesize_sub(&config, &challenges, &mut layouter)?;
@@ -2,6 +2,9 @@
 pub(super) const BLOCK_LEN: usize = 10;
 pub(super) const BYTE_POW_BASE: u64 = 256;
 pub(super) const BLOCK_HEADER_BYTES_NUM: usize = 58;
+/// Block number, logs bloom and receipts root of a block
+#[cfg(feature = "receipt-circuit")]
+pub(super) const RECEIPTS_BYTES_NUM: usize = 296;
 pub(super) const KECCAK_DIGEST_SIZE: usize = 32;
 
 pub(super) const RPI_CELL_IDX: usize = 0;
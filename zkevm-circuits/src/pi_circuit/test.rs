@@ -106,6 +106,28 @@ fn serial_test_simple_pi() {
     );
 }
 
+#[cfg(feature = "scroll")]
+#[test]
+fn serial_test_pi_prev_l1_msg_hash() {
+    const MAX_TXS: usize = 4;
+    const MAX_CALLDATA: usize = 20;
+    const MAX_INNER_BLOCKS: usize = 4;
+
+    let mut difficulty_be_bytes = [0u8; 32];
+    MOCK_DIFFICULTY.to_big_endian(&mut difficulty_be_bytes);
+    set_var("DIFFICULTY", hex::encode(difficulty_be_bytes));
+    set_var("COINBASE", "0x0000000000000000000000000000000000000000");
+
+    let mut block = block_1tx();
+    block.prev_l1_msg_hash = H256::repeat_byte(0xab);
+
+    let k = 16;
+    assert_eq!(
+        run::<Fr, MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS>(k, block),
+        Ok(())
+    );
+}
+
 fn run_size_check<
     F: Field,
     const MAX_TXS: usize,
@@ -0,0 +1,3119 @@
+//! The receipt circuit constrains the receipts root and the logs bloom of every block in the
+//! chunk, and exports them to the PI circuit.
+//!
+//! The circuit is laid out in four parts:
+//! - Bloom sections of [`BLOOM_BITS`] rows holding the bits of a bloom, one per block and one per
+//!   tx. Every set bit of a tx is set in its block, and every set bit of a block is set by one of
+//!   its txs.
+//! - Items of [`ITEM_ROWS`] rows, one per address or topic of a log: its 32 bytes (addresses are
+//!   left padded with zeros), looked up in the rw table, followed by the 32 bytes of its keccak
+//!   hash, looked up in the keccak table. The three 11-bit words taken from the hash are the bloom
+//!   bits of the item, tied to the set bits of the section of its tx by lookups both ways.
+//! - The node stream, holding byte by byte the RLP encoding of the nodes of the receipts trie of
+//!   each block (see [`ReceiptTrieNode`]), split into tagged segments. The stream checks the
+//!   fields of each receipt against the tx, block and rw tables and against the bloom section of
+//!   its tx, hashes each node with a keccak lookup, and looks every child of a branch up among the
+//!   hashed nodes. The root of each block is looked up from its bloom section.
+//! - A fixed table of the allowed transitions between segments and of the shape of the trie.
+//!
+//! The addresses, topics and data bytes of the logs in the node stream are assigned to the
+//! [`LogTable`] and looked up in the rw table, while the state circuit looks every `TxLog` row up
+//! in the [`LogTable`], so that the receipts hold exactly the logs of the rw table.
+
+#[cfg(any(feature = "test", test, feature = "test-circuits"))]
+mod dev;
+#[cfg(any(feature = "test", test, feature = "test-circuits"))]
+mod test;
+#[cfg(any(feature = "test", test, feature = "test-circuits"))]
+pub use dev::ReceiptTestCircuit;
+
+use std::{cell::RefCell, marker::PhantomData};
+
+use crate::{
+    evm_circuit::{
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_WORD},
+        util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    },
+    table::{
+        BlockContextFieldTag, BlockTable, KeccakTable, LogTable, LookupTable, RwTable, RwTableTag,
+        TxFieldTag, TxLogFieldTag, TxReceiptFieldTag, TxTable, U8Table,
+    },
+    util::{
+        build_tx_log_address, build_tx_log_expression,
+        is_zero::{IsZeroChip, IsZeroConfig},
+        rlc_be_bytes, Challenges, Field, SubCircuit, SubCircuitConfig,
+    },
+    witness::{
+        self, leaf_key, logs_bloom, receipt_trie_nodes, receipts_root, tx_type_byte, Receipt,
+        ReceiptTag, ReceiptTrieNode, Rw, BLOOM_BITS, MAX_RECEIPTS_PER_BLOCK,
+    },
+};
+use eth_types::{geth_types::TxType, ToAddress, ToBigEndian, ToScalar, Word, H256};
+use ethers_core::{types::Bloom, utils::keccak256};
+use gadgets::{
+    impl_expr,
+    util::{and, not, select, sum, Expr},
+};
+#[cfg(feature = "onephase")]
+use halo2_proofs::plonk::FirstPhase as SecondPhase;
+#[cfg(not(feature = "onephase"))]
+use halo2_proofs::plonk::SecondPhase;
+use halo2_proofs::{
+    circuit::{AssignedCell, Cell, Layouter, Region, Value},
+    plonk::{Advice, Assigned, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use strum::IntoEnumIterator;
+
+/// Number of rows of a log address or topic: 32 input bytes, then 32 bytes of its hash.
+pub const ITEM_ROWS: usize = 2 * N_BYTES_WORD;
+/// Number of zero bytes an address is left padded with.
+const ADDRESS_PADDING: usize = N_BYTES_WORD - N_BYTES_ACCOUNT_ADDRESS;
+/// Number of bloom bits set by an item.
+const N_BLOOM_BITS_PER_ITEM: usize = 3;
+/// Number of segment tags of the node stream.
+const N_RECEIPT_TAGS: usize = ReceiptTag::BranchValue as usize + 1;
+/// Number of columns of the fixed table.
+const N_ROM_COLUMNS: usize = 8;
+
+/// Segments starting with an RLP list or string header whose length is carried in `acc`.
+const HEADER_TAGS: [ReceiptTag; 8] = [
+    ReceiptTag::LeafHeader,
+    ReceiptTag::ValueHeader,
+    ReceiptTag::ReceiptHeader,
+    ReceiptTag::LogsHeader,
+    ReceiptTag::LogHeader,
+    ReceiptTag::TopicsHeader,
+    ReceiptTag::DataHeader,
+    ReceiptTag::BranchHeader,
+];
+/// Headers of RLP strings, the others are headers of RLP lists.
+const STRING_HEADER_TAGS: [ReceiptTag; 2] = [ReceiptTag::ValueHeader, ReceiptTag::DataHeader];
+/// Headers whose payload runs until the end of the node.
+const NODE_END_HEADER_TAGS: [ReceiptTag; 5] = [
+    ReceiptTag::LeafHeader,
+    ReceiptTag::ValueHeader,
+    ReceiptTag::ReceiptHeader,
+    ReceiptTag::LogsHeader,
+    ReceiptTag::BranchHeader,
+];
+/// Segments of the payload of a log.
+const LOG_BODY_TAGS: [ReceiptTag; 5] = [
+    ReceiptTag::Address,
+    ReceiptTag::TopicsHeader,
+    ReceiptTag::Topic,
+    ReceiptTag::DataHeader,
+    ReceiptTag::Data,
+];
+/// Segments that may follow a node.
+const NODE_START_TAGS: [ReceiptTag; 3] = [
+    ReceiptTag::LeafHeader,
+    ReceiptTag::BranchHeader,
+    ReceiptTag::Padding,
+];
+
+/// Allowed transitions from a segment to the next one.
+const TRANSITIONS: [(ReceiptTag, &[ReceiptTag]); 21] = {
+    use ReceiptTag::*;
+    [
+        (LeafHeader, &[Key]),
+        (Key, &[ValueHeader]),
+        (ValueHeader, &[TxType, ReceiptHeader]),
+        (TxType, &[ReceiptHeader]),
+        (ReceiptHeader, &[Status]),
+        (Status, &[Gas]),
+        (Gas, &[BloomHeader]),
+        (BloomHeader, &[Bloom]),
+        (Bloom, &[LogsHeader]),
+        (LogsHeader, &[LogHeader, LeafHeader, BranchHeader, Padding]),
+        (LogHeader, &[Address]),
+        (Address, &[TopicsHeader]),
+        (TopicsHeader, &[Topic, DataHeader, Data]),
+        (Topic, &[Topic, DataHeader, Data]),
+        (
+            DataHeader,
+            &[Data, LogHeader, LeafHeader, BranchHeader, Padding],
+        ),
+        (Data, &[LogHeader, LeafHeader, BranchHeader, Padding]),
+        (BranchHeader, &[Child, EmptyChild]),
+        (Child, &[Child, EmptyChild, BranchValue]),
+        (EmptyChild, &[Child, EmptyChild, BranchValue]),
+        (BranchValue, &[LeafHeader, BranchHeader, Padding]),
+        (Padding, &[Padding]),
+    ]
+};
+
+/// Kind of a row of the fixed table, in its first column.
+#[derive(Clone, Copy, Debug)]
+enum RomKind {
+    /// (tag, next tag)
+    Transition = 1,
+    /// (tx type, has type byte, type byte)
+    TxType,
+    /// (level, index, key, key length, path, min receipts, max receipts)
+    Leaf,
+    /// (level, path, slot, child path, min receipts)
+    Slot,
+}
+impl_expr!(RomKind);
+
+/// Rows of the leaf table: the leaf of the receipt `index` in a block of `n` receipts, with `n`
+/// in `[n_lo, n_hi]`, is at `(level, path)` and has the encoded key `key` of `key_rem + 1`
+/// bytes. See [`ReceiptTrieNode`] for the shape of the trie.
+fn leaf_rom() -> Vec<[u64; 7]> {
+    let max = MAX_RECEIPTS_PER_BLOCK as u64;
+    let key = |level: u64, index: u64| {
+        leaf_key(level, index as usize)
+            .into_iter()
+            .fold(0, |acc, byte| acc * 256 + byte as u64)
+    };
+
+    // [level, index, key, key_rem, path, n_lo, n_hi]
+    let mut rows = vec![
+        [0, 0, key(0, 0), 2, 0, 1, 1],
+        [1, 0, key(1, 0), 0, 8, 2, max],
+        [1, 1, key(1, 1), 0, 0, 2, 2],
+    ];
+    rows.extend((1..16).map(|i| [2, i, key(2, i), 0, i, 3, max]));
+    for g in 1..8 {
+        rows.push([1, 16 * g, key(1, 16 * g), 0, g, 16 * g + 1, 16 * g + 1]);
+        rows.extend((16 * g..16 * g + 16).map(|i| [2, i, key(2, i), 0, i, 16 * g + 2, max]));
+    }
+    rows
+}
+
+/// Rows of the slot table: the slot `slot` of the branch at `(level, path)` holds the node at
+/// `(level + 1, child_path)` if the block has more than `min` receipts, and is empty otherwise.
+fn slot_rom() -> Vec<[u64; 5]> {
+    let max = MAX_RECEIPTS_PER_BLOCK as u64;
+
+    // [level, path, slot, child_path, min]
+    let mut rows = (0..16)
+        .map(|slot| {
+            let min = match slot {
+                8 => 0,
+                0 => 1,
+                1..=7 => 16 * slot,
+                _ => max,
+            };
+            [0, 0, slot, slot, min]
+        })
+        .collect::<Vec<_>>();
+    for g in 0..8 {
+        rows.extend((0..16).map(|j| {
+            let child_path = 16 * g + j;
+            // the receipt 0 lives in the slot 8 of the root
+            let min = if child_path == 0 { max } else { child_path };
+            [1, g, j, child_path, min]
+        }));
+    }
+    rows
+}
+
+/// Config for ReceiptCircuit
+#[derive(Clone, Debug)]
+pub struct ReceiptCircuitConfig<F> {
+    // bloom sections
+    q_bloom: Column<Fixed>,
+    is_tx_section: Column<Fixed>,
+    q_block_start: Column<Fixed>,
+    q_tx_start: Column<Fixed>,
+    // section start of all the blocks but the first one, or of all the txs but the first one
+    q_section_next: Column<Fixed>,
+    q_byte_first: Column<Fixed>,
+    q_byte_last: Column<Fixed>,
+    bit_index: Column<Fixed>,
+    is_set: Column<Advice>,
+    is_padding: Column<Advice>,
+    bloom_byte_acc: Column<Advice>,
+    bloom_rlc: Column<Advice>,
+    num_txs_is_zero: IsZeroConfig<F>,
+
+    // items
+    q_item: Column<Fixed>,
+    q_item_first: Column<Fixed>,
+    q_input: Column<Fixed>,
+    q_input_last: Column<Fixed>,
+    q_hash_first: Column<Fixed>,
+    q_hash_last: Column<Fixed>,
+    q_address_padding: Column<Fixed>,
+    // rows holding the high byte of an 11-bit word of the hash
+    q_bit_hi: Column<Fixed>,
+    // rows holding the low byte of an 11-bit word of the hash
+    q_bit_idx: Column<Fixed>,
+    is_enabled: Column<Advice>,
+    is_address: Column<Advice>,
+    topic_index: Column<Advice>,
+    // the 3 lowest bits of the high byte on `q_bit_hi` rows, the bloom bit on `q_bit_idx` rows
+    aux: Column<Advice>,
+
+    // node stream
+    q_node: Column<Fixed>,
+    q_node_first: Column<Fixed>,
+    q_node_last: Column<Fixed>,
+    is_tag: [Column<Advice>; N_RECEIPT_TAGS],
+    seg_rem: Column<Advice>,
+    seg_rem_is_zero: IsZeroConfig<F>,
+    seg_first: Column<Advice>,
+    node_rem: Column<Advice>,
+    node_rem_is_zero: IsZeroConfig<F>,
+    is_node_end: Column<Advice>,
+    is_log_item: Column<Advice>,
+    // constant over the rows of a node
+    node_len: Column<Advice>,
+    level: Column<Advice>,
+    path: Column<Advice>,
+    index: Column<Advice>,
+    index_is_zero: IsZeroConfig<F>,
+    cum_num_txs: Column<Advice>,
+    tx_type: Column<Advice>,
+    has_type: Column<Advice>,
+    type_byte: Column<Advice>,
+    status: Column<Advice>,
+    key: Column<Advice>,
+    key_rem: Column<Advice>,
+    min_num_txs: Column<Advice>,
+    max_num_txs: Column<Advice>,
+    // carried from a leaf to the next one
+    rw_gas: Column<Advice>,
+    gas_base: Column<Advice>,
+    // log and branch counters
+    log_rem: Column<Advice>,
+    topics_rem: Column<Advice>,
+    count: Column<Advice>,
+    child_path: Column<Advice>,
+    child_min: Column<Advice>,
+
+    // shared by the parts
+    block_number: Column<Advice>,
+    tx_id: Column<Advice>,
+    num_txs: Column<Advice>,
+    log_id: Column<Advice>,
+    byte: Column<Advice>,
+    // bytes of an item or a segment, as a value or an RLC
+    acc: Column<Advice>,
+    input_rlc: Column<Advice>,
+    hash_rlc: Column<Advice>,
+
+    // fixed table
+    rom: [Column<Fixed>; N_ROM_COLUMNS],
+
+    /// LogTable
+    pub log_table: LogTable,
+
+    // External tables
+    rw_table: RwTable,
+    tx_table: TxTable,
+    block_table: BlockTable,
+    keccak_table: KeccakTable,
+    u8_table: U8Table,
+}
+
+/// Circuit configuration arguments
+pub struct ReceiptCircuitConfigArgs<F: Field> {
+    /// RwTable
+    pub rw_table: RwTable,
+    /// TxTable
+    pub tx_table: TxTable,
+    /// BlockTable
+    pub block_table: BlockTable,
+    /// KeccakTable
+    pub keccak_table: KeccakTable,
+    /// u8 lookup table
+    pub u8_table: U8Table,
+    /// LogTable, assigned by this circuit
+    pub log_table: LogTable,
+    /// Challenges
+    pub challenges: Challenges<Expression<F>>,
+}
+
+impl<F: Field> SubCircuitConfig<F> for ReceiptCircuitConfig<F> {
+    type ConfigArgs = ReceiptCircuitConfigArgs<F>;
+
+    /// Return a new ReceiptCircuitConfig
+    fn new(
+        meta: &mut ConstraintSystem<F>,
+        Self::ConfigArgs {
+            rw_table,
+            tx_table,
+            block_table,
+            keccak_table,
+            u8_table,
+            log_table,
+            challenges,
+        }: Self::ConfigArgs,
+    ) -> Self {
+        let q_bloom = meta.fixed_column();
+        let is_tx_section = meta.fixed_column();
+        let q_block_start = meta.fixed_column();
+        let q_tx_start = meta.fixed_column();
+        let q_section_next = meta.fixed_column();
+        let q_byte_first = meta.fixed_column();
+        let q_byte_last = meta.fixed_column();
+        let bit_index = meta.fixed_column();
+        let is_set = meta.advice_column();
+        let is_padding = meta.advice_column();
+        let bloom_byte_acc = meta.advice_column();
+        let bloom_rlc = meta.advice_column_in(SecondPhase);
+
+        let q_item = meta.fixed_column();
+        let q_item_first = meta.fixed_column();
+        let q_input = meta.fixed_column();
+        let q_input_last = meta.fixed_column();
+        let q_hash_first = meta.fixed_column();
+        let q_hash_last = meta.fixed_column();
+        let q_address_padding = meta.fixed_column();
+        let q_bit_hi = meta.fixed_column();
+        let q_bit_idx = meta.fixed_column();
+        let is_enabled = meta.advice_column();
+        let is_address = meta.advice_column();
+        let topic_index = meta.advice_column();
+        let aux = meta.advice_column();
+
+        let q_node = meta.fixed_column();
+        let q_node_first = meta.fixed_column();
+        let q_node_last = meta.fixed_column();
+        let is_tag = [(); N_RECEIPT_TAGS].map(|_| meta.advice_column());
+        let seg_rem = meta.advice_column();
+        let seg_first = meta.advice_column();
+        let node_rem = meta.advice_column();
+        let is_node_end = meta.advice_column();
+        let is_log_item = meta.advice_column();
+        let node_len = meta.advice_column();
+        let level = meta.advice_column();
+        let path = meta.advice_column();
+        let index = meta.advice_column();
+        let cum_num_txs = meta.advice_column();
+        let tx_type = meta.advice_column();
+        let has_type = meta.advice_column();
+        let type_byte = meta.advice_column();
+        let status = meta.advice_column();
+        let key = meta.advice_column();
+        let key_rem = meta.advice_column();
+        let min_num_txs = meta.advice_column();
+        let max_num_txs = meta.advice_column();
+        let rw_gas = meta.advice_column();
+        let gas_base = meta.advice_column();
+        let log_rem = meta.advice_column();
+        let topics_rem = meta.advice_column();
+        let count = meta.advice_column();
+        let child_path = meta.advice_column();
+        let child_min = meta.advice_column();
+
+        let block_number = meta.advice_column();
+        let tx_id = meta.advice_column();
+        let num_txs = meta.advice_column();
+        let log_id = meta.advice_column();
+        let byte = meta.advice_column();
+        let acc = meta.advice_column_in(SecondPhase);
+        let input_rlc = meta.advice_column_in(SecondPhase);
+        let hash_rlc = meta.advice_column_in(SecondPhase);
+
+        let rom = [(); N_ROM_COLUMNS].map(|_| meta.fixed_column());
+
+        meta.enable_equality(block_number);
+        meta.enable_equality(hash_rlc);
+        meta.enable_equality(bloom_rlc);
+
+        rw_table.annotate_columns(meta);
+        tx_table.annotate_columns(meta);
+        block_table.annotate_columns(meta);
+        keccak_table.annotate_columns(meta);
+        log_table.annotate_columns(meta);
+
+        let num_txs_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_block_start, Rotation::cur()),
+            num_txs,
+            |meta| meta.advice_column(),
+        );
+        let seg_rem_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_node, Rotation::cur()),
+            seg_rem,
+            |meta| meta.advice_column(),
+        );
+        let node_rem_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_node, Rotation::cur()),
+            node_rem,
+            |meta| meta.advice_column(),
+        );
+        let index_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_node, Rotation::cur()),
+            index,
+            |meta| meta.advice_column(),
+        );
+
+        // 1. bloom sections
+        // | bit_index | is_set |   bloom_byte_acc    |        bloom_rlc         |
+        // |   2047    |   s0   |         s0          |            0             |
+        // |   2046    |   s1   |      2*s0 + s1      |            0             |
+        // |    ...    |  ...   |         ...         |            0             |
+        // |   2040    |   s7   |  128*s0 + .. + s7   |            b0            |
+        // |   2039    |   s8   |         s8          |            b0            |
+        // |    ...    |  ...   |         ...         |           ...            |
+        // |     0     |  s2047 |        b255         | b0*r^255 + ... + b255    |
+        // the byte accumulated over rows [8k, 8k + 8) is the byte k of the bloom, and
+        // bloom_rlc ends up as the RLC of the 256 bloom bytes.
+        meta.create_gate("bloom section", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let is_set = meta.query_advice(is_set, Rotation::cur());
+            let padding = meta.query_advice(is_padding, Rotation::cur());
+            let byte_acc = meta.query_advice(bloom_byte_acc, Rotation::cur());
+            let is_section_start = meta.query_fixed(q_block_start, Rotation::cur())
+                + meta.query_fixed(q_tx_start, Rotation::cur());
+
+            cb.require_boolean("is_set is boolean", is_set.expr());
+            cb.require_boolean("is_padding is boolean", padding.expr());
+            cb.require_equal(
+                "bloom_byte_acc = (q_byte_first ? 0 : bloom_byte_acc::prev * 2) + is_set",
+                byte_acc.expr(),
+                select::expr(
+                    meta.query_fixed(q_byte_first, Rotation::cur()),
+                    0.expr(),
+                    meta.query_advice(bloom_byte_acc, Rotation::prev()) * 2.expr(),
+                ) + is_set.expr(),
+            );
+            let bloom_rlc_prev =
+                not::expr(is_section_start.expr()) * meta.query_advice(bloom_rlc, Rotation::prev());
+            cb.require_equal(
+                "bloom_rlc = q_byte_last ? bloom_rlc::prev * r + bloom_byte_acc : bloom_rlc::prev",
+                meta.query_advice(bloom_rlc, Rotation::cur()),
+                select::expr(
+                    meta.query_fixed(q_byte_last, Rotation::cur()),
+                    bloom_rlc_prev.expr() * challenges.evm_word() + byte_acc,
+                    bloom_rlc_prev,
+                ),
+            );
+            cb.require_zero("no bit is set for padding", padding.expr() * is_set);
+            for (name, column) in [
+                ("block_number is 0 for padding", block_number),
+                ("tx_id is 0 for padding", tx_id),
+                ("num_txs is 0 for padding", num_txs),
+                ("hash_rlc is 0 for padding", hash_rlc),
+            ] {
+                cb.require_zero(
+                    name,
+                    padding.expr() * meta.query_advice(column, Rotation::cur()),
+                );
+            }
+            cb.require_zero(
+                "tx_id is 0 in the section of a block",
+                not::expr(meta.query_fixed(is_tx_section, Rotation::cur()))
+                    * meta.query_advice(tx_id, Rotation::cur()),
+            );
+            cb.condition(not::expr(is_section_start), |cb| {
+                for (name, column) in [
+                    ("block_number does not change in a section", block_number),
+                    ("tx_id does not change in a section", tx_id),
+                    ("is_padding does not change in a section", is_padding),
+                ] {
+                    cb.require_equal(
+                        name,
+                        meta.query_advice(column, Rotation::cur()),
+                        meta.query_advice(column, Rotation::prev()),
+                    );
+                }
+            });
+
+            cb.gate(meta.query_fixed(q_bloom, Rotation::cur()))
+        });
+
+        meta.create_gate("bloom sections of consecutive blocks or txs", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let padding = meta.query_advice(is_padding, Rotation::cur());
+
+            cb.require_zero(
+                "padding sections come last",
+                meta.query_advice(is_padding, Rotation::prev()) * not::expr(padding.expr()),
+            );
+            cb.condition(
+                and::expr([
+                    not::expr(meta.query_fixed(is_tx_section, Rotation::cur())),
+                    not::expr(padding),
+                ]),
+                |cb| {
+                    cb.require_equal(
+                        "block_number = block_number::prev + 1",
+                        meta.query_advice(block_number, Rotation::cur()),
+                        meta.query_advice(block_number, Rotation::prev()) + 1.expr(),
+                    );
+                },
+            );
+
+            cb.gate(meta.query_fixed(q_section_next, Rotation::cur()))
+        });
+
+        meta.create_gate("bloom section of a tx", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            cb.condition(
+                not::expr(meta.query_advice(is_padding, Rotation::cur())),
+                |cb| {
+                    cb.require_equal(
+                        "tx_id = (first tx ? 0 : tx_id::prev) + 1",
+                        meta.query_advice(tx_id, Rotation::cur()),
+                        meta.query_fixed(q_section_next, Rotation::cur())
+                            * meta.query_advice(tx_id, Rotation::prev())
+                            + 1.expr(),
+                    );
+                },
+            );
+
+            cb.gate(meta.query_fixed(q_tx_start, Rotation::cur()))
+        });
+
+        meta.lookup_any("block number of a tx section in tx table", |meta| {
+            let enable = and::expr([
+                meta.query_fixed(q_tx_start, Rotation::cur()),
+                not::expr(meta.query_advice(is_padding, Rotation::cur())),
+            ]);
+            let input_exprs = vec![
+                1.expr(), // q_enable = true
+                meta.query_advice(tx_id, Rotation::cur()),
+                TxFieldTag::BlockNumber.expr(),
+                0.expr(), // index
+                meta.query_advice(block_number, Rotation::cur()),
+            ];
+
+            input_exprs
+                .into_iter()
+                .zip(tx_table_exprs(meta, &tx_table))
+                .map(|(input, table)| (enable.expr() * input, table))
+                .collect()
+        });
+
+        meta.lookup_any("num_txs of a block section in block table", |meta| {
+            let enable = and::expr([
+                meta.query_fixed(q_block_start, Rotation::cur()),
+                not::expr(meta.query_advice(is_padding, Rotation::cur())),
+            ]);
+            let input_exprs = vec![
+                BlockContextFieldTag::NumTxs.expr(),
+                meta.query_advice(block_number, Rotation::cur()),
+                meta.query_advice(num_txs, Rotation::cur()),
+            ];
+
+            input_exprs
+                .into_iter()
+                .zip(block_table.table_exprs(meta))
+                .map(|(input, table)| (enable.expr() * input, table))
+                .collect()
+        });
+
+        meta.lookup("num_txs <= MAX_RECEIPTS_PER_BLOCK", |meta| {
+            let enable = and::expr([
+                meta.query_fixed(q_block_start, Rotation::cur()),
+                not::expr(meta.query_advice(is_padding, Rotation::cur())),
+            ]);
+            let num_txs = meta.query_advice(num_txs, Rotation::cur());
+            vec![(
+                enable * (MAX_RECEIPTS_PER_BLOCK.expr() - num_txs),
+                u8_table.into(),
+            )]
+        });
+
+        // the receipts trie of a block without txs is empty, its root is keccak(rlp(""))
+        meta.lookup_any("empty receipts root", |meta| {
+            let enable = and::expr([
+                meta.query_fixed(q_block_start, Rotation::cur()),
+                not::expr(meta.query_advice(is_padding, Rotation::cur())),
+                num_txs_is_zero.expr(Rotation::cur())(meta),
+            ]);
+            let input_exprs = vec![
+                1.expr(),    // q_enable = true
+                1.expr(),    // is_final = true
+                0x80.expr(), // input_rlc of rlp("")
+                1.expr(),    // input_len
+                meta.query_advice(hash_rlc, Rotation::cur()),
+            ];
+
+            input_exprs
+                .into_iter()
+                .zip(keccak_table.table_exprs(meta))
+                .map(|(input, table)| (enable.expr() * input, table))
+                .collect()
+        });
+
+        // 2. items
+        // | byte | acc (RLC or value) | input_rlc |    aux    |    q    |
+        // |  i0  |         i0         |    i0     |           |  first  |
+        // | ...  |        ...         |   ...     |           |  input  |
+        // | i31  |  value of the item | input_rlc |           |  last   |
+        // |  h0  |         h0         |           | h0 & 7    | bit_hi  |
+        // |  h1  |      h0*r + h1     |           | bit 0     | bit_idx |
+        // | ...  |        ...         |           |    ...    |         |
+        // |  h5  |        ...         |           | bit 2     | bit_idx |
+        // | ...  |        ...         |           |           |         |
+        // | h31  |     hash RLC       |           |           |  last   |
+        meta.create_gate("log address or topic", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_item_first = meta.query_fixed(q_item_first, Rotation::cur());
+            let q_input = meta.query_fixed(q_input, Rotation::cur());
+            let is_address_cur = meta.query_advice(is_address, Rotation::cur());
+            let byte = meta.query_advice(byte, Rotation::cur());
+
+            cb.require_boolean(
+                "is_enabled is boolean",
+                meta.query_advice(is_enabled, Rotation::cur()),
+            );
+            cb.require_boolean("is_address is boolean", is_address_cur.expr());
+            cb.condition(not::expr(q_item_first.expr()), |cb| {
+                for (name, column) in [
+                    ("is_enabled", is_enabled),
+                    ("is_address", is_address),
+                    ("tx_id", tx_id),
+                ] {
+                    cb.require_equal(
+                        name,
+                        meta.query_advice(column, Rotation::cur()),
+                        meta.query_advice(column, Rotation::prev()),
+                    );
+                }
+            });
+
+            // addresses accumulate their value, topics and hashes their RLC
+            let base = select::expr(
+                and::expr([q_input.expr(), is_address_cur.expr()]),
+                256.expr(),
+                challenges.evm_word(),
+            );
+            cb.require_equal(
+                "acc = (first byte ? 0 : acc::prev * base) + byte",
+                meta.query_advice(acc, Rotation::cur()),
+                select::expr(
+                    q_item_first.expr() + meta.query_fixed(q_hash_first, Rotation::cur()),
+                    0.expr(),
+                    meta.query_advice(acc, Rotation::prev()) * base,
+                ) + byte.expr(),
+            );
+            cb.condition(q_input, |cb| {
+                cb.require_equal(
+                    "input_rlc = (first byte ? 0 : input_rlc::prev * keccak_rand) + byte",
+                    meta.query_advice(input_rlc, Rotation::cur()),
+                    select::expr(
+                        q_item_first,
+                        0.expr(),
+                        meta.query_advice(input_rlc, Rotation::prev()) * challenges.keccak_input(),
+                    ) + byte.expr(),
+                );
+            });
+            // leading zeros leave input_rlc at 0, so it is the RLC of the 20 address bytes
+            cb.condition(
+                and::expr([
+                    meta.query_fixed(q_address_padding, Rotation::cur()),
+                    is_address_cur,
+                ]),
+                |cb| {
+                    cb.require_zero("address padding byte is 0", byte);
+                },
+            );
+
+            cb.gate(meta.query_fixed(q_item, Rotation::cur()))
+        });
+
+        meta.lookup("byte in [0, 256)", |meta| {
+            let enable = meta.query_fixed(q_item, Rotation::cur())
+                + meta.query_fixed(q_node, Rotation::cur());
+            let byte = meta.query_advice(byte, Rotation::cur());
+            vec![(enable * byte, u8_table.into())]
+        });
+
+        // The bloom bit of the 11-bit word (hi, lo) is (hi & 7) * 256 + lo, where hi & 7 is
+        // witnessed in `aux` with hi = 8 * k + aux, aux in [0, 8) and k in [0, 256).
+        let bit_hi = |meta: &mut VirtualCells<F>| {
+            (
+                meta.query_fixed(q_bit_hi, Rotation::cur()),
+                meta.query_advice(byte, Rotation::cur()),
+                meta.query_advice(aux, Rotation::cur()),
+            )
+        };
+        meta.lookup("hi & 7 in [0, 256)", |meta| {
+            let (q_bit_hi, _, aux) = bit_hi(meta);
+            vec![(q_bit_hi * aux, u8_table.into())]
+        });
+        // with the lookup above, 32 * aux < 256 iff aux < 8
+        meta.lookup("hi & 7 in [0, 8)", |meta| {
+            let (q_bit_hi, _, aux) = bit_hi(meta);
+            vec![(q_bit_hi * aux * 32.expr(), u8_table.into())]
+        });
+        meta.lookup("hi >> 3 in [0, 256)", |meta| {
+            let (q_bit_hi, hi, aux) = bit_hi(meta);
+            let inv_8 = Expression::Constant(F::from(8).invert().unwrap());
+            vec![(q_bit_hi * (hi - aux) * inv_8, u8_table.into())]
+        });
+
+        meta.create_gate("bloom bit = (hi & 7) * 256 + lo", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            cb.require_equal(
+                "aux = aux::prev * 256 + byte",
+                meta.query_advice(aux, Rotation::cur()),
+                meta.query_advice(aux, Rotation::prev()) * 256.expr()
+                    + meta.query_advice(byte, Rotation::cur()),
+            );
+
+            cb.gate(meta.query_fixed(q_bit_idx, Rotation::cur()))
+        });
+
+        meta.lookup_any("log address or topic in rw table", |meta| {
+            let enable = and::expr([
+                meta.query_fixed(q_input_last, Rotation::cur()),
+                meta.query_advice(is_enabled, Rotation::cur()),
+            ]);
+            let field_tag = select::expr(
+                meta.query_advice(is_address, Rotation::cur()),
+                TxLogFieldTag::Address.expr(),
+                TxLogFieldTag::Topic.expr(),
+            );
+            let input_exprs = vec![
+                1.expr(), // q_enable = true
+                1.expr(), // is_write = true
+                RwTableTag::TxLog.expr(),
+                meta.query_advice(tx_id, Rotation::cur()),
+                build_tx_log_expression(
+                    meta.query_advice(topic_index, Rotation::cur()),
+                    field_tag,
+                    meta.query_advice(log_id, Rotation::cur()),
+                ),
+                0.expr(), // field_tag
+                meta.query_advice(acc, Rotation::cur()),
+            ];
+
+            input_exprs
+                .into_iter()
+                .zip(rw_table_exprs(meta, &rw_table))
+                .map(|(input, table)| (enable.expr() * input, table))
+                .collect()
+        });
+
+        meta.lookup_any("keccak(log address or topic)", |meta| {
+            let enable = and::expr([
+                meta.query_fixed(q_hash_last, Rotation::cur()),
+                meta.query_advice(is_enabled, Rotation::cur()),
+            ]);
+
+            let input_exprs = vec![
+                1.expr(), // q_enable = true
+                1.expr(), // is_final = true
+                meta.query_advice(input_rlc, Rotation(-(N_BYTES_WORD as i32))),
+                select::expr(
+                    meta.query_advice(is_address, Rotation::cur()),
+                    N_BYTES_ACCOUNT_ADDRESS.expr(),
+                    N_BYTES_WORD.expr(),
+                ),
+                meta.query_advice(acc, Rotation::cur()),
+            ];
+            let keccak_table_exprs = keccak_table.table_exprs(meta);
+            assert_eq!(input_exprs.len(), keccak_table_exprs.len());
+
+            input_exprs
+                .into_iter()
+                .zip(keccak_table_exprs)
+                .map(|(input, table)| (enable.expr() * input, table))
+                .collect()
+        });
+
+        // 3. bloom bits of the items <-> set bits of the txs <-> set bits of the blocks
+        let item_bit = |meta: &mut VirtualCells<F>| {
+            let enable = and::expr([
+                meta.query_fixed(q_bit_idx, Rotation::cur()),
+                meta.query_advice(is_enabled, Rotation::cur()),
+            ]);
+            vec![
+                enable.expr(),
+                enable.expr() * meta.query_advice(tx_id, Rotation::cur()),
+                enable * meta.query_advice(aux, Rotation::cur()),
+            ]
+        };
+        let tx_bit = |meta: &mut VirtualCells<F>, key: Column<Advice>| {
+            let enable = and::expr([
+                meta.query_fixed(is_tx_section, Rotation::cur()),
+                meta.query_advice(is_set, Rotation::cur()),
+            ]);
+            vec![
+                enable.expr(),
+                enable.expr() * meta.query_advice(key, Rotation::cur()),
+                enable * meta.query_fixed(bit_index, Rotation::cur()),
+            ]
+        };
+        let block_bit = |meta: &mut VirtualCells<F>| {
+            let enable = and::expr([
+                meta.query_fixed(q_bloom, Rotation::cur())
+                    - meta.query_fixed(is_tx_section, Rotation::cur()),
+                meta.query_advice(is_set, Rotation::cur()),
+            ]);
+            vec![
+                enable.expr(),
+                enable.expr() * meta.query_advice(block_number, Rotation::cur()),
+                enable * meta.query_fixed(bit_index, Rotation::cur()),
+            ]
+        };
+        meta.lookup_any("bloom bit of an item is set in its tx", |meta| {
+            item_bit(meta)
+                .into_iter()
+                .zip(tx_bit(meta, tx_id))
+                .collect()
+        });
+        meta.lookup_any("set bit of a tx comes from an item", |meta| {
+            tx_bit(meta, tx_id)
+                .into_iter()
+                .zip(item_bit(meta))
+                .collect()
+        });
+        meta.lookup_any("set bit of a tx is set in its block", |meta| {
+            tx_bit(meta, block_number)
+                .into_iter()
+                .zip(block_bit(meta))
+                .collect()
+        });
+        meta.lookup_any("set bit of a block comes from one of its txs", |meta| {
+            block_bit(meta)
+                .into_iter()
+                .zip(tx_bit(meta, block_number))
+                .collect()
+        });
+
+        // 4. node stream
+        // Each row holds a byte of the encoding of a node, in a segment tagged by `is_tag`.
+        // `seg_rem` and `node_rem` count the bytes left in the segment and in the node.
+        let tag = |meta: &mut VirtualCells<F>, tag: ReceiptTag, at: Rotation| {
+            meta.query_advice(is_tag[tag as usize], at)
+        };
+        let tags = |meta: &mut VirtualCells<F>, tags: &[ReceiptTag], at: Rotation| {
+            sum::expr(tags.iter().map(|t| tag(meta, *t, at)))
+        };
+        let node_first = |meta: &mut VirtualCells<F>| {
+            meta.query_advice(seg_first, Rotation::cur())
+                * tags(
+                    meta,
+                    &[ReceiptTag::LeafHeader, ReceiptTag::BranchHeader],
+                    Rotation::cur(),
+                )
+        };
+        let leaf_first = |meta: &mut VirtualCells<F>| {
+            meta.query_advice(seg_first, Rotation::cur())
+                * tag(meta, ReceiptTag::LeafHeader, Rotation::cur())
+        };
+
+        meta.create_gate("receipt trie node", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_first = meta.query_fixed(q_node_first, Rotation::cur());
+            let not_first = not::expr(q_first.expr());
+            let seg_first_cur = meta.query_advice(seg_first, Rotation::cur());
+            let seg_last_prev = seg_rem_is_zero.expr(Rotation::prev())(meta);
+            let node_last = node_rem_is_zero.expr(Rotation::cur())(meta);
+            let node_last_prev = node_rem_is_zero.expr(Rotation::prev())(meta);
+            let node_first = node_first(meta);
+            let is_padding = tag(meta, ReceiptTag::Padding, Rotation::cur());
+
+            for t in ReceiptTag::iter() {
+                cb.require_boolean("is_tag is boolean", tag(meta, t, Rotation::cur()));
+            }
+            cb.require_equal(
+                "one tag per row",
+                sum::expr(is_tag.map(|column| meta.query_advice(column, Rotation::cur()))),
+                1.expr(),
+            );
+            cb.require_equal(
+                "seg_first = q_node_first || seg_last::prev",
+                seg_first_cur.expr(),
+                q_first.expr() + not_first.expr() * seg_last_prev,
+            );
+            cb.condition(not_first.expr() * not::expr(seg_first_cur.expr()), |cb| {
+                cb.require_equal(
+                    "seg_rem = seg_rem::prev - 1",
+                    meta.query_advice(seg_rem, Rotation::cur()),
+                    meta.query_advice(seg_rem, Rotation::prev()) - 1.expr(),
+                );
+                for column in is_tag {
+                    cb.require_equal(
+                        "tag does not change in a segment",
+                        meta.query_advice(column, Rotation::cur()),
+                        meta.query_advice(column, Rotation::prev()),
+                    );
+                }
+            });
+
+            // nodes start right after the previous one ends, and the stream starts with a leaf
+            cb.require_zero(
+                "the stream starts with a leaf or padding",
+                q_first.expr()
+                    * (tags(
+                        meta,
+                        &[ReceiptTag::LeafHeader, ReceiptTag::Padding],
+                        Rotation::cur(),
+                    ) - 1.expr()),
+            );
+            cb.require_equal(
+                "a node starts iff the previous one ended",
+                not_first.expr()
+                    * seg_first_cur.expr()
+                    * tags(meta, &NODE_START_TAGS, Rotation::cur()),
+                not_first.expr() * node_last_prev,
+            );
+            cb.require_zero(
+                "a node ends with a segment",
+                node_last.expr() * meta.query_advice(seg_rem, Rotation::cur()),
+            );
+            for (name, column) in [
+                ("seg_rem is 0 for padding", seg_rem),
+                ("node_rem is 0 for padding", node_rem),
+                ("byte is 0 for padding", byte),
+            ] {
+                cb.require_zero(
+                    name,
+                    is_padding.expr() * meta.query_advice(column, Rotation::cur()),
+                );
+            }
+            cb.require_zero(
+                "the stream ends with padding",
+                meta.query_fixed(q_node_last, Rotation::cur()) * not::expr(is_padding.expr()),
+            );
+
+            cb.condition(node_first.expr(), |cb| {
+                cb.require_equal(
+                    "node_rem = node_len - 1 at the first byte of a node",
+                    meta.query_advice(node_rem, Rotation::cur()),
+                    meta.query_advice(node_len, Rotation::cur()) - 1.expr(),
+                );
+            });
+            cb.condition(
+                not_first.expr() * not::expr(node_first.expr() + is_padding.expr()),
+                |cb| {
+                    cb.require_equal(
+                        "node_rem = node_rem::prev - 1",
+                        meta.query_advice(node_rem, Rotation::cur()),
+                        meta.query_advice(node_rem, Rotation::prev()) - 1.expr(),
+                    );
+                },
+            );
+            cb.condition(not_first.expr() * not::expr(node_first.expr()), |cb| {
+                for column in [
+                    node_len,
+                    block_number,
+                    level,
+                    path,
+                    index,
+                    num_txs,
+                    cum_num_txs,
+                    tx_type,
+                    has_type,
+                    type_byte,
+                    status,
+                    key,
+                    key_rem,
+                    min_num_txs,
+                    max_num_txs,
+                ] {
+                    cb.require_equal(
+                        "node constant does not change in a node",
+                        meta.query_advice(column, Rotation::cur()),
+                        meta.query_advice(column, Rotation::prev()),
+                    );
+                }
+            });
+            cb.condition(not_first * not::expr(leaf_first(meta)), |cb| {
+                for column in [tx_id, rw_gas, gas_base] {
+                    cb.require_equal(
+                        "leaf value does not change until the next leaf",
+                        meta.query_advice(column, Rotation::cur()),
+                        meta.query_advice(column, Rotation::prev()),
+                    );
+                }
+            });
+            cb.condition(not::expr(is_padding.expr()), |cb| {
+                let byte = meta.query_advice(byte, Rotation::cur());
+                cb.require_equal(
+                    "input_rlc = (first byte of node ? 0 : input_rlc::prev * keccak_rand) + byte",
+                    meta.query_advice(input_rlc, Rotation::cur()),
+                    select::expr(
+                        node_first.expr(),
+                        0.expr(),
+                        meta.query_advice(input_rlc, Rotation::prev()) * challenges.keccak_input(),
+                    ) + byte,
+                );
+            });
+            cb.require_equal(
+                "is_node_end = node_last && !padding",
+                meta.query_advice(is_node_end, Rotation::cur()),
+                node_last * not::expr(is_padding),
+            );
+            cb.require_equal(
+                "is_log_item = seg_last && (address || topic)",
+                meta.query_advice(is_log_item, Rotation::cur()),
+                seg_rem_is_zero.expr(Rotation::cur())(meta)
+                    * tags(
+                        meta,
+                        &[ReceiptTag::Address, ReceiptTag::Topic],
+                        Rotation::cur(),
+                    ),
+            );
+
+            cb.gate(meta.query_fixed(q_node, Rotation::cur()))
+        });
+
+        meta.lookup_any("transition between segments", |meta| {
+            let enable = and::expr([
+                meta.query_fixed(q_node, Rotation::cur()),
+                not::expr(meta.query_fixed(q_node_first, Rotation::cur())),
+                meta.query_advice(seg_first, Rotation::cur()),
+            ]);
+            let tag_expr = |meta: &mut VirtualCells<F>, at: Rotation| {
+                sum::expr(ReceiptTag::iter().map(|t| t.expr() * tag(meta, t, at)))
+            };
+            let input_exprs = vec![
+                RomKind::Transition.expr(),
+                tag_expr(meta, Rotation::prev()),
+                tag_expr(meta, Rotation::cur()),
+            ];
+
+            input_exprs
+                .into_iter()
+                .zip(rom)
+                .map(|(input, column)| {
+                    (
+                        enable.expr() * input,
+                        meta.query_fixed(column, Rotation::cur()),
+                    )
+                })
+                .collect()
+        });
+
+        meta.lookup_any("keccak(node)", |meta| {
+            let enable = and::expr([
+                meta.query_fixed(q_node, Rotation::cur()),
+                meta.query_advice(is_node_end, Rotation::cur()),
+            ]);
+            let input_exprs = vec![
+                1.expr(), // q_enable = true
+                1.expr(), // is_final = true
+                meta.query_advice(input_rlc, Rotation::cur()),
+                meta.query_advice(node_len, Rotation::cur()),
+                meta.query_advice(hash_rlc, Rotation::cur()),
+            ];
+
+            input_exprs
+                .into_iter()
+                .zip(keccak_table.table_exprs(meta))
+                .map(|(input, table)| (enable.expr() * input, table))
+                .collect()
+        });
+
+        // The RLP headers: a short header `base + len` with len < 56, or a long header
+        // `base + 55 + len_of_len` followed by the len_of_len bytes of len.
+        meta.create_gate("receipt trie rlp header", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let seg_first = meta.query_advice(seg_first, Rotation::cur());
+            let seg_last = seg_rem_is_zero.expr(Rotation::cur())(meta);
+            let is_header = tags(meta, &HEADER_TAGS, Rotation::cur());
+            let base = 0xc0.expr() - 0x40.expr() * tags(meta, &STRING_HEADER_TAGS, Rotation::cur());
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let seg_rem = meta.query_advice(seg_rem, Rotation::cur());
+
+            cb.condition(
+                seg_first.expr() * is_header.expr() * seg_last.expr(),
+                |cb| {
+                    cb.require_equal(
+                        "acc = byte - base for a short header",
+                        acc.expr(),
+                        byte.expr() - base.expr(),
+                    );
+                },
+            );
+            cb.condition(
+                seg_first.expr() * is_header.expr() * not::expr(seg_last.expr()),
+                |cb| {
+                    cb.require_equal(
+                        "byte = base + 55 + seg_rem for a long header",
+                        byte.expr(),
+                        base + 55.expr() + seg_rem,
+                    );
+                    cb.require_zero("acc = 0 at the first byte of a long header", acc.expr());
+                },
+            );
+            cb.condition(not::expr(seg_first) * is_header, |cb| {
+                cb.require_equal(
+                    "acc = acc::prev * 256 + byte",
+                    acc.expr(),
+                    acc_prev * 256.expr() + byte.expr(),
+                );
+            });
+
+            cb.condition(
+                seg_last.expr() * tags(meta, &NODE_END_HEADER_TAGS, Rotation::cur()),
+                |cb| {
+                    cb.require_equal(
+                        "the payload runs until the end of the node",
+                        meta.query_advice(node_rem, Rotation::cur()),
+                        acc.expr(),
+                    );
+                },
+            );
+            cb.condition(
+                seg_last.expr() * tag(meta, ReceiptTag::LogHeader, Rotation::cur()),
+                |cb| {
+                    cb.require_equal(
+                        "log_rem = payload length of the log",
+                        meta.query_advice(log_rem, Rotation::cur()),
+                        acc.expr(),
+                    );
+                },
+            );
+            cb.condition(
+                seg_last * tag(meta, ReceiptTag::TopicsHeader, Rotation::cur()),
+                |cb| {
+                    cb.require_equal(
+                        "topics_rem = payload length of the topics",
+                        meta.query_advice(topics_rem, Rotation::cur()),
+                        acc,
+                    );
+                },
+            );
+
+            cb.gate(meta.query_fixed(q_node, Rotation::cur()))
+        });
+
+        meta.create_gate("receipt trie leaf", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let not_first = not::expr(meta.query_fixed(q_node_first, Rotation::cur()));
+            let seg_first = meta.query_advice(seg_first, Rotation::cur());
+            let seg_last = seg_rem_is_zero.expr(Rotation::cur())(meta);
+            let seg_rem = meta.query_advice(seg_rem, Rotation::cur());
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let index_is_zero = index_is_zero.expr(Rotation::cur())(meta);
+
+            cb.condition(leaf_first(meta), |cb| {
+                cb.require_equal(
+                    "tx_id = (first leaf ? 0 : tx_id::prev) + 1",
+                    meta.query_advice(tx_id, Rotation::cur()),
+                    not_first.expr() * meta.query_advice(tx_id, Rotation::prev()) + 1.expr(),
+                );
+                cb.require_equal(
+                    "index = tx_id - 1 - (cum_num_txs - num_txs)",
+                    meta.query_advice(index, Rotation::cur()),
+                    meta.query_advice(tx_id, Rotation::cur())
+                        - 1.expr()
+                        - meta.query_advice(cum_num_txs, Rotation::cur())
+                        + meta.query_advice(num_txs, Rotation::cur()),
+                );
+                // the cumulative gas used of the rw table runs over the chunk, the one of the
+                // receipts over the block
+                cb.require_equal(
+                    "gas_base = first receipt of a block ? rw_gas::prev : gas_base::prev",
+                    meta.query_advice(gas_base, Rotation::cur()),
+                    not_first.expr()
+                        * select::expr(
+                            index_is_zero,
+                            meta.query_advice(rw_gas, Rotation::prev()),
+                            meta.query_advice(gas_base, Rotation::prev()),
+                        ),
+                );
+                cb.require_boolean(
+                    "status is boolean",
+                    meta.query_advice(status, Rotation::cur()),
+                );
+            });
+
+            let is_key = tag(meta, ReceiptTag::Key, Rotation::cur());
+            cb.condition(seg_first.expr() * is_key.expr(), |cb| {
+                cb.require_equal(
+                    "seg_rem = key_rem at the first byte of the key",
+                    seg_rem.expr(),
+                    meta.query_advice(key_rem, Rotation::cur()),
+                );
+                cb.require_equal(
+                    "acc = byte at the first byte of the key",
+                    acc.expr(),
+                    byte.expr(),
+                );
+            });
+            cb.condition(not::expr(seg_first.expr()) * is_key.expr(), |cb| {
+                cb.require_equal(
+                    "acc = acc::prev * 256 + byte in the key",
+                    acc.expr(),
+                    acc_prev.expr() * 256.expr() + byte.expr(),
+                );
+            });
+            cb.condition(seg_last.expr() * is_key, |cb| {
+                cb.require_equal(
+                    "the key of the leaf",
+                    acc.expr(),
+                    meta.query_advice(key, Rotation::cur()),
+                );
+            });
+
+            cb.condition(tag(meta, ReceiptTag::TxType, Rotation::cur()), |cb| {
+                cb.require_zero("the tx type is a single byte", seg_rem.expr());
+                cb.require_equal(
+                    "the type byte of the tx",
+                    byte.expr(),
+                    meta.query_advice(type_byte, Rotation::cur()),
+                );
+            });
+            cb.condition(
+                seg_first.expr() * tag(meta, ReceiptTag::ValueHeader, Rotation::prev()),
+                |cb| {
+                    cb.require_equal(
+                        "the receipt of a typed tx starts with its type byte",
+                        tag(meta, ReceiptTag::TxType, Rotation::cur()),
+                        meta.query_advice(has_type, Rotation::cur()),
+                    );
+                },
+            );
+
+            cb.condition(tag(meta, ReceiptTag::Status, Rotation::cur()), |cb| {
+                cb.require_zero("the status is a single byte", seg_rem.expr());
+                cb.require_equal(
+                    "byte = status ? 0x01 : 0x80",
+                    byte.expr(),
+                    0x80.expr() - 0x7f.expr() * meta.query_advice(status, Rotation::cur()),
+                );
+            });
+
+            let is_gas = tag(meta, ReceiptTag::Gas, Rotation::cur());
+            cb.condition(seg_first.expr() * is_gas.expr(), |cb| {
+                cb.require_equal(
+                    "byte = 0x80 + length of the cumulative gas used",
+                    byte.expr(),
+                    0x80.expr() + seg_rem.expr(),
+                );
+                cb.require_zero("acc = 0 at the header of the gas", acc.expr());
+            });
+            cb.condition(not::expr(seg_first.expr()) * is_gas.expr(), |cb| {
+                cb.require_equal(
+                    "acc = acc::prev * 256 + byte in the gas",
+                    acc.expr(),
+                    acc_prev.expr() * 256.expr() + byte.expr(),
+                );
+            });
+            cb.condition(seg_last.expr() * is_gas, |cb| {
+                cb.require_equal(
+                    "cumulative gas used of the block = rw_gas - gas_base",
+                    acc.expr(),
+                    meta.query_advice(rw_gas, Rotation::cur())
+                        - meta.query_advice(gas_base, Rotation::cur()),
+                );
+            });
+
+            let is_bloom_header = tag(meta, ReceiptTag::BloomHeader, Rotation::cur());
+            cb.condition(seg_first.expr() * is_bloom_header.expr(), |cb| {
+                cb.require_equal("the bloom is a long string", byte.expr(), 0xb9.expr());
+                cb.require_equal("of 2 length bytes", seg_rem.expr(), 2.expr());
+            });
+            cb.condition(not::expr(seg_first.expr()) * is_bloom_header, |cb| {
+                cb.require_equal("the bloom is 0x0100 bytes long", byte, seg_rem.expr());
+            });
+            cb.condition(
+                seg_first * tag(meta, ReceiptTag::Bloom, Rotation::cur()),
+                |cb| {
+                    cb.require_equal("the bloom is 256 bytes long", seg_rem, 255.expr());
+                },
+            );
+
+            cb.gate(meta.query_fixed(q_node, Rotation::cur()))
+        });
+
+        meta.create_gate("receipt trie logs", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let seg_first = meta.query_advice(seg_first, Rotation::cur());
+            let seg_rem = meta.query_advice(seg_rem, Rotation::cur());
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let is_log_body = tags(meta, &LOG_BODY_TAGS, Rotation::cur());
+            let is_log_header = tag(meta, ReceiptTag::LogHeader, Rotation::cur());
+            let is_topic = tag(meta, ReceiptTag::Topic, Rotation::cur());
+            let is_data = tag(meta, ReceiptTag::Data, Rotation::cur());
+            let is_data_header_prev = tag(meta, ReceiptTag::DataHeader, Rotation::prev());
+            let is_data_end = tags(
+                meta,
+                &[ReceiptTag::Data, ReceiptTag::DataHeader],
+                Rotation::prev(),
+            );
+
+            cb.condition(is_log_body.expr(), |cb| {
+                cb.require_equal(
+                    "log_rem = log_rem::prev - 1",
+                    meta.query_advice(log_rem, Rotation::cur()),
+                    meta.query_advice(log_rem, Rotation::prev()) - 1.expr(),
+                );
+            });
+            cb.require_zero(
+                "a log ends with its payload",
+                seg_first.expr()
+                    * tags(
+                        meta,
+                        &[
+                            ReceiptTag::LogHeader,
+                            ReceiptTag::LeafHeader,
+                            ReceiptTag::BranchHeader,
+                            ReceiptTag::Padding,
+                        ],
+                        Rotation::cur(),
+                    )
+                    * is_data_end
+                    * meta.query_advice(log_rem, Rotation::prev()),
+            );
+            cb.condition(is_topic.expr(), |cb| {
+                cb.require_equal(
+                    "topics_rem = topics_rem::prev - 1",
+                    meta.query_advice(topics_rem, Rotation::cur()),
+                    meta.query_advice(topics_rem, Rotation::prev()) - 1.expr(),
+                );
+            });
+            cb.require_zero(
+                "the topics end with their payload",
+                seg_first.expr()
+                    * tags(
+                        meta,
+                        &[ReceiptTag::Data, ReceiptTag::DataHeader],
+                        Rotation::cur(),
+                    )
+                    * tags(
+                        meta,
+                        &[ReceiptTag::TopicsHeader, ReceiptTag::Topic],
+                        Rotation::prev(),
+                    )
+                    * meta.query_advice(topics_rem, Rotation::prev()),
+            );
+
+            cb.condition(tag(meta, ReceiptTag::LogsHeader, Rotation::cur()), |cb| {
+                cb.require_zero(
+                    "log_id starts at 0",
+                    meta.query_advice(log_id, Rotation::cur()),
+                );
+            });
+            cb.condition(seg_first.expr() * is_log_header.expr(), |cb| {
+                cb.require_equal(
+                    "log_id = log_id::prev + 1 for a new log",
+                    meta.query_advice(log_id, Rotation::cur()),
+                    meta.query_advice(log_id, Rotation::prev()) + 1.expr(),
+                );
+            });
+            cb.condition(
+                not::expr(seg_first.expr()) * is_log_header + is_log_body,
+                |cb| {
+                    cb.require_equal(
+                        "log_id does not change in a log",
+                        meta.query_advice(log_id, Rotation::cur()),
+                        meta.query_advice(log_id, Rotation::prev()),
+                    );
+                },
+            );
+
+            // count numbers the topics of a log and the children of a branch
+            let is_child = tags(
+                meta,
+                &[ReceiptTag::Child, ReceiptTag::EmptyChild],
+                Rotation::cur(),
+            );
+            cb.condition(seg_first.expr() * is_topic.expr(), |cb| {
+                cb.require_equal(
+                    "count = topic::prev ? count::prev + 1 : 0",
+                    meta.query_advice(count, Rotation::cur()),
+                    tag(meta, ReceiptTag::Topic, Rotation::prev())
+                        * (meta.query_advice(count, Rotation::prev()) + 1.expr()),
+                );
+            });
+            cb.condition(seg_first.expr() * is_child.expr(), |cb| {
+                cb.require_equal(
+                    "count = child::prev ? count::prev + 1 : 0",
+                    meta.query_advice(count, Rotation::cur()),
+                    tags(
+                        meta,
+                        &[ReceiptTag::Child, ReceiptTag::EmptyChild],
+                        Rotation::prev(),
+                    ) * (meta.query_advice(count, Rotation::prev()) + 1.expr()),
+                );
+            });
+            cb.condition(
+                not::expr(seg_first.expr()) * (is_topic.expr() + is_child),
+                |cb| {
+                    cb.require_equal(
+                        "count does not change in a segment",
+                        meta.query_advice(count, Rotation::cur()),
+                        meta.query_advice(count, Rotation::prev()),
+                    );
+                },
+            );
+
+            let is_address = tag(meta, ReceiptTag::Address, Rotation::cur());
+            cb.condition(seg_first.expr() * is_address.expr(), |cb| {
+                cb.require_equal("the address is a 20 bytes string", byte.expr(), 0x94.expr());
+                cb.require_equal(
+                    "seg_rem = 20 at the header of the address",
+                    seg_rem.expr(),
+                    N_BYTES_ACCOUNT_ADDRESS.expr(),
+                );
+                cb.require_zero("acc = 0 at the header of the address", acc.expr());
+            });
+            cb.condition(not::expr(seg_first.expr()) * is_address, |cb| {
+                cb.require_equal(
+                    "acc = acc::prev * 256 + byte in the address",
+                    acc.expr(),
+                    acc_prev.expr() * 256.expr() + byte.expr(),
+                );
+            });
+
+            let is_word = tags(
+                meta,
+                &[ReceiptTag::Topic, ReceiptTag::Child],
+                Rotation::cur(),
+            );
+            cb.condition(seg_first.expr() * is_word.expr(), |cb| {
+                cb.require_equal("the word is a 32 bytes string", byte.expr(), 0xa0.expr());
+                cb.require_equal(
+                    "seg_rem = 32 at the header of the word",
+                    seg_rem.expr(),
+                    N_BYTES_WORD.expr(),
+                );
+                cb.require_zero("acc = 0 at the header of the word", acc.expr());
+            });
+            cb.condition(not::expr(seg_first.expr()) * is_word, |cb| {
+                cb.require_equal(
+                    "acc = acc::prev * r + byte in the word",
+                    acc.expr(),
+                    acc_prev.expr() * challenges.evm_word() + byte.expr(),
+                );
+            });
+
+            // the header of the data is followed by as many data bytes as its length, data
+            // without header is a single byte below 0x80
+            cb.condition(
+                seg_first.expr() * is_data.expr() * is_data_header_prev.expr(),
+                |cb| {
+                    cb.require_equal(
+                        "seg_rem = length of the data - 1",
+                        seg_rem.expr(),
+                        acc_prev.expr() - 1.expr(),
+                    );
+                },
+            );
+            cb.condition(
+                seg_first.expr() * not::expr(is_data.expr()) * is_data_header_prev,
+                |cb| {
+                    cb.require_zero("the header of empty data", acc_prev.expr());
+                },
+            );
+            cb.condition(
+                seg_first.expr()
+                    * is_data.expr()
+                    * tags(
+                        meta,
+                        &[ReceiptTag::TopicsHeader, ReceiptTag::Topic],
+                        Rotation::prev(),
+                    ),
+                |cb| {
+                    cb.require_zero("data without header is a single byte", seg_rem);
+                },
+            );
+            cb.condition(seg_first.expr() * is_data.expr(), |cb| {
+                cb.require_zero("acc = 0 at the first data byte", acc.expr());
+            });
+            cb.condition(not::expr(seg_first) * is_data.expr(), |cb| {
+                cb.require_equal(
+                    "acc = acc::prev + 1 numbers the data bytes",
+                    acc.expr(),
+                    acc_prev + 1.expr(),
+                );
+            });
+
+            // the log table holds the addresses, topics and data bytes of the logs
+            let is_log_item = meta.query_advice(is_log_item, Rotation::cur());
+            let is_entry = is_log_item.expr() + is_data.expr();
+            let log_id = meta.query_advice(log_id, Rotation::cur());
+            cb.require_equal(
+                "log table tx_id",
+                meta.query_advice(log_table.tx_id, Rotation::cur()),
+                is_entry * meta.query_advice(tx_id, Rotation::cur()),
+            );
+            cb.require_equal(
+                "log table address",
+                meta.query_advice(log_table.address, Rotation::cur()),
+                is_log_item.expr()
+                    * build_tx_log_expression(
+                        is_topic.expr() * meta.query_advice(count, Rotation::cur()),
+                        tag(meta, ReceiptTag::Address, Rotation::cur())
+                            * TxLogFieldTag::Address.expr()
+                            + is_topic * TxLogFieldTag::Topic.expr(),
+                        log_id.expr(),
+                    )
+                    + is_data.expr()
+                        * build_tx_log_expression(acc.expr(), TxLogFieldTag::Data.expr(), log_id),
+            );
+            cb.require_equal(
+                "log table value",
+                meta.query_advice(log_table.value, Rotation::cur()),
+                is_log_item * acc + is_data * byte,
+            );
+
+            cb.gate(meta.query_fixed(q_node, Rotation::cur()))
+        });
+
+        meta.create_gate("receipt trie branch", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let seg_first = meta.query_advice(seg_first, Rotation::cur());
+
+            cb.condition(
+                tags(
+                    meta,
+                    &[ReceiptTag::EmptyChild, ReceiptTag::BranchValue],
+                    Rotation::cur(),
+                ),
+                |cb| {
+                    cb.require_equal(
+                        "an empty child or value is an empty string",
+                        meta.query_advice(byte, Rotation::cur()),
+                        0x80.expr(),
+                    );
+                    cb.require_zero(
+                        "an empty string is a single byte",
+                        meta.query_advice(seg_rem, Rotation::cur()),
+                    );
+                },
+            );
+            cb.condition(
+                seg_first.expr() * tag(meta, ReceiptTag::BranchValue, Rotation::cur()),
+                |cb| {
+                    cb.require_equal(
+                        "a branch has 16 children",
+                        meta.query_advice(count, Rotation::prev()),
+                        15.expr(),
+                    );
+                },
+            );
+            cb.condition(
+                not::expr(seg_first) * tag(meta, ReceiptTag::Child, Rotation::cur()),
+                |cb| {
+                    for column in [child_path, child_min] {
+                        cb.require_equal(
+                            "child value does not change in a child",
+                            meta.query_advice(column, Rotation::cur()),
+                            meta.query_advice(column, Rotation::prev()),
+                        );
+                    }
+                },
+            );
+
+            cb.gate(meta.query_fixed(q_node, Rotation::cur()))
+        });
+
+        // the RLP headers and the cumulative gas used are canonical
+        let header_first = |meta: &mut VirtualCells<F>| {
+            meta.query_fixed(q_node, Rotation::cur())
+                * meta.query_advice(seg_first, Rotation::cur())
+                * tags(meta, &HEADER_TAGS, Rotation::cur())
+        };
+        meta.lookup("short rlp header length in [0, 56)", |meta| {
+            let enable = header_first(meta) * seg_rem_is_zero.expr(Rotation::cur())(meta);
+            let acc = meta.query_advice(acc, Rotation::cur());
+            vec![
+                (enable.expr() * acc.expr(), u8_table.into()),
+                (enable * (55.expr() - acc), u8_table.into()),
+            ]
+        });
+        meta.lookup("long rlp header length of length in [1, 3]", |meta| {
+            let enable =
+                header_first(meta) * not::expr(seg_rem_is_zero.expr(Rotation::cur())(meta));
+            let seg_rem = meta.query_advice(seg_rem, Rotation::cur());
+            vec![
+                (enable.expr() * (seg_rem.expr() - 1.expr()), u8_table.into()),
+                (enable * (3.expr() - seg_rem), u8_table.into()),
+            ]
+        });
+        meta.lookup("long rlp header length without leading zero", |meta| {
+            let enable = meta.query_fixed(q_node, Rotation::cur())
+                * meta.query_advice(seg_first, Rotation::prev())
+                * not::expr(meta.query_advice(seg_first, Rotation::cur()))
+                * tags(meta, &HEADER_TAGS, Rotation::cur());
+            let min_byte = 1.expr() + 55.expr() * seg_rem_is_zero.expr(Rotation::cur())(meta);
+            vec![(
+                enable * (meta.query_advice(byte, Rotation::cur()) - min_byte),
+                u8_table.into(),
+            )]
+        });
+        meta.lookup("gas length in [1, 8]", |meta| {
+            let enable = meta.query_fixed(q_node, Rotation::cur())
+                * meta.query_advice(seg_first, Rotation::cur())
+                * tag(meta, ReceiptTag::Gas, Rotation::cur());
+            let seg_rem = meta.query_advice(seg_rem, Rotation::cur());
+            vec![
+                (enable.expr() * (seg_rem.expr() - 1.expr()), u8_table.into()),
+                (enable * (8.expr() - seg_rem), u8_table.into()),
+            ]
+        });
+        meta.lookup("gas without leading zero", |meta| {
+            let enable = meta.query_fixed(q_node, Rotation::cur())
+                * meta.query_advice(seg_first, Rotation::prev())
+                * not::expr(meta.query_advice(seg_first, Rotation::cur()))
+                * tag(meta, ReceiptTag::Gas, Rotation::cur());
+            let min_byte = 1.expr() + 0x7f.expr() * seg_rem_is_zero.expr(Rotation::cur())(meta);
+            vec![(
+                enable * (meta.query_advice(byte, Rotation::cur()) - min_byte),
+                u8_table.into(),
+            )]
+        });
+        meta.lookup(
+            "data after a header is not a single byte below 0x80",
+            |meta| {
+                let enable = meta.query_fixed(q_node, Rotation::cur())
+                    * meta.query_advice(seg_first, Rotation::cur())
+                    * tag(meta, ReceiptTag::Data, Rotation::cur())
+                    * tag(meta, ReceiptTag::DataHeader, Rotation::prev());
+                let min_byte = 0x80.expr() * seg_rem_is_zero.expr(Rotation::cur())(meta);
+                vec![(
+                    enable * (meta.query_advice(byte, Rotation::cur()) - min_byte),
+                    u8_table.into(),
+                )]
+            },
+        );
+        meta.lookup("data without header is below 0x80", |meta| {
+            let enable = meta.query_fixed(q_node, Rotation::cur())
+                * meta.query_advice(seg_first, Rotation::cur())
+                * tag(meta, ReceiptTag::Data, Rotation::cur())
+                * tags(
+                    meta,
+                    &[ReceiptTag::TopicsHeader, ReceiptTag::Topic],
+                    Rotation::prev(),
+                );
+            vec![(
+                enable * (0x7f.expr() - meta.query_advice(byte, Rotation::cur())),
+                u8_table.into(),
+            )]
+        });
+
+        // the leaves
+        let leaf_enable = |meta: &mut VirtualCells<F>| {
+            meta.query_fixed(q_node, Rotation::cur()) * leaf_first(meta)
+        };
+        for (name, field_tag, column) in [
+            (
+                "block number of a leaf in tx table",
+                TxFieldTag::BlockNumber,
+                block_number,
+            ),
+            ("tx type of a leaf in tx table", TxFieldTag::TxType, tx_type),
+        ] {
+            meta.lookup_any(name, |meta| {
+                let enable = leaf_enable(meta);
+                let input_exprs = vec![
+                    1.expr(), // q_enable = true
+                    meta.query_advice(tx_id, Rotation::cur()),
+                    field_tag.expr(),
+                    0.expr(), // index
+                    meta.query_advice(column, Rotation::cur()),
+                ];
+
+                enabled_lookup(enable, input_exprs, tx_table_exprs(meta, &tx_table))
+            });
+        }
+        meta.lookup_any("num_txs of a node in block table", |meta| {
+            let enable = meta.query_fixed(q_node, Rotation::cur()) * node_first(meta);
+            let input_exprs = vec![
+                BlockContextFieldTag::NumTxs.expr(),
+                meta.query_advice(block_number, Rotation::cur()),
+                meta.query_advice(num_txs, Rotation::cur()),
+            ];
+
+            enabled_lookup(enable, input_exprs, block_table.table_exprs(meta))
+        });
+        meta.lookup_any("cum_num_txs of a leaf in block table", |meta| {
+            let enable = leaf_enable(meta);
+            let input_exprs = vec![
+                BlockContextFieldTag::CumNumTxs.expr(),
+                meta.query_advice(block_number, Rotation::cur()),
+                meta.query_advice(cum_num_txs, Rotation::cur()),
+            ];
+
+            enabled_lookup(enable, input_exprs, block_table.table_exprs(meta))
+        });
+        for (name, field_tag, column) in [
+            (
+                "status of a leaf in rw table",
+                TxReceiptFieldTag::PostStateOrStatus,
+                status,
+            ),
+            (
+                "cumulative gas used of a leaf in rw table",
+                TxReceiptFieldTag::CumulativeGasUsed,
+                rw_gas,
+            ),
+        ] {
+            meta.lookup_any(name, |meta| {
+                let enable = leaf_enable(meta);
+                let input_exprs = vec![
+                    1.expr(), // q_enable = true
+                    1.expr(), // is_write = true
+                    RwTableTag::TxReceipt.expr(),
+                    meta.query_advice(tx_id, Rotation::cur()),
+                    0.expr(), // address
+                    field_tag.expr(),
+                    meta.query_advice(column, Rotation::cur()),
+                ];
+
+                enabled_lookup(enable, input_exprs, rw_table_exprs(meta, &rw_table))
+            });
+        }
+        meta.lookup_any("type byte of a leaf", |meta| {
+            let enable = leaf_enable(meta);
+            let input_exprs = vec![
+                RomKind::TxType.expr(),
+                meta.query_advice(tx_type, Rotation::cur()),
+                meta.query_advice(has_type, Rotation::cur()),
+                meta.query_advice(type_byte, Rotation::cur()),
+            ];
+
+            enabled_lookup(enable, input_exprs, rom_exprs(meta, &rom))
+        });
+        meta.lookup_any("position and key of a leaf", |meta| {
+            let enable = leaf_enable(meta);
+            let input_exprs = vec![
+                RomKind::Leaf.expr(),
+                meta.query_advice(level, Rotation::cur()),
+                meta.query_advice(index, Rotation::cur()),
+                meta.query_advice(key, Rotation::cur()),
+                meta.query_advice(key_rem, Rotation::cur()),
+                meta.query_advice(path, Rotation::cur()),
+                meta.query_advice(min_num_txs, Rotation::cur()),
+                meta.query_advice(max_num_txs, Rotation::cur()),
+            ];
+
+            enabled_lookup(enable, input_exprs, rom_exprs(meta, &rom))
+        });
+        meta.lookup("index of a leaf in [0, num_txs)", |meta| {
+            let enable = leaf_enable(meta);
+            let index = meta.query_advice(index, Rotation::cur());
+            let num_txs = meta.query_advice(num_txs, Rotation::cur());
+            vec![
+                (enable.expr() * index.expr(), u8_table.into()),
+                (enable * (num_txs - 1.expr() - index), u8_table.into()),
+            ]
+        });
+        meta.lookup("num_txs of a leaf in [min_num_txs, max_num_txs]", |meta| {
+            let enable = leaf_enable(meta);
+            let num_txs = meta.query_advice(num_txs, Rotation::cur());
+            vec![
+                (
+                    enable.expr()
+                        * (num_txs.expr() - meta.query_advice(min_num_txs, Rotation::cur())),
+                    u8_table.into(),
+                ),
+                (
+                    enable * (meta.query_advice(max_num_txs, Rotation::cur()) - num_txs),
+                    u8_table.into(),
+                ),
+            ]
+        });
+        meta.lookup_any("bloom of a leaf in the bloom section of its tx", |meta| {
+            let enable = meta.query_fixed(q_node, Rotation::cur())
+                * tag(meta, ReceiptTag::Bloom, Rotation::cur());
+            let input_exprs = vec![
+                1.expr(),
+                meta.query_advice(tx_id, Rotation::cur()),
+                8.expr() * meta.query_advice(seg_rem, Rotation::cur()),
+                meta.query_advice(byte, Rotation::cur()),
+            ];
+            let table_enable = meta.query_fixed(q_byte_last, Rotation::cur())
+                * meta.query_fixed(is_tx_section, Rotation::cur());
+            let table_exprs = vec![
+                1.expr(),
+                meta.query_advice(tx_id, Rotation::cur()),
+                meta.query_fixed(bit_index, Rotation::cur()),
+                meta.query_advice(bloom_byte_acc, Rotation::cur()),
+            ]
+            .into_iter()
+            .map(|expr| table_enable.expr() * expr)
+            .collect();
+
+            enabled_lookup(enable, input_exprs, table_exprs)
+        });
+
+        // the logs
+        meta.lookup_any("log address, topic or data byte in rw table", |meta| {
+            let enable = meta.query_fixed(q_node, Rotation::cur())
+                * (meta.query_advice(is_log_item, Rotation::cur())
+                    + tag(meta, ReceiptTag::Data, Rotation::cur()));
+            let input_exprs = vec![
+                1.expr(), // q_enable = true
+                1.expr(), // is_write = true
+                RwTableTag::TxLog.expr(),
+                meta.query_advice(log_table.tx_id, Rotation::cur()),
+                meta.query_advice(log_table.address, Rotation::cur()),
+                0.expr(), // field_tag
+                meta.query_advice(log_table.value, Rotation::cur()),
+            ];
+
+            enabled_lookup(enable, input_exprs, rw_table_exprs(meta, &rw_table))
+        });
+        meta.lookup_any("log address or topic is an item", |meta| {
+            let enable = meta.query_fixed(q_node, Rotation::cur())
+                * meta.query_advice(is_log_item, Rotation::cur());
+            let input_exprs = vec![
+                1.expr(),
+                meta.query_advice(log_table.tx_id, Rotation::cur()),
+                meta.query_advice(log_table.address, Rotation::cur()),
+                meta.query_advice(log_table.value, Rotation::cur()),
+            ];
+            let table_enable = meta.query_fixed(q_input_last, Rotation::cur())
+                * meta.query_advice(is_enabled, Rotation::cur());
+            let table_exprs = vec![
+                1.expr(),
+                meta.query_advice(tx_id, Rotation::cur()),
+                build_tx_log_expression(
+                    meta.query_advice(topic_index, Rotation::cur()),
+                    select::expr(
+                        meta.query_advice(is_address, Rotation::cur()),
+                        TxLogFieldTag::Address.expr(),
+                        TxLogFieldTag::Topic.expr(),
+                    ),
+                    meta.query_advice(log_id, Rotation::cur()),
+                ),
+                meta.query_advice(acc, Rotation::cur()),
+            ]
+            .into_iter()
+            .map(|expr| table_enable.expr() * expr)
+            .collect();
+
+            enabled_lookup(enable, input_exprs, table_exprs)
+        });
+
+        // the branches
+        meta.lookup_any("slot of a branch", |meta| {
+            let enable = meta.query_fixed(q_node, Rotation::cur())
+                * meta.query_advice(seg_first, Rotation::cur())
+                * tags(
+                    meta,
+                    &[ReceiptTag::Child, ReceiptTag::EmptyChild],
+                    Rotation::cur(),
+                );
+            let input_exprs = vec![
+                RomKind::Slot.expr(),
+                meta.query_advice(level, Rotation::cur()),
+                meta.query_advice(path, Rotation::cur()),
+                meta.query_advice(count, Rotation::cur()),
+                meta.query_advice(child_path, Rotation::cur()),
+                meta.query_advice(child_min, Rotation::cur()),
+            ];
+
+            enabled_lookup(enable, input_exprs, rom_exprs(meta, &rom))
+        });
+        meta.lookup("slot of a branch is used iff num_txs > child_min", |meta| {
+            let enable = meta.query_fixed(q_node, Rotation::cur())
+                * meta.query_advice(seg_first, Rotation::cur());
+            let num_txs = meta.query_advice(num_txs, Rotation::cur());
+            let child_min = meta.query_advice(child_min, Rotation::cur());
+            vec![
+                (
+                    enable.expr()
+                        * tag(meta, ReceiptTag::Child, Rotation::cur())
+                        * (num_txs.expr() - 1.expr() - child_min.expr()),
+                    u8_table.into(),
+                ),
+                (
+                    enable
+                        * tag(meta, ReceiptTag::EmptyChild, Rotation::cur())
+                        * (child_min - num_txs),
+                    u8_table.into(),
+                ),
+            ]
+        });
+        let node_end = |meta: &mut VirtualCells<F>| {
+            let enable = meta.query_fixed(q_node, Rotation::cur())
+                * meta.query_advice(is_node_end, Rotation::cur());
+            [
+                1.expr(),
+                meta.query_advice(block_number, Rotation::cur()),
+                meta.query_advice(level, Rotation::cur()),
+                meta.query_advice(path, Rotation::cur()),
+                meta.query_advice(hash_rlc, Rotation::cur()),
+            ]
+            .into_iter()
+            .map(|expr| enable.expr() * expr)
+            .collect::<Vec<_>>()
+        };
+        meta.lookup_any("child of a branch is a hashed node", |meta| {
+            // looked up at the row following the child
+            let enable = meta.query_fixed(q_node, Rotation::cur())
+                * meta.query_advice(seg_first, Rotation::cur())
+                * tag(meta, ReceiptTag::Child, Rotation::prev());
+            let input_exprs = vec![
+                1.expr(),
+                meta.query_advice(block_number, Rotation::prev()),
+                meta.query_advice(level, Rotation::prev()) + 1.expr(),
+                meta.query_advice(child_path, Rotation::prev()),
+                meta.query_advice(acc, Rotation::prev()),
+            ];
+
+            enabled_lookup(enable, input_exprs, node_end(meta))
+        });
+        meta.lookup_any("receipts root of a block is a hashed node", |meta| {
+            // not padding since num_txs is 0 for padding
+            let enable = meta.query_fixed(q_block_start, Rotation::cur())
+                * not::expr(num_txs_is_zero.expr(Rotation::cur())(meta));
+            let input_exprs = vec![
+                1.expr(),
+                meta.query_advice(block_number, Rotation::cur()),
+                0.expr(), // level
+                0.expr(), // path
+                meta.query_advice(hash_rlc, Rotation::cur()),
+            ];
+
+            enabled_lookup(enable, input_exprs, node_end(meta))
+        });
+
+        Self {
+            q_bloom,
+            is_tx_section,
+            q_block_start,
+            q_tx_start,
+            q_section_next,
+            q_byte_first,
+            q_byte_last,
+            bit_index,
+            is_set,
+            is_padding,
+            bloom_byte_acc,
+            bloom_rlc,
+            num_txs_is_zero,
+            q_item,
+            q_item_first,
+            q_input,
+            q_input_last,
+            q_hash_first,
+            q_hash_last,
+            q_address_padding,
+            q_bit_hi,
+            q_bit_idx,
+            is_enabled,
+            is_address,
+            topic_index,
+            aux,
+            q_node,
+            q_node_first,
+            q_node_last,
+            is_tag,
+            seg_rem,
+            seg_rem_is_zero,
+            seg_first,
+            node_rem,
+            node_rem_is_zero,
+            is_node_end,
+            is_log_item,
+            node_len,
+            level,
+            path,
+            index,
+            index_is_zero,
+            cum_num_txs,
+            tx_type,
+            has_type,
+            type_byte,
+            status,
+            key,
+            key_rem,
+            min_num_txs,
+            max_num_txs,
+            rw_gas,
+            gas_base,
+            log_rem,
+            topics_rem,
+            count,
+            child_path,
+            child_min,
+            block_number,
+            tx_id,
+            num_txs,
+            log_id,
+            byte,
+            acc,
+            input_rlc,
+            hash_rlc,
+            rom,
+            log_table,
+            rw_table,
+            tx_table,
+            block_table,
+            keccak_table,
+            u8_table,
+        }
+    }
+}
+
+impl<F: Field> ReceiptCircuitConfig<F> {
+    /// Assigns the fixed table of the transitions and of the shape of the trie.
+    fn assign_rom(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        let transitions = TRANSITIONS.iter().flat_map(|(from, tos)| {
+            tos.iter()
+                .map(|to| vec![RomKind::Transition as u64, *from as u64, *to as u64])
+        });
+        let tx_types = TxType::iter().map(|tx_type| {
+            let type_byte = tx_type_byte(tx_type);
+            vec![
+                RomKind::TxType as u64,
+                tx_type as u64,
+                type_byte.is_some() as u64,
+                type_byte.unwrap_or_default() as u64,
+            ]
+        });
+        let leaves = leaf_rom()
+            .into_iter()
+            .map(|row| [&[RomKind::Leaf as u64][..], &row].concat());
+        let slots = slot_rom()
+            .into_iter()
+            .map(|row| [&[RomKind::Slot as u64][..], &row].concat());
+
+        layouter.assign_region(
+            || "receipt circuit rom",
+            |mut region| {
+                // the first row is left at zero for the disabled lookups
+                let rows = transitions
+                    .clone()
+                    .chain(tx_types.clone())
+                    .chain(leaves.clone())
+                    .chain(slots.clone());
+                for (offset, row) in rows.enumerate() {
+                    for (column, value) in self.rom.iter().zip(row) {
+                        region.assign_fixed(
+                            || "receipt circuit rom",
+                            *column,
+                            offset + 1,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assigns the bloom section of a block or a tx. Returns the cells of the block number and
+    /// of the receipts root RLC at its first row, and of the bloom RLC at its last row.
+    fn assign_bloom_section(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        is_tx_section: bool,
+        is_first: bool,
+        section: &BloomSection,
+        challenges: &Challenges<Value<F>>,
+    ) -> Result<[AssignedCell<F, F>; 3], Error> {
+        let hash_rlc = if section.is_padding || is_tx_section {
+            Value::known(F::zero())
+        } else {
+            rlc_be_bytes(&section.root.0, challenges.evm_word())
+        };
+
+        let mut cells = vec![];
+        let mut byte_acc = 0u64;
+        let mut bloom_rlc = Value::known(F::zero());
+        for j in 0..BLOOM_BITS {
+            let row = offset + j;
+            let (k, t) = (j / 8, j % 8);
+            let is_set = (section.bloom.0[k] >> (7 - t)) & 1;
+            byte_acc = if t == 0 { 0 } else { byte_acc * 2 } + is_set as u64;
+            if t == 7 {
+                bloom_rlc = bloom_rlc * challenges.evm_word() + Value::known(F::from(byte_acc));
+            }
+
+            for (name, column, value) in [
+                ("q_bloom", self.q_bloom, true),
+                ("is_tx_section", self.is_tx_section, is_tx_section),
+                (
+                    "q_block_start",
+                    self.q_block_start,
+                    !is_tx_section && j == 0,
+                ),
+                ("q_tx_start", self.q_tx_start, is_tx_section && j == 0),
+                ("q_section_next", self.q_section_next, !is_first && j == 0),
+                ("q_byte_first", self.q_byte_first, t == 0),
+                ("q_byte_last", self.q_byte_last, t == 7),
+            ] {
+                region.assign_fixed(
+                    || name,
+                    column,
+                    row,
+                    || Value::known(F::from(value as u64)),
+                )?;
+            }
+            region.assign_fixed(
+                || "bit_index",
+                self.bit_index,
+                row,
+                || Value::known(F::from((BLOOM_BITS - 1 - j) as u64)),
+            )?;
+
+            let num_txs = if j == 0 { section.num_txs as u64 } else { 0 };
+            for (name, column, value) in [
+                ("is_set", self.is_set, is_set as u64),
+                ("is_padding", self.is_padding, section.is_padding as u64),
+                ("bloom_byte_acc", self.bloom_byte_acc, byte_acc),
+                ("tx_id", self.tx_id, section.tx_id as u64),
+                ("num_txs", self.num_txs, num_txs),
+            ] {
+                region.assign_advice(|| name, column, row, || Value::known(F::from(value)))?;
+            }
+            let block_number = region.assign_advice(
+                || "block_number",
+                self.block_number,
+                row,
+                || Value::known(F::from(section.block_number)),
+            )?;
+            let hash_rlc = region.assign_advice(
+                || "hash_rlc",
+                self.hash_rlc,
+                row,
+                || {
+                    if j == 0 {
+                        hash_rlc
+                    } else {
+                        Value::known(F::zero())
+                    }
+                },
+            )?;
+            let bloom_rlc =
+                region.assign_advice(|| "bloom_rlc", self.bloom_rlc, row, || bloom_rlc)?;
+
+            if j == 0 {
+                cells.extend([block_number, hash_rlc]);
+                if !is_tx_section {
+                    IsZeroChip::construct(self.num_txs_is_zero.clone()).assign(
+                        region,
+                        row,
+                        Value::known(F::from(num_txs)),
+                    )?;
+                }
+            }
+            if j == BLOOM_BITS - 1 {
+                cells.push(bloom_rlc);
+            }
+        }
+
+        Ok(cells.try_into().unwrap())
+    }
+
+    /// Assigns a log address or topic, or a padding item.
+    fn assign_item(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        item: Option<&LogItem>,
+        challenges: &Challenges<Value<F>>,
+    ) -> Result<(), Error> {
+        let is_enabled = item.is_some();
+        let item = item.cloned().unwrap_or_default();
+        let input = item.input_word();
+        let hash = if is_enabled {
+            keccak256(item.input())
+        } else {
+            [0; N_BYTES_WORD]
+        };
+
+        let mut acc = Value::known(F::zero());
+        let mut input_rlc = Value::known(F::zero());
+        for (i, &byte) in input.iter().chain(hash.iter()).enumerate() {
+            let row = offset + i;
+            let is_input = i < N_BYTES_WORD;
+            let hash_index = i.checked_sub(N_BYTES_WORD);
+            let is_bit_hi =
+                matches!(hash_index, Some(j) if j < 2 * N_BLOOM_BITS_PER_ITEM && j % 2 == 0);
+            let is_bit_idx =
+                matches!(hash_index, Some(j) if j < 2 * N_BLOOM_BITS_PER_ITEM && j % 2 == 1);
+
+            for (name, column, value) in [
+                ("q_item", self.q_item, true),
+                ("q_item_first", self.q_item_first, i == 0),
+                ("q_input", self.q_input, is_input),
+                ("q_input_last", self.q_input_last, i == N_BYTES_WORD - 1),
+                ("q_hash_first", self.q_hash_first, i == N_BYTES_WORD),
+                ("q_hash_last", self.q_hash_last, i == ITEM_ROWS - 1),
+                (
+                    "q_address_padding",
+                    self.q_address_padding,
+                    i < ADDRESS_PADDING,
+                ),
+                ("q_bit_hi", self.q_bit_hi, is_bit_hi),
+                ("q_bit_idx", self.q_bit_idx, is_bit_idx),
+            ] {
+                region.assign_fixed(
+                    || name,
+                    column,
+                    row,
+                    || Value::known(F::from(value as u64)),
+                )?;
+            }
+
+            let byte_f = Value::known(F::from(byte as u64));
+            acc = if i == 0 || i == N_BYTES_WORD {
+                byte_f
+            } else if is_input && item.is_address {
+                acc * Value::known(F::from(256)) + byte_f
+            } else {
+                acc * challenges.evm_word() + byte_f
+            };
+            input_rlc = if i == 0 {
+                byte_f
+            } else if is_input {
+                input_rlc * challenges.keccak_input() + byte_f
+            } else {
+                Value::known(F::zero())
+            };
+            let aux = if is_bit_hi {
+                byte as u64 & 7
+            } else if is_bit_idx {
+                (hash[i - N_BYTES_WORD - 1] as u64 & 7) * 256 + byte as u64
+            } else {
+                0
+            };
+
+            for (name, column, value) in [
+                (
+                    "is_enabled",
+                    self.is_enabled,
+                    Value::known(F::from(is_enabled as u64)),
+                ),
+                (
+                    "is_address",
+                    self.is_address,
+                    Value::known(F::from(item.is_address as u64)),
+                ),
+                (
+                    "tx_id",
+                    self.tx_id,
+                    Value::known(F::from(item.tx_id as u64)),
+                ),
+                ("log_id", self.log_id, Value::known(F::from(item.log_id))),
+                (
+                    "topic_index",
+                    self.topic_index,
+                    Value::known(F::from(item.topic_index as u64)),
+                ),
+                ("byte", self.byte, byte_f),
+                ("acc", self.acc, acc),
+                ("input_rlc", self.input_rlc, input_rlc),
+                ("aux", self.aux, Value::known(F::from(aux))),
+            ] {
+                region.assign_advice(|| name, column, row, || value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Assigns the node stream, followed by padding up to `max_node_rows` rows.
+    fn assign_nodes(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        max_node_rows: usize,
+        nodes: &[NodeWitness],
+        challenges: &Challenges<Value<F>>,
+    ) -> Result<(), Error> {
+        let num_rows = nodes.iter().map(|node| node.bytes.len()).sum::<usize>();
+        assert!(
+            num_rows < max_node_rows,
+            "no enough rows for the receipts trie nodes, expected {}, limit {}",
+            num_rows + 1,
+            max_node_rows,
+        );
+
+        let mut stream = StreamState::default();
+        let mut prev_tag = None;
+        let mut row = offset;
+        for node in nodes {
+            let hash_rlc = rlc_be_bytes(&node.node.hash().0, challenges.evm_word());
+            let mut node_rem = node.bytes.len();
+            let mut input_rlc = Value::known(F::zero());
+            for (tag, bytes) in node.node.segments.iter() {
+                let seg_len = bytes.len();
+                let mut acc = Value::known(F::zero());
+                let mut len = 0u64;
+                for (i, &byte) in bytes.iter().enumerate() {
+                    node_rem -= 1;
+                    let seg_rem = seg_len - 1 - i;
+                    let byte_f = Value::known(F::from(byte as u64));
+                    input_rlc = if node_rem == node.bytes.len() - 1 {
+                        byte_f
+                    } else {
+                        input_rlc * challenges.keccak_input() + byte_f
+                    };
+
+                    acc = if HEADER_TAGS.contains(tag) {
+                        len = if seg_len == 1 {
+                            let base = if STRING_HEADER_TAGS.contains(tag) {
+                                0x80
+                            } else {
+                                0xc0
+                            };
+                            byte as u64 - base
+                        } else if i == 0 {
+                            0
+                        } else {
+                            len * 256 + byte as u64
+                        };
+                        Value::known(F::from(len))
+                    } else {
+                        match tag {
+                            ReceiptTag::Key if i == 0 => byte_f,
+                            ReceiptTag::Key | ReceiptTag::Gas | ReceiptTag::Address if i > 0 => {
+                                acc * Value::known(F::from(256)) + byte_f
+                            }
+                            ReceiptTag::Topic | ReceiptTag::Child if i > 0 => {
+                                acc * challenges.evm_word() + byte_f
+                            }
+                            ReceiptTag::Data => Value::known(F::from(i as u64)),
+                            _ => Value::known(F::zero()),
+                        }
+                    };
+                    stream.update(*tag, prev_tag, i, seg_rem, len, node);
+
+                    let is_log_item =
+                        seg_rem == 0 && matches!(tag, ReceiptTag::Address | ReceiptTag::Topic);
+                    let log_entry = if is_log_item {
+                        let (index, field_tag) = match tag {
+                            ReceiptTag::Address => (0, TxLogFieldTag::Address),
+                            _ => (stream.count, TxLogFieldTag::Topic),
+                        };
+                        Some((build_tx_log_address(index, field_tag, stream.log_id), acc))
+                    } else if *tag == ReceiptTag::Data {
+                        Some((
+                            build_tx_log_address(i as u64, TxLogFieldTag::Data, stream.log_id),
+                            byte_f,
+                        ))
+                    } else {
+                        None
+                    };
+
+                    self.assign_node_row(
+                        region,
+                        row,
+                        NodeRow {
+                            tag: *tag,
+                            seg_first: i == 0,
+                            seg_rem,
+                            node_rem,
+                            byte,
+                            acc,
+                            input_rlc,
+                            hash_rlc,
+                            is_log_item,
+                            log_entry,
+                        },
+                        node,
+                        &stream,
+                    )?;
+                    prev_tag = Some(*tag);
+                    row += 1;
+                }
+            }
+        }
+
+        let last = nodes.last().cloned().unwrap_or_default();
+        for row in row..offset + max_node_rows {
+            self.assign_node_row(
+                region,
+                row,
+                NodeRow {
+                    tag: ReceiptTag::Padding,
+                    seg_first: true,
+                    seg_rem: 0,
+                    node_rem: 0,
+                    byte: 0,
+                    acc: Value::known(F::zero()),
+                    input_rlc: Value::known(F::zero()),
+                    hash_rlc: Value::known(F::zero()),
+                    is_log_item: false,
+                    log_entry: None,
+                },
+                &last,
+                &stream,
+            )?;
+        }
+        for (name, column, row) in [
+            ("q_node_first", self.q_node_first, offset),
+            ("q_node_last", self.q_node_last, offset + max_node_rows - 1),
+        ] {
+            region.assign_fixed(|| name, column, row, || Value::known(F::one()))?;
+        }
+
+        Ok(())
+    }
+
+    fn assign_node_row(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        node_row: NodeRow<F>,
+        node: &NodeWitness,
+        stream: &StreamState,
+    ) -> Result<(), Error> {
+        for (name, column) in [
+            ("q_node", self.q_node),
+            ("log_table", self.log_table.q_enable),
+        ] {
+            region.assign_fixed(|| name, column, row, || Value::known(F::one()))?;
+        }
+        for (i, column) in self.is_tag.iter().enumerate() {
+            region.assign_advice(
+                || "is_tag",
+                *column,
+                row,
+                || Value::known(F::from((node_row.tag as usize == i) as u64)),
+            )?;
+        }
+
+        let is_padding = node_row.tag == ReceiptTag::Padding;
+        let (lt_tx_id, lt_address, lt_value) = match node_row.log_entry {
+            Some((address, value)) => (
+                Value::known(F::from(node.tx_id as u64)),
+                Value::known(address.to_scalar().unwrap()),
+                value,
+            ),
+            None => (
+                Value::known(F::zero()),
+                Value::known(F::zero()),
+                Value::known(F::zero()),
+            ),
+        };
+        let c = &node.constants;
+        for (name, column, value) in [
+            ("seg_first", self.seg_first, node_row.seg_first as u64),
+            ("seg_rem", self.seg_rem, node_row.seg_rem as u64),
+            ("node_rem", self.node_rem, node_row.node_rem as u64),
+            (
+                "is_node_end",
+                self.is_node_end,
+                (!is_padding && node_row.node_rem == 0) as u64,
+            ),
+            ("is_log_item", self.is_log_item, node_row.is_log_item as u64),
+            ("byte", self.byte, node_row.byte as u64),
+            ("node_len", self.node_len, c.node_len),
+            ("block_number", self.block_number, c.block_number),
+            ("level", self.level, c.level),
+            ("path", self.path, c.path),
+            ("index", self.index, c.index),
+            ("num_txs", self.num_txs, c.num_txs),
+            ("cum_num_txs", self.cum_num_txs, c.cum_num_txs),
+            ("tx_type", self.tx_type, c.tx_type),
+            ("has_type", self.has_type, c.has_type),
+            ("type_byte", self.type_byte, c.type_byte),
+            ("status", self.status, c.status),
+            ("key", self.key, c.key),
+            ("key_rem", self.key_rem, c.key_rem),
+            ("min_num_txs", self.min_num_txs, c.min_num_txs),
+            ("max_num_txs", self.max_num_txs, c.max_num_txs),
+            ("tx_id", self.tx_id, node.tx_id as u64),
+            ("rw_gas", self.rw_gas, node.rw_gas),
+            ("gas_base", self.gas_base, node.gas_base),
+            ("log_id", self.log_id, stream.log_id),
+            ("log_rem", self.log_rem, stream.log_rem),
+            ("topics_rem", self.topics_rem, stream.topics_rem),
+            ("count", self.count, stream.count),
+            ("child_path", self.child_path, stream.child_path),
+            ("child_min", self.child_min, stream.child_min),
+        ] {
+            region.assign_advice(|| name, column, row, || Value::known(F::from(value)))?;
+        }
+        for (name, column, value) in [
+            ("acc", self.acc, node_row.acc),
+            ("input_rlc", self.input_rlc, node_row.input_rlc),
+            ("hash_rlc", self.hash_rlc, node_row.hash_rlc),
+            ("log_table tx_id", self.log_table.tx_id, lt_tx_id),
+            ("log_table address", self.log_table.address, lt_address),
+            ("log_table value", self.log_table.value, lt_value),
+        ] {
+            region.assign_advice(|| name, column, row, || value)?;
+        }
+
+        for (config, value) in [
+            (&self.seg_rem_is_zero, node_row.seg_rem as u64),
+            (&self.node_rem_is_zero, node_row.node_rem as u64),
+            (&self.index_is_zero, c.index),
+        ] {
+            IsZeroChip::construct(config.clone()).assign(
+                region,
+                row,
+                Value::known(F::from(value)),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A bloom section: the bloom of a block or of a tx, or padding.
+#[derive(Clone, Debug)]
+struct BloomSection {
+    is_padding: bool,
+    block_number: u64,
+    /// 0 for the section of a block
+    tx_id: usize,
+    /// Number of txs of the block, 0 for the section of a tx
+    num_txs: usize,
+    /// Receipts root of the block
+    root: H256,
+    bloom: Bloom,
+}
+
+impl Default for BloomSection {
+    fn default() -> Self {
+        Self {
+            is_padding: true,
+            block_number: 0,
+            tx_id: 0,
+            num_txs: 0,
+            root: H256::zero(),
+            bloom: Bloom::zero(),
+        }
+    }
+}
+
+/// Values constant over the rows of a node.
+#[derive(Clone, Copy, Debug, Default)]
+struct NodeConstants {
+    node_len: u64,
+    block_number: u64,
+    level: u64,
+    path: u64,
+    index: u64,
+    num_txs: u64,
+    cum_num_txs: u64,
+    tx_type: u64,
+    has_type: u64,
+    type_byte: u64,
+    status: u64,
+    key: u64,
+    key_rem: u64,
+    min_num_txs: u64,
+    max_num_txs: u64,
+}
+
+/// A node of the receipts trie with the values of its rows.
+#[derive(Clone, Debug, Default)]
+struct NodeWitness {
+    node: ReceiptTrieNode,
+    bytes: Vec<u8>,
+    constants: NodeConstants,
+    /// Tx of the last leaf up to this node
+    tx_id: usize,
+    /// Cumulative gas used of the rw table at that tx
+    rw_gas: u64,
+    /// Cumulative gas used of the rw table before the block of that tx
+    gas_base: u64,
+}
+
+/// A row of the node stream.
+#[derive(Clone, Debug)]
+struct NodeRow<F> {
+    tag: ReceiptTag,
+    seg_first: bool,
+    seg_rem: usize,
+    node_rem: usize,
+    byte: u8,
+    acc: Value<F>,
+    input_rlc: Value<F>,
+    hash_rlc: Value<F>,
+    is_log_item: bool,
+    /// Address and value of the log table
+    log_entry: Option<(eth_types::Address, Value<F>)>,
+}
+
+/// Counters of the logs and children running over the node stream.
+#[derive(Clone, Debug, Default)]
+struct StreamState {
+    log_id: u64,
+    log_rem: u64,
+    topics_rem: u64,
+    count: u64,
+    child_path: u64,
+    child_min: u64,
+}
+
+impl StreamState {
+    /// Updates the counters for the byte `i` of a segment, `len` being the length carried by a
+    /// header.
+    fn update(
+        &mut self,
+        tag: ReceiptTag,
+        prev_tag: Option<ReceiptTag>,
+        i: usize,
+        seg_rem: usize,
+        len: u64,
+        node: &NodeWitness,
+    ) {
+        use ReceiptTag::*;
+
+        match tag {
+            LogsHeader => self.log_id = 0,
+            LogHeader if i == 0 => self.log_id += 1,
+            _ => (),
+        }
+        if LOG_BODY_TAGS.contains(&tag) {
+            self.log_rem -= 1;
+        }
+        if tag == Topic {
+            self.topics_rem -= 1;
+        }
+        if seg_rem == 0 {
+            match tag {
+                LogHeader => self.log_rem = len,
+                TopicsHeader => self.topics_rem = len,
+                _ => (),
+            }
+        }
+        if i == 0 {
+            match (tag, prev_tag) {
+                (Topic, Some(Topic)) | (Child | EmptyChild, Some(Child | EmptyChild)) => {
+                    self.count += 1
+                }
+                (Topic | Child | EmptyChild, _) => self.count = 0,
+                _ => (),
+            }
+            if matches!(tag, Child | EmptyChild) {
+                let [_, _, _, child_path, child_min] = slot_rom()
+                    .into_iter()
+                    .find(|row| row[..3] == [node.constants.level, node.constants.path, self.count])
+                    .expect("slot of a branch");
+                self.child_path = child_path;
+                self.child_min = child_min;
+            }
+        }
+    }
+}
+
+/// An address or a topic of a log, which sets bits in the bloom of its tx.
+#[derive(Clone, Debug, Default)]
+pub struct LogItem {
+    /// Tx of the log
+    pub tx_id: usize,
+    /// Id of the log in the tx
+    pub log_id: u64,
+    /// Index of the topic, 0 for the address
+    pub topic_index: usize,
+    /// Whether this is the address of the log
+    pub is_address: bool,
+    /// The address or topic
+    pub value: Word,
+}
+
+impl LogItem {
+    /// Bytes hashed for the bloom: 20 for an address, 32 for a topic.
+    pub fn input(&self) -> Vec<u8> {
+        if self.is_address {
+            self.value.to_address().as_bytes().to_vec()
+        } else {
+            self.value.to_be_bytes().to_vec()
+        }
+    }
+
+    fn input_word(&self) -> [u8; N_BYTES_WORD] {
+        self.value.to_be_bytes()
+    }
+}
+
+/// Cells of the receipt circuit connected to the PI circuit, one per block, padding included
+#[derive(Clone, Debug)]
+pub struct ReceiptCircuitExports<V> {
+    /// Number of the block
+    pub block_numbers: Vec<(Cell, Value<V>)>,
+    /// RLC of the receipts root of the block
+    pub receipts_roots: Vec<(Cell, Value<V>)>,
+    /// RLC of the logs bloom of the block
+    pub logs_blooms: Vec<(Cell, Value<V>)>,
+}
+
+/// Receipt Circuit for verifying the receipts root and the logs bloom of the blocks in a chunk
+#[derive(Clone, Default, Debug)]
+pub struct ReceiptCircuit<F: Field> {
+    /// Max number of log addresses and topics
+    pub max_log_items: usize,
+    /// Max number of blocks
+    pub max_inner_blocks: usize,
+    /// Max number of txs
+    pub max_txs: usize,
+    /// Max number of rows of the receipts trie nodes
+    pub max_node_rows: usize,
+    /// Number and receipts of each block
+    pub blocks: Vec<(u64, Vec<Receipt>)>,
+    /// Addresses and topics of the logs
+    pub items: Vec<LogItem>,
+    /// Cells exported to the PI circuit
+    pub(crate) exports: RefCell<Option<ReceiptCircuitExports<Assigned<F>>>>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ReceiptCircuit<F> {
+    /// Return a new ReceiptCircuit
+    pub fn new(
+        max_log_items: usize,
+        max_inner_blocks: usize,
+        max_txs: usize,
+        max_node_rows: usize,
+        block: &witness::Block,
+    ) -> Self {
+        let items = block
+            .rws
+            .0
+            .get(&RwTableTag::TxLog)
+            .into_iter()
+            .flatten()
+            .filter_map(|rw| match rw {
+                Rw::TxLog {
+                    tx_id,
+                    log_id,
+                    field_tag,
+                    index,
+                    value,
+                    ..
+                } if matches!(field_tag, TxLogFieldTag::Address | TxLogFieldTag::Topic) => {
+                    Some(LogItem {
+                        tx_id: *tx_id,
+                        log_id: *log_id,
+                        topic_index: *index,
+                        is_address: matches!(field_tag, TxLogFieldTag::Address),
+                        value: *value,
+                    })
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let blocks = block.receipts_by_block().into_iter().collect::<Vec<_>>();
+
+        assert!(
+            items.len() <= max_log_items,
+            "no enough rows for receipt circuit, expected {}, limit {}",
+            items.len(),
+            max_log_items,
+        );
+        assert!(blocks.len() <= max_inner_blocks);
+        assert!(
+            blocks
+                .iter()
+                .map(|(_, receipts)| receipts.len())
+                .sum::<usize>()
+                <= max_txs
+        );
+
+        Self {
+            max_log_items,
+            max_inner_blocks,
+            max_txs,
+            max_node_rows,
+            blocks,
+            items,
+            exports: RefCell::new(None),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Nodes of the receipts tries of the blocks, with the values of their rows.
+    fn node_witnesses(&self) -> Vec<NodeWitness> {
+        let leaf_rom = leaf_rom();
+
+        let mut witnesses = vec![];
+        let (mut tx_id, mut rw_gas, mut gas_base) = (0, 0, 0);
+        let mut cum_num_txs = 0;
+        for (block_number, receipts) in self.blocks.iter() {
+            cum_num_txs += receipts.len();
+            for node in receipt_trie_nodes(*block_number, receipts) {
+                let bytes = node.bytes();
+                let mut constants = NodeConstants {
+                    node_len: bytes.len() as u64,
+                    block_number: *block_number,
+                    level: node.level,
+                    path: node.path,
+                    num_txs: receipts.len() as u64,
+                    ..Default::default()
+                };
+                if let Some(index) = node.leaf {
+                    let receipt = &receipts[index];
+                    // the cumulative gas used of the rw table runs over the chunk
+                    if index == 0 {
+                        gas_base = rw_gas;
+                    }
+                    tx_id = receipt.id;
+                    rw_gas = gas_base + receipt.cumulative_gas_used;
+
+                    let [_, _, key, key_rem, _, min_num_txs, max_num_txs] = *leaf_rom
+                        .iter()
+                        .find(|row| row[..2] == [node.level, index as u64])
+                        .expect("leaf of a receipt");
+                    let type_byte = receipt.type_byte();
+                    constants = NodeConstants {
+                        index: index as u64,
+                        cum_num_txs: cum_num_txs as u64,
+                        tx_type: receipt.tx_type as u64,
+                        has_type: type_byte.is_some() as u64,
+                        type_byte: type_byte.unwrap_or_default() as u64,
+                        status: receipt.status as u64,
+                        key,
+                        key_rem,
+                        min_num_txs,
+                        max_num_txs,
+                        ..constants
+                    };
+                }
+                witnesses.push(NodeWitness {
+                    node,
+                    bytes,
+                    constants,
+                    tx_id,
+                    rw_gas,
+                    gas_base,
+                });
+            }
+        }
+        witnesses
+    }
+
+    /// Inputs of the keccak table looked up by the nodes and the items, and the empty string
+    /// hashed for the root of a block without txs.
+    pub fn keccak_inputs(&self) -> Vec<Vec<u8>> {
+        self.blocks
+            .iter()
+            .flat_map(|(block_number, receipts)| receipt_trie_nodes(*block_number, receipts))
+            .map(|node| node.bytes())
+            .chain(self.items.iter().map(LogItem::input))
+            .chain(
+                self.blocks
+                    .iter()
+                    .any(|(_, receipts)| receipts.is_empty())
+                    .then(|| vec![0x80]),
+            )
+            .collect()
+    }
+}
+
+/// Number of rows of the receipts trie nodes of the block, the padding row included.
+fn node_rows(block: &witness::Block) -> usize {
+    block
+        .receipt_trie_nodes()
+        .iter()
+        .map(|node| node.bytes().len())
+        .sum::<usize>()
+        + 1
+}
+
+/// Number of log addresses and topics of the block.
+fn num_log_items(block: &witness::Block) -> usize {
+    block.rws.0.get(&RwTableTag::TxLog).map_or(0, |rws| {
+        rws.iter()
+            .filter(|rw| {
+                matches!(
+                    rw,
+                    Rw::TxLog {
+                        field_tag: TxLogFieldTag::Address | TxLogFieldTag::Topic,
+                        ..
+                    }
+                )
+            })
+            .count()
+    })
+}
+
+impl<F: Field> SubCircuit<F> for ReceiptCircuit<F> {
+    type Config = ReceiptCircuitConfig<F>;
+
+    fn unusable_rows() -> usize {
+        // No column queried at more than 3 distinct rotations, so returns 6 as
+        // minimum unusable rows.
+        6
+    }
+
+    fn new_from_block(block: &witness::Block) -> Self {
+        let max_inner_blocks = block.circuits_params.max_inner_blocks;
+        let max_txs = block.circuits_params.max_txs;
+        let (max_log_items, max_node_rows) = if block.circuits_params.max_vertical_circuit_rows == 0
+        {
+            (num_log_items(block), node_rows(block))
+        } else {
+            // the rows left by the bloom sections are shared evenly by items and nodes
+            let rows = block
+                .circuits_params
+                .max_vertical_circuit_rows
+                .saturating_sub((max_inner_blocks + max_txs) * BLOOM_BITS);
+            let max_log_items = rows / 2 / ITEM_ROWS;
+            (max_log_items, rows - max_log_items * ITEM_ROWS)
+        };
+
+        Self::new(
+            max_log_items,
+            max_inner_blocks,
+            max_txs,
+            max_node_rows,
+            block,
+        )
+    }
+
+    fn synthesize_sub(
+        &self,
+        config: &Self::Config,
+        challenges: &Challenges<Value<F>>,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.assign_rom(layouter)?;
+
+        let nodes = self.node_witnesses();
+        let exports = layouter.assign_region(
+            || "receipt circuit",
+            |mut region| {
+                let mut exports = ReceiptCircuitExports {
+                    block_numbers: vec![],
+                    receipts_roots: vec![],
+                    logs_blooms: vec![],
+                };
+                for i in 0..self.max_inner_blocks {
+                    let section = self
+                        .blocks
+                        .get(i)
+                        .map(|(block_number, receipts)| BloomSection {
+                            is_padding: false,
+                            block_number: *block_number,
+                            tx_id: 0,
+                            num_txs: receipts.len(),
+                            root: receipts_root(receipts),
+                            bloom: logs_bloom(receipts.iter().flat_map(|r| r.logs.iter())),
+                        })
+                        .unwrap_or_default();
+                    let [block_number, receipts_root, logs_bloom] = config.assign_bloom_section(
+                        &mut region,
+                        i * BLOOM_BITS,
+                        false,
+                        i == 0,
+                        &section,
+                        challenges,
+                    )?;
+                    for (cells, cell) in [
+                        (&mut exports.block_numbers, block_number),
+                        (&mut exports.receipts_roots, receipts_root),
+                        (&mut exports.logs_blooms, logs_bloom),
+                    ] {
+                        cells.push((cell.cell(), cell.value_field()));
+                    }
+                }
+
+                let txs_offset = self.max_inner_blocks * BLOOM_BITS;
+                let mut receipts = self.blocks.iter().flat_map(|(block_number, receipts)| {
+                    receipts.iter().map(move |receipt| (*block_number, receipt))
+                });
+                for i in 0..self.max_txs {
+                    let section = receipts
+                        .next()
+                        .map(|(block_number, receipt)| BloomSection {
+                            is_padding: false,
+                            block_number,
+                            tx_id: receipt.id,
+                            num_txs: 0,
+                            root: H256::zero(),
+                            bloom: receipt.bloom,
+                        })
+                        .unwrap_or_default();
+                    config.assign_bloom_section(
+                        &mut region,
+                        txs_offset + i * BLOOM_BITS,
+                        true,
+                        i == 0,
+                        &section,
+                        challenges,
+                    )?;
+                }
+
+                let items_offset = txs_offset + self.max_txs * BLOOM_BITS;
+                for i in 0..self.max_log_items {
+                    config.assign_item(
+                        &mut region,
+                        items_offset + i * ITEM_ROWS,
+                        self.items.get(i),
+                        challenges,
+                    )?;
+                }
+
+                let nodes_offset = items_offset + self.max_log_items * ITEM_ROWS;
+                config.assign_nodes(
+                    &mut region,
+                    nodes_offset,
+                    self.max_node_rows,
+                    &nodes,
+                    challenges,
+                )?;
+
+                Ok(exports)
+            },
+        )?;
+        *self.exports.borrow_mut() = Some(exports);
+
+        Ok(())
+    }
+
+    fn min_num_rows_block(block: &witness::Block) -> (usize, usize) {
+        let rows = (block.circuits_params.max_inner_blocks + block.circuits_params.max_txs)
+            * BLOOM_BITS
+            + num_log_items(block) * ITEM_ROWS
+            + node_rows(block);
+
+        (
+            rows,
+            rows.max(block.circuits_params.max_vertical_circuit_rows),
+        )
+    }
+}
+
+/// Returns the `exprs` looked up in `table_exprs` when `enable` is set, zeros otherwise.
+fn enabled_lookup<F: Field>(
+    enable: Expression<F>,
+    exprs: Vec<Expression<F>>,
+    table_exprs: Vec<Expression<F>>,
+) -> Vec<(Expression<F>, Expression<F>)> {
+    exprs
+        .into_iter()
+        .zip(table_exprs)
+        .map(|(expr, table)| (enable.expr() * expr, table))
+        .collect()
+}
+
+/// (q_enable, is_write, tag, id, address, field_tag, value) of the rw table.
+fn rw_table_exprs<F: Field>(meta: &mut VirtualCells<F>, rw_table: &RwTable) -> Vec<Expression<F>> {
+    vec![
+        meta.query_fixed(rw_table.q_enable, Rotation::cur()),
+        meta.query_advice(rw_table.is_write, Rotation::cur()),
+        meta.query_advice(rw_table.tag, Rotation::cur()),
+        meta.query_advice(rw_table.id, Rotation::cur()),
+        meta.query_advice(rw_table.address, Rotation::cur()),
+        meta.query_advice(rw_table.field_tag, Rotation::cur()),
+        meta.query_advice(rw_table.value, Rotation::cur()),
+    ]
+}
+
+/// (q_enable, tx_id, tag, index, value) of the tx table.
+fn tx_table_exprs<F: Field>(meta: &mut VirtualCells<F>, tx_table: &TxTable) -> Vec<Expression<F>> {
+    vec![
+        meta.query_fixed(tx_table.q_enable, Rotation::cur()),
+        meta.query_advice(tx_table.tx_id, Rotation::cur()),
+        meta.query_advice(tx_table.tag, Rotation::cur()),
+        meta.query_advice(tx_table.index, Rotation::cur()),
+        meta.query_advice(tx_table.value, Rotation::cur()),
+    ]
+}
+
+fn rom_exprs<F: Field>(
+    meta: &mut VirtualCells<F>,
+    rom: &[Column<Fixed>; N_ROM_COLUMNS],
+) -> Vec<Expression<F>> {
+    rom.iter()
+        .map(|column| meta.query_fixed(*column, Rotation::cur()))
+        .collect()
+}
@@ -0,0 +1,114 @@
+use super::*;
+use crate::witness::Block;
+use halo2_proofs::{
+    circuit::SimpleFloorPlanner,
+    plonk::{Challenge, Circuit},
+};
+
+/// Test circuit for ReceiptCircuit, which also loads the rw, tx, block and keccak tables of its
+/// witness block.
+#[derive(Clone, Debug, Default)]
+pub struct ReceiptTestCircuit<F: Field> {
+    /// The receipt circuit
+    pub circuit: ReceiptCircuit<F>,
+    /// Block the tables are loaded from
+    pub block: Block,
+}
+
+impl<F: Field> ReceiptTestCircuit<F> {
+    /// Return a new ReceiptTestCircuit
+    pub fn new(
+        max_log_items: usize,
+        max_inner_blocks: usize,
+        max_txs: usize,
+        max_node_rows: usize,
+        block: Block,
+    ) -> Self {
+        Self {
+            circuit: ReceiptCircuit::new(
+                max_log_items,
+                max_inner_blocks,
+                max_txs,
+                max_node_rows,
+                &block,
+            ),
+            block,
+        }
+    }
+}
+
+impl<F: Field> Circuit<F> for ReceiptTestCircuit<F> {
+    type Config = (ReceiptCircuitConfig<F>, Challenges<Challenge>);
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let rw_table = RwTable::construct(meta);
+        let tx_table = TxTable::construct(meta);
+        let block_table = BlockTable::construct(meta);
+        let keccak_table = KeccakTable::construct(meta);
+        let u8_table = U8Table::construct(meta);
+        let log_table = LogTable::construct(meta);
+        let challenges = Challenges::construct(meta);
+
+        let config = {
+            let challenges = challenges.exprs(meta);
+            ReceiptCircuitConfig::new(
+                meta,
+                ReceiptCircuitConfigArgs {
+                    rw_table,
+                    tx_table,
+                    block_table,
+                    keccak_table,
+                    u8_table,
+                    log_table,
+                    challenges,
+                },
+            )
+        };
+
+        (config, challenges)
+    }
+
+    fn synthesize(
+        &self,
+        (config, challenges): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let challenges = challenges.values(&layouter);
+        let params = &self.block.circuits_params;
+
+        config.u8_table.load(&mut layouter)?;
+        config.rw_table.load(
+            &mut layouter,
+            &self.block.rws.table_assignments(),
+            params.max_rws,
+            challenges.evm_word(),
+        )?;
+        config.tx_table.load(
+            &mut layouter,
+            &self.block.txs,
+            params.max_txs,
+            params.max_calldata,
+            self.block.chain_id,
+            &challenges,
+        )?;
+        config.block_table.dev_load(
+            &mut layouter,
+            &self.block.context,
+            &self.block.txs,
+            &challenges,
+        )?;
+        config
+            .keccak_table
+            .dev_load(&mut layouter, &self.circuit.keccak_inputs(), &challenges)?;
+
+        self.circuit
+            .synthesize_sub(&config, &challenges, &mut layouter)
+    }
+}
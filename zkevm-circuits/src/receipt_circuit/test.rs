@@ -0,0 +1,125 @@
+#![allow(unused_imports)]
+use super::{dev::*, *};
+use crate::witness::{block_convert, Block};
+use bus_mapping::{circuit_input_builder::CircuitsParams, mock::BlockData};
+use eth_types::{bytecode, geth_types::GethData};
+use halo2_proofs::{
+    dev::{MockProver, VerifyFailure},
+    halo2curves::bn256::Fr,
+};
+use mock::{test_ctx::helpers::account_0_code_wallet_0_no_code, TestContext, MOCK_WALLETS};
+
+const K: u32 = 16;
+
+/// A block of `NTX` txs, each emitting a log with a topic and a log without topic.
+fn block_with_logs<const NTX: usize>() -> Block {
+    let code = bytecode! {
+        PUSH32(Word::from(0x1234))
+        PUSH1(0x20)
+        PUSH1(0x00)
+        LOG1
+        PUSH1(0x00)
+        PUSH1(0x00)
+        LOG0
+        STOP
+    };
+    let block: GethData = TestContext::<2, NTX>::new(
+        None,
+        account_0_code_wallet_0_no_code(code),
+        |txs, accs| {
+            for tx in txs {
+                tx.from(MOCK_WALLETS[0].clone()).to(accs[0].address);
+            }
+        },
+        |block, _txs| block.number(0xcafeu64),
+    )
+    .unwrap()
+    .into();
+    let params = CircuitsParams {
+        max_rws: 256 * NTX,
+        max_txs: NTX,
+        max_inner_blocks: 1,
+        ..Default::default()
+    };
+    let mut builder = BlockData::new_from_geth_data_with_params(block.clone(), params)
+        .new_circuit_input_builder();
+    builder
+        .handle_block(&block.eth_block, &block.geth_traces)
+        .unwrap();
+    block_convert(&builder.block, &builder.code_db).unwrap()
+}
+
+fn test_circuit(block: Block) -> ReceiptTestCircuit<Fr> {
+    let params = block.circuits_params;
+    ReceiptTestCircuit::new(
+        num_log_items(&block),
+        params.max_inner_blocks,
+        params.max_txs,
+        node_rows(&block),
+        block,
+    )
+}
+
+fn run(circuit: &ReceiptTestCircuit<Fr>) -> Result<(), Vec<VerifyFailure>> {
+    let prover = match MockProver::run(K, circuit, vec![]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{e:#?}"),
+    };
+    prover.verify()
+}
+
+fn receipt_circuit_receipts<const NTX: usize>() {
+    let block = block_with_logs::<NTX>();
+    let (block_number, (_, bloom)) = block
+        .receipts_roots_and_blooms()
+        .into_iter()
+        .next()
+        .unwrap();
+    assert_ne!(bloom, Default::default());
+
+    let circuit = test_circuit(block);
+    assert_eq!(circuit.circuit.blocks[0].1.len(), NTX);
+    assert_eq!(circuit.circuit.items.len(), 3 * NTX);
+    assert_eq!(run(&circuit), Ok(()));
+
+    let exports = circuit.circuit.exports.borrow().clone().unwrap();
+    assert_eq!(exports.block_numbers.len(), 1);
+    exports.block_numbers[0]
+        .1
+        .assert_if_known(|value| value.evaluate() == Fr::from(block_number));
+}
+
+#[test]
+fn receipt_circuit_two_receipts() {
+    receipt_circuit_receipts::<2>();
+}
+
+#[test]
+fn receipt_circuit_branch_of_receipts() {
+    // more than 16 receipts put the leaves below a branch of the root
+    receipt_circuit_receipts::<17>();
+}
+
+#[test]
+fn receipt_circuit_wrong_cumulative_gas_used() {
+    let mut circuit = test_circuit(block_with_logs::<2>());
+    circuit.circuit.blocks[0].1[1].cumulative_gas_used += 1;
+
+    assert!(run(&circuit).is_err());
+}
+
+#[test]
+fn receipt_circuit_wrong_status() {
+    let mut circuit = test_circuit(block_with_logs::<2>());
+    circuit.circuit.blocks[0].1[0].status ^= 1;
+
+    assert!(run(&circuit).is_err());
+}
+
+#[test]
+fn receipt_circuit_wrong_topic() {
+    let mut circuit = test_circuit(block_with_logs::<2>());
+    circuit.circuit.blocks[0].1[0].logs[0].topics[0] = H256::repeat_byte(0x11);
+
+    assert!(run(&circuit).is_err());
+}
@@ -18,12 +18,16 @@ use self::{
     constraint_builder::{MptUpdateTableQueries, RwTableQueries},
     lexicographic_ordering::LimbIndex,
 };
+#[cfg(feature = "receipt-circuit")]
+use crate::table::LogTable;
 use crate::{
     evm_circuit::{param::N_BYTES_WORD, util::rlc},
     table::{AccountFieldTag, LookupTable, MptTable, RwTable, RwTableTag},
     util::{Challenges, Expr, Field, SubCircuit, SubCircuitConfig},
     witness::{self, MptUpdates, Rw, RwMap},
 };
+#[cfg(feature = "receipt-circuit")]
+use constraint_builder::LogTableQueries;
 use constraint_builder::{ConstraintBuilder, Queries};
 use eth_types::{Address, ToLittleEndian, ToWord};
 use gadgets::{
@@ -76,6 +80,8 @@ pub struct StateCircuitConfig<F> {
     power_of_randomness: [Expression<F>; N_BYTES_WORD - 1],
     // External tables
     mpt_table: MptTable,
+    #[cfg(feature = "receipt-circuit")]
+    log_table: LogTable,
 }
 
 /// Circuit configuration arguments
@@ -84,6 +90,9 @@ pub struct StateCircuitConfigArgs<F: Field> {
     pub rw_table: RwTable,
     /// MptTable
     pub mpt_table: MptTable,
+    /// LogTable
+    #[cfg(feature = "receipt-circuit")]
+    pub log_table: LogTable,
     /// Challenges
     pub challenges: Challenges<Expression<F>>,
 }
@@ -106,6 +115,8 @@ impl<F: Field> SubCircuitConfig<F> for StateCircuitConfig<F> {
         Self::ConfigArgs {
             rw_table,
             mpt_table,
+            #[cfg(feature = "receipt-circuit")]
+            log_table,
             challenges,
         }: Self::ConfigArgs,
     ) -> Self {
@@ -170,6 +181,8 @@ impl<F: Field> SubCircuitConfig<F> for StateCircuitConfig<F> {
         // annotate columns
         rw_table.annotate_columns(meta);
         mpt_table.annotate_columns(meta);
+        #[cfg(feature = "receipt-circuit")]
+        log_table.annotate_columns(meta);
 
         let config = Self {
             selector,
@@ -184,6 +197,8 @@ impl<F: Field> SubCircuitConfig<F> for StateCircuitConfig<F> {
             power_of_randomness,
             rw_table,
             mpt_table,
+            #[cfg(feature = "receipt-circuit")]
+            log_table,
         };
 
         let mut constraint_builder = ConstraintBuilder::new();
@@ -1062,6 +1077,13 @@ fn queries<F: Field>(meta: &mut VirtualCells<'_, F>, c: &StateCircuitConfig<F>)
             new_value: meta.query_advice(c.mpt_table.new_value, Rotation::cur()),
             old_value: meta.query_advice(c.mpt_table.old_value, Rotation::cur()),
         },
+        #[cfg(feature = "receipt-circuit")]
+        log_table: LogTableQueries {
+            q_enable: meta.query_fixed(c.log_table.q_enable, Rotation::cur()),
+            tx_id: meta.query_advice(c.log_table.tx_id, Rotation::cur()),
+            address: meta.query_advice(c.log_table.address, Rotation::cur()),
+            value: meta.query_advice(c.log_table.value, Rotation::cur()),
+        },
         lexicographic_ordering_selector: meta
             .query_fixed(c.lexicographic_ordering.selector, Rotation::cur()),
         rw_counter: MpiQueries::new(meta, c.sort_keys.rw_counter),
@@ -41,11 +41,22 @@ pub struct MptUpdateTableQueries<F: Field> {
     pub old_value: Expression<F>,
 }
 
+#[cfg(feature = "receipt-circuit")]
+#[derive(Clone)]
+pub struct LogTableQueries<F: Field> {
+    pub q_enable: Expression<F>,
+    pub tx_id: Expression<F>,
+    pub address: Expression<F>,
+    pub value: Expression<F>,
+}
+
 #[derive(Clone)]
 pub struct Queries<F: Field> {
     pub selector: Expression<F>,
     pub rw_table: RwTableQueries<F>,
     pub mpt_update_table: MptUpdateTableQueries<F>,
+    #[cfg(feature = "receipt-circuit")]
+    pub log_table: LogTableQueries<F>,
     pub lexicographic_ordering_selector: Expression<F>,
     pub rw_counter: MpiQueries<F, N_LIMBS_RW_COUNTER>,
     pub tag_bits: [Expression<F>; 4],
@@ -500,6 +511,16 @@ impl<F: Field> ConstraintBuilder<F> {
             q.value_prev_column(),
             q.initial_value(),
         );
+        #[cfg(feature = "receipt-circuit")]
+        self.add_lookup(
+            "TxLog is encoded in the receipt of its tx",
+            vec![
+                (1.expr(), q.log_table.q_enable.clone()),
+                (q.rw_table.id.clone(), q.log_table.tx_id.clone()),
+                (q.rw_table.address.clone(), q.log_table.address.clone()),
+                (q.value(), q.log_table.value.clone()),
+            ],
+        );
     }
 
     fn build_tx_receipt_constraints(&mut self, q: &Queries<F>) {
@@ -26,6 +26,8 @@ where
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
         let rw_table = RwTable::construct(meta);
         let mpt_table = MptTable::construct(meta);
+        #[cfg(feature = "receipt-circuit")]
+        let log_table = crate::table::LogTable::construct(meta);
         let challenges = Challenges::construct(meta);
 
         let config = {
@@ -35,6 +37,8 @@ where
                 StateCircuitConfigArgs {
                     rw_table,
                     mpt_table,
+                    #[cfg(feature = "receipt-circuit")]
+                    log_table,
                     challenges,
                 },
             )
@@ -55,6 +59,10 @@ where
             self.n_rows,
             challenges.evm_word(),
         )?;
+        #[cfg(feature = "receipt-circuit")]
+        config
+            .log_table
+            .dev_load(&mut layouter, &self.rows, challenges.evm_word())?;
         self.synthesize_sub(&config, &challenges, &mut layouter)
     }
 }
@@ -71,8 +71,7 @@ use crate::{
     evm_circuit::{EvmCircuit, EvmCircuitConfig, EvmCircuitConfigArgs},
     exp_circuit::{ExpCircuit, ExpCircuitArgs, ExpCircuitConfig},
     keccak_circuit::{
-        keccak_packed_multi::get_num_rows_per_round, KeccakCircuit, KeccakCircuitConfig,
-        KeccakCircuitConfigArgs,
+        KeccakCircuit, KeccakCircuitConfig, KeccakCircuitConfigArgs, DEFAULT_KECCAK_ROWS,
     },
     modexp_circuit::{ModExpCircuit, ModExpCircuitConfig},
     pi_circuit::{PiCircuit, PiCircuitConfig, PiCircuitConfigArgs},
@@ -96,6 +95,11 @@ use crate::{
 
 #[cfg(feature = "zktrie")]
 use crate::mpt_circuit::{MptCircuit, MptCircuitConfig, MptCircuitConfigArgs};
+#[cfg(feature = "receipt-circuit")]
+use crate::{
+    receipt_circuit::{ReceiptCircuit, ReceiptCircuitConfig, ReceiptCircuitConfigArgs},
+    table::LogTable,
+};
 
 use crate::util::Field;
 use bus_mapping::circuit_input_builder::{CircuitInputBuilder, CircuitsParams};
@@ -135,6 +139,9 @@ pub struct SuperCircuitConfig<F: Field> {
     pi_circuit: PiCircuitConfig<F>,
     exp_circuit: ExpCircuitConfig<F>,
     rlp_circuit: RlpCircuitConfig<F>,
+    /// Receipt Circuit
+    #[cfg(feature = "receipt-circuit")]
+    receipt_circuit: ReceiptCircuitConfig<F>,
     /// Mpt Circuit
     #[cfg(feature = "zktrie")]
     mpt_circuit: MptCircuitConfig<F>,
@@ -207,18 +214,22 @@ impl SubCircuitConfig<Fr> for SuperCircuitConfig<Fr> {
         log_circuit_info(meta, "ecc table");
         let pow_of_rand_table = PowOfRandTable::construct(meta, &challenges_expr);
         log_circuit_info(meta, "power of randomness table");
+        #[cfg(feature = "receipt-circuit")]
+        let log_table = LogTable::construct(meta);
+        #[cfg(feature = "receipt-circuit")]
+        log_circuit_info(meta, "log table");
 
         let u8_table = U8Table::construct(meta);
         log_circuit_info(meta, "u8 table");
         let u16_table = U16Table::construct(meta);
         log_circuit_info(meta, "u16 table");
 
-        assert!(get_num_rows_per_round() == 12);
         let keccak_circuit = KeccakCircuitConfig::new(
             meta,
             KeccakCircuitConfigArgs {
                 keccak_table: keccak_table.clone(),
                 challenges: challenges_expr.clone(),
+                num_rows_per_round: DEFAULT_KECCAK_ROWS,
             },
         );
         log_circuit_info(meta, "keccak circuit");
@@ -329,11 +340,29 @@ impl SubCircuitConfig<Fr> for SuperCircuitConfig<Fr> {
             StateCircuitConfigArgs {
                 rw_table,
                 mpt_table,
+                #[cfg(feature = "receipt-circuit")]
+                log_table,
                 challenges: challenges_expr.clone(),
             },
         );
         log_circuit_info(meta, "state circuit");
 
+        #[cfg(feature = "receipt-circuit")]
+        let receipt_circuit = ReceiptCircuitConfig::new(
+            meta,
+            ReceiptCircuitConfigArgs {
+                rw_table,
+                tx_table: tx_table.clone(),
+                block_table: block_table.clone(),
+                keccak_table: keccak_table.clone(),
+                u8_table,
+                log_table,
+                challenges: challenges_expr.clone(),
+            },
+        );
+        #[cfg(feature = "receipt-circuit")]
+        log_circuit_info(meta, "receipt circuit");
+
         let exp_circuit = ExpCircuitConfig::new(
             meta,
             ExpCircuitArgs {
@@ -412,6 +441,8 @@ impl SubCircuitConfig<Fr> for SuperCircuitConfig<Fr> {
             pi_circuit,
             exp_circuit,
             rlp_circuit,
+            #[cfg(feature = "receipt-circuit")]
+            receipt_circuit,
             #[cfg(feature = "zktrie")]
             mpt_circuit,
         }
@@ -467,6 +498,9 @@ pub struct SuperCircuit<
     pub ecc_circuit: EccCircuit<F, 9>,
     /// Rlp Circuit
     pub rlp_circuit: RlpCircuit<F, Transaction>,
+    /// Receipt Circuit
+    #[cfg(feature = "receipt-circuit")]
+    pub receipt_circuit: ReceiptCircuit<F>,
     /// Mpt Circuit
     #[cfg(feature = "zktrie")]
     pub mpt_circuit: MptCircuit<F>,
@@ -544,6 +578,11 @@ impl<
             let mpt = MptCircuit::<Fr>::min_num_rows_block(block);
             push("mpt", mpt);
         }
+        #[cfg(feature = "receipt-circuit")]
+        {
+            let receipt = ReceiptCircuit::<Fr>::min_num_rows_block(block);
+            push("receipt", receipt);
+        }
 
         let row_usage_details = rows
             .into_iter()
@@ -579,6 +618,10 @@ impl<
     type Config = SuperCircuitConfig<Fr>;
 
     fn unusable_rows() -> usize {
+        #[cfg(feature = "receipt-circuit")]
+        let receipt_unusable_rows = ReceiptCircuit::<Fr>::unusable_rows();
+        #[cfg(not(feature = "receipt-circuit"))]
+        let receipt_unusable_rows = 0;
         itertools::max([
             EvmCircuit::<Fr>::unusable_rows(),
             StateCircuit::<Fr>::unusable_rows(),
@@ -590,6 +633,7 @@ impl<
             CopyCircuit::<Fr>::unusable_rows(),
             ExpCircuit::<Fr>::unusable_rows(),
             KeccakCircuit::<Fr>::unusable_rows(),
+            receipt_unusable_rows,
         ])
         .unwrap()
     }
@@ -610,6 +654,8 @@ impl<
         let rlp_circuit = RlpCircuit::new_from_block(block);
         let sig_circuit = SigCircuit::new_from_block(block);
         let ecc_circuit = EccCircuit::new_from_block(block);
+        #[cfg(feature = "receipt-circuit")]
+        let receipt_circuit = ReceiptCircuit::new_from_block(block);
         #[cfg(feature = "zktrie")]
         let mpt_circuit = MptCircuit::new_from_block(block);
         SuperCircuit::<Fr, MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, MOCK_RANDOMNESS> {
@@ -627,6 +673,8 @@ impl<
             sig_circuit,
             modexp_circuit,
             ecc_circuit,
+            #[cfg(feature = "receipt-circuit")]
+            receipt_circuit,
             #[cfg(feature = "zktrie")]
             mpt_circuit,
             circuit_params: block.circuits_params,
@@ -715,6 +763,13 @@ impl<
         self.exp_circuit
             .synthesize_sub(&config.exp_circuit, challenges, layouter)?;
 
+        #[cfg(feature = "receipt-circuit")]
+        {
+            log::debug!("assigning receipt_circuit");
+            self.receipt_circuit
+                .synthesize_sub(&config.receipt_circuit, challenges, layouter)?;
+        }
+
         log::debug!("assigning pi_circuit");
         self.pi_circuit
             .import_tx_values(self.tx_circuit.value_cells.borrow().clone().unwrap());
@@ -724,7 +779,11 @@ impl<
             layouter,
             self.state_circuit.exports.borrow().as_ref(),
             self.evm_circuit.exports.borrow().as_ref(),
+            self.tx_circuit.exports.borrow().as_ref(),
         )?;
+        #[cfg(feature = "receipt-circuit")]
+        self.pi_circuit
+            .connect_receipts(layouter, self.receipt_circuit.exports.borrow().as_ref())?;
 
         log::debug!("assigning rlp_circuit");
         self.rlp_circuit
@@ -763,6 +763,89 @@ impl RwTable {
     }
 }
 
+/// The LogTable holds the addresses, topics and data bytes of the logs encoded in the receipts
+/// trie, keyed as the `TxLog` rows of the RwTable. It is assigned by the Receipt Circuit and
+/// looked up by the State Circuit, so that every log written in the rw table ends up in a
+/// receipt.
+#[derive(Clone, Copy, Debug)]
+pub struct LogTable {
+    /// Is enabled
+    pub q_enable: Column<Fixed>,
+    /// Tx of the log
+    pub tx_id: Column<Advice>,
+    /// Log id, field tag and index packed as the address of the rw table
+    pub address: Column<Advice>,
+    /// Address, topic or data byte
+    pub value: Column<Advice>,
+}
+
+impl<F: Field> LookupTable<F> for LogTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        vec![
+            self.q_enable.into(),
+            self.tx_id.into(),
+            self.address.into(),
+            self.value.into(),
+        ]
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        vec![
+            String::from("q_enable"),
+            String::from("tx_id"),
+            String::from("address"),
+            String::from("value"),
+        ]
+    }
+}
+
+impl LogTable {
+    /// Construct a new LogTable
+    pub fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            q_enable: meta.fixed_column(),
+            tx_id: meta.advice_column(),
+            address: meta.advice_column(),
+            value: meta.advice_column_in(SecondPhase),
+        }
+    }
+
+    /// Assign the `LogTable` from the `TxLog` rws, for the tests of the circuits
+    /// looking it up without the Receipt Circuit.
+    pub fn dev_load<F: Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        rws: &[Rw],
+        challenges: Value<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "log table",
+            |mut region| {
+                let rows = rws
+                    .iter()
+                    .filter(|rw| matches!(rw, Rw::TxLog { .. }))
+                    .map(|rw| rw.table_assignment(challenges));
+                for (offset, row) in rows.enumerate() {
+                    region.assign_fixed(
+                        || "log table q_enable",
+                        self.q_enable,
+                        offset,
+                        || Value::known(F::one()),
+                    )?;
+                    for (column, value) in [
+                        (self.tx_id, row.id),
+                        (self.address, row.address),
+                        (self.value, row.value),
+                    ] {
+                        region.assign_advice(|| "log table row", column, offset, || value)?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
 pub use mpt_circuits::MPTProofType;
 
 impl From<AccountFieldTag> for MPTProofType {
@@ -5,17 +5,25 @@ use crate::{
     evm_circuit::{cached::EvmCircuitCached, EvmCircuit},
     state_circuit::StateCircuit,
     util::{log2_ceil, SubCircuit},
-    witness::{Block, Rw},
+    witness::{Block, Rw, RwMap},
 };
 use bus_mapping::{circuit_input_builder::CircuitsParams, mock::BlockData};
 use eth_types::geth_types::GethData;
 
 use halo2_proofs::{
     circuit::Value,
-    dev::{unwrap_value, MockProver},
+    dev::{unwrap_value, MockProver, VerifyFailure},
     halo2curves::bn256::Fr,
 };
+use itertools::Itertools;
 use mock::TestContext;
+use std::fmt::Display;
+
+mod diagnose;
+pub use diagnose::{
+    diagnose_evm_failures, diagnose_state_failures, evm_step_layout, EvmStepFailure,
+    EvmStepLocation, StateRowFailure,
+};
 
 #[cfg(feature = "scroll")]
 use bus_mapping::circuit_input_builder::CircuitInputBuilder;
@@ -82,12 +90,27 @@ pub struct CircuitTestBuilder<const NACC: usize, const NTX: usize> {
     test_ctx: Option<TestContext<NACC, NTX>>,
     circuits_params: Option<CircuitsParams>,
     block: Option<Block>,
-    evm_checks: Option<Box<dyn Fn(MockProver<Fr>, &Vec<usize>, &Vec<usize>)>>,
-    state_checks: Option<Box<dyn Fn(MockProver<Fr>, &Vec<usize>, &Vec<usize>)>>,
-    copy_checks: Option<Box<dyn Fn(MockProver<Fr>, &Vec<usize>, &Vec<usize>)>>,
+    evm_checks: Option<CircuitChecks>,
+    state_checks: Option<CircuitChecks>,
+    copy_checks: Option<CircuitChecks>,
     block_modifiers: Vec<Box<dyn Fn(&mut Block)>>,
 }
 
+/// Checks run by the [`CircuitTestBuilder`] on the MockProver of a circuit.
+enum CircuitChecks {
+    /// Verify the active rows. Failures of the EVM and state circuits are
+    /// explained in terms of the steps and rws of the block.
+    Default,
+    /// Checks provided by the test.
+    Custom(Box<dyn Fn(MockProver<Fr>, &Vec<usize>, &Vec<usize>)>),
+}
+
+impl From<Box<dyn Fn(MockProver<Fr>, &Vec<usize>, &Vec<usize>)>> for CircuitChecks {
+    fn from(checks: Box<dyn Fn(MockProver<Fr>, &Vec<usize>, &Vec<usize>)>) -> Self {
+        Self::Custom(checks)
+    }
+}
+
 impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
     /// Generates an empty/set to default `CircuitTestBuilder`.
     fn empty() -> Self {
@@ -95,24 +118,9 @@ impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
             test_ctx: None,
             circuits_params: None,
             block: None,
-            evm_checks: Some(Box::new(|prover, gate_rows, lookup_rows| {
-                assert_eq!(prover.verify_at_rows_par(
-                    gate_rows.iter().cloned(),
-                    lookup_rows.iter().cloned(),
-                ), Ok(()));
-            })),
-            state_checks: Some(Box::new(|prover, gate_rows, lookup_rows| {
-                assert_eq!(prover.verify_at_rows_par(
-                    gate_rows.iter().cloned(),
-                    lookup_rows.iter().cloned(),
-                ), Ok(()));
-            })),
-            copy_checks: Some(Box::new(|prover, gate_rows, lookup_rows| {
-                assert_eq!(prover.verify_at_rows_par(
-                    gate_rows.iter().cloned(),
-                    lookup_rows.iter().cloned(),
-                ), Ok(()));
-            })),
+            evm_checks: Some(CircuitChecks::Default),
+            state_checks: Some(CircuitChecks::Default),
+            copy_checks: Some(CircuitChecks::Default),
             block_modifiers: vec![],
         }
     }
@@ -160,7 +168,7 @@ impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
         mut self,
         state_checks: Option<Box<dyn Fn(MockProver<Fr>, &Vec<usize>, &Vec<usize>)>>,
     ) -> Self {
-        self.state_checks = state_checks;
+        self.state_checks = state_checks.map(Into::into);
         self
     }
 
@@ -171,7 +179,7 @@ impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
         mut self,
         evm_checks: Option<Box<dyn Fn(MockProver<Fr>, &Vec<usize>, &Vec<usize>)>>,
     ) -> Self {
-        self.evm_checks = evm_checks;
+        self.evm_checks = evm_checks.map(Into::into);
         self
     }
 
@@ -182,7 +190,7 @@ impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
         mut self,
         copy_checks: Option<Box<dyn Fn(MockProver<Fr>, &Vec<usize>, &Vec<usize>)>>,
     ) -> Self {
-        self.copy_checks = copy_checks;
+        self.copy_checks = copy_checks.map(Into::into);
         self
     }
 
@@ -265,7 +273,24 @@ impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
             let circuit = EvmCircuitCached::get_test_cicuit_from_block(block.clone());
             let prover = MockProver::<Fr>::run(k, &circuit, vec![]).unwrap();
 
-            evm_checks(prover, &active_gate_rows, &active_lookup_rows)
+            match evm_checks {
+                CircuitChecks::Default => {
+                    if let Err(failures) = prover.verify_at_rows_par(
+                        active_gate_rows.iter().cloned(),
+                        active_lookup_rows.iter().cloned(),
+                    ) {
+                        let diagnosis = diagnose_evm_failures(&prover, &block);
+                        panic!(
+                            "EVM circuit not satisfied, {} failures:\n{}",
+                            failures.len(),
+                            failure_report(&failures, &diagnosis)
+                        );
+                    }
+                }
+                CircuitChecks::Custom(checks) => {
+                    checks(prover, &active_gate_rows, &active_lookup_rows)
+                }
+            }
         }
 
         // Run state circuit test
@@ -282,9 +307,25 @@ impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
                 .iter()
                 .filter(|rw| !matches!(rw, Rw::Start { .. }))
                 .count();
-            let rows = (rows_needed - non_start_rows_len..rows_needed).collect();
+            let rows = (rows_needed - non_start_rows_len..rows_needed).collect::<Vec<_>>();
 
-            state_checks(prover, &rows, &rows);
+            match state_checks {
+                CircuitChecks::Default => {
+                    if let Err(failures) =
+                        prover.verify_at_rows_par(rows.iter().cloned(), rows.iter().cloned())
+                    {
+                        let (assignments, _) =
+                            RwMap::table_assignments_prepad(&state_circuit.rows, rows_needed);
+                        let diagnosis = diagnose_state_failures(&prover, &assignments, &rows);
+                        panic!(
+                            "state circuit not satisfied, {} failures:\n{}",
+                            failures.len(),
+                            failure_report(&failures, &diagnosis)
+                        );
+                    }
+                }
+                CircuitChecks::Custom(checks) => checks(prover, &rows, &rows),
+            }
         }
 
         // Run copy circuit test
@@ -296,13 +337,29 @@ impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
             let copy_circuit = CopyCircuit::<Fr>::new_from_block(&block);
             let instance = copy_circuit.instance();
             let prover = MockProver::<Fr>::run(k, &copy_circuit, instance).unwrap();
-            let rows = (0..active_rows).collect();
+            let rows = (0..active_rows).collect::<Vec<_>>();
 
-            copy_checks(prover, &rows, &rows);
+            match copy_checks {
+                CircuitChecks::Default => assert_eq!(
+                    prover.verify_at_rows_par(rows.iter().cloned(), rows.iter().cloned()),
+                    Ok(())
+                ),
+                CircuitChecks::Custom(checks) => checks(prover, &rows, &rows),
+            }
         }
     }
 }
 
+/// Print the diagnosis of the failures, or the failures themselves if none
+/// of them could be attributed to a step or a row.
+fn failure_report<D: Display>(failures: &[VerifyFailure], diagnosis: &[D]) -> String {
+    if diagnosis.is_empty() {
+        format!("{failures:#?}")
+    } else {
+        diagnosis.iter().join("")
+    }
+}
+
 /// Escape the type safety of Value in tests.
 pub fn escape_value<T>(v: Value<T>) -> Option<T> {
     if v.is_none() {
@@ -0,0 +1,299 @@
+//! Explain MockProver failures in terms of the execution trace.
+//!
+//! MockProver reports failures by region and offset, which is hard to map
+//! back to the EVM execution, especially since the EVM and state circuits
+//! assign their rows in many parallel regions. Instead, the failing rows are
+//! found by bisecting the verification over the steps (EVM circuit) or the rw
+//! rows (state circuit), and every failure is reported together with the
+//! [`ExecStep`] or [`Rw`] it belongs to.
+
+use crate::{
+    evm_circuit::{EvmCircuit, ExecutionState},
+    witness::{Block, ExecStep, Rw},
+};
+use eth_types::evm_types::OpcodeId;
+use halo2_proofs::{
+    dev::{MockProver, VerifyFailure},
+    halo2curves::bn256::Fr,
+};
+use std::{fmt, ops::Range};
+
+/// Position of an EVM circuit step in the block.
+#[derive(Clone, Debug)]
+pub struct EvmStepLocation {
+    /// Index of the tx in the block
+    pub tx_idx: usize,
+    /// Index of the step in the tx
+    pub step_idx: usize,
+    /// Id of the call the step belongs to
+    pub call_id: usize,
+    /// Program counter
+    pub pc: u64,
+    /// Executed opcode, if any
+    pub opcode: Option<OpcodeId>,
+    /// Execution state of the step
+    pub execution_state: ExecutionState,
+    /// Rows of the EVM circuit assigned to the step
+    pub rows: Range<usize>,
+}
+
+impl fmt::Display for EvmStepLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tx {} step {} (call {}, pc {:#x}, ",
+            self.tx_idx, self.step_idx, self.call_id, self.pc
+        )?;
+        if let Some(opcode) = self.opcode {
+            write!(f, "{opcode:?}, ")?;
+        }
+        write!(
+            f,
+            "{:?}) at rows {}..{}",
+            self.execution_state, self.rows.start, self.rows.end
+        )
+    }
+}
+
+/// Rows of the EVM circuit assigned to each step of the block, in order.
+/// The padding and `EndBlock` rows follow the last step.
+pub fn evm_step_layout(block: &Block) -> Vec<EvmStepLocation> {
+    let mut offset = 0;
+    let mut layout = Vec::new();
+    for (tx_idx, tx) in block.txs.iter().enumerate() {
+        for (step_idx, step) in tx.steps.iter().enumerate() {
+            let height = step.execution_state.get_step_height();
+            layout.push(EvmStepLocation {
+                tx_idx,
+                step_idx,
+                call_id: tx.calls[step.call_index].id,
+                pc: step.program_counter,
+                opcode: step.opcode,
+                execution_state: step.execution_state,
+                rows: offset..offset + height,
+            });
+            offset += height;
+        }
+    }
+    layout
+}
+
+/// Failures of the EVM circuit at the rows of a single step.
+#[derive(Debug)]
+pub struct EvmStepFailure {
+    /// The failing step, or `None` for the padding and `EndBlock` rows
+    pub step: Option<EvmStepLocation>,
+    /// The step before the failing one
+    pub previous: Option<EvmStepLocation>,
+    /// The step after the failing one
+    pub next: Option<EvmStepLocation>,
+    /// The rws read or written by the failing step
+    pub rws: Vec<Rw>,
+    /// The failures reported by MockProver
+    pub failures: Vec<VerifyFailure>,
+}
+
+impl fmt::Display for EvmStepFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.step {
+            Some(step) => writeln!(f, "EVM circuit failure at {step}")?,
+            None => writeln!(f, "EVM circuit failure after the last step")?,
+        }
+        if let Some(previous) = &self.previous {
+            writeln!(f, "  previous: {previous}")?;
+        }
+        if let Some(next) = &self.next {
+            writeln!(f, "  next: {next}")?;
+        }
+        for rw in &self.rws {
+            writeln!(f, "  rw: {rw:?}")?;
+        }
+        for failure in &self.failures {
+            writeln!(f, "  - {failure}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Find the steps of `block` at which the EVM circuit `prover` fails.
+pub fn diagnose_evm_failures(prover: &MockProver<Fr>, block: &Block) -> Vec<EvmStepFailure> {
+    let layout = evm_step_layout(block);
+    let steps_end = layout.last().map_or(0, |step| step.rows.end);
+    let mut segments = layout
+        .iter()
+        .map(|step| step.rows.clone())
+        .collect::<Vec<_>>();
+    segments.push(steps_end..EvmCircuit::<Fr>::get_num_rows_required(block));
+
+    let step_of = |idx: usize| -> Option<&ExecStep> {
+        let location = layout.get(idx)?;
+        Some(&block.txs[location.tx_idx].steps[location.step_idx])
+    };
+    failing_segments(prover, &segments)
+        .into_iter()
+        .map(|(idx, failures)| EvmStepFailure {
+            step: layout.get(idx).cloned(),
+            previous: idx.checked_sub(1).and_then(|idx| layout.get(idx)).cloned(),
+            next: layout.get(idx + 1).cloned(),
+            rws: step_of(idx)
+                .map(|step| step.rw_indices.iter().map(|idx| block.rws[*idx]).collect())
+                .unwrap_or_default(),
+            failures,
+        })
+        .collect()
+}
+
+/// Failures of the state circuit at a single rw row.
+#[derive(Debug)]
+pub struct StateRowFailure {
+    /// Row of the state circuit
+    pub row: usize,
+    /// The rw assigned to the row
+    pub rw: Rw,
+    /// The rw assigned to the previous row
+    pub previous: Option<Rw>,
+    /// The failures reported by MockProver
+    pub failures: Vec<VerifyFailure>,
+}
+
+impl fmt::Display for StateRowFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "state circuit failure at row {}: {:?}",
+            self.row, self.rw
+        )?;
+        if let Some(previous) = &self.previous {
+            writeln!(f, "  previous: {previous:?}")?;
+        }
+        for failure in &self.failures {
+            writeln!(f, "  - {failure}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Find the rows at which the state circuit `prover` fails, among `rows`.
+/// `assignments` are the rws of the circuit as assigned, i.e. sorted and
+/// prepadded with `Rw::Start` rows.
+pub fn diagnose_state_failures(
+    prover: &MockProver<Fr>,
+    assignments: &[Rw],
+    rows: &[usize],
+) -> Vec<StateRowFailure> {
+    let segments = rows.iter().map(|row| *row..*row + 1).collect::<Vec<_>>();
+    failing_segments(prover, &segments)
+        .into_iter()
+        .map(|(idx, failures)| {
+            let row = rows[idx];
+            StateRowFailure {
+                row,
+                rw: assignments[row],
+                previous: row.checked_sub(1).map(|row| assignments[row]),
+                failures,
+            }
+        })
+        .collect()
+}
+
+/// Bisect the verification of `prover` over `segments` of rows, returning the
+/// index and the failures of every failing segment. Failures that are reported
+/// regardless of the rows, like permutation failures, are ignored.
+fn failing_segments(
+    prover: &MockProver<Fr>,
+    segments: &[Range<usize>],
+) -> Vec<(usize, Vec<VerifyFailure>)> {
+    let verify = |segments: &[Range<usize>]| {
+        let rows = segments.iter().flat_map(Clone::clone).collect::<Vec<_>>();
+        prover
+            .verify_at_rows_par(rows.iter().cloned(), rows.iter().cloned())
+            .err()
+            .unwrap_or_default()
+    };
+    let global = verify(&[]);
+    let local = |segments: &[Range<usize>]| {
+        verify(segments)
+            .into_iter()
+            .filter(|failure| !global.contains(failure))
+            .collect::<Vec<_>>()
+    };
+
+    let mut failing = Vec::new();
+    let mut pending = vec![0..segments.len()];
+    while let Some(range) = pending.pop() {
+        let failures = local(&segments[range.clone()]);
+        if failures.is_empty() {
+            continue;
+        }
+        if range.len() == 1 {
+            failing.push((range.start, failures));
+        } else {
+            let mid = range.start + range.len() / 2;
+            pending.push(mid..range.end);
+            pending.push(range.start..mid);
+        }
+    }
+    failing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witness::block_convert;
+    use bus_mapping::mock::BlockData;
+    use eth_types::{bytecode, geth_types::GethData};
+    use mock::TestContext;
+
+    fn add_block() -> Block {
+        let code = bytecode! {
+            PUSH1(1)
+            PUSH1(2)
+            ADD
+            STOP
+        };
+        let block: GethData = TestContext::<2, 1>::simple_ctx_with_bytecode(code)
+            .unwrap()
+            .into();
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        block_convert(&builder.block, &builder.code_db).unwrap()
+    }
+
+    #[test]
+    fn step_layout_covers_steps() {
+        let block = add_block();
+        let layout = evm_step_layout(&block);
+        assert_eq!(
+            layout.len(),
+            block.txs.iter().map(|tx| tx.steps.len()).sum::<usize>()
+        );
+        assert_eq!(layout[0].rows.start, 0);
+        for (step, next) in layout.iter().zip(layout.iter().skip(1)) {
+            assert_eq!(step.rows.end, next.rows.start);
+        }
+    }
+
+    #[test]
+    fn diagnose_corrupted_step() {
+        let mut block = add_block();
+        let add_idx = block.txs[0]
+            .steps
+            .iter()
+            .position(|step| step.opcode == Some(OpcodeId::ADD))
+            .unwrap();
+        block.txs[0].steps[add_idx].gas_left += 1;
+
+        let k = block.get_evm_test_circuit_degree();
+        let circuit = EvmCircuit::<Fr>::get_test_cicuit_from_block(block.clone());
+        let prover = MockProver::<Fr>::run(k, &circuit, vec![]).unwrap();
+
+        let diagnosis = diagnose_evm_failures(&prover, &block);
+        assert!(diagnosis.iter().any(|failure| failure
+            .step
+            .as_ref()
+            .is_some_and(|step| step.step_idx == add_idx)));
+        assert!(diagnosis.iter().all(|failure| !failure.failures.is_empty()));
+    }
+}
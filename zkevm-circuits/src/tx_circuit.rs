@@ -62,8 +62,8 @@ use gadgets::{
     util::{and, not, select, sum, Expr},
 };
 use halo2_proofs::{
-    circuit::{AssignedCell, Layouter, Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    circuit::{AssignedCell, Cell, Layouter, Region, Value},
+    plonk::{Advice, Assigned, Column, ConstraintSystem, Error, Expression, VirtualCells},
     poly::Rotation,
 };
 use log::error;
@@ -327,6 +327,15 @@ impl TxRomTable {
     }
 }
 
+/// Circuit exported cells after synthesis, used for subcircuit
+#[derive(Clone, Debug)]
+pub struct TxCircuitExports<V> {
+    /// L1 queue index of the first L1 message the chunk may pop
+    pub start_l1_queue_index: (Cell, Value<V>),
+    /// Total number of L1 messages popped after the chunk
+    pub end_l1_queue_index: (Cell, Value<V>),
+}
+
 /// Config for TxCircuit
 #[derive(Clone, Debug)]
 pub struct TxCircuitConfig<F: Field> {
@@ -570,6 +579,7 @@ impl<F: Field> SubCircuitConfig<F> for TxCircuitConfig<F> {
         let chunk_txbytes_len_acc = meta.advice_column();
         let pow_of_rand = meta.advice_column_in(SecondPhase);
 
+        meta.enable_equality(total_l1_popped_before);
         meta.enable_equality(chunk_bytes_len);
         meta.enable_equality(chunk_txbytes_rlc);
         meta.enable_equality(chunk_txbytes_len_acc);
@@ -1511,7 +1521,9 @@ impl<F: Field> SubCircuitConfig<F> for TxCircuitConfig<F> {
                 },
             );
 
-            // no constraints on last tx in the fixed part of tx table
+            // no constraints on last tx in the fixed part of tx table, the total number of l1
+            // msgs popped after it is carried to the first row of the dynamic part (see the gate
+            // below)
 
             cb.gate(and::expr([
                 meta.query_fixed(tx_table.q_enable, Rotation::cur()),
@@ -1522,6 +1534,24 @@ impl<F: Field> SubCircuitConfig<F> for TxCircuitConfig<F> {
             ]))
         });
 
+        meta.create_gate("total_l1_popped after the last tx", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            // The row before the first row of the dynamic part is the BlockNumber row of the
+            // last tx in the fixed part.
+            cb.require_equal(
+                "total_l1_popped = last_tx.is_l1_msg ? queue_index + 1 : total_l1_popped_before",
+                meta.query_advice(total_l1_popped_before, Rotation::cur()),
+                select::expr(
+                    meta.query_advice(is_l1_msg, Rotation::prev()),
+                    meta.query_advice(tx_nonce, Rotation::prev()) + 1.expr(),
+                    meta.query_advice(total_l1_popped_before, Rotation::prev()),
+                ),
+            );
+
+            cb.gate(meta.query_fixed(q_dynamic_first, Rotation::cur()))
+        });
+
         meta.lookup_any("num_all_txs in block table", |meta| {
             let is_tag_block_num = meta.query_advice(is_tag_block_num, Rotation::cur());
             let block_num = meta.query_advice(tx_table.value, Rotation::cur());
@@ -2384,7 +2414,9 @@ impl<F: Field> SubCircuitConfig<F> for TxCircuitConfig<F> {
     }
 }
 
-type FixedRowsAssignmentResult<F> = (Vec<AssignedCell<F, F>>, Vec<Value<F>>);
+/// The exported tx value cells, the running (chunk_txbytes_rlc, chunk_txbytes_len) and the
+/// total_l1_popped_before cell at the BlockNumber row of a tx.
+type FixedRowsAssignmentResult<F> = (Vec<AssignedCell<F, F>>, Vec<Value<F>>, AssignedCell<F, F>);
 impl<F: Field> TxCircuitConfig<F> {
     #[allow(clippy::too_many_arguments)]
     fn configure_lookups(
@@ -3038,6 +3070,7 @@ impl<F: Field> TxCircuitConfig<F> {
         };
         let mut supplemental_data: Vec<Value<F>> = vec![];
         let mut txbytes_hash_assignment: Option<AssignedCell<F, F>> = None;
+        let mut block_num_l1_popped_cell: Option<AssignedCell<F, F>> = None;
         let mut tx_value_cells = vec![];
         let rlp_sign_tag_length = if tx.tx_type.is_l1_msg() {
             // l1 msg does not have sign data
@@ -3360,11 +3393,6 @@ impl<F: Field> TxCircuitConfig<F> {
                 // num_all_txs, num_txs, cum_num_txs related assignment
                 ("tx_nonce", self.tx_nonce, F::from(tx.nonce)),
                 ("block_num", self.block_num, F::from(tx.block_number)),
-                (
-                    "total_l1_popped_before",
-                    self.total_l1_popped_before,
-                    F::from(total_l1_popped_before),
-                ),
                 (
                     "num_all_txs_acc",
                     self.num_all_txs_acc,
@@ -3422,6 +3450,15 @@ impl<F: Field> TxCircuitConfig<F> {
             ] {
                 region.assign_advice(|| col_anno, col, *offset, || Value::known(col_val))?;
             }
+            let total_l1_popped_before_cell = region.assign_advice(
+                || "total_l1_popped_before",
+                self.total_l1_popped_before,
+                *offset,
+                || Value::known(F::from(total_l1_popped_before)),
+            )?;
+            if tx_tag == BlockNumber {
+                block_num_l1_popped_cell = Some(total_l1_popped_before_cell);
+            }
             region.assign_advice(
                 || "chunk_txbytes_len_acc",
                 self.chunk_txbytes_len_acc,
@@ -3567,7 +3604,11 @@ impl<F: Field> TxCircuitConfig<F> {
         if is_last_tx {
             tx_value_cells.push(txbytes_hash_assignment.unwrap());
         }
-        Ok((tx_value_cells, supplemental_data))
+        Ok((
+            tx_value_cells,
+            supplemental_data,
+            block_num_l1_popped_cell.expect("BlockNumber row should have been assigned"),
+        ))
     }
 
     /// Assign calldata byte rows of each tx
@@ -3983,6 +4024,7 @@ pub struct TxCircuit<F: Field> {
     pub size: usize,
     /// Tx value cells (exported for PI circuit)
     pub value_cells: RefCell<Option<Vec<AssignedCell<F, F>>>>,
+    pub(crate) exports: RefCell<Option<TxCircuitExports<Assigned<F>>>>,
     _marker: PhantomData<F>,
 }
 
@@ -4011,6 +4053,7 @@ impl<F: Field> TxCircuit<F> {
             chain_id,
             start_l1_queue_index,
             value_cells: RefCell::new(None),
+            exports: RefCell::new(None),
             _marker: PhantomData,
         }
     }
@@ -4167,7 +4210,7 @@ impl<F: Field> TxCircuit<F> {
         start_l1_queue_index: u64,
         sign_datas: Vec<SignData>,
         padding_txs: &[Transaction],
-    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    ) -> Result<(Vec<AssignedCell<F, F>>, TxCircuitExports<Assigned<F>>), Error> {
         config.tx_rom_table.load(layouter)?;
 
         layouter.assign_region(
@@ -4214,6 +4257,7 @@ impl<F: Field> TxCircuit<F> {
                 let mut chunk_txbytes_rlc_acc = Value::known(F::zero());
                 let mut chunk_txbytes_len_acc = Value::known(F::zero());
                 let mut pows_of_rand: Vec<Value<F>> = vec![Value::known(F::one())];
+                let mut start_l1_queue_index_cell = None;
                 for (i, sign_data) in sigs.iter().enumerate() {
                     let tx = get_tx(i);
                     let block_num = tx.block_number;
@@ -4274,7 +4318,7 @@ impl<F: Field> TxCircuit<F> {
                         i,
                         num_all_txs_acc,
                     );
-                    let (assigned_cells, supplemental_data) = config.assign_fixed_rows(
+                    let (assigned_cells, supplemental_data, l1_popped_cell) = config.assign_fixed_rows(
                         &mut region,
                         &mut offset,
                         tx,
@@ -4298,6 +4342,9 @@ impl<F: Field> TxCircuit<F> {
 
                     chunk_txbytes_rlc_acc = supplemental_data[0];
                     chunk_txbytes_len_acc = supplemental_data[1];
+                    if i == 0 {
+                        start_l1_queue_index_cell = Some(l1_popped_cell);
+                    }
 
                     // set next tx's total_l1_popped_before
                     total_l1_popped_before = total_l1_popped_after;
@@ -4306,6 +4353,15 @@ impl<F: Field> TxCircuit<F> {
 
                 let calldata_first_row = self.max_txs * TX_LEN + 1;
                 let calldata_last_row = calldata_first_row + self.max_calldata;
+                // the total number of l1 msgs popped after the last tx is carried to the first
+                // row of the dynamic part
+                let end_l1_queue_index = total_l1_popped_before;
+                let end_l1_queue_index_cell = region.assign_advice(
+                    || "total_l1_popped after the last tx",
+                    config.total_l1_popped_before,
+                    calldata_first_row,
+                    || Value::known(F::from(end_l1_queue_index)),
+                )?;
                 // 3. Assign call data of txs
                 // 3.1 padding txs have no calldata bytes
                 for (i, tx) in self.txs.iter().enumerate() {
@@ -4344,7 +4400,20 @@ impl<F: Field> TxCircuit<F> {
                     region.assign_fixed(|| col_anno, col, row, || Value::known(F::one()))?;
                 }
 
-                Ok(tx_value_cells)
+                let start_l1_queue_index_cell = start_l1_queue_index_cell
+                    .expect("tx table should have at least one tx");
+                let exports = TxCircuitExports {
+                    start_l1_queue_index: (
+                        start_l1_queue_index_cell.cell(),
+                        Value::known(F::from(start_l1_queue_index)).into(),
+                    ),
+                    end_l1_queue_index: (
+                        end_l1_queue_index_cell.cell(),
+                        Value::known(F::from(end_l1_queue_index)).into(),
+                    ),
+                };
+
+                Ok((tx_value_cells, exports))
             },
         )
     }
@@ -4500,7 +4569,7 @@ impl<F: Field> SubCircuit<F> for TxCircuit<F> {
             }
         }
 
-        let tx_value_cells = self.assign(
+        let (tx_value_cells, exports) = self.assign(
             config,
             challenges,
             layouter,
@@ -4510,6 +4579,7 @@ impl<F: Field> SubCircuit<F> for TxCircuit<F> {
         )?;
         // export tx value cells
         *self.value_cells.borrow_mut() = Some(tx_value_cells);
+        *self.exports.borrow_mut() = Some(exports);
 
         Ok(())
     }
@@ -18,7 +18,12 @@ mod mpt;
 pub use mpt::{MptUpdate, MptUpdateRow, MptUpdates, WithdrawProof};
 
 mod receipt;
-pub use receipt::Receipt;
+pub use receipt::{
+    bloom_bits, logs_bloom, receipt_trie_nodes, receipts_bytes, receipts_from_rws, receipts_root,
+    Receipt, ReceiptTag, ReceiptTrieNode, BLOOM_BITS, MAX_RECEIPTS_PER_BLOCK,
+};
+#[cfg(feature = "receipt-circuit")]
+pub(crate) use receipt::{leaf_key, tx_type_byte};
 
 pub(crate) mod rlp_fsm;
 pub use rlp_fsm::{
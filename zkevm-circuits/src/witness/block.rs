@@ -1,4 +1,4 @@
-use ethers_core::types::Signature;
+use ethers_core::types::{Bloom, Signature};
 use gadgets::ToScalar;
 use std::collections::{BTreeMap, HashMap};
 
@@ -28,8 +28,9 @@ use halo2_proofs::{circuit::Value, halo2curves::bn256::Fr};
 use itertools::Itertools;
 
 use super::{
-    mpt::ZktrieState as MptState, step::step_convert, tx::tx_convert, Bytecode, ExecStep,
-    MptUpdates, RwMap, Transaction,
+    logs_bloom, mpt::ZktrieState as MptState, receipt_trie_nodes, receipts_bytes,
+    receipts_from_rws, receipts_root, step::step_convert, tx::tx_convert, Bytecode, ExecStep,
+    MptUpdates, Receipt, ReceiptTrieNode, RwMap, Transaction,
 };
 use crate::util::Challenges;
 
@@ -72,6 +73,8 @@ pub struct Block {
     pub chain_id: u64,
     /// StartL1QueueIndex
     pub start_l1_queue_index: u64,
+    /// Rolling hash of the L1 messages popped before this chunk, set by the prover
+    pub prev_l1_msg_hash: H256,
     /// IO to/from precompile calls.
     pub precompile_events: PrecompileEvents,
 }
@@ -116,6 +119,48 @@ impl Block {
         }
         post_state_root_in_trie
     }
+    /// Receipts of the txs in this chunk, rebuilt from the rws.
+    pub fn receipts(&self) -> Vec<Receipt> {
+        receipts_from_rws(&self.rws, &self.txs)
+    }
+    /// Receipts of the txs of each block in this chunk, by block number
+    pub fn receipts_by_block(&self) -> BTreeMap<u64, Vec<Receipt>> {
+        let mut receipts_by_block = self
+            .context
+            .ctxs
+            .keys()
+            .map(|&number| (number, vec![]))
+            .collect::<BTreeMap<_, _>>();
+        for (tx, receipt) in self.txs.iter().zip(self.receipts()) {
+            receipts_by_block
+                .entry(tx.block_number)
+                .or_default()
+                .push(receipt);
+        }
+        receipts_by_block
+    }
+    /// Receipts root and logs bloom of each block in this chunk, by block number
+    pub fn receipts_roots_and_blooms(&self) -> BTreeMap<u64, (H256, Bloom)> {
+        self.receipts_by_block()
+            .into_iter()
+            .map(|(number, receipts)| {
+                let bloom = logs_bloom(receipts.iter().flat_map(|r| r.logs.iter()));
+                (number, (receipts_root(&receipts), bloom))
+            })
+            .collect()
+    }
+    /// Number, logs bloom and receipts root of each block in this chunk, as hashed into the
+    /// chunk public input
+    pub fn receipts_bytes(&self) -> Vec<u8> {
+        receipts_bytes(&self.receipts_roots_and_blooms())
+    }
+    /// Nodes of the receipts trie of each block in this chunk, in the order of the blocks
+    pub fn receipt_trie_nodes(&self) -> Vec<ReceiptTrieNode> {
+        self.receipts_by_block()
+            .into_iter()
+            .flat_map(|(number, receipts)| receipt_trie_nodes(number, &receipts))
+            .collect()
+    }
     /// Replay mpt updates to generate mpt witness
     pub fn apply_mpt_updates(&mut self, mpt_state: &MptState) {
         self.mpt_updates.fill_state_roots(mpt_state);
@@ -612,6 +657,7 @@ pub fn block_convert(
         mpt_updates,
         chain_id,
         start_l1_queue_index: block.start_l1_queue_index,
+        prev_l1_msg_hash: block.prev_l1_msg_hash,
         precompile_events: block.precompile_events.clone(),
     };
     Ok(block)
@@ -7,7 +7,13 @@ use eth_types::{
 use ethers_core::utils::keccak256;
 use itertools::Itertools;
 
+#[cfg(feature = "receipt-circuit")]
+use super::Rw;
 use super::{Block, BlockContexts, Transaction};
+#[cfg(feature = "receipt-circuit")]
+use crate::table::{RwTableTag, TxLogFieldTag};
+#[cfg(feature = "receipt-circuit")]
+use eth_types::ToAddress;
 
 /// Return all the keccak inputs used during the processing of the current
 /// block.
@@ -34,9 +40,14 @@ pub fn keccak_inputs(block: &Block) -> Result<Vec<Vec<u8>>, Error> {
         block.prev_state_root.to_word(),
         block.post_state_root().to_word(),
         block.withdraw_root,
+        block.prev_l1_msg_hash,
+        cfg!(feature = "receipt-circuit").then(|| block.receipts_bytes()),
         &block.context,
         &block.txs,
     ));
+    // Receipt circuit
+    #[cfg(feature = "receipt-circuit")]
+    keccak_inputs.extend(keccak_inputs_receipt_circuit(block));
     /*
     // Bytecode Circuit don't use keccak code hash
     for bytecode in block.code_db.0.values() {
@@ -80,12 +91,15 @@ pub fn keccak_inputs_sign_verify(sigs: &[SignData]) -> Vec<Vec<u8>> {
     inputs
 }
 
+#[allow(clippy::too_many_arguments)]
 fn keccak_inputs_pi_circuit(
     chain_id: u64,
     start_l1_queue_index: u64,
     prev_state_root: Word,
     after_state_root: Word,
     withdraw_trie_root: Word,
+    prev_l1_msg_hash: H256,
+    receipts_bytes: Option<Vec<u8>>,
     block_headers: &BlockContexts,
     transactions: &[Transaction],
 ) -> Vec<Vec<u8>> {
@@ -144,6 +158,20 @@ fn keccak_inputs_pi_circuit(
         hex::encode(data_hash.to_fixed_bytes())
     );
 
+    let l1_msg_hash_bytes = std::iter::empty()
+        .chain(prev_l1_msg_hash.to_fixed_bytes())
+        .chain(
+            l1transactions
+                .iter()
+                .flat_map(|&tx| tx.hash.to_fixed_bytes()),
+        )
+        .collect::<Vec<u8>>();
+    let post_l1_msg_hash = H256(keccak256(&l1_msg_hash_bytes));
+
+    let receipts_hash = receipts_bytes
+        .as_ref()
+        .map(|receipts_bytes| H256(keccak256(receipts_bytes)));
+
     let chunk_txbytes = transactions
         .iter()
         .filter(|&tx| tx.tx_type != TxType::L1Msg)
@@ -157,9 +185,56 @@ fn keccak_inputs_pi_circuit(
         .chain(withdraw_trie_root.to_be_bytes())
         .chain(data_hash.to_fixed_bytes())
         .chain(chunk_txbytes_hash.to_fixed_bytes())
+        .chain(start_l1_queue_index.to_be_bytes())
+        .chain(total_l1_popped.to_be_bytes())
+        .chain(prev_l1_msg_hash.to_fixed_bytes())
+        .chain(post_l1_msg_hash.to_fixed_bytes())
+        .chain(receipts_hash.into_iter().flat_map(H256::to_fixed_bytes))
         .collect::<Vec<u8>>();
 
-    vec![data_bytes, pi_bytes]
+    [data_bytes, l1_msg_hash_bytes]
+        .into_iter()
+        .chain(receipts_bytes)
+        .chain([pi_bytes])
+        .collect()
+}
+
+/// Generate the keccak inputs required by the Receipt Circuit: the nodes of the receipts tries,
+/// the log addresses and topics, and the empty string hashed for a block without txs.
+#[cfg(feature = "receipt-circuit")]
+pub fn keccak_inputs_receipt_circuit(block: &Block) -> Vec<Vec<u8>> {
+    let items = block
+        .rws
+        .0
+        .get(&RwTableTag::TxLog)
+        .into_iter()
+        .flatten()
+        .filter_map(|rw| match rw {
+            Rw::TxLog {
+                field_tag: TxLogFieldTag::Address,
+                value,
+                ..
+            } => Some(value.to_address().as_bytes().to_vec()),
+            Rw::TxLog {
+                field_tag: TxLogFieldTag::Topic,
+                value,
+                ..
+            } => Some(value.to_be_bytes().to_vec()),
+            _ => None,
+        });
+    let empty_block = block
+        .receipts_by_block()
+        .values()
+        .any(Vec::is_empty)
+        .then(|| vec![0x80]);
+
+    block
+        .receipt_trie_nodes()
+        .iter()
+        .map(|node| node.bytes())
+        .chain(items)
+        .chain(empty_block)
+        .collect()
 }
 
 /// Generate the keccak inputs required by the Tx Circuit from the transactions.
@@ -1,13 +1,35 @@
+use std::collections::BTreeMap;
+
+use eth_types::{geth_types::TxType, ToAddress, ToBigEndian, H256};
 use ethers_core::{
     types::{Bloom, Log},
-    utils::rlp::{Encodable, RlpStream},
+    utils::{
+        keccak256,
+        rlp::{self, Encodable, RlpStream},
+    },
+};
+
+use gadgets::impl_expr;
+use halo2_proofs::plonk::Expression;
+use strum_macros::EnumIter;
+
+use super::{Rw, RwMap, Transaction};
+use crate::{
+    evm_circuit::param::N_BYTES_WORD,
+    table::{RwTableTag, TxLogFieldTag, TxReceiptFieldTag},
+    util::Field,
 };
 
+/// Number of bits in the logs bloom.
+pub const BLOOM_BITS: usize = 2048;
+
 /// EVM log's receipt.
 #[derive(Clone, Debug, Default)]
 pub struct Receipt {
     /// Denotes the ID of the tx.
     pub id: usize,
+    /// Type of the tx, which decides the envelope of the encoded receipt.
+    pub tx_type: TxType,
     /// Denotes whether or not the tx was executed successfully.
     pub status: u8,
     /// Denotes the cumulative gas used by the tx execution.
@@ -33,3 +55,725 @@ impl Encodable for Receipt {
         }
     }
 }
+
+impl Receipt {
+    /// EIP-2718 type byte of the receipt, none for legacy receipts.
+    pub fn type_byte(&self) -> Option<u8> {
+        tx_type_byte(self.tx_type)
+    }
+
+    /// EIP-2718 encoding of the receipt, i.e. the value stored in the receipts trie: legacy
+    /// receipts are the bare RLP list, typed receipts are prefixed by the tx type byte.
+    pub fn encode_typed(&self) -> Vec<u8> {
+        self.type_byte()
+            .into_iter()
+            .chain(rlp::encode(self).iter().copied())
+            .collect()
+    }
+}
+
+/// EIP-2718 type byte of the receipts of `tx_type`.
+pub(crate) fn tx_type_byte(tx_type: TxType) -> Option<u8> {
+    match tx_type {
+        TxType::Eip155 | TxType::PreEip155 => None,
+        TxType::Eip2930 => Some(0x01),
+        TxType::Eip1559 => Some(0x02),
+        TxType::L1Msg => Some(0x7e),
+    }
+}
+
+/// Returns the indices in [0, 2048) of the three bloom bits set by `input`, taken from the
+/// first three big-endian 11-bit words of `keccak(input)`.
+pub fn bloom_bits(input: &[u8]) -> [usize; 3] {
+    let hash = keccak256(input);
+    [0, 2, 4].map(|i| (((hash[i] as usize) << 8) | hash[i + 1] as usize) % BLOOM_BITS)
+}
+
+/// Sets the bits of `input` in the bloom. Bit `b` lives in byte `255 - b / 8` at position
+/// `b % 8`.
+pub fn accrue_bloom(bloom: &mut Bloom, input: &[u8]) {
+    for bit in bloom_bits(input) {
+        bloom.0[BLOOM_BITS / 8 - 1 - bit / 8] |= 1 << (bit % 8);
+    }
+}
+
+/// Bloom of the addresses and topics of the logs.
+pub fn logs_bloom<'a>(logs: impl IntoIterator<Item = &'a Log>) -> Bloom {
+    let mut bloom = Bloom::zero();
+    for log in logs {
+        accrue_bloom(&mut bloom, log.address.as_bytes());
+        for topic in log.topics.iter() {
+            accrue_bloom(&mut bloom, topic.as_bytes());
+        }
+    }
+    bloom
+}
+
+/// Rebuilds the receipts of `txs` from the `TxReceipt` and `TxLog` rws. The cumulative gas
+/// written in the rws runs over the whole chunk, so it is rebased onto the start of the block
+/// each tx belongs to.
+pub fn receipts_from_rws(rws: &RwMap, txs: &[Transaction]) -> Vec<Receipt> {
+    let mut fields: BTreeMap<(usize, u64), u64> = BTreeMap::new();
+    for rw in rws.0.get(&RwTableTag::TxReceipt).into_iter().flatten() {
+        if let Rw::TxReceipt {
+            is_write: true,
+            tx_id,
+            field_tag,
+            value,
+            ..
+        } = rw
+        {
+            fields.insert((*tx_id, *field_tag as u64), *value);
+        }
+    }
+
+    let mut logs: BTreeMap<(usize, u64), Log> = BTreeMap::new();
+    for rw in rws.0.get(&RwTableTag::TxLog).into_iter().flatten() {
+        if let Rw::TxLog {
+            tx_id,
+            log_id,
+            field_tag,
+            index,
+            value,
+            ..
+        } = rw
+        {
+            let log = logs.entry((*tx_id, *log_id)).or_default();
+            match field_tag {
+                TxLogFieldTag::Address => log.address = value.to_address(),
+                TxLogFieldTag::Topic => {
+                    if log.topics.len() <= *index {
+                        log.topics.resize(*index + 1, H256::zero());
+                    }
+                    log.topics[*index] = H256::from(value.to_be_bytes());
+                }
+                TxLogFieldTag::Data => {
+                    let mut data = log.data.to_vec();
+                    if data.len() <= *index {
+                        data.resize(*index + 1, 0);
+                    }
+                    data[*index] = value.low_u64() as u8;
+                    log.data = data.into();
+                }
+            }
+        }
+    }
+
+    let field = |tx_id: usize, tag: TxReceiptFieldTag| {
+        fields
+            .get(&(tx_id, tag as u64))
+            .copied()
+            .unwrap_or_default()
+    };
+
+    let mut gas_before_block = 0;
+    txs.iter()
+        .enumerate()
+        .map(|(i, tx)| {
+            if i > 0 && txs[i - 1].block_number != tx.block_number {
+                gas_before_block = field(txs[i - 1].id, TxReceiptFieldTag::CumulativeGasUsed);
+            }
+            let logs = logs
+                .range((tx.id, 0)..(tx.id + 1, 0))
+                .map(|(_, log)| log.clone())
+                .collect::<Vec<_>>();
+            Receipt {
+                id: tx.id,
+                tx_type: tx.tx_type,
+                status: field(tx.id, TxReceiptFieldTag::PostStateOrStatus) as u8,
+                cumulative_gas_used: field(tx.id, TxReceiptFieldTag::CumulativeGasUsed)
+                    - gas_before_block,
+                bloom: logs_bloom(&logs),
+                logs,
+            }
+        })
+        .collect()
+}
+
+/// Root of the receipts trie of a block, i.e. the ordered trie keyed by `rlp(index)` over the
+/// typed encoding of each receipt.
+pub fn receipts_root(receipts: &[Receipt]) -> H256 {
+    ordered_trie_root(receipts.iter().map(Receipt::encode_typed))
+}
+
+/// Number, logs bloom and receipts root of each block, as hashed into the chunk public input.
+pub fn receipts_bytes(receipts: &BTreeMap<u64, (H256, Bloom)>) -> Vec<u8> {
+    receipts
+        .iter()
+        .flat_map(|(number, (root, bloom))| {
+            [&number.to_be_bytes()[..], bloom.as_bytes(), root.as_bytes()].concat()
+        })
+        .collect()
+}
+
+/// Root of the Merkle Patricia trie mapping `rlp(i)` to the i-th value, as used for the
+/// transactions and receipts roots of a block header.
+pub(crate) fn ordered_trie_root(values: impl IntoIterator<Item = Vec<u8>>) -> H256 {
+    let entries = values
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| (to_nibbles(&rlp::encode(&(i as u64))), value))
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    H256(keccak256(trie_node(&entries, 0)))
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0xf])
+        .collect()
+}
+
+/// Hex-prefix encoding of a nibble path.
+fn hex_prefix(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = 2 * is_leaf as u8 + (nibbles.len() % 2) as u8;
+    let padded = if nibbles.len() % 2 == 1 {
+        [&[flag][..], nibbles].concat()
+    } else {
+        [&[flag, 0][..], nibbles].concat()
+    };
+    padded
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair[1])
+        .collect()
+}
+
+/// A child is embedded in its parent when its encoding is shorter than 32 bytes, and referenced
+/// by hash otherwise.
+fn append_child(s: &mut RlpStream, node: Vec<u8>) {
+    if node.len() < 32 {
+        s.append_raw(&node, 1);
+    } else {
+        s.append(&H256(keccak256(&node)));
+    }
+}
+
+/// RLP encoding of the node holding the (sorted) `entries` below `depth` nibbles.
+fn trie_node(entries: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Vec<u8> {
+    let mut s = RlpStream::new();
+    match entries {
+        [] => s.append_empty_data(),
+        [(key, value)] => {
+            s.begin_list(2);
+            s.append(&hex_prefix(&key[depth..], true));
+            s.append(value)
+        }
+        _ => {
+            let (first, last) = (&entries[0].0, &entries[entries.len() - 1].0);
+            let shared = first[depth..]
+                .iter()
+                .zip(last[depth..].iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            if shared > 0 {
+                s.begin_list(2);
+                s.append(&hex_prefix(&first[depth..depth + shared], false));
+                append_child(&mut s, trie_node(entries, depth + shared));
+            } else {
+                // keys are sorted, so an entry ending at this branch comes first
+                let (value, children) = match entries.split_first() {
+                    Some(((key, value), rest)) if key.len() == depth => (Some(value), rest),
+                    _ => (None, entries),
+                };
+                s.begin_list(17);
+                for nibble in 0..16 {
+                    let start = children.partition_point(|(key, _)| key[depth] < nibble);
+                    let end = children.partition_point(|(key, _)| key[depth] <= nibble);
+                    if start == end {
+                        s.append_empty_data();
+                    } else {
+                        append_child(&mut s, trie_node(&children[start..end], depth + 1));
+                    }
+                }
+                match value {
+                    Some(value) => s.append(value),
+                    None => s.append_empty_data(),
+                };
+            }
+            &mut s
+        }
+    };
+    s.out().to_vec()
+}
+
+/// Max number of receipts in a block whose receipts trie is laid out by the receipt circuit.
+/// The key `rlp(i)` is then the single byte `i` (or 0x80 for 0), so the trie is made of a root
+/// branch, branches on the first nibble of the key and leaves.
+pub const MAX_RECEIPTS_PER_BLOCK: usize = 128;
+
+/// Tag of a segment of the RLP encoding of a receipts trie node, see [`ReceiptTrieNode`].
+#[derive(Default, Clone, Copy, Debug, EnumIter, PartialEq, Eq)]
+pub enum ReceiptTag {
+    /// Rows after the last node
+    #[default]
+    Padding = 0,
+    /// List header of a leaf
+    LeafHeader,
+    /// Encoded hex-prefix key of a leaf, header included
+    Key,
+    /// String header of the typed receipt
+    ValueHeader,
+    /// EIP-2718 type of the receipt
+    TxType,
+    /// List header of the receipt
+    ReceiptHeader,
+    /// Encoded status
+    Status,
+    /// Encoded cumulative gas used
+    Gas,
+    /// String header of the bloom
+    BloomHeader,
+    /// Bytes of the bloom
+    Bloom,
+    /// List header of the logs
+    LogsHeader,
+    /// List header of a log
+    LogHeader,
+    /// Encoded address of a log
+    Address,
+    /// List header of the topics of a log
+    TopicsHeader,
+    /// Encoded topic
+    Topic,
+    /// String header of the data of a log
+    DataHeader,
+    /// Bytes of the data of a log
+    Data,
+    /// List header of a branch
+    BranchHeader,
+    /// Hash of a child of a branch, header included
+    Child,
+    /// Empty child of a branch
+    EmptyChild,
+    /// Empty value of a branch
+    BranchValue,
+}
+
+impl From<ReceiptTag> for usize {
+    fn from(value: ReceiptTag) -> Self {
+        value as usize
+    }
+}
+
+impl_expr!(ReceiptTag);
+
+/// A node of the receipts trie of a block, split into the segments of its RLP encoding.
+///
+/// For a block of `n` receipts, the trie is
+/// - empty for `n == 0`,
+/// - a single leaf at level 0 for `n == 1`,
+/// - a root branch otherwise, whose slot 8 holds the receipt 0, slot 0 the receipts 1..16 and
+///   slot `g` the receipts `16g..16g+16`. A slot holding a single receipt is a leaf at level 1,
+///   otherwise it is a branch at level 1 whose slot `j` is the leaf at level 2 of the receipt
+///   `16g + j`.
+///
+/// All the nodes are at least 32 bytes long, so every child is referenced by its hash.
+#[derive(Clone, Debug, Default)]
+pub struct ReceiptTrieNode {
+    /// Block of the trie
+    pub block_number: u64,
+    /// Number of branches above the node
+    pub level: u64,
+    /// 0 at level 0, the slot in the root at level 1, the index of the receipt at level 2
+    pub path: u64,
+    /// Index in the block of the receipt of a leaf
+    pub leaf: Option<usize>,
+    /// Tagged segments of the RLP encoding
+    pub segments: Vec<(ReceiptTag, Vec<u8>)>,
+}
+
+impl ReceiptTrieNode {
+    /// RLP encoding of the node.
+    pub fn bytes(&self) -> Vec<u8> {
+        self.segments
+            .iter()
+            .flat_map(|(_, bytes)| bytes.iter().copied())
+            .collect()
+    }
+
+    /// Hash of the node, which is the receipts root for the node at level 0.
+    pub fn hash(&self) -> H256 {
+        H256(keccak256(self.bytes()))
+    }
+}
+
+/// RLP header of a string (`base = 0x80`) or a list (`base = 0xc0`) of `len` bytes.
+fn rlp_header(len: usize, base: u8) -> Vec<u8> {
+    if len < 56 {
+        vec![base + len as u8]
+    } else {
+        let len_bytes = (len as u64)
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|byte| *byte == 0)
+            .collect::<Vec<_>>();
+        [vec![base + 55 + len_bytes.len() as u8], len_bytes].concat()
+    }
+}
+
+/// Encoded hex-prefix key of the leaf of the receipt `index` at `level`.
+pub(crate) fn leaf_key(level: u64, index: usize) -> Vec<u8> {
+    match level {
+        0 => vec![0x82, 0x20, 0x80],
+        1 => vec![0x30 + (index as u8 & 0xf)],
+        _ => vec![0x20],
+    }
+}
+
+fn leaf_node(
+    block_number: u64,
+    level: u64,
+    path: u64,
+    index: usize,
+    receipt: &Receipt,
+) -> ReceiptTrieNode {
+    let mut value = vec![];
+    if let Some(type_byte) = receipt.type_byte() {
+        value.push((ReceiptTag::TxType, vec![type_byte]));
+    }
+
+    let gas = receipt
+        .cumulative_gas_used
+        .to_be_bytes()
+        .into_iter()
+        .skip_while(|byte| *byte == 0)
+        .collect::<Vec<_>>();
+    assert!(
+        receipt.cumulative_gas_used >= 0x80,
+        "cumulative gas used is encoded as a string"
+    );
+    let mut fields = vec![
+        (
+            ReceiptTag::Status,
+            vec![if receipt.status == 1 { 1 } else { 0x80 }],
+        ),
+        (ReceiptTag::Gas, [rlp_header(gas.len(), 0x80), gas].concat()),
+        (ReceiptTag::BloomHeader, vec![0xb9, 0x01, 0x00]),
+        (ReceiptTag::Bloom, receipt.bloom.0.to_vec()),
+    ];
+
+    let mut logs = vec![];
+    for log in receipt.logs.iter() {
+        let mut log_fields = vec![(
+            ReceiptTag::Address,
+            [&[0x94][..], log.address.as_bytes()].concat(),
+        )];
+        log_fields.push((
+            ReceiptTag::TopicsHeader,
+            rlp_header(log.topics.len() * (N_BYTES_WORD + 1), 0xc0),
+        ));
+        for topic in log.topics.iter() {
+            log_fields.push((ReceiptTag::Topic, [&[0xa0][..], topic.as_bytes()].concat()));
+        }
+        match log.data.as_ref() {
+            [byte] if *byte < 0x80 => log_fields.push((ReceiptTag::Data, vec![*byte])),
+            data => {
+                log_fields.push((ReceiptTag::DataHeader, rlp_header(data.len(), 0x80)));
+                if !data.is_empty() {
+                    log_fields.push((ReceiptTag::Data, data.to_vec()));
+                }
+            }
+        }
+        logs.push((
+            ReceiptTag::LogHeader,
+            rlp_header(segments_len(&log_fields), 0xc0),
+        ));
+        logs.extend(log_fields);
+    }
+    fields.push((
+        ReceiptTag::LogsHeader,
+        rlp_header(segments_len(&logs), 0xc0),
+    ));
+    fields.extend(logs);
+
+    value.push((
+        ReceiptTag::ReceiptHeader,
+        rlp_header(segments_len(&fields), 0xc0),
+    ));
+    value.extend(fields);
+
+    let mut payload = vec![(ReceiptTag::Key, leaf_key(level, index))];
+    payload.push((
+        ReceiptTag::ValueHeader,
+        rlp_header(segments_len(&value), 0x80),
+    ));
+    payload.extend(value);
+
+    let mut segments = vec![(
+        ReceiptTag::LeafHeader,
+        rlp_header(segments_len(&payload), 0xc0),
+    )];
+    segments.extend(payload);
+
+    ReceiptTrieNode {
+        block_number,
+        level,
+        path,
+        leaf: Some(index),
+        segments,
+    }
+}
+
+fn branch_node(
+    block_number: u64,
+    level: u64,
+    path: u64,
+    children: [Option<H256>; 16],
+) -> ReceiptTrieNode {
+    let mut payload = children
+        .iter()
+        .map(|child| match child {
+            Some(hash) => (ReceiptTag::Child, [&[0xa0][..], hash.as_bytes()].concat()),
+            None => (ReceiptTag::EmptyChild, vec![0x80]),
+        })
+        .collect::<Vec<_>>();
+    payload.push((ReceiptTag::BranchValue, vec![0x80]));
+
+    let mut segments = vec![(
+        ReceiptTag::BranchHeader,
+        rlp_header(segments_len(&payload), 0xc0),
+    )];
+    segments.extend(payload);
+
+    ReceiptTrieNode {
+        block_number,
+        level,
+        path,
+        leaf: None,
+        segments,
+    }
+}
+
+fn segments_len(segments: &[(ReceiptTag, Vec<u8>)]) -> usize {
+    segments.iter().map(|(_, bytes)| bytes.len()).sum()
+}
+
+/// Nodes of the receipts trie of a block, the leaves in the order of the receipts followed by
+/// the branches, the root last. See [`ReceiptTrieNode`] for the shape of the trie.
+pub fn receipt_trie_nodes(block_number: u64, receipts: &[Receipt]) -> Vec<ReceiptTrieNode> {
+    let n = receipts.len();
+    assert!(
+        n <= MAX_RECEIPTS_PER_BLOCK,
+        "too many receipts in block {block_number}: {n}"
+    );
+    if n == 1 {
+        return vec![leaf_node(block_number, 0, 0, 0, &receipts[0])];
+    }
+
+    // members of each slot of the root
+    let slots = (0..16)
+        .map(|slot| match slot {
+            8 => (0..n.min(1)).collect::<Vec<_>>(),
+            0 => (1..n.min(16)).collect(),
+            1..=7 => (16 * slot..n.min(16 * slot + 16)).collect(),
+            _ => vec![],
+        })
+        .collect::<Vec<_>>();
+
+    let mut leaves = vec![None; n];
+    let mut branches = vec![];
+    let mut root_children = [None; 16];
+    for (slot, members) in slots.iter().enumerate() {
+        let child = match members[..] {
+            [] => continue,
+            [index] => leaf_node(block_number, 1, slot as u64, index, &receipts[index]),
+            _ => {
+                let mut children = [None; 16];
+                for &index in members {
+                    let leaf = leaf_node(block_number, 2, index as u64, index, &receipts[index]);
+                    children[index & 0xf] = Some(leaf.hash());
+                    leaves[index] = Some(leaf);
+                }
+                branch_node(block_number, 1, slot as u64, children)
+            }
+        };
+        root_children[slot] = Some(child.hash());
+        match child.leaf {
+            Some(index) => leaves[index] = Some(child),
+            None => branches.push(child),
+        }
+    }
+    if n > 0 {
+        branches.push(branch_node(block_number, 0, 0, root_children));
+    }
+
+    leaves
+        .into_iter()
+        .map(Option::unwrap)
+        .chain(branches)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth_types::{l2_types::BlockTraceV2, Address};
+    use std::str::FromStr;
+
+    #[test]
+    fn empty_receipts_root() {
+        assert_eq!(
+            receipts_root(&[]),
+            H256::from_str("0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn single_receipt_root() {
+        let receipt = Receipt {
+            tx_type: TxType::Eip1559,
+            status: 1,
+            cumulative_gas_used: 21000,
+            ..Default::default()
+        };
+        let encoded = receipt.encode_typed();
+        assert_eq!(encoded[0], 0x02);
+
+        // a lone leaf under key rlp(0) = 0x80, whose nibbles [8, 0] have the leaf prefix 0x20
+        let mut leaf = RlpStream::new_list(2);
+        leaf.append(&vec![0x20u8, 0x80]);
+        leaf.append(&encoded);
+        assert_eq!(receipts_root(&[receipt]), H256(keccak256(leaf.out())));
+    }
+
+    /// Receipts with legacy and typed envelopes, failed txs, logs with 0 to 3 topics and empty,
+    /// bare single byte, short and long data.
+    fn test_receipts(n: usize) -> Vec<Receipt> {
+        (0..n)
+            .map(|i| {
+                let mut logs = vec![];
+                if i % 2 == 0 {
+                    logs.push(Log {
+                        address: Address::repeat_byte(i as u8),
+                        topics: vec![H256::repeat_byte(i as u8 + 1); i % 4],
+                        data: vec![i as u8; i % 3].into(),
+                        ..Default::default()
+                    });
+                }
+                if i % 5 == 4 {
+                    logs.push(Log {
+                        address: Address::repeat_byte(0xaa),
+                        data: (0..60).collect::<Vec<u8>>().into(),
+                        ..Default::default()
+                    });
+                }
+                Receipt {
+                    id: i + 1,
+                    tx_type: [
+                        TxType::Eip155,
+                        TxType::Eip1559,
+                        TxType::Eip2930,
+                        TxType::L1Msg,
+                    ][i % 4],
+                    status: (i % 3 != 0) as u8,
+                    cumulative_gas_used: 21000 * (i as u64 + 1) + i as u64,
+                    bloom: logs_bloom(&logs),
+                    logs,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mainnet_transactions_root() {
+        // the transactions root of a block goes through the same ordered trie as its receipts
+        // root, its trace holds the 3 signed txs but not their receipts.
+        let trace: BlockTraceV2 = serde_json::from_str(include_str!(
+            "../../../eth-types/src/testdata/trace_v2_5224657.json"
+        ))
+        .unwrap();
+        let txs = trace
+            .transactions
+            .iter()
+            .map(|tx| tx.to_eth_tx(None, None, None, None).rlp().to_vec());
+
+        assert_eq!(ordered_trie_root(txs), trace.header.transactions_root);
+    }
+
+    #[test]
+    fn receipts_root_of_many_receipts() {
+        // computed with an independent implementation of the trie, which reproduces the
+        // transactions root of the mainnet block above
+        for (n, root) in [
+            (
+                2,
+                "0x3beb7a1cf697221456464b9012a33584931412bd2507391e0844793bcdac0bfd",
+            ),
+            (
+                20,
+                "0x7c82b36590b495bbc89ab71bf424c917f7af426b890ff835aba9c08b78d639ad",
+            ),
+        ] {
+            assert_eq!(
+                receipts_root(&test_receipts(n)),
+                H256::from_str(root).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn receipt_trie_nodes_root() {
+        for n in [0, 1, 2, 3, 16, 17, 18, 33, 127, 128] {
+            let receipts = test_receipts(n);
+            let nodes = receipt_trie_nodes(1, &receipts);
+
+            let root = nodes
+                .last()
+                .map(|node| {
+                    assert_eq!(node.level, 0);
+                    node.hash()
+                })
+                .unwrap_or_else(|| receipts_root(&[]));
+            assert_eq!(root, receipts_root(&receipts), "{n} receipts");
+
+            // every receipt has a single leaf, and leaves come first
+            let leaves = nodes.iter().map_while(|node| node.leaf).collect::<Vec<_>>();
+            assert_eq!(leaves, (0..n).collect::<Vec<_>>());
+            assert!(nodes[n..].iter().all(|node| node.leaf.is_none()));
+            for node in nodes.iter() {
+                assert!(node.bytes().len() >= 32);
+            }
+        }
+    }
+
+    #[test]
+    fn receipt_trie_leaf_segments() {
+        let receipts = test_receipts(5);
+        let nodes = receipt_trie_nodes(1, &receipts);
+
+        for node in nodes.iter().filter(|node| node.leaf.is_some()) {
+            let index = node.leaf.unwrap();
+            let mut leaf = RlpStream::new_list(2);
+            leaf.append_raw(&leaf_key(node.level, index), 1);
+            leaf.append(&receipts[index].encode_typed());
+            assert_eq!(node.bytes(), leaf.out().to_vec());
+        }
+        // the bare single byte data of the receipt 4
+        assert!(nodes[4]
+            .segments
+            .iter()
+            .any(|(tag, bytes)| *tag == ReceiptTag::Data && bytes[..] == [4]));
+    }
+
+    #[test]
+    fn bloom_bits_of_log() {
+        let log = Log {
+            address: [0x11; 20].into(),
+            topics: vec![H256::repeat_byte(0x22)],
+            ..Default::default()
+        };
+        let bloom = logs_bloom([&log]);
+
+        for input in [log.address.as_bytes(), log.topics[0].as_bytes()] {
+            for bit in bloom_bits(input) {
+                assert_ne!(bloom.0[255 - bit / 8] & (1 << (bit % 8)), 0);
+            }
+        }
+        let num_bits = bloom.0.iter().map(|b| b.count_ones()).sum::<u32>();
+        assert!((1..=6).contains(&num_bits));
+    }
+}